@@ -1,27 +1,400 @@
 // src/api.rs
 
-use reqwest::blocking;
-use std::thread;
+use reqwest::Client;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use tokio::runtime::Runtime;
+use tracing::{debug, info, warn};
+use std::fmt;
 use std::time::Duration;
 
 // FIX: Import structs from the new module location
+use crate::backoff::Backoff;
 use crate::data_types::{
     TandCResponse, RegistrationReceipt, ChallengeData, ChallengeResponse,
     SolutionReceipt, DonateResponse, Statistics, StatisticsApiResponse, CliChallengeData, ApiErrorResponse
 };
 
+// --- SHARED RUNTIME ---
+//
+// Every API request runs on one small tokio runtime. The poller and the solution submissions
+// are tasks on it; synchronous code (the manager, one-shot commands, mining loops) waits for
+// its requests with `block_on`. Mining threads never touch the runtime.
+
+/// Worker threads of the API runtime. Requests are I/O bound; two threads keep a slow TLS
+/// handshake from delaying everything else.
+const RUNTIME_THREADS: usize = 2;
+
+// Process-wide: started on first use.
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The runtime the API client, the poller and the submission tasks run on.
+pub fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(RUNTIME_THREADS)
+            .thread_name("api")
+            .enable_all()
+            .build()
+            .expect("Failed to start the API runtime")
+    })
+}
+
+/// Runs an API request to completion from synchronous code. Must not be called from a task on
+/// the runtime itself.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
+
+// Process-wide: --dry-run. Registration and donation POSTs are logged instead of sent, and
+// the state worker validates solutions locally instead of submitting them.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+// Process-wide: --offline. Nothing may contact the API; solutions are exported instead of
+// queued for submission.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(enabled: bool) {
+    OFFLINE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+// --- TIMEOUTS AND RETRIES ---
+
+// Process-wide: --http-timeout, --max-retries and --retry-max-backoff. Read by the HTTP client
+// builder and by every retry loop (submissions, donations, receipt recovery, registrations).
+static HTTP_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(30);
+static MAX_RETRIES: AtomicU32 = AtomicU32::new(10);
+static RETRY_MAX_BACKOFF_SECS: AtomicU64 = AtomicU64::new(300);
+
+/// Sets the timeout of each API request in seconds; 0 disables it.
+pub fn set_http_timeout(secs: u64) {
+    HTTP_TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+}
+
+pub fn http_timeout() -> Option<Duration> {
+    match HTTP_TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Sets how often a failed request is retried and the longest wait between two attempts.
+pub fn set_retry_policy(max_retries: u32, max_backoff_secs: u64) {
+    MAX_RETRIES.store(max_retries, Ordering::Relaxed);
+    RETRY_MAX_BACKOFF_SECS.store(max_backoff_secs.max(1), Ordering::Relaxed);
+}
+
+pub fn max_retries() -> u32 {
+    MAX_RETRIES.load(Ordering::Relaxed)
+}
+
+pub fn retry_max_backoff_secs() -> u64 {
+    RETRY_MAX_BACKOFF_SECS.load(Ordering::Relaxed)
+}
+
+// --- RATE LIMIT (--max-rps) ---
+//
+// Registering and checking dozens of mnemonic indices in quick succession makes the API answer
+// with 429s. Every request takes a token from one process-wide bucket first: up to `rate`
+// requests start back to back, after that they are spaced out at `rate` per second.
+
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+// Process-wide: None while unlimited.
+static RATE_LIMIT: Mutex<Option<TokenBucket>> = Mutex::new(None);
+
+/// Limits API requests to `max_rps` per second across all callers; 0 removes the limit.
+pub fn set_max_rps(max_rps: f64) {
+    let mut bucket = RATE_LIMIT.lock().unwrap_or_else(|e| e.into_inner());
+    *bucket = (max_rps > 0.0).then(|| TokenBucket {
+        rate: max_rps,
+        tokens: max_rps.max(1.0),
+        last_refill: std::time::Instant::now(),
+    });
+}
+
+/// Waits for a token. Tokens are reserved in call order, so waiting requests keep their turn.
+async fn throttle() {
+    let wait = {
+        let mut guard = RATE_LIMIT.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(bucket) = guard.as_mut() else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        let burst = bucket.rate.max(1.0);
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * bucket.rate).min(burst);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+        (bucket.tokens < 0.0).then(|| Duration::from_secs_f64(-bucket.tokens / bucket.rate))
+    };
+    if let Some(wait) = wait {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// --- TLS ---
+//
+// Behind a TLS-intercepting proxy the API's certificate is signed by an internal CA, which
+// --tls-ca-cert adds to the system roots. --tls-client-cert/--tls-client-key present a client
+// certificate to servers (or proxies) that require one.
+
+#[derive(Default)]
+struct TlsConfig {
+    ca_certs: Vec<reqwest::Certificate>,
+    identity: Option<reqwest::Identity>,
+}
+
+// Process-wide: loaded once at startup, applied by every API client builder.
+static TLS_CONFIG: OnceLock<TlsConfig> = OnceLock::new();
+
+/// Loads the extra CA certificates (a PEM bundle) and the client certificate and its PKCS#8
+/// key (PEM). Only the first call takes effect.
+pub fn set_tls_config(ca_cert: Option<&str>, client_cert: Option<&str>, client_key: Option<&str>) -> Result<(), String> {
+    let read = |path: &str| std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e));
+    let mut config = TlsConfig::default();
+    if let Some(path) = ca_cert {
+        config.ca_certs = reqwest::Certificate::from_pem_bundle(&read(path)?)
+            .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+        if config.ca_certs.is_empty() {
+            return Err(format!("No PEM certificate found in {}.", path));
+        }
+    }
+    match (client_cert, client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let identity = reqwest::Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?)
+                .map_err(|e| format!("Invalid client certificate {} or key {} (the key must be PKCS#8 PEM): {}", cert_path, key_path, e))?;
+            config.identity = Some(identity);
+        }
+        (None, None) => {}
+        _ => return Err("--tls-client-cert and --tls-client-key must be given together.".to_string()),
+    }
+    let _ = TLS_CONFIG.set(config);
+    Ok(())
+}
+
+/// Adds the configured CA certificates and client identity to an API client builder.
+pub fn apply_tls_config(mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    if let Some(config) = TLS_CONFIG.get() {
+        for cert in &config.ca_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(identity) = &config.identity {
+            builder = builder.identity(identity.clone());
+        }
+    }
+    builder
+}
+
+// --- ENDPOINT URLS ---
+
+// Process-wide: path prefix inserted between --api-url and every endpoint (--api-base-path).
+static API_BASE_PATH: OnceLock<String> = OnceLock::new();
+
+/// Sets the path prefix (e.g. `/api` or `/api/v2`) for mirrors that mount the API below the
+/// host root. Leading and trailing slashes are optional. Only the first call takes effect.
+pub fn set_api_base_path(path: &str) {
+    let path = path.trim_matches('/');
+    let _ = API_BASE_PATH.set(if path.is_empty() { String::new() } else { format!("/{}", path) });
+}
+
+/// Builds the URL of `endpoint` (e.g. `challenge` or `statistics/<ADDRESS>`). Every request
+/// URL is constructed here.
+pub fn endpoint_url(api_url: &str, endpoint: &str) -> String {
+    format!(
+        "{}{}/{}",
+        api_url.trim_end_matches('/'),
+        API_BASE_PATH.get().map(String::as_str).unwrap_or(""),
+        endpoint.trim_start_matches('/')
+    )
+}
+
+// --- ERRORS ---
+//
+// Failed requests are classified once, here, from the HTTP status and the structured fields of
+// the error body. Callers decide what to do from `kind()` and `is_retryable()` instead of
+// searching the message, which the server is free to reword.
+
+/// What a rejected request means for the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiErrorKind {
+    /// The solution (or the address/challenge pair) was already credited.
+    AlreadySubmitted,
+    /// The challenge's submission window has closed.
+    SubmissionClosed,
+    /// The API does not know the address, typically because its registration has not propagated.
+    NotRegistered,
+    /// 429: the request may be repeated after a pause.
+    RateLimited,
+    /// 5xx or 408: the server failed to handle a request that may succeed later.
+    ServerError,
+    /// Any other rejection; repeating the same request will not help.
+    Rejected,
+}
+
+/// A failed API request.
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// No HTTP response: DNS, connection, TLS or timeout failure.
+    Network(String),
+    /// The server answered with a non-success status.
+    Http {
+        status: u16,
+        /// Machine-readable `error_code` of the body, if any.
+        code: Option<String>,
+        /// The body's `error` type (e.g. "Bad Request"), if any.
+        error_type: Option<String>,
+        /// The body's `message`, or the raw body when it is not the API's error JSON.
+        message: String,
+    },
+    /// A success status whose body could not be read or parsed.
+    InvalidResponse(String),
+    /// --dry-run kept the request from being sent.
+    DryRun(String),
+}
+
+impl ApiError {
+    /// Builds the error for a non-success response from its status and body.
+    fn from_response(status: reqwest::StatusCode, body: &str) -> Self {
+        match serde_json::from_str::<ApiErrorResponse>(body) {
+            Ok(err) => ApiError::Http { status: status.as_u16(), code: err.error_code, error_type: err.error, message: err.message },
+            Err(_) => ApiError::Http { status: status.as_u16(), code: None, error_type: None, message: body.to_string() },
+        }
+    }
+
+    fn network(e: reqwest::Error) -> Self {
+        ApiError::Network(e.to_string())
+    }
+
+    /// HTTP status of a rejected request.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Classification of a rejected request; None when no response arrived.
+    pub fn kind(&self) -> Option<ApiErrorKind> {
+        let ApiError::Http { status, code, message, .. } = self else {
+            return None;
+        };
+        // 1. The error code, when the server sends one.
+        match code.as_deref().map(str::to_ascii_uppercase).as_deref() {
+            Some("SOLUTION_EXISTS" | "ALREADY_SUBMITTED" | "DUPLICATE_SOLUTION") => return Some(ApiErrorKind::AlreadySubmitted),
+            Some("CHALLENGE_EXPIRED" | "SUBMISSION_CLOSED") => return Some(ApiErrorKind::SubmissionClosed),
+            Some("NOT_REGISTERED" | "ADDRESS_NOT_REGISTERED") => return Some(ApiErrorKind::NotRegistered),
+            _ => {}
+        }
+        // 2. Statuses that only mean one thing.
+        match status {
+            409 => return Some(ApiErrorKind::AlreadySubmitted),
+            429 => return Some(ApiErrorKind::RateLimited),
+            408 | 500..=599 => return Some(ApiErrorKind::ServerError),
+            _ => {}
+        }
+        // 3. Plain 400/404 replies carry only a message. "Not registered" is only taken from the
+        // error code: misreading a rejection as that would re-register and resubmit for nothing.
+        let message = message.to_lowercase();
+        let has = |markers: &[&str]| markers.iter().any(|marker| message.contains(marker));
+        Some(if has(&["already submitted", "already exists"]) {
+            ApiErrorKind::AlreadySubmitted
+        } else if has(&["submission window closed", "challenge expired"]) {
+            ApiErrorKind::SubmissionClosed
+        } else {
+            ApiErrorKind::Rejected
+        })
+    }
+
+    /// Whether --dry-run kept the request from being sent (nothing happened, nothing failed).
+    pub fn is_dry_run(&self) -> bool {
+        matches!(self, ApiError::DryRun(_))
+    }
+
+    /// Whether sending the same request again later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            // A garbled success body may come from a proxy; a retry gets a real answer.
+            ApiError::Network(_) | ApiError::InvalidResponse(_) => true,
+            ApiError::Http { .. } => matches!(self.kind(), Some(ApiErrorKind::RateLimited | ApiErrorKind::ServerError)),
+            ApiError::DryRun(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "API request failed: {}", e),
+            ApiError::Http { status, code, error_type, message } => {
+                write!(f, "API error (Status {}) {}", status, message)?;
+                if let Some(error_type) = error_type {
+                    write!(f, " [Type: {}]", error_type)?;
+                }
+                if let Some(code) = code {
+                    write!(f, " [API Code: {}]", code)?;
+                }
+                Ok(())
+            }
+            ApiError::InvalidResponse(e) => write!(f, "Invalid API response: {}", e),
+            ApiError::DryRun(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ApiError> for String {
+    fn from(e: ApiError) -> Self {
+        e.to_string()
+    }
+}
+
 // --- API FUNCTIONS ---
 
 /// Fetches the T&C from the API, returning the full response object.
-pub fn fetch_tandc(client: &blocking::Client, api_url: &str) -> Result<TandCResponse, reqwest::Error> {
-    let url = format!("{}/TandC/1-0", api_url);
-    println!("-> Fetching Terms and Conditions from: {}", url);
+pub async fn fetch_tandc(client: &Client, api_url: &str) -> Result<TandCResponse, ApiError> {
+    let url = endpoint_url(api_url, "TandC/1-0");
+    info!("-> Fetching Terms and Conditions from: {}", url);
 
-    let response = client.get(url).send()?;
+    throttle().await;
+    let response = client.get(url).send().await.map_err(ApiError::network)?;
+    let status = response.status();
+    let body = response.text().await.map_err(ApiError::network)?;
+    if !status.is_success() {
+        return Err(ApiError::from_response(status, &body));
+    }
 
-    let response = response.error_for_status()?;
+    serde_json::from_str(&body).map_err(|e| ApiError::InvalidResponse(format!("T&C: {}", e)))
+}
 
-    response.json()
+/// Sends a plain GET to the `path` endpoint and returns the HTTP status and the raw body,
+/// whatever the status. Used by the read-only `api` commands.
+pub async fn get_raw(client: &Client, api_url: &str, path: &str) -> Result<(reqwest::StatusCode, String), ApiError> {
+    let url = endpoint_url(api_url, path);
+    throttle().await;
+    let response = client.get(&url)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| ApiError::Network(format!("{}: {}", url, e)))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| ApiError::Network(format!("{}: {}", url, e)))?;
+    Ok((status, body))
 }
 
 pub fn parse_cli_challenge_string(challenge_str: &str) -> Result<CliChallengeData, String> {
@@ -45,250 +418,165 @@ pub fn parse_cli_challenge_string(challenge_str: &str) -> Result<CliChallengeDat
 
 
 /// Performs the POST /register call using key/signature arguments.
-pub fn register_address(
-    client: &blocking::Client,
+pub async fn register_address(
+    client: &Client,
     api_url: &str,
     address: &str,
     _tc_message: &str,
     signature: &str,
     pubkey: &str,
-) -> Result<(), reqwest::Error> {
-    let url = format!(
-        "{}/register/{}/{}/{}",
-        api_url,
-        address,
-        signature,
-        pubkey
-    );
+) -> Result<(), ApiError> {
+    let url = endpoint_url(api_url, &format!("register/{}/{}/{}", address, signature, pubkey));
+    if dry_run() {
+        info!("🧪 Dry run: would POST {}", url);
+        return Err(ApiError::DryRun(format!("Dry run: not sending {}", url)));
+    }
 
-    println!("-> Attempting address registration for address: {}", address);
+    info!("-> Attempting address registration for address: {}", address);
 
+    throttle().await;
     let response = client
         .post(url)
         .header("Content-Type", "application/json; charset=utf-8")
-        .send()?;
-
-    let response = response.error_for_status()?;
+        .send().await.map_err(ApiError::network)?;
+    let status = response.status();
+    let body = response.text().await.map_err(ApiError::network)?;
+    if !status.is_success() {
+        return Err(ApiError::from_response(status, &body));
+    }
 
-    let registration_receipt: RegistrationReceipt = response.json()?;
-    println!("✅ Address registered successfully.");
-    println!("Receipt: {}", registration_receipt.registration_receipt);
+    let registration_receipt: RegistrationReceipt = serde_json::from_str(&body)
+        .map_err(|e| ApiError::InvalidResponse(format!("registration receipt: {}", e)))?;
+    info!("✅ Address registered successfully.");
+    debug!(receipt = %registration_receipt.registration_receipt, "registration receipt");
 
     Ok(())
 }
 
-/// Helper to format a detailed error message from the API response body.
-fn format_detailed_api_error(err: ApiErrorResponse, status: reqwest::StatusCode) -> String {
-    let mut msg = format!("(Status {}) {}", status.as_u16(), err.message);
-
-    if let Some(e) = err.error {
-        msg.push_str(&format!(" [Type: {}]", e));
-    }
-    if let Some(code) = err.status_code {
-        msg.push_str(&format!(" [API Code: {}]", code));
-    }
-    msg
-}
-
 /// Performs the POST /solution call.
-pub fn submit_solution(
-    client: &blocking::Client,
+pub async fn submit_solution(
+    client: &Client,
     api_url: &str,
     address: &str,
     challenge_id: &str,
     nonce: &str,
-) -> Result<serde_json::Value, String> {
-    let url = format!(
-        "{}/solution/{}/{}/{}",
-        api_url,
-        address,
-        challenge_id,
-        nonce
-    );
+) -> Result<serde_json::Value, ApiError> {
+    let url = endpoint_url(api_url, &format!("solution/{}/{}/{}", address, challenge_id, nonce));
+    // The state worker does not submit in dry-run mode; this guards any other caller.
+    if dry_run() {
+        return Err(ApiError::DryRun(format!("Dry run: not sending {}", url)));
+    }
 
-    println!("-> Submitting solution (Nonce: {})", nonce);
+    info!("-> Submitting solution (Nonce: {})", nonce);
 
+    throttle().await;
     let response = client
         .post(url)
         .header("Content-Type", "application/json; charset=utf-8")
-        .send().map_err(|e| format!("Network/Client Error: {}", e))?;
+        .send().await.map_err(ApiError::network)?;
 
     let status = response.status();
+    let body_text = response.text().await.unwrap_or_else(|_| format!("Could not read response body for status {}", status));
+    debug!(status = %status, body = %body_text, "solution response");
 
     if status.is_success() {
         // Successful submission
-        let receipt: SolutionReceipt = response.json().map_err(|e| format!("Failed to parse successful receipt JSON: {}", e))?;
+        let receipt: SolutionReceipt = serde_json::from_str(&body_text)
+            .map_err(|e| ApiError::InvalidResponse(format!("receipt JSON: {}", e)))?;
         Ok(receipt.crypto_receipt)
     } else {
         // Submission failed (4xx or 5xx)
-        let body_text = response.text().unwrap_or_else(|_| format!("Could not read response body for status {}", status));
-
-        let api_error: Result<ApiErrorResponse, _> = serde_json::from_str(&body_text);
-
-        match api_error {
-            Ok(err) => {
-                // FIX: Use all error fields for detailed reporting
-                Err(format!("API Validation Failed: {}", format_detailed_api_error(err, status)))
-            }
-            Err(_) => {
-                // API returned a non-structured error (e.g., plain text or unreadable JSON)
-                Err(format!("HTTP Error {} with unparseable body: {}", status.as_u16(), body_text))
-            }
-        }
+        Err(ApiError::from_response(status, &body_text))
     }
 }
 
-/// Performs the POST /donate_to call.
-pub fn donate_to(
-    client: &blocking::Client,
+/// Retries of a donation request. The manager donates synchronously, so a failing endpoint
+/// must not hold up mining for the full --max-retries schedule (5s, 10s, 20s: about 35s).
+const DONATION_MAX_RETRIES: u32 = 3;
+const DONATION_MAX_BACKOFF_SECS: u64 = 20;
+
+/// Performs the POST /donate_to call. Retryable failures are retried up to 3 times (fewer with a
+/// lower --max-retries) with exponential backoff; when they persist, the last one is returned.
+pub async fn donate_to(
+    client: &Client,
     api_url: &str,
     original_address: &str,
     destination_address: &str,
     donation_signature: &str,
-) -> Result<String, String> {
-    let url = format!(
-        "{}/donate_to/{}/{}/{}",
-        api_url.trim_end_matches('/'),
-        destination_address,
-        original_address,
-        donation_signature
-    );
+) -> Result<String, ApiError> {
+    let url = endpoint_url(api_url, &format!("donate_to/{}/{}/{}", destination_address, original_address, donation_signature));
+    if dry_run() {
+        info!("🧪 Dry run: would POST {}", url);
+        return Err(ApiError::DryRun(format!("Dry run: not sending {}", url)));
+    }
 
     // Same empty JSON body as before (explicit for logging)
     let body = serde_json::json!({});
-    let mut attempt: u32 = 0;
-    let max_attempts: u32 = 3;
+    let mut backoff = Backoff::with_retry_policy(5);
+    backoff.max = backoff.max.min(DONATION_MAX_BACKOFF_SECS as f64);
+    backoff.max_retries = Some(max_retries().min(DONATION_MAX_RETRIES));
 
-    println!("-> Donating funds from {} to {}", original_address, destination_address);
+    info!("-> Donating funds from {} to {}", original_address, destination_address);
 
-    while attempt <= max_attempts {
-        let resp = client
+    loop {
+        throttle().await;
+        let error = match client
             .post(&url)
             .header("Content-Type", "application/json; charset=utf-8")
             .json(&body)
-            .send();
-
-        match resp {
+            .send()
+            .await
+        {
             Ok(response) => {
                 let status = response.status();
                 // Read once (text may be JSON or plain)
-                let text = response.text().unwrap_or_default();
-
-                // Always log request/response for debugging
-                println!("\n----------------------------------------------");
-                println!("📤 Request:");
-                println!("  URL : {}", url);
-                println!("  Body: {}", body); // prints {}
-                println!("📥 Response:");
-                println!("  Status: {}", status);
-                println!("  Body  : {}", text);
-                println!("----------------------------------------------");
+                let text = response.text().await.unwrap_or_default();
+
+                debug!(url = %url, request_body = %body, status = %status, response_body = %text, "donate_to exchange");
 
                 // Treat 2xx as success; 409 as success/“already done”
                 if status.is_success() || status.as_u16() == 409 {
                     // Try to parse donation_id; if absent (e.g., some 409s), return a marker
                     if let Ok(parsed) = serde_json::from_str::<DonateResponse>(&text) {
-                        println!("✅ Donation successful. Donation ID: {}", parsed.donation_id);
+                        info!("✅ Donation successful. Donation ID: {}", parsed.donation_id);
                         return Ok(parsed.donation_id);
                     } else {
-                        println!("✅ SUCCESS/ALREADY DONE (no donation_id in response JSON)");
+                        info!("✅ SUCCESS/ALREADY DONE (no donation_id in response JSON)");
                         return Ok("(already-done)".to_string());
                     }
                 }
-
-                // Handle common 4xx we care about with detailed JSON-parsed error if available
-                match status.as_u16() {
-                    400 | 404 => {
-                        if let Ok(err) = serde_json::from_str::<ApiErrorResponse>(&text) {
-                            return Err(format!(
-                                "Donation Failed: {}",
-                                format_detailed_api_error(err, status)
-                            ));
-                        }
-                        return Err(format!(
-                            "HTTP Error {} with unparseable body: {}",
-                            status.as_u16(),
-                            text
-                        ));
-                    }
-                    // Retryable server / rate limiting / timeout style errors
-                    s if s >= 500 || s == 429 || s == 408 => {
-                        attempt = attempt.saturating_add(1);
-                        if attempt > max_attempts {
-                            break;
-                        }
-                        let wait_ms = 5000u64.saturating_mul(1u64 << (attempt - 1)); // 5s, 10s, 20s
-                        eprintln!(
-                            "⏳ Server {} – retry {}/{} in {}s…",
-                            s,
-                            attempt,
-                            max_attempts,
-                            wait_ms / 1000
-                        );
-                        thread::sleep(Duration::from_millis(wait_ms));
-                        continue;
-                    }
-                    // Other non-retryable 4xx
-                    _ => {
-                        if let Ok(err) = serde_json::from_str::<ApiErrorResponse>(&text) {
-                            return Err(format!(
-                                "Donation Failed: {}",
-                                format_detailed_api_error(err, status)
-                            ));
-                        }
-                        return Err(format!(
-                            "HTTP Error {} with unparseable body: {}",
-                            status.as_u16(),
-                            text
-                        ));
-                    }
-                }
-            }
-            Err(e) => {
-                attempt = attempt.saturating_add(1);
-                let wait_ms = 5000u64.saturating_mul(1u64 << (attempt - 1)); // 5s, 10s, 20s
-                eprintln!(
-                    "\n----------------------------------------------\n\
-                     🌐 NETWORK ERROR on attempt {}/{}\n\
-                     URL : {}\nError: {}\n\
-                     Retrying in {}s…\n----------------------------------------------",
-                    attempt,
-                    max_attempts,
-                    url,
-                    e,
-                    wait_ms / 1000
-                );
-                if attempt > max_attempts {
-                    break;
-                }
-                thread::sleep(Duration::from_millis(wait_ms));
+                ApiError::from_response(status, &text)
             }
+            Err(e) => ApiError::network(e),
+        };
+
+        if !error.is_retryable() || backoff.exhausted() {
+            return Err(error);
         }
+        let delay = backoff.next_delay();
+        warn!("⏳ Donation request failed ({}) – retry {}/{} in {}s…", error, backoff.retries, max_retries().min(DONATION_MAX_RETRIES), delay.as_secs());
+        tokio::time::sleep(delay).await;
     }
-
-    Err(format!(
-        "Max retries exceeded for original_address {} → destination {}",
-        original_address, destination_address
-    ))
 }
 
 /// Fetches the raw Challenge Response object from the API.
-pub fn fetch_challenge_status(client: &blocking::Client, api_url: &str) -> Result<ChallengeResponse, String> {
-    let url = format!("{}/challenge", api_url);
+pub async fn fetch_challenge_status(client: &Client, api_url: &str) -> Result<ChallengeResponse, ApiError> {
+    let url = endpoint_url(api_url, "challenge");
 
-    let response = client.get(url).send().map_err(|e| format!("API request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!("Challenge API returned non-success status: {}", response.status()));
+    throttle().await;
+    let response = client.get(url).send().await.map_err(ApiError::network)?;
+    let status = response.status();
+    let body = response.text().await.map_err(ApiError::network)?;
+    if !status.is_success() {
+        return Err(ApiError::from_response(status, &body));
     }
 
-    let challenge_response: ChallengeResponse = response.json().map_err(|e| format!("JSON parsing failed: {}", e))?;
-    Ok(challenge_response)
+    serde_json::from_str(&body).map_err(|e| ApiError::InvalidResponse(format!("challenge JSON: {}", e)))
 }
 
 /// Fetches and validates the active challenge parameters, returning data only if active.
-pub fn get_active_challenge_data(client: &blocking::Client, api_url: &str) -> Result<ChallengeData, String> {
-    let challenge_response = fetch_challenge_status(client, api_url)?;
+pub async fn get_active_challenge_data(client: &Client, api_url: &str) -> Result<ChallengeData, String> {
+    let challenge_response = fetch_challenge_status(client, api_url).await?;
 
     match challenge_response.code.as_str() {
         "active" => {
@@ -311,19 +599,27 @@ pub fn get_active_challenge_data(client: &blocking::Client, api_url: &str) -> Re
 
 // ... (existing API FUNCTIONS)
 
-pub fn fetch_statistics(client: &blocking::Client, api_url: &str, address: &str) -> Result<Statistics, String> {
-    let url = format!("{}/statistics/{}", api_url, address);
-    println!("\n📊 Fetching statistics for address: {}", address);
+pub async fn fetch_statistics(client: &Client, api_url: &str, address: &str) -> Result<Statistics, ApiError> {
+    info!("📊 Fetching statistics for address: {}", address);
+    fetch_statistics_silent(client, api_url, address).await
+}
+
+/// Same as `fetch_statistics`, without the console output (used by concurrent sweeps).
+pub async fn fetch_statistics_silent(client: &Client, api_url: &str, address: &str) -> Result<Statistics, ApiError> {
+    let url = endpoint_url(api_url, &format!("statistics/{}", address));
 
+    throttle().await;
     let response = client.get(url)
         .header("Accept", "application/json")
         .send()
-        .map_err(|e| format!("Network/Client Error: {}", e))?;
+        .await
+        .map_err(ApiError::network)?;
 
     let status = response.status();
 
     if status.is_success() {
-        let api_data: StatisticsApiResponse = response.json().map_err(|e| format!("JSON parsing failed: {}", e))?;
+        let api_data: StatisticsApiResponse = response.json().await
+            .map_err(|e| ApiError::InvalidResponse(format!("statistics JSON: {}", e)))?;
 
         // Transform nested API response into the desired flat Statistics struct
         Ok(Statistics {
@@ -337,17 +633,8 @@ pub fn fetch_statistics(client: &blocking::Client, api_url: &str, address: &str)
             night_allocation: api_data.local.night_allocation,
         })
     } else {
-        let body_text = response.text().unwrap_or_else(|_| format!("(Could not read response body for status {})", status));
-        let api_error: Result<ApiErrorResponse, _> = serde_json::from_str(&body_text);
-
-        match api_error {
-            Ok(err) => {
-                // FIX: Use all error fields for detailed reporting
-                Err(format!("API Error: {}", format_detailed_api_error(err, status)))
-            }
-            Err(_) => {
-                Err(format!("HTTP Error {} with unparseable body: {}", status.as_u16(), body_text))
-            }
-        }
+        let body_text = response.text().await.unwrap_or_else(|_| format!("(Could not read response body for status {})", status));
+        debug!(status = %status, body = %body_text, "statistics error response");
+        Err(ApiError::from_response(status, &body_text))
     }
 }
@@ -0,0 +1,72 @@
+// src/api_explorer.rs
+
+use crate::api;
+use crate::api_schema;
+use crate::cli::{ApiCommands, Cli};
+use crate::exit_codes::{self, ExitError, WithExitCode};
+use crate::utils;
+
+// ===============================================
+// READ-ONLY API EXPLORER
+// ===============================================
+
+/// Resolves the API URL the same way mining does: `--api-url`, or the local mock server.
+fn explorer_api_url(cli: &Cli) -> Result<String, String> {
+    match (cli.api_url.as_ref(), cli.mock_api_port) {
+        (Some(url), _) => Ok(url.clone()),
+        (None, Some(port)) => Ok(format!("http://127.0.0.1:{}/api", port)),
+        (None, None) => Err("The '--api-url' flag must be specified to query the Scavenger Mine API.".to_string()),
+    }
+}
+
+/// Runs one `api` subcommand. Nothing is read from or written to the local database.
+pub fn run_api_command(cli: &Cli, command: &ApiCommands) -> Result<(), ExitError> {
+    let api_url = explorer_api_url(cli).exit_code(exit_codes::USAGE_ERROR)?;
+    let client = utils::create_api_client().map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let (path, json) = match command {
+        ApiCommands::Challenge { json } => ("challenge".to_string(), *json),
+        ApiCommands::Stats { address, json } => (format!("statistics/{}", address), *json),
+        ApiCommands::Tandc { version, json } => (format!("TandC/{}", version), *json),
+        ApiCommands::Schema { json } => return run_schema_check(&client, &api_url, *json).exit_code(exit_codes::NETWORK_ERROR),
+    };
+
+    let (status, body) = api::block_on(api::get_raw(&client, &api_url, &path)).exit_code(exit_codes::NETWORK_ERROR)?;
+
+    if json {
+        cprintln!("{}", body);
+    } else {
+        cprintln!("\n==============================================");
+        cprintln!("🔎 GET {}", api::endpoint_url(&api_url, &path));
+        cprintln!("==============================================");
+        cprintln!("HTTP Status: {}", status);
+        cprintln!("----------------------------------------------");
+        match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(value) => cprintln!("{}", serde_json::to_string_pretty(&value).unwrap_or(body)),
+            Err(_) => cprintln!("{}", body),
+        }
+        cprintln!("==============================================");
+    }
+
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(ExitError::new(exit_codes::NETWORK_ERROR, format!("API returned HTTP {} for /{}", status, path)))
+    }
+}
+
+/// `api schema`: runs the startup schema probe on its own. Fails when the API has drifted.
+fn run_schema_check(client: &reqwest::Client, api_url: &str, json: bool) -> Result<(), String> {
+    let tandc = api::block_on(api::fetch_tandc(client, api_url))?;
+    let report = api_schema::probe(client, api_url, &tandc.version)?;
+    if json {
+        cprintln!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+    } else {
+        api_schema::print_report(&report);
+    }
+    if report.is_compatible() {
+        Ok(())
+    } else {
+        Err("API schema does not match this build.".to_string())
+    }
+}
@@ -0,0 +1,172 @@
+// src/api_schema.rs
+
+use crate::api;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use shadow_harvester_lib::{Deadline, DifficultyMask, RomKey};
+use std::sync::OnceLock;
+
+// ===============================================
+// API SCHEMA PROBE
+// ===============================================
+//
+// Serde ignores fields it does not know and fails on missing ones only when they are
+// required, so a changed challenge response can parse "fine" and still produce preimages the
+// server rejects as "does not meet difficulty". At startup the raw GET /challenge body is
+// compared against the fields this build reads, and any drift is reported loudly.
+
+/// Top-level fields of GET /challenge known to this build.
+const KNOWN_RESPONSE_FIELDS: &[&str] = &[
+    "code", "challenge", "starts_at", "mining_period_ends", "max_day", "total_challenges",
+    "current_day", "next_challenge_starts_at",
+];
+
+/// Fields of the `challenge` object this build reads, and which of them mining needs.
+const KNOWN_CHALLENGE_FIELDS: &[&str] = &[
+    "challenge_id", "difficulty", "no_pre_mine", "no_pre_mine_hour", "latest_submission",
+    "challenge_number", "day", "issued_at", "rom_params",
+];
+const REQUIRED_CHALLENGE_FIELDS: &[&str] = &[
+    "challenge_id", "difficulty", "no_pre_mine", "no_pre_mine_hour", "latest_submission",
+    "challenge_number", "day", "issued_at",
+];
+
+/// Fields a server may use to report its version; never counted as unknown.
+const VERSION_FIELDS: &[&str] = &["version", "api_version", "schema_version"];
+
+/// What the startup probe saw.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchemaReport {
+    /// Version the API reports in the challenge response, if any.
+    pub api_version: Option<String>,
+    /// Version of the T&C the API returned, for the record.
+    pub tandc_version: Option<String>,
+    /// Fields present in the response but unknown to this build (e.g. `challenge.salt`).
+    pub unknown_fields: Vec<String>,
+    /// Fields this build requires that the response lacks.
+    pub missing_fields: Vec<String>,
+    /// Known fields whose value this build cannot mine with.
+    pub invalid_values: Vec<String>,
+}
+
+impl SchemaReport {
+    /// True when the response matches what this build was written against.
+    pub fn is_compatible(&self) -> bool {
+        self.unknown_fields.is_empty()
+            && self.missing_fields.is_empty()
+            && self.invalid_values.is_empty()
+    }
+}
+
+// Process-wide: the report of the startup probe, for `status`.
+static LAST_REPORT: OnceLock<SchemaReport> = OnceLock::new();
+
+/// The report of the startup probe, if it ran.
+pub fn last_report() -> Option<&'static SchemaReport> {
+    LAST_REPORT.get()
+}
+
+fn version_of(object: &Map<String, Value>) -> Option<String> {
+    VERSION_FIELDS.iter().find_map(|field| match object.get(*field)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// Compares a GET /challenge body against the fields this build knows.
+pub fn inspect_challenge_response(body: &Value) -> SchemaReport {
+    let mut report = SchemaReport::default();
+    let Some(object) = body.as_object() else {
+        report.invalid_values.push("response is not a JSON object".to_string());
+        return report;
+    };
+    report.api_version = version_of(object);
+
+    let is_known = |known: &[&str], field: &str| known.contains(&field) || VERSION_FIELDS.contains(&field);
+    for field in object.keys().filter(|field| !is_known(KNOWN_RESPONSE_FIELDS, field)) {
+        report.unknown_fields.push(field.clone());
+    }
+    if !object.contains_key("code") {
+        report.missing_fields.push("code".to_string());
+    }
+
+    // Only an active challenge carries the challenge object.
+    let Some(challenge) = object.get("challenge").and_then(Value::as_object) else {
+        return report;
+    };
+    if report.api_version.is_none() {
+        report.api_version = version_of(challenge);
+    }
+    for field in challenge.keys().filter(|field| !is_known(KNOWN_CHALLENGE_FIELDS, field)) {
+        report.unknown_fields.push(format!("challenge.{}", field));
+    }
+    for field in REQUIRED_CHALLENGE_FIELDS.iter().filter(|field| !challenge.contains_key(**field)) {
+        report.missing_fields.push(format!("challenge.{}", field));
+    }
+
+    // The values that go into the preimage must parse the way the miner parses them.
+    let mut check = |field: &str, parse: fn(&str) -> Result<(), String>| match challenge.get(field) {
+        None => {} // Reported as missing above.
+        Some(Value::String(s)) => {
+            if let Err(e) = parse(s) {
+                report.invalid_values.push(format!("challenge.{}: {}", field, e));
+            }
+        }
+        Some(_) => report.invalid_values.push(format!("challenge.{}: expected a string", field)),
+    };
+    check("challenge_id", |_| Ok(()));
+    check("difficulty", |s| s.parse::<DifficultyMask>().map(|_| ()));
+    check("no_pre_mine", |s| s.parse::<RomKey>().map(|_| ()));
+    check("no_pre_mine_hour", |_| Ok(()));
+    check("latest_submission", |s| s.parse::<Deadline>().map(|_| ()));
+
+    report
+}
+
+/// Fetches GET /challenge, inspects it and records the report.
+pub fn probe(client: &Client, api_url: &str, tandc_version: &str) -> Result<SchemaReport, String> {
+    let (status, body) = api::block_on(api::get_raw(client, api_url, "challenge"))?;
+    if !status.is_success() {
+        return Err(format!("API returned HTTP {} for /challenge", status));
+    }
+    let value: Value = serde_json::from_str(&body).map_err(|e| format!("challenge response is not JSON: {}", e))?;
+    let mut report = inspect_challenge_response(&value);
+    report.tandc_version = Some(tandc_version.to_string());
+    let _ = LAST_REPORT.set(report.clone());
+    Ok(report)
+}
+
+/// Prints the report: one line when compatible, a warning block listing every difference
+/// otherwise. Also emitted as an `api_schema` event.
+pub fn print_report(report: &SchemaReport) {
+    crate::console::event("api_schema", serde_json::to_value(report).unwrap_or_default());
+    let version = report.api_version.as_deref().unwrap_or("not reported");
+    if report.is_compatible() {
+        cprintln!("🧩 API schema matches this build (API version: {}).", version);
+        return;
+    }
+
+    let mut out = String::new();
+    out.push_str("\n==============================================\n");
+    out.push_str("🚨 API SCHEMA MISMATCH\n");
+    out.push_str("==============================================\n");
+    out.push_str(&format!(
+        "This build (v{}) was written against an older or different API schema (API version: {}).\n",
+        env!("CARGO_PKG_VERSION"), version
+    ));
+    for field in &report.unknown_fields {
+        out.push_str(&format!("  + unknown field: {}\n", field));
+    }
+    for field in &report.missing_fields {
+        out.push_str(&format!("  - missing field: {}\n", field));
+    }
+    for problem in &report.invalid_values {
+        out.push_str(&format!("  ! unusable value: {}\n", problem));
+    }
+    out.push_str("Solutions may be built from misread parameters and rejected as \"does not meet difficulty\".\n");
+    out.push_str("Upgrade shadow-harvester before mining, or check the API URL.\n");
+    out.push_str("==============================================");
+    ceprintln!("{}", out);
+}
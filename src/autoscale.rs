@@ -0,0 +1,180 @@
+// src/autoscale.rs
+
+use crate::data_types::ChallengeData;
+use chrono::{DateTime, Utc};
+
+// ===============================================
+// DEADLINE-DRIVEN THREAD SCALING
+// ===============================================
+//
+// With --max-threads above --threads, the miner spawns --max-threads workers but lets only
+// --threads of them hash (`shadow_harvester_lib::set_active_workers`). Once less than
+// --scale-up-at percent of the submission window is left and the challenge has no solution
+// yet, the manager lets every worker hash, borrowing the cores normally left to other work.
+// It scales back down when a solution is found or the challenge ends.
+//
+// Before that point the manager rebalances every few minutes: it lends the challenge a share
+// of the spare workers (--max-threads minus --threads) equal to the chance that --threads alone
+// misses a solution before the deadline. A near deadline or a hard difficulty lowers that
+// chance and draws more workers; a challenge already solved gives them all back.
+
+/// Extra share of the window (in percent points) that must open up again before a scaled-up
+/// miner drops back to the base thread count, so a deadline moved by a re-issued challenge
+/// or clock jitter doesn't flip the thread count on every check.
+const HYSTERESIS_PERCENT: f64 = 5.0;
+
+/// Scaling state of the challenge being mined.
+pub struct ThreadScaler {
+    base: u32,
+    max: u32,
+    /// --max-threads, if given; without it scaling stays off whatever the base becomes.
+    configured_max: Option<u32>,
+    scale_up_at: f64,
+    scaled_up: bool,
+    /// Spare workers lent by the last rebalance while not scaled up.
+    lent: u32,
+}
+
+impl ThreadScaler {
+    /// `max` below or equal to `base` disables scaling.
+    pub fn new(base: u32, max: Option<u32>, scale_up_at: u8) -> Self {
+        let base = base.max(1);
+        ThreadScaler {
+            base,
+            max: max.unwrap_or(base).max(base),
+            configured_max: max,
+            scale_up_at: scale_up_at as f64,
+            scaled_up: false,
+            lent: 0,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.max > self.base
+    }
+
+    /// Number of worker threads to spawn per cycle: every thread that may ever hash.
+    pub fn spawn_threads(&self) -> u32 {
+        self.max
+    }
+
+    /// Number of workers currently allowed to hash.
+    pub fn active_threads(&self) -> u32 {
+        if self.scaled_up { self.max } else { self.base + self.lent }
+    }
+
+    /// Base thread count, i.e. --threads or its replacement from the control interface.
+    pub fn base_threads(&self) -> u32 {
+        self.base
+    }
+
+    /// Changes the base thread count (e.g. from the control interface). Returns the new
+    /// active thread count.
+    pub fn set_base(&mut self, base: u32) -> u32 {
+        self.base = base.max(1);
+        self.max = self.configured_max.unwrap_or(self.base).max(self.base);
+        self.lent = self.lent.min(self.max - self.base);
+        self.active_threads()
+    }
+
+    /// Decides on the share of the submission window still left (in percent) and whether
+    /// the challenge already has a solution. Returns the new active thread count when it
+    /// changes.
+    pub fn evaluate(&mut self, remaining_percent: f64, solved: bool) -> Option<u32> {
+        if !self.enabled() {
+            return None;
+        }
+        let scale_up = !solved && remaining_percent < self.scale_up_at;
+        let scale_down = solved || remaining_percent > self.scale_up_at + HYSTERESIS_PERCENT;
+        if !self.scaled_up && scale_up {
+            self.scaled_up = true;
+            Some(self.max)
+        } else if self.scaled_up && scale_down {
+            self.scaled_up = false;
+            self.lent = 0;
+            Some(self.base)
+        } else {
+            None
+        }
+    }
+
+    /// Lends spare workers by `base_chance`, the chance of a solution before the deadline on
+    /// the base thread count alone: `1 - base_chance` of them, none once solved. Leaves a
+    /// deadline scale-up alone. Returns the new active thread count when it changes.
+    pub fn rebalance(&mut self, base_chance: f64, solved: bool) -> Option<u32> {
+        if !self.enabled() || self.scaled_up {
+            return None;
+        }
+        let spare = self.max - self.base;
+        let lent = if solved { 0 } else { (spare as f64 * (1.0 - base_chance.clamp(0.0, 1.0))).round() as u32 };
+        if lent == self.lent {
+            return None;
+        }
+        self.lent = lent;
+        Some(self.active_threads())
+    }
+
+    /// Drops back to the base thread count when the challenge ends. Returns it when this
+    /// changed the active thread count.
+    pub fn reset(&mut self) -> Option<u32> {
+        let changed = std::mem::take(&mut self.scaled_up) | (std::mem::take(&mut self.lent) > 0);
+        changed.then_some(self.base)
+    }
+}
+
+/// Share of `challenge`'s submission window (issued_at to latest_submission) still left at
+/// `now`, in percent. None when the deadline is unparseable; a missing issued_at counts as a
+/// 24 hour window.
+pub fn remaining_percent(challenge: &ChallengeData, now: DateTime<Utc>) -> Option<f64> {
+    let deadline = DateTime::parse_from_rfc3339(&challenge.latest_submission).ok()?.with_timezone(&Utc);
+    let start = DateTime::parse_from_rfc3339(&challenge.issued_at)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(deadline - chrono::Duration::hours(24));
+    let window = (deadline - start).num_seconds();
+    if window <= 0 {
+        return Some(0.0);
+    }
+    let left = (deadline - now).num_seconds().clamp(0, window);
+    Some(left as f64 * 100.0 / window as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebalance_lends_the_missed_chance_of_spare_workers() {
+        let mut scaler = ThreadScaler::new(4, Some(8), 25);
+        assert_eq!(scaler.rebalance(1.0, false), None);
+        assert_eq!(scaler.rebalance(0.5, false), Some(6));
+        assert_eq!(scaler.rebalance(0.5, false), None);
+        assert_eq!(scaler.rebalance(0.0, false), Some(8));
+        assert_eq!(scaler.rebalance(0.0, true), Some(4));
+    }
+
+    #[test]
+    fn rebalance_leaves_a_deadline_scale_up_alone() {
+        let mut scaler = ThreadScaler::new(4, Some(8), 25);
+        assert_eq!(scaler.rebalance(0.75, false), Some(5));
+        assert_eq!(scaler.evaluate(10.0, false), Some(8));
+        assert_eq!(scaler.rebalance(0.75, false), None);
+        assert_eq!(scaler.evaluate(10.0, true), Some(4));
+        assert_eq!(scaler.active_threads(), 4);
+    }
+
+    #[test]
+    fn reset_returns_lent_workers() {
+        let mut scaler = ThreadScaler::new(4, Some(8), 25);
+        assert_eq!(scaler.reset(), None);
+        scaler.rebalance(0.0, false);
+        assert_eq!(scaler.reset(), Some(4));
+        assert_eq!(scaler.active_threads(), 4);
+    }
+
+    #[test]
+    fn rebalance_is_off_without_max_threads() {
+        let mut scaler = ThreadScaler::new(4, None, 25);
+        assert_eq!(scaler.rebalance(0.0, false), None);
+        assert_eq!(scaler.active_threads(), 4);
+    }
+}
@@ -6,6 +6,10 @@ pub struct Backoff {
     pub min: f64,
     pub max: f64,
     pub factor: f64,
+    /// Delays handed out since the last reset.
+    pub retries: u32,
+    /// None retries forever.
+    pub max_retries: Option<u32>,
 }
 
 impl Backoff {
@@ -15,17 +19,40 @@ impl Backoff {
             min: min as f64,
             max: max as f64,
             factor,
+            retries: 0,
+            max_retries: None,
         }
     }
 
+    /// Doubling backoff from `min` seconds, capped by --retry-max-backoff and limited to
+    /// --max-retries retries.
+    pub fn with_retry_policy(min: u64) -> Self {
+        let max = crate::api::retry_max_backoff_secs();
+        Self { max_retries: Some(crate::api::max_retries()), ..Self::new(min.min(max), max, 2.0) }
+    }
+
+    /// True once `max_retries` delays have been handed out.
+    pub fn exhausted(&self) -> bool {
+        self.max_retries.is_some_and(|max| self.retries >= max)
+    }
+
     pub fn sleep(&mut self) {
-        let secs = self.cur.min(self.max);
-        println!("sleep {secs:.0}s");
-        thread::sleep(Duration::from_secs_f64(secs));
+        let delay = self.next_delay();
+        cprintln!("sleep {:.0}s", delay.as_secs_f64());
+        thread::sleep(delay);
+    }
+
+    /// Returns the current delay and advances to the next one, for callers that wait on their
+    /// own (e.g. async tasks).
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = Duration::from_secs_f64(self.cur.min(self.max));
         self.cur = (self.cur * self.factor).min(self.max);
+        self.retries = self.retries.saturating_add(1);
+        delay
     }
 
     pub fn reset(&mut self) {
         self.cur = self.min;
+        self.retries = 0;
     }
 }
@@ -0,0 +1,309 @@
+// src/bench.rs
+
+use crate::utils;
+use serde::Deserialize;
+use shadow_harvester_lib::{spin, ChallengeParams, CpuHasher, NonceAllocator, Result as MinerResult, Rom, RomParams};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===============================================
+// OFFLINE HASHRATE BENCHMARK
+// ===============================================
+
+/// Address used in the benchmark preimage. It is never submitted anywhere.
+const BENCH_ADDRESS: &str = "addr1benchmark";
+/// A zero mask requires 32 leading zero bits, so the benchmark practically never "finds" anything.
+const BENCH_DIFFICULTY_MASK: u32 = 0x0000_0000;
+
+/// Generates a throwaway ROM and runs the full hash pipeline on `threads` workers for
+/// `seconds`, then prints ROM generation time, per-thread and aggregate hash rates.
+/// Makes no API calls and touches no local state.
+pub fn run_bench(seconds: u64, threads: u32, rom_params: RomParams) -> Result<(), String> {
+    let threads = threads.max(1);
+
+    cprintln!("\n==============================================");
+    cprintln!("⏱️  Shadow Harvester Benchmark");
+    cprintln!("==============================================");
+    cprintln!("Threads: {}", threads);
+    cprintln!("Duration: {}s", seconds);
+    let (params, rom_secs) = bench_params(&rom_params);
+
+    cprintln!("Hashing...");
+    let config = BenchConfig { name: None, threads, affinity: Vec::new(), backend: HashBackend::Cpu };
+    let trial = run_trial(&params, &config, Duration::from_secs(seconds))?;
+
+    let total_hashes: u64 = trial.thread_hashes.iter().sum();
+    cprintln!("\n--- Results ---");
+    for (thread_id, hashes) in trial.thread_hashes.iter().enumerate() {
+        cprintln!("  Thread {:>3}: {:>12.2} H/s", thread_id, *hashes as f64 / trial.elapsed);
+    }
+    cprintln!("----------------------------------------------");
+    cprintln!("ROM Generation: {}", utils::format_duration(rom_secs));
+    cprintln!("Total Hashes: {}", total_hashes);
+    cprintln!("Elapsed: {}", utils::format_duration(trial.elapsed));
+    cprintln!("Aggregate Hash Rate: {:.2} H/s", trial.hashrate());
+    cprintln!("==============================================");
+
+    Ok(())
+}
+
+/// Generates the throwaway ROM and the challenge parameters every trial hashes against.
+/// Returns them with the ROM generation time in seconds.
+fn bench_params(rom_params: &RomParams) -> (ChallengeParams, f64) {
+    let rom_key = format!("shadow-harvester-bench-{}", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    cprintln!("Generating throwaway ROM ({} MiB)...", rom_params.size / (1024 * 1024));
+
+    let rom_start = Instant::now();
+    let rom = Rom::from_params(rom_key.as_bytes(), rom_params);
+    let rom_secs = rom_start.elapsed().as_secs_f64();
+    cprintln!("ROM generated in {} ({}).", utils::format_duration(rom_secs), rom.pages());
+
+    let params = ChallengeParams {
+        rom_key,
+        difficulty_mask: BENCH_DIFFICULTY_MASK,
+        address: BENCH_ADDRESS.to_string(),
+        challenge_id: "**BENCH".to_string(),
+        latest_submission: (chrono::Utc::now() + chrono::Duration::days(1)).to_rfc3339(),
+        no_pre_mine_hour: "0".to_string(),
+        hasher: Arc::new(CpuHasher::new(Arc::new(rom))),
+    };
+    (params, rom_secs)
+}
+
+/// Hash counts of one timed run.
+struct Trial {
+    thread_hashes: Vec<u64>,
+    elapsed: f64,
+}
+
+impl Trial {
+    fn hashrate(&self) -> f64 {
+        self.thread_hashes.iter().sum::<u64>() as f64 / self.elapsed
+    }
+}
+
+/// Hashes with `config.threads` workers for `duration` and returns each worker's hash count.
+fn run_trial(params: &ChallengeParams, config: &BenchConfig, duration: Duration) -> Result<Trial, String> {
+    let threads = config.threads.max(1) as usize;
+    let (sender, receiver) = channel();
+    let stop_signal = Arc::new(AtomicBool::new(false));
+    let mut handles = Vec::with_capacity(threads);
+    let nonces = Arc::new(NonceAllocator::new(0));
+    for worker in 0..threads {
+        let params = params.clone();
+        let sender = sender.clone();
+        let stop_signal = stop_signal.clone();
+        let nonces = nonces.clone();
+        let core = (!config.affinity.is_empty()).then(|| config.affinity[worker % config.affinity.len()]);
+        handles.push(thread::spawn(move || {
+            if let Some(core) = core
+                && let Err(e) = pin_current_thread(core) {
+                ceprintln!("⚠️ Worker {} could not be pinned to core {}: {}", worker, core, e);
+            }
+            spin(params, sender, stop_signal, nonces, worker)
+        }));
+    }
+    drop(sender);
+
+    let start = Instant::now();
+    let mut thread_hashes = vec![0u64; threads];
+
+    loop {
+        let remaining = duration.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            stop_signal.store(true, Ordering::Relaxed);
+        }
+
+        match receiver.recv_timeout(remaining.max(Duration::from_millis(100))) {
+            // Each worker's final checkpoint carries its exact hash count.
+            Ok(MinerResult::Checkpoint { worker, hashes, .. }) => {
+                thread_hashes[worker] = hashes;
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    for handle in handles {
+        if handle.join().is_err() {
+            return Err("A benchmark worker thread panicked.".to_string());
+        }
+    }
+    Ok(Trial { thread_hashes, elapsed })
+}
+
+/// Pins the calling thread to CPU `core`.
+#[cfg(target_os = "linux")]
+fn pin_current_thread(core: usize) -> Result<(), String> {
+    // SAFETY: cpu_set_t is a plain bitmask; it is zeroed, filled with CPU_SET and passed by
+    // reference with its exact size. pid 0 means the calling thread.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_core: usize) -> Result<(), String> {
+    Err("CPU affinity is only supported on Linux".to_string())
+}
+
+// ===============================================
+// A/B COMPARISON (bench compare)
+// ===============================================
+//
+// Two configurations hash the same ROM in alternating timed trials (A, B, A, B, ...), so
+// thermal throttling and background load affect both alike. The per-trial hash rates are
+// compared with Welch's t-test.
+
+/// Hash implementation used by a benchmark configuration.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashBackend {
+    /// The VM hasher on the CPU (`CpuHasher`), the only backend this build ships.
+    #[default]
+    Cpu,
+}
+
+/// One side of `bench compare`, loaded from a TOML file:
+///
+/// ```toml
+/// name = "8 threads, pinned"
+/// threads = 8
+/// affinity = [0, 1, 2, 3, 4, 5, 6, 7]
+/// backend = "cpu"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchConfig {
+    /// Label in the report; defaults to the file name.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub threads: u32,
+    /// CPU cores the workers are pinned to, round-robin. Empty leaves scheduling to the OS.
+    #[serde(default)]
+    pub affinity: Vec<usize>,
+    #[serde(default)]
+    pub backend: HashBackend,
+}
+
+impl BenchConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read benchmark config {}: {}", path, e))?;
+        let mut config: BenchConfig = toml::from_str(&text).map_err(|e| format!("Invalid benchmark config {}: {}", path, e))?;
+        if config.threads == 0 {
+            return Err(format!("Benchmark config {}: 'threads' must be at least 1.", path));
+        }
+        let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(usize::MAX);
+        if let Some(core) = config.affinity.iter().find(|&&core| core >= cores) {
+            return Err(format!("Benchmark config {}: core {} in 'affinity' does not exist ({} cores available).", path, core, cores));
+        }
+        config.name.get_or_insert_with(|| path.to_string());
+        Ok(config)
+    }
+
+    fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("?")
+    }
+
+    fn describe(&self) -> String {
+        let affinity = if self.affinity.is_empty() {
+            "none".to_string()
+        } else {
+            self.affinity.iter().map(|core| core.to_string()).collect::<Vec<_>>().join(",")
+        };
+        format!("threads {}, affinity {}, backend {:?}", self.threads, affinity, self.backend).to_lowercase()
+    }
+}
+
+/// Mean and sample standard deviation.
+fn mean_stddev(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+/// Two-sided 95% critical value of Student's t distribution for `df` degrees of freedom.
+fn t_critical_95(df: f64) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+        2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+        2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    match df.floor() as usize {
+        0 => f64::INFINITY,
+        df if df <= TABLE.len() => TABLE[df - 1],
+        df if df <= 60 => 2.000,
+        df if df <= 120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// Runs `trials` alternating trials of each configuration, `duration` in total per
+/// configuration, on one throwaway ROM and prints the comparison.
+pub fn run_compare(config_a: BenchConfig, config_b: BenchConfig, duration: u64, trials: u32, rom_params: RomParams) -> Result<(), String> {
+    if trials < 2 {
+        return Err("At least 2 trials per configuration are needed for a comparison.".to_string());
+    }
+    let trial_duration = Duration::from_secs_f64(duration as f64 / trials as f64);
+    if trial_duration < Duration::from_secs(1) {
+        return Err(format!("A {}s duration split into {} trials leaves less than a second per trial.", duration, trials));
+    }
+
+    cprintln!("\n==============================================");
+    cprintln!("⚖️  Shadow Harvester Benchmark Comparison");
+    cprintln!("==============================================");
+    cprintln!("A: {} ({})", config_a.label(), config_a.describe());
+    cprintln!("B: {} ({})", config_b.label(), config_b.describe());
+    cprintln!("Trials: {} per configuration, {:.1}s each ({}s total per configuration)", trials, trial_duration.as_secs_f64(), duration);
+    let (params, _) = bench_params(&rom_params);
+
+    let mut rates: [Vec<f64>; 2] = [Vec::new(), Vec::new()];
+    for round in 1..=trials {
+        for (side, (label, config)) in [("A", &config_a), ("B", &config_b)].into_iter().enumerate() {
+            let trial = run_trial(&params, config, trial_duration)?;
+            cprintln!("  Trial {:>2}/{} {}: {:>12.2} H/s", round, trials, label, trial.hashrate());
+            rates[side].push(trial.hashrate());
+        }
+    }
+
+    let (mean_a, sd_a) = mean_stddev(&rates[0]);
+    let (mean_b, sd_b) = mean_stddev(&rates[1]);
+    let n = trials as f64;
+    let (var_a, var_b) = (sd_a.powi(2) / n, sd_b.powi(2) / n);
+    let std_error = (var_a + var_b).sqrt();
+    let diff = mean_b - mean_a;
+    // Welch-Satterthwaite degrees of freedom.
+    let df = if std_error > 0.0 { (var_a + var_b).powi(2) / (var_a.powi(2) / (n - 1.0) + var_b.powi(2) / (n - 1.0)) } else { f64::INFINITY };
+    let margin = t_critical_95(df) * std_error;
+
+    cprintln!("\n--- Results ---");
+    cprintln!("  A: {:>12.2} H/s ± {:.2} (stddev, {})", mean_a, sd_a, config_a.label());
+    cprintln!("  B: {:>12.2} H/s ± {:.2} (stddev, {})", mean_b, sd_b, config_b.label());
+    cprintln!("----------------------------------------------");
+    cprintln!("B - A: {:+.2} H/s ({:+.2}%)", diff, diff * 100.0 / mean_a);
+    cprintln!("95% confidence interval: [{:+.2}, {:+.2}] H/s", diff - margin, diff + margin);
+    if std_error > 0.0 {
+        cprintln!("Welch's t: {:.2} (df {:.1})", diff / std_error, df);
+    }
+    if diff.abs() > margin {
+        cprintln!("Verdict: {} is faster (significant at 95%).", if diff > 0.0 { "B" } else { "A" });
+    } else {
+        cprintln!("Verdict: no significant difference at 95%. Run longer or more trials to detect smaller effects.");
+    }
+    cprintln!("==============================================");
+    Ok(())
+}
@@ -13,10 +13,29 @@ use minicbor::*;
 use rand_core::{OsRng};
 use bip39::Mnemonic;
 use ed25519_bip32::{self, XPrv, XPRV_SIZE};
+use crate::ledger::{self, LedgerKey};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Process-wide: network id of derived addresses (1 = mainnet, 0 = the test networks).
+static NETWORK_ID: AtomicU8 = AtomicU8::new(1);
+
+/// Sets the network every address is derived for from now on. Keys are the same on every
+/// network; the network id goes into the address header and picks the bech32 prefix
+/// (`addr` or `addr_test`).
+pub fn set_network_id(id: u8) {
+    NETWORK_ID.store(id, Ordering::Relaxed);
+}
+
+/// The network addresses are derived for.
+pub fn network() -> Network {
+    Network::from(NETWORK_ID.load(Ordering::Relaxed))
+}
 
 pub enum FlexibleSecretKey {
     Standard(SecretKey),
     Extended(SecretKeyExtended),
+    /// The key stays on a Ledger; messages are signed on the device.
+    Ledger(LedgerKey),
 }
 
 // 2. Define the new type alias using the enum
@@ -30,7 +49,7 @@ pub fn generate_cardano_key_and_address() -> KeyPairAndAddress {
     let vk = sk.public_key();
 
     let addr = ShelleyAddress::new(
-        Network::Mainnet,
+        network(),
         ShelleyPaymentPart::key_hash(vk.compute_hash()),
         ShelleyDelegationPart::Null
     );
@@ -39,19 +58,33 @@ pub fn generate_cardano_key_and_address() -> KeyPairAndAddress {
     (sk_flex, vk, addr)
 }
 
+/// Wallet identifier hash of a mnemonic, as used in `mnemonic_index:<HASH>:...` keys. A
+/// passphrase derives a different wallet, so it is part of the hash; without one the hash is
+/// that of the phrase alone.
+pub fn mnemonic_hash(mnemonic: &str, passphrase: Option<&str>) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    mnemonic.hash(&mut hasher);
+    if let Some(passphrase) = passphrase.filter(|passphrase| !passphrase.is_empty()) {
+        passphrase.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
 pub fn harden_index(index: u32) -> u32 {
     // The constant 0x80000000 is 2^31, which sets the most significant bit.
     index | 0x80000000
 }
 
-pub fn derive_key_pair_from_mnemonic(mnemonic: &str, account: u32, index: u32) -> KeyPairAndAddress {
+/// `passphrase` is the optional BIP-39 passphrase ("25th word"); None and "" derive the same keys.
+pub fn derive_key_pair_from_mnemonic(mnemonic: &str, passphrase: Option<&str>, account: u32, index: u32) -> KeyPairAndAddress {
     // NOTE: This is a simplified, non-compliant derivation for demonstration purposes.
     // A real Cardano application MUST use BIP39/BIP44-compliant HD derivation.
     let bip39 = Mnemonic::parse(mnemonic).expect("Need a valid mnemonic");
     let entropy = bip39.clone().to_entropy();
     let mut pbkdf2_result = [0; XPRV_SIZE];
     const ITER: u32 = 4096;
-    let mut mac = Hmac::new(Sha512::new(), "".as_bytes());
+    let mut mac = Hmac::new(Sha512::new(), passphrase.unwrap_or("").as_bytes());
     pbkdf2(&mut mac, &entropy, ITER, &mut pbkdf2_result);
     let xprv = XPrv::normalize_bytes_force3rd(pbkdf2_result);
 
@@ -69,7 +102,7 @@ pub fn derive_key_pair_from_mnemonic(mnemonic: &str, account: u32, index: u32) -
 
         // Cardano (Shelley) address derivation
         let addr = ShelleyAddress::new(
-            Network::Mainnet, // Assuming Mainnet environment
+            network(),
             ShelleyPaymentPart::key_hash(vk.compute_hash()),
             ShelleyDelegationPart::Null
         );
@@ -80,14 +113,16 @@ pub fn derive_key_pair_from_mnemonic(mnemonic: &str, account: u32, index: u32) -
 
 }
 
-pub fn derive_key_pair_from_mnemonic_base(mnemonic: &str, account: u32, index: u32) -> KeyPairAndAddress {
+/// The base address of payment key 1852'/1815'/<account>'/0/<index> and stake key
+/// 1852'/1815'/<account>'/2/<stake_index>. Wallets use stake index 0 for every address.
+pub fn derive_key_pair_from_mnemonic_base(mnemonic: &str, passphrase: Option<&str>, account: u32, index: u32, stake_index: u32) -> KeyPairAndAddress {
     // NOTE: This is a simplified, non-compliant derivation for demonstration purposes.
     // A real Cardano application MUST use BIP39/BIP44-compliant HD derivation.
     let bip39 = Mnemonic::parse(mnemonic).expect("Need a valid mnemonic");
     let entropy = bip39.clone().to_entropy();
     let mut pbkdf2_result = [0; XPRV_SIZE];
     const ITER: u32 = 4096;
-    let mut mac = Hmac::new(Sha512::new(), "".as_bytes());
+    let mut mac = Hmac::new(Sha512::new(), passphrase.unwrap_or("").as_bytes());
     pbkdf2(&mut mac, &entropy, ITER, &mut pbkdf2_result);
     let xprv = XPrv::normalize_bytes_force3rd(pbkdf2_result);
 
@@ -99,13 +134,13 @@ pub fn derive_key_pair_from_mnemonic_base(mnemonic: &str, account: u32, index: u
         .derive(ed25519_bip32::DerivationScheme::V2, 0)
         .derive(ed25519_bip32::DerivationScheme::V2, index)
         .extended_secret_key();
-    // stake key 1852'/1815'/<account>'/2/<index>
+    // stake key 1852'/1815'/<account>'/2/<stake_index>
     let stake_xprv = &xprv
         .derive(ed25519_bip32::DerivationScheme::V2, harden_index(1852))
         .derive(ed25519_bip32::DerivationScheme::V2, harden_index(1815))
         .derive(ed25519_bip32::DerivationScheme::V2, harden_index(account))
         .derive(ed25519_bip32::DerivationScheme::V2, 2)
-        .derive(ed25519_bip32::DerivationScheme::V2, index)
+        .derive(ed25519_bip32::DerivationScheme::V2, stake_index)
         .extended_secret_key();
     unsafe {
         let pay_priv = SecretKeyExtended::from_bytes_unchecked(*pay_xprv);
@@ -113,7 +148,7 @@ pub fn derive_key_pair_from_mnemonic_base(mnemonic: &str, account: u32, index: u
         let stake_pub = SecretKeyExtended::from_bytes_unchecked(*stake_xprv).public_key();
 
         let addr = ShelleyAddress::new(
-            Network::Mainnet,
+            network(),
             ShelleyPaymentPart::key_hash(pay_pub.compute_hash()),
             ShelleyDelegationPart::key_hash(stake_pub.compute_hash())
         );
@@ -124,6 +159,27 @@ pub fn derive_key_pair_from_mnemonic_base(mnemonic: &str, account: u32, index: u
 
 }
 
+/// The key pair of payment key 1852'/1815'/<account>'/0/<index> on a Ledger, with its
+/// enterprise address, or with a `stake_index` its base address (stake key .../2/<stake_index>).
+/// Only public keys are read from the device.
+pub fn ledger_key_pair(device: Option<String>, account: u32, index: u32, stake_index: Option<u32>) -> Result<KeyPairAndAddress, String> {
+    let key = LedgerKey::payment(device, account, index, network().value(), stake_index);
+    let vk = PublicKey::from(ledger::public_key(&key.path, key.device.as_deref())?);
+    let delegation = match &key.stake_path {
+        Some(stake_path) => {
+            let stake_vk = PublicKey::from(ledger::public_key(stake_path, key.device.as_deref())?);
+            ShelleyDelegationPart::key_hash(stake_vk.compute_hash())
+        }
+        None => ShelleyDelegationPart::Null,
+    };
+    let addr = ShelleyAddress::new(
+        network(),
+        ShelleyPaymentPart::key_hash(vk.compute_hash()),
+        delegation
+    );
+    Ok((FlexibleSecretKey::Ledger(key), vk, addr))
+}
+
 pub fn generate_cardano_key_pair_from_skey(sk_hex: &String) -> KeyPairAndAddress {
     let skey_bytes = hex::decode(sk_hex).expect("Invalid secret key hex");
     let skey_array: [u8; 32] = skey_bytes
@@ -133,7 +189,7 @@ pub fn generate_cardano_key_pair_from_skey(sk_hex: &String) -> KeyPairAndAddress
     let vk = sk.public_key();
 
     let addr = ShelleyAddress::new(
-        Network::Mainnet,
+        network(),
         ShelleyPaymentPart::key_hash(vk.compute_hash()),
         ShelleyDelegationPart::Null
     );
@@ -218,7 +274,14 @@ where
 /// Creates a placeholder hex string simulating a CIP-8 signed message payload.
 /// NOTE: The actual CIP-8 structure (CBOR headers/map) is not dynamically built here,
 /// but the signature and public key components are guaranteed to be unique.
+/// Panics when signing fails, which only a Ledger key can; use `try_cip8_sign` for those.
 pub fn cip8_sign(kp: &KeyPairAndAddress, message: &str) -> (String, String) {
+    try_cip8_sign(kp, message).expect("Failed to sign the message.")
+}
+
+/// `cip8_sign` for keys whose signing can fail: a Ledger signature is rejected on the device,
+/// or does not verify against the structure built here.
+pub fn try_cip8_sign(kp: &KeyPairAndAddress, message: &str) -> Result<(String, String), String> {
 
     let pubkey = hex::encode(kp.1.as_ref());
     let prot_header = CoseProtHeader {
@@ -232,23 +295,25 @@ pub fn cip8_sign(kp: &KeyPairAndAddress, message: &str) -> (String, String) {
         payload: message.as_bytes(),
     };
     let to_sign_cbor = pallas::codec::minicbor::to_vec(&to_sign).unwrap();
-    // The specific error type the compiler couldn't figure out. Let's use &'static str
-    type SignResult = Result<Signature, &'static str>;
-
-    // Assume kp.0 is &FlexibleSecretKey
-    let signature_result: SignResult = match &kp.0 {
-        FlexibleSecretKey::Standard(sk) => {
-            // Now the compiler knows the full result type is Result<Signature, &'static str>
-            Ok(sk.sign(&to_sign_cbor))
-        }
-        FlexibleSecretKey::Extended(ske) => {
-            // All match arms must return SignResult
-            Ok(ske.sign(&to_sign_cbor))
+
+    let sig: Signature = match &kp.0 {
+        FlexibleSecretKey::Standard(sk) => sk.sign(&to_sign_cbor),
+        FlexibleSecretKey::Extended(ske) => ske.sign(&to_sign_cbor),
+        FlexibleSecretKey::Ledger(key) => {
+            // The device builds the COSE structure itself; only use its signature if it
+            // covers exactly the structure submitted below.
+            let signed = ledger::sign_message(key, message.as_bytes())?;
+            if signed.public_key != <[u8; 32]>::from(kp.1) || signed.address != kp.2.to_vec() {
+                return Err(format!("The Ledger signed with a different key or address than {} ({}).", kp.2.to_bech32().unwrap_or_default(), key.path_string()));
+            }
+            let sig = Signature::from(signed.signature);
+            if !kp.1.verify(&to_sign_cbor, &sig) {
+                return Err("The Ledger's signature does not verify against the CIP-8 structure; update the Cardano app.".to_string());
+            }
+            sig
         }
     };
 
-    let sig = signature_result.expect("Failed to sign the message.");
-
     let cose_struct = CoseSign1 {
         protected_header: &cose_prot_cbor,
         payload: message.as_bytes(),
@@ -256,5 +321,5 @@ pub fn cip8_sign(kp: &KeyPairAndAddress, message: &str) -> (String, String) {
     };
     let cose_sign1_cbor = pallas::codec::minicbor::to_vec(&cose_struct).unwrap();
 
-    (hex::encode(&cose_sign1_cbor).to_string(), hex::encode(pubkey).to_string())
+    Ok((hex::encode(&cose_sign1_cbor).to_string(), hex::encode(pubkey).to_string()))
 }
@@ -1,24 +1,44 @@
 // src/challenge_manager.rs
 
 use std::sync::mpsc::{Receiver, Sender};
-use crate::data_types::{ManagerCommand, SubmitterCommand, ChallengeData, MiningContext, Statistics};
+use crate::data_types::{ControlRequest, ManagerCommand, SubmitterCommand, PollerCommand, WebSocketCommand, ChallengeData, CycleRecord, DifficultyRecord, DonationRecord, MiningContext, PendingSolution, RegistrationProof, Statistics};
 use std::thread;
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 use std::time::Duration;
 use crate::cli::Cli;
 use crate::cardano;
 use super::mining;
 use crate::api;
+use crate::backoff::Backoff;
 use std::fs;
-use std::hash::{Hash, Hasher};
+use std::collections::HashSet;
 use crate::utils;
+use crate::autoscale::{self, ThreadScaler};
+use crate::challenge_sources::{self, ChallengeSource, SourceArbiter};
+use crate::watchdog;
+use crate::state_worker;
+use crate::schema::{nonce_cursor_key, SLED_KEY_CHALLENGE, SLED_KEY_MNEMONIC_INDEX, SLED_KEY_PENDING, SLED_KEY_RECEIPT};
+use shadow_harvester_lib::{ChallengeHasher, CpuHasher, DifficultyRule, Rom, RomParams, RomStore};
 
 // Key constants for SLED state
 const SLED_KEY_MINING_MODE: &str = "last_active_key_mode";
-const SLED_KEY_MNEMONIC_INDEX: &str = "mnemonic_index";
 const SLED_KEY_LAST_CHALLENGE: &str = "last_challenge_id";
-const SLED_KEY_CHALLENGE: &str = "challenge";
-const SLED_KEY_RECEIPT: &str = "receipt";
+const SLED_KEY_ROM_DIGEST: &str = "rom_digest";
+
+/// Shares of a challenge's submission window (issued_at to latest_submission) at which its
+/// progress is announced.
+const COUNTDOWN_MILESTONES: [u8; 3] = [50, 75, 90];
+/// How often a countdown waiting for its next milestone checks whether it was stopped.
+const COUNTDOWN_STOP_CHECK: Duration = Duration::from_secs(1);
+
+/// How often the thread scaling policy (--max-threads) is re-evaluated while mining.
+const SCALE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How often spare workers are rebalanced by the chance of a solution; one rolling hash rate
+/// window, so each decision sees a rate measured at the previous allocation.
+const REBALANCE_INTERVAL: Duration = utils::ROLLING_HASHRATE_WINDOW;
+
+/// Stand-in for the statistics lookup when the mode (WebSocket, --offline) forbids API contact.
+const API_CONTACT_SKIPPED: &str = "API contact skipped.";
 
 const SUBMITTER_SEND_FAIL: &str = "FATAL: Submitter channel closed. Submitter thread likely failed to open Sled DB.";
 
@@ -45,11 +65,342 @@ fn sync_check_receipt_exists(submitter_tx: &Sender<SubmitterCommand>, address: &
 /// Helper function to stop the currently running miner thread.
 fn stop_current_miner(stop_signal: &mut Option<Arc<AtomicBool>>) {
     if let Some(signal) = stop_signal.take() {
-        println!("🛑 Manager sending STOP signal to miner thread.");
+        cprintln!("🛑 Manager sending STOP signal to miner thread.");
         signal.store(true, Ordering::Relaxed);
     }
 }
 
+/// A ROM built in the background for an upcoming challenge: (ROM key, ROM).
+type PrebuiltRom = Arc<Mutex<Option<(String, Arc<Rom>)>>>;
+
+/// Builds the ROM for `challenge` on a background thread while the current challenge keeps
+/// mining. When done, the ROM is parked in `slot` and the challenge is re-posted to the
+/// manager, which then switches over without waiting for ROM generation.
+fn spawn_rom_prebuild(challenge: ChallengeData, rom_params: RomParams, cache_dir: Option<String>, slot: PrebuiltRom, manager_tx: Sender<ManagerCommand>) {
+    thread::spawn(move || {
+        let rom = mining::generate_rom(&challenge, &rom_params, cache_dir.as_deref());
+        *slot.lock().unwrap() = Some((rom_params.rom_id(&challenge.no_pre_mine_key), Arc::new(rom)));
+        cprintln!("✅ Background ROM for challenge {} is ready. Switching over.", challenge.challenge_id);
+        let _ = manager_tx.send(ManagerCommand::NewChallenge(challenge));
+    });
+}
+
+/// Schedules `ManagerCommand::ChallengeMilestone` for each of `COUNTDOWN_MILESTONES` that
+/// still lies ahead. The window starts at `issued_at`, or now when that is missing. Returns the
+/// flag that stops the countdown, set when the next challenge replaces this one.
+fn spawn_countdown(challenge: &ChallengeData, manager_tx: Sender<ManagerCommand>) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let Ok(deadline) = chrono::DateTime::parse_from_rfc3339(&challenge.latest_submission) else {
+        return stop;
+    };
+    let now = chrono::Utc::now();
+    let start = chrono::DateTime::parse_from_rfc3339(&challenge.issued_at)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .unwrap_or(now);
+    let window = deadline.with_timezone(&chrono::Utc) - start;
+    if window <= chrono::Duration::zero() {
+        return stop;
+    }
+
+    let challenge_id = challenge.challenge_id.clone();
+    let thread_stop = stop.clone();
+    thread::spawn(move || {
+        for percent in COUNTDOWN_MILESTONES {
+            let at = start + window * percent as i32 / 100;
+            let Ok(wait) = (at - chrono::Utc::now()).to_std() else {
+                continue; // Already past this milestone.
+            };
+            if !sleep_unless_stopped(wait, &thread_stop) {
+                return;
+            }
+            if manager_tx.send(ManagerCommand::ChallengeMilestone(challenge_id.clone(), percent)).is_err() {
+                return;
+            }
+        }
+    });
+    stop
+}
+
+/// Sleeps for `duration`, waking every `COUNTDOWN_STOP_CHECK`; false as soon as `stop` is set.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) -> bool {
+    let until = std::time::Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = until.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(COUNTDOWN_STOP_CHECK));
+    }
+    false
+}
+
+/// Sends `ManagerCommand::ScaleCheck` every `SCALE_CHECK_INTERVAL` until the challenge's
+/// submission deadline passes or the manager is gone.
+fn spawn_scale_checks(challenge: &ChallengeData, manager_tx: Sender<ManagerCommand>) {
+    let Ok(deadline) = chrono::DateTime::parse_from_rfc3339(&challenge.latest_submission) else {
+        return;
+    };
+    let challenge_id = challenge.challenge_id.clone();
+    thread::spawn(move || {
+        while deadline > chrono::Utc::now() {
+            thread::sleep(SCALE_CHECK_INTERVAL);
+            if manager_tx.send(ManagerCommand::ScaleCheck(challenge_id.clone())).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Lets `threads` workers hash and reports the change.
+fn apply_thread_scaling(challenge_id: &str, threads: u32, reason: &str) {
+    shadow_harvester_lib::set_active_workers(threads as usize);
+    cprintln!("🧵 Challenge {}: {} worker threads hashing ({}).", challenge_id, threads, reason);
+    crate::console::event("threads_scaled", serde_json::json!({
+        "challenge_id": challenge_id,
+        "threads": threads,
+        "reason": reason,
+    }));
+}
+
+/// Persists the cycle record (insert or update) through the state worker.
+fn save_cycle(submitter_tx: &Sender<SubmitterCommand>, record: &CycleRecord) -> Result<(), String> {
+    let json = serde_json::to_string(record).map_err(|e| format!("Failed to serialize cycle record: {}", e))?;
+    submitter_tx.send(SubmitterCommand::SaveState(record.sled_key(), json))
+        .map_err(|_| SUBMITTER_SEND_FAIL.to_string())
+}
+
+/// Forgets the donation components of an abandoned cycle, releasing the submitter to donate
+/// for that address again.
+fn drop_donation_components(components: &mut Option<(String, String)>) {
+    if let Some((address, _)) = components.take() {
+        state_worker::manager_donation_finished(&address, None);
+    }
+}
+
+/// Persists a successful donation through the state worker, for `report audit`.
+fn save_donation(submitter_tx: &Sender<SubmitterCommand>, record: &DonationRecord) -> Result<(), String> {
+    let json = serde_json::to_string(record).map_err(|e| format!("Failed to serialize donation record: {}", e))?;
+    submitter_tx.send(SubmitterCommand::SaveState(record.sled_key(), json))
+        .map_err(|_| SUBMITTER_SEND_FAIL.to_string())
+}
+
+/// Closes the running cycle (if any) with `outcome` and persists it.
+fn finish_cycle(submitter_tx: &Sender<SubmitterCommand>, current_cycle: &mut Option<CycleRecord>, outcome: &str) {
+    if let Some(mut record) = current_cycle.take() {
+        record.outcome = outcome.to_string();
+        record.ended_at = Some(chrono::Utc::now().to_rfc3339());
+        if let Err(e) = save_cycle(submitter_tx, &record) {
+            ceprintln!("⚠️ Failed to save cycle record {}: {}", record.sled_key(), e);
+        }
+    }
+}
+
+/// Moves the challenge's mnemonic index past the address just mined.
+fn advance_mnemonic_index(submitter_tx: &Sender<SubmitterCommand>, challenge_id: &str) -> Result<(), String> {
+    let mnemonic_index_key = format!("{}:{}", SLED_KEY_MNEMONIC_INDEX, challenge_id);
+    if let Ok(Some(index_str)) = sync_get_state(submitter_tx, &mnemonic_index_key)
+        && let Ok(index) = index_str.parse::<u32>() {
+        submitter_tx.send(SubmitterCommand::SaveState(mnemonic_index_key, index.wrapping_add(1).to_string()))
+            .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
+    }
+    Ok(())
+}
+
+/// Hashes `nonce` for `challenge` with the loaded ROM, for the control socket's `verify`.
+fn verify_nonce(
+    context: &MiningContext,
+    rom_store: &mut RomStore,
+    challenge: Option<&ChallengeData>,
+    address: Option<String>,
+    nonce: &str,
+) -> Result<serde_json::Value, String> {
+    let challenge = challenge.ok_or("No challenge is being mined.")?;
+    let address = address.ok_or("No mining address yet; pass one explicitly.")?;
+    let nonce_value = u64::from_str_radix(nonce.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("Nonce '{}' is not a hex number.", nonce))?;
+    let rom_id = context.rom_overrides.resolve(Some(challenge)).rom_id(&challenge.no_pre_mine_key);
+    let rom = rom_store.get(&rom_id).ok_or("The challenge's ROM is not loaded.")?;
+    let difficulty_mask = u32::from_str_radix(&challenge.difficulty, 16)
+        .map_err(|_| format!("Challenge difficulty '{}' is not hex.", challenge.difficulty))?;
+
+    let preimage = shadow_harvester_lib::build_preimage(
+        nonce_value,
+        &address,
+        &challenge.challenge_id,
+        difficulty_mask,
+        &challenge.no_pre_mine_key,
+        &challenge.latest_submission,
+        &challenge.no_pre_mine_hour_str,
+    );
+    let hash = CpuHasher::new(rom).hash(preimage.as_bytes());
+    Ok(serde_json::json!({
+        "challenge_id": challenge.challenge_id,
+        "address": address,
+        "nonce": format!("{:016x}", nonce_value),
+        "hash": hex::encode(hash),
+        "meets_difficulty": shadow_harvester_lib::hash_structure_good(&hash, difficulty_mask),
+    }))
+}
+
+/// Re-checks a solution reported by the workers before it is queued: the preimage must be the
+/// one the API rebuilds from nonce and address, and the hash recomputed from it on the loaded
+/// ROM must equal the reported one and meet the difficulty mask. Returns the recomputed hash,
+/// None when the challenge is no longer current or its ROM was unloaded (nothing to check
+/// against), or the reason the solution fails.
+fn validate_found_solution(
+    context: &MiningContext,
+    rom_store: &mut RomStore,
+    challenge: Option<&ChallengeData>,
+    solution: &PendingSolution,
+) -> Result<Option<String>, String> {
+    let Some(challenge) = challenge.filter(|c| c.challenge_id == solution.challenge_id) else {
+        return Ok(None);
+    };
+    let rom_id = context.rom_overrides.resolve(Some(challenge)).rom_id(&challenge.no_pre_mine_key);
+    let Some(rom) = rom_store.get(&rom_id) else {
+        return Ok(None);
+    };
+    let mask = u32::from_str_radix(&challenge.difficulty, 16)
+        .map_err(|_| format!("difficulty '{}' is not hex", challenge.difficulty))?;
+    let nonce = u64::from_str_radix(&solution.nonce, 16)
+        .map_err(|_| format!("nonce '{}' is not hex", solution.nonce))?;
+    let expected_preimage = shadow_harvester_lib::build_preimage(
+        nonce, &solution.address, &challenge.challenge_id, mask,
+        &challenge.no_pre_mine_key, &challenge.latest_submission, &challenge.no_pre_mine_hour_str,
+    );
+    if solution.preimage != expected_preimage {
+        return Err(format!("preimage does not match nonce, address and challenge (expected {})", expected_preimage));
+    }
+
+    let hash = CpuHasher::new(rom).hash(solution.preimage.as_bytes());
+    let hash_hex = hex::encode(hash);
+    if hash_hex != solution.hash_output {
+        return Err(format!("recomputed hash {} differs from the worker's", hash_hex));
+    }
+    if !shadow_harvester_lib::hash_structure_good(&hash, mask) {
+        return Err(format!("hash does not meet difficulty mask {:08x}", mask));
+    }
+    Ok(Some(hash_hex))
+}
+
+/// --difficulty-cross-check: reports a found solution whose hash the difficulty rules judge
+/// differently, i.e. one the server may reject (or accept) depending on its interpretation.
+fn cross_check_difficulty(solution: &PendingSolution, challenge: Option<&ChallengeData>) {
+    let Some(mask) = challenge.and_then(|c| u32::from_str_radix(&c.difficulty, 16).ok()) else {
+        return;
+    };
+    let Some(hash) = hex::decode(&solution.hash_output).ok().filter(|hash| hash.len() >= 4) else {
+        return;
+    };
+    let Some(verdicts) = DifficultyRule::disagreement(&hash, mask) else {
+        return;
+    };
+    let summary = verdicts.iter()
+        .map(|(rule, meets)| format!("{}={}", rule, if *meets { "meets" } else { "fails" }))
+        .collect::<Vec<_>>()
+        .join(", ");
+    ceprintln!("⚖️ Difficulty rules disagree on nonce {} for challenge {} (mask {:08x}, hash {}): {}",
+        solution.nonce, solution.challenge_id, mask, &solution.hash_output[..8], summary);
+    crate::console::event("difficulty_disagreement", serde_json::json!({
+        "challenge_id": solution.challenge_id,
+        "address": solution.address,
+        "nonce": solution.nonce,
+        "difficulty": format!("{:08x}", mask),
+        "hash": solution.hash_output,
+        "verdicts": verdicts.iter().map(|(rule, meets)| (rule.name().to_string(), serde_json::Value::Bool(*meets))).collect::<serde_json::Map<_, _>>(),
+    }));
+}
+
+/// Prints everything needed to reproduce a solution that failed local validation.
+fn print_invalid_solution(solution: &PendingSolution, challenge: Option<&ChallengeData>, reason: &str) {
+    let mut out = String::new();
+    out.push_str("\n==============================================\n");
+    out.push_str("🚨 SOLUTION FAILED LOCAL VALIDATION — NOT QUEUED\n");
+    out.push_str("==============================================\n");
+    out.push_str(&format!("Reason:            {}\n", reason));
+    out.push_str(&format!("Challenge:         {}\n", solution.challenge_id));
+    out.push_str(&format!("Address:           {}\n", solution.address));
+    out.push_str(&format!("Nonce:             {}\n", solution.nonce));
+    if let Some(challenge) = challenge {
+        out.push_str(&format!("Difficulty:        {}\n", challenge.difficulty));
+        out.push_str(&format!("ROM key:           {}\n", challenge.no_pre_mine_key));
+        out.push_str(&format!("Latest submission: {}\n", challenge.latest_submission));
+        out.push_str(&format!("No-pre-mine hour:  {}\n", challenge.no_pre_mine_hour_str));
+    }
+    out.push_str(&format!("Preimage:          {}\n", solution.preimage));
+    out.push_str(&format!("Worker hash:       {}\n", solution.hash_output));
+    out.push_str("The server would reject this solution. Check the ROM (`rom digest`) and the hasher.\n");
+    out.push_str("==============================================");
+    ceprintln!("{}", out);
+}
+
+/// Registers `address` once the API is reachable again, retrying with backoff on its own thread.
+/// `pending` holds the addresses with a retry in flight, so each gets only one.
+fn spawn_deferred_registration(
+    context: &MiningContext,
+    pending: &Arc<Mutex<HashSet<String>>>,
+    address: String,
+    signature: String,
+    pubkey_hex: String,
+) {
+    if !pending.lock().unwrap().insert(address.clone()) {
+        return;
+    }
+    cprintln!("🔌 Registration of {} deferred until the API is reachable.", address);
+    let client = context.client.clone();
+    let api_url = context.api_url.clone();
+    let message = context.tc_response.message.clone();
+    let pending = pending.clone();
+    thread::spawn(move || {
+        // Retried until the API answers, however long that takes; only the wait is capped.
+        let max_backoff = api::retry_max_backoff_secs();
+        let mut backoff = Backoff::new(10.min(max_backoff), max_backoff, 2.0);
+        loop {
+            backoff.sleep();
+            match api::block_on(api::register_address(&client, &api_url, &address, &message, &signature, &pubkey_hex)) {
+                Ok(()) => {
+                    cprintln!("📋 Deferred registration completed for {}.", address);
+                    break;
+                }
+                Err(e) if is_unreachable(&e) => continue,
+                Err(e) => {
+                    ceprintln!("⚠️ Deferred registration failed for {}: {}. Giving up.", address, e);
+                    break;
+                }
+            }
+        }
+        pending.lock().unwrap().remove(&address);
+    });
+}
+
+/// Connection failures, timeouts, 5xx and rate limiting: the API is down or busy rather than refusing us.
+fn is_unreachable(e: &api::ApiError) -> bool {
+    e.is_retryable()
+}
+
+/// Drift check of a freshly generated ROM digest against the registry (key: rom_digest:<ROM_ID>,
+/// see `RomParams::rom_id`, so a key reused with other ROM parameters is a different ROM).
+/// The first digest seen for a ROM ID is recorded as the reference, so this only catches a ROM
+/// that differs from an earlier build (bad RAM, a changed build); a ROM that was wrong from its
+/// first build passes. Registries imported from other hosts (`db import`) make it a cross-host
+/// check; `rom digest` compares against another implementation. A mismatch means this host
+/// must not mine or submit.
+fn verify_rom_digest(submitter_tx: &Sender<SubmitterCommand>, rom_id: &str, digest_hex: &str) -> Result<(), String> {
+    let registry_key = format!("{}:{}", SLED_KEY_ROM_DIGEST, rom_id);
+    match sync_get_state(submitter_tx, &registry_key)? {
+        Some(expected) if expected != digest_hex => {
+            ceprintln!("🚨🚨 ROM DIGEST MISMATCH for ROM {} 🚨🚨", rom_id);
+            ceprintln!("   Recorded: {}", expected);
+            ceprintln!("   Computed: {}", digest_hex);
+            ceprintln!("   This host is excluded from mining this challenge. Check RAM health and the challenge parameters.");
+            Err(format!("ROM digest mismatch for ROM {}. Host excluded from nonce assignment.", rom_id))
+        }
+        Some(_) => Ok(()),
+        None => submitter_tx.send(SubmitterCommand::SaveState(registry_key, digest_hex.to_string()))
+            .map_err(|_| SUBMITTER_SEND_FAIL.to_string()),
+    }
+}
+
 /// The main orchestration loop, replacing the old core logic in src/mining.rs.
 pub fn run_challenge_manager(
     // Receives commands from network/miner threads
@@ -58,48 +409,110 @@ pub fn run_challenge_manager(
     submitter_tx: Sender<SubmitterCommand>,
     // Pass the Manager's own Sender (manager_tx) for self-posting tasks (like fixed challenges)
     manager_tx: Sender<ManagerCommand>,
+    // Asks the HTTP Polling thread for an immediate poll (receiver is absent in WebSocket/fixed-challenge mode)
+    poller_tx: tokio::sync::mpsc::UnboundedSender<PollerCommand>,
+    // Reports to the WebSocket client (ROM digests for cross-host verification)
+    ws_tx: Sender<WebSocketCommand>,
     // The CLI context needed for configuration
     mut cli: Cli,
-    context: MiningContext,
+    mut context: MiningContext,
 ) -> Result<(), String> {
-    println!("🟢 Challenge Manager thread started.");
+    cprintln!("🟢 Challenge Manager thread started.");
 
     // State maintained by the Manager
     let mut current_stop_signal: Option<Arc<AtomicBool>> = None;
+    let mut current_dispatcher: Option<thread::JoinHandle<()>> = None;
     let mut current_challenge: Option<ChallengeData> = None;
     let mut last_processed_address: Option<String> = None;
     // NEW: Stores (original_address, donation_signature_hex) for the *current* cycle
     let mut last_signing_key_components: Option<(String, String)> = None;
+    // Registration of the address being mined, attached to its solutions for re-registration.
+    let mut current_registration: Option<(String, RegistrationProof)> = None;
+    // Key of the address being mined; --offline signs exported solutions with it.
+    let mut current_key_pair: Option<cardano::KeyPairAndAddress> = None;
+    // Record of the cycle currently mining, and what caused the next NewChallenge when the manager posts it itself.
+    let mut current_cycle: Option<CycleRecord> = None;
+    let mut next_trigger: Option<&'static str> = None;
+    // ROM pre-generation for the next challenge: the finished ROM, and the ROM ID being built.
+    let prebuilt_rom: PrebuiltRom = Arc::new(Mutex::new(None));
+    let mut prebuild_key: Option<String> = None;
+    // ROM of the running cycle; the next mnemonic index on the same challenge reuses it.
+    let mut rom_store = RomStore::new(context.rom_store_size);
+    // Solutions found for the current challenge (across cycles), for the countdown announcements.
+    let mut challenge_solutions: u32 = 0;
+    // Stops the milestone countdown of the previous challenge when a new one arrives.
+    let mut countdown_stop: Option<Arc<AtomicBool>> = None;
+    // Deadline-driven scaling between --threads and --max-threads.
+    let mut scaler = ThreadScaler::new(context.threads, cli.max_threads, cli.scale_up_at);
+    if scaler.enabled() {
+        shadow_harvester_lib::set_active_workers(scaler.active_threads() as usize);
+    }
+    // When the scaler next rebalances spare workers for the current challenge.
+    let mut next_rebalance = std::time::Instant::now() + REBALANCE_INTERVAL;
+    // Solutions found since startup, for telemetry.
+    let mut session_solutions: u64 = 0;
+    // Control socket pause: challenges arriving while paused are kept for the resume.
+    let mut paused = false;
+    let mut paused_challenge: Option<ChallengeData> = None;
+    let mut resume_pending = false;
+    // Addresses whose registration is being retried until the API is reachable.
+    let deferred_registrations: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+    // Picks the challenge to mine when several sources report one (--challenge-priority).
+    let mut source_arbiter = SourceArbiter::new(cli.challenge_priority, challenge_sources::enabled_sources(&cli));
+    let external_trigger = if cli.websocket {
+        "websocket"
+    } else if context.cli_challenge.is_some() {
+        "fixed_challenge"
+    } else {
+        "poller"
+    };
 
     // Initial State Setup: Load Mnemonic from File
-    if cli.mnemonic.is_none() {
-        if let Some(file_path) = cli.mnemonic_file.as_ref() {
-            match fs::read_to_string(file_path) {
-                Ok(content) => {
-                    // Trim whitespace and update cli.mnemonic
-                    cli.mnemonic = Some(content.trim().to_string());
-                }
-                Err(e) => {
-                    // CRITICAL FAILURE: Cannot proceed if mnemonic file is specified but unreadable.
-                    eprintln!("🚨 Failed to read mnemonic file {}: {}", file_path, e);
-                    return Err("Mnemonic file read error.".to_string());
-                }
+    if cli.mnemonic.is_none()
+        && let Some(file_path) = cli.mnemonic_file.as_ref() {
+        match fs::read_to_string(file_path) {
+            Ok(content) => {
+                // Trim whitespace and update cli.mnemonic
+                cli.mnemonic = Some(content.trim().to_string());
+            }
+            Err(e) => {
+                // CRITICAL FAILURE: Cannot proceed if mnemonic file is specified but unreadable.
+                ceprintln!("🚨 Failed to read mnemonic file {}: {}", file_path, e);
+                return Err("Mnemonic file read error.".to_string());
             }
         }
     }
 
+    // Validate the mnemonic up front so a typo fails cleanly instead of panicking during derivation.
+    if let Some(mnemonic) = cli.mnemonic.as_ref() {
+        bip39::Mnemonic::parse(mnemonic).map_err(|e| format!("Invalid mnemonic: {}", e))?;
+    }
+
     // Determine the mining mode.
     let initial_mode = if cli.ephemeral_key {
         "ephemeral".to_string()
+    } else if cli.ledger {
+        "ledger".to_string()
     } else if cli.payment_key.is_some() {
         "persistent".to_string()
     } else if cli.mnemonic.is_some() || cli.mnemonic_file.is_some() {
         "mnemonic".to_string()
     } else {
-        return Err("FATAL: No mining mode (ephemeral, payment-key, or mnemonic) configured.".to_string());
+        return Err("FATAL: No mining mode (ephemeral, payment-key, mnemonic or ledger) configured.".to_string());
     };
 
-    println!("⛏️ Initial Mining Mode: {}", initial_mode);
+    cprintln!("⛏️ Initial Mining Mode: {}", initial_mode);
+    if cli.ledger {
+        let version = crate::ledger::check_app(cli.ledger_device.as_deref())?;
+        cprintln!("🔐 Ledger Cardano app {}: confirm registration and donation messages on the device.", version);
+    }
+    // The Ledger address never changes, so its public keys are read from the device once here
+    // rather than on every challenge.
+    let ledger_key_pair = if initial_mode == "ledger" {
+        Some(cardano::ledger_key_pair(cli.ledger_device.clone(), cli.ledger_account, cli.ledger_index, cli.base_address.then_some(cli.stake_index))?)
+    } else {
+        None
+    };
     submitter_tx.send(SubmitterCommand::SaveState(SLED_KEY_MINING_MODE.to_string(), initial_mode.clone()))
         .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?; // Replaced unwrap
 
@@ -119,6 +532,7 @@ pub fn run_challenge_manager(
                 challenge_number: 0,
                 day: 0,
                 issued_at: String::new(),
+                rom_params: None,
             };
 
             // --- DEADLINE CHECK (Case 1: 5-part CLI string) ---
@@ -140,8 +554,8 @@ pub fn run_challenge_manager(
         };
 
 
-        println!("🎯 Starting with fixed challenge: {}", fixed_challenge_params.challenge_id);
-        if manager_tx.send(ManagerCommand::NewChallenge(fixed_challenge_params)).is_err() {
+        cprintln!("🎯 Starting with fixed challenge: {}", fixed_challenge_params.challenge_id);
+        if manager_tx.send(ManagerCommand::ChallengeOffer(fixed_challenge_params, ChallengeSource::Fixed)).is_err() {
             return Err("Failed to post initial fixed challenge to manager channel.".to_string());
         }
     }
@@ -152,25 +566,83 @@ pub fn run_challenge_manager(
 
         let cycle_result: Result<(), String> = (|| {
             match command {
+                ManagerCommand::ChallengeOffer(challenge, source) => {
+                    let current_id = current_challenge.as_ref().map(|c| c.challenge_id.as_str());
+                    if let Some((source, challenge)) = source_arbiter.offer(source, challenge, current_id) {
+                        next_trigger = Some(source.trigger());
+                        manager_tx.send(ManagerCommand::NewChallenge(challenge))
+                            .map_err(|_| "Manager channel closed.".to_string())?;
+                    }
+                    Ok(())
+                }
+
                 ManagerCommand::NewChallenge(challenge) => {
+                    if paused {
+                        cprintln!("⏸️ Mining is paused; challenge {} will be mined on resume.", challenge.challenge_id);
+                        paused_challenge = Some(challenge);
+                        return Ok(());
+                    }
+                    let resuming = std::mem::take(&mut resume_pending);
+
+                    // 0. A challenge with a different ROM key arrived while the current one can still be
+                    // mined: keep hashing and build its ROM in the background instead of idling for minutes.
+                    let rom_params = context.rom_overrides.resolve(Some(&challenge));
+                    rom_params.validate().map_err(|e| format!("Challenge {} has invalid ROM parameters: {}", challenge.challenge_id, e))?;
+                    let rom_id = rom_params.rom_id(&challenge.no_pre_mine_key);
+                    let rom_ready = prebuilt_rom.lock().unwrap().as_ref().is_some_and(|(id, _)| *id == rom_id);
+                    let can_keep_mining = current_stop_signal.is_some()
+                        && current_challenge.as_ref().is_some_and(|c| {
+                            c.challenge_id != challenge.challenge_id
+                                && c.no_pre_mine_key != challenge.no_pre_mine_key
+                                && !shadow_harvester_lib::submission_deadline_passed(&c.latest_submission)
+                        });
+                    if context.rom_prebuild && can_keep_mining && !rom_ready {
+                        if prebuild_key.as_deref() != Some(rom_id.as_str()) {
+                            cprintln!("🏗️ Building ROM for challenge {} in the background; still mining {}.",
+                                challenge.challenge_id, current_challenge.as_ref().unwrap().challenge_id);
+                            prebuild_key = Some(rom_id);
+                            spawn_rom_prebuild(challenge, rom_params, context.rom_cache_dir.clone(), prebuilt_rom.clone(), manager_tx.clone());
+                        }
+                        return Ok(());
+                    }
+
                     // 1. Stop current mining if active
                     stop_current_miner(&mut current_stop_signal);
-                    last_signing_key_components = None; // Reset signing components
+                    finish_cycle(&submitter_tx, &mut current_cycle, "preempted");
+                    let trigger = next_trigger.take().unwrap_or(external_trigger);
+                    let mut cycle_index: Option<u32> = None;
+                    let mut skipped_indices: Vec<u32> = Vec::new();
+                    drop_donation_components(&mut last_signing_key_components); // Reset signing components
+                    current_registration = None;
 
                     // Check if this is the same challenge we just processed
                     let is_duplicate = current_challenge.as_ref().is_some_and(|c| c.challenge_id == challenge.challenge_id);
 
                     if is_duplicate {
-                        if initial_mode != "mnemonic" {
+                        if initial_mode != "mnemonic" && !resuming {
                             // Stop persistent/ephemeral mode from re-starting unnecessarily
-                            println!("🎯 Challenge {} is the same. Waiting for miner to stop/exit.", challenge.challenge_id);
+                            cprintln!("🎯 Challenge {} is the same. Waiting for miner to stop/exit.", challenge.challenge_id);
                             return Ok(());
                         } else {
                             // Mnemonic mode must re-run derivation to skip solved index. Log and proceed.
-                            println!("♻️ Restarting Mnemonic cycle to derive next address.");
+                            cprintln!("♻️ Restarting Mnemonic cycle to derive next address.");
                         }
                     }
 
+                    if !is_duplicate {
+                        challenge_solutions = 0;
+                        if let Some(stop) = countdown_stop.take() {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                        countdown_stop = Some(spawn_countdown(&challenge, manager_tx.clone()));
+                        if let Some(threads) = scaler.reset() {
+                            apply_thread_scaling(&challenge.challenge_id, threads, "new challenge");
+                        }
+                        next_rebalance = std::time::Instant::now() + REBALANCE_INTERVAL;
+                        if scaler.enabled() {
+                            spawn_scale_checks(&challenge, manager_tx.clone());
+                        }
+                    }
                     current_challenge = Some(challenge.clone());
 
                     // Save ChallengeData to Sled DB
@@ -181,6 +653,13 @@ pub fn run_challenge_manager(
                         .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
                     submitter_tx.send(SubmitterCommand::SaveState(SLED_KEY_LAST_CHALLENGE.to_string(), challenge.challenge_id.clone()))
                         .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
+                    if !is_duplicate {
+                        let record = DifficultyRecord::from_challenge(&challenge);
+                        let record_json = serde_json::to_string(&record)
+                            .map_err(|e| format!("Failed to serialize difficulty record: {}", e))?;
+                        submitter_tx.send(SubmitterCommand::SaveState(record.sled_key(), record_json))
+                            .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
+                    }
 
 
                     // 2. Determine address and key pair based on mode
@@ -192,7 +671,7 @@ pub fn run_challenge_manager(
                             let kp = cardano::generate_cardano_key_pair_from_skey(skey_hex);
                             let address = kp.2.to_bech32().unwrap();
 
-                            println!("Solving for Persistent Address: {}", address);
+                            cprintln!("Solving for Persistent Address: {}", address);
                             (Some(kp), address)
                         }
                         "mnemonic" => {
@@ -207,48 +686,51 @@ pub fn run_challenge_manager(
 
                             if let Ok(Some(index_str)) = sync_get_state(&submitter_tx, &mnemonic_index_key) {
                                 deriv_index = index_str.parse().unwrap_or(cli.mnemonic_starting_index);
-                                println!("▶️ Resuming challenge {} at index {}.", challenge.challenge_id, deriv_index);
+                                cprintln!("▶️ Resuming challenge {} at index {}.", challenge.challenge_id, deriv_index);
                             } else {
                                 deriv_index = cli.mnemonic_starting_index;
-                                println!("🟢 Starting new challenge {} at index {}.", challenge.challenge_id, deriv_index);
+                                cprintln!("🟢 Starting new challenge {} at index {}.", challenge.challenge_id, deriv_index);
                             }
 
                             let mut current_index = deriv_index;
+                            let derive = |mnemonic: &str, passphrase: Option<&str>, account: u32, index: u32| if cli.base_address {
+                                cardano::derive_key_pair_from_mnemonic_base(mnemonic, passphrase, account, index, cli.stake_index)
+                            } else {
+                                cardano::derive_key_pair_from_mnemonic(mnemonic, passphrase, account, index)
+                            };
 
                             loop {
-                                let temp_keypair = cardano::derive_key_pair_from_mnemonic(mnemonic, account, current_index);
+                                let temp_keypair = derive(mnemonic, cli.mnemonic_passphrase.as_deref(), account, current_index);
                                 let temp_address = temp_keypair.2.to_bech32().unwrap();
 
                                 match sync_check_receipt_exists(&submitter_tx, &temp_address, &challenge.challenge_id) {
                                     Ok(true) => {
-                                        println!("⏭ Skipping solved address (Index {}).", current_index);
+                                        cprintln!("⏭ Skipping solved address (Index {}).", current_index);
+                                        skipped_indices.push(current_index);
                                         current_index = current_index.wrapping_add(1);
                                     }
                                     Ok(false) => { break; }
                                     Err(e) => {
-                                        eprintln!("⚠️ Sled error during receipt check: {}. Mining at index {} as fallback.", e, current_index);
+                                        ceprintln!("⚠️ Sled error during receipt check: {}. Mining at index {} as fallback.", e, current_index);
                                         break;
                                     }
                                 }
                             }
 
                             let final_deriv_index = current_index;
+                            cycle_index = Some(final_deriv_index);
 
                             submitter_tx.send(SubmitterCommand::SaveState(
                                 mnemonic_index_key.clone(), // Use the challenge-specific key
                                 final_deriv_index.to_string())
                             ).map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
 
-                            let kp = cardano::derive_key_pair_from_mnemonic(mnemonic, account, final_deriv_index);
+                            let kp = derive(mnemonic, cli.mnemonic_passphrase.as_deref(), account, final_deriv_index);
                             let address = kp.2.to_bech32().unwrap();
 
-                            println!("Solving for Address Index {}: {}", final_deriv_index, address);
+                            cprintln!("Solving for Address Index {}: {}", final_deriv_index, address);
 
-                            let mnemonic_hash = {
-                                let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                                mnemonic.hash(&mut hasher);
-                                hasher.finish()
-                            };
+                            let mnemonic_hash = cardano::mnemonic_hash(mnemonic, cli.mnemonic_passphrase.as_deref());
                             let wallet_key = format!(
                                 "{}:{}:{}:{}",
                                 SLED_KEY_MNEMONIC_INDEX,
@@ -261,19 +743,29 @@ pub fn run_challenge_manager(
 
                             (Some(kp), address)
                         }
+                        "ledger" => {
+                            let Some((cardano::FlexibleSecretKey::Ledger(key), vk, addr)) = ledger_key_pair.as_ref() else {
+                                return Err("FATAL: Ledger mode selected but its key was not read at startup.".to_string());
+                            };
+                            let kp = (cardano::FlexibleSecretKey::Ledger(key.clone()), *vk, addr.clone());
+                            let address = kp.2.to_bech32().unwrap();
+
+                            cprintln!("Solving for Ledger Address ({}'/{}): {}", cli.ledger_account, cli.ledger_index, address);
+                            (Some(kp), address)
+                        }
                         "ephemeral" => {
                             // ... (ephemeral key logic remains the same)
                             let kp = cardano::generate_cardano_key_and_address();
                             let address = kp.2.to_bech32().unwrap();
 
-                            println!("Solving for Ephemeral Address: {}", address);
+                            cprintln!("Solving for Ephemeral Address: {}", address);
                             (Some(kp), address)
                         }
                         _ => { return Ok(()); },
                     };
 
                     // 3. Registration
-                    let should_contact_api = !cli.websocket; // <-- Check WS mode flag
+                    let should_contact_api = !cli.websocket && !cli.offline;
 
                     if key_pair_and_address.is_some() {
                         let challenge_data = current_challenge.as_ref().unwrap();
@@ -290,46 +782,63 @@ pub fn run_challenge_manager(
 
                     let stats_result: Result<Statistics, String> = if should_contact_api {
                         // Only fetch statistics if NOT in WebSocket mode
-                        api::fetch_statistics(&context.client, &context.api_url, &mining_address)
+                        api::block_on(api::fetch_statistics(&context.client, &context.api_url, &mining_address)).map_err(String::from)
                     } else {
-                        // In WS mode, return a dummy error that the match block below will handle gracefully.
-                        Err("WebSocket mode: API contact skipped.".to_string())
+                        // In WS or offline mode, return a dummy error that the match block below will handle gracefully.
+                        Err(API_CONTACT_SKIPPED.to_string())
                     };
 
                     if let Some((_key_pair, pubkey, address_obj)) = key_pair_and_address.as_ref() {
                         let reg_message = context.tc_response.message.clone();
                         let address_str = address_obj.to_bech32().unwrap();
-                        let reg_signature = cardano::cip8_sign(key_pair_and_address.as_ref().unwrap(), &reg_message);
+                        let reg_signature = cardano::try_cip8_sign(key_pair_and_address.as_ref().unwrap(), &reg_message)?;
+                        // Offline, the proof travels with the exported solutions (if a cached T&C was available to sign).
+                        if should_contact_api || (cli.offline && !reg_message.is_empty()) {
+                            current_registration = Some((address_str.clone(), RegistrationProof {
+                                message: reg_message.clone(),
+                                signature: reg_signature.0.clone(),
+                                pubkey: hex::encode(pubkey.as_ref()),
+                            }));
+                        }
 
                         // Handle conditional registration and stats print
                         match stats_result {
                             Ok(ref stats) => { // Stats successfully fetched (implies HTTP mode)
-                                 println!("📋 Address {} is already registered (Receipts: {}). Skipping registration.", address_str, stats.crypto_receipts);
+                                 cprintln!("📋 Address {} is already registered (Receipts: {}). Skipping registration.", address_str, stats.crypto_receipts);
                             },
-                            Err(ref e) if e == "WebSocket mode: API contact skipped." => { // Handle WS skip gracefully
-                                println!("📋 Address registration and statistics fetch skipped (WebSocket Mode).");
+                            Err(ref e) if e == API_CONTACT_SKIPPED => { // Handle WS/offline skip gracefully
+                                cprintln!("📋 Address registration and statistics fetch skipped ({}).", if cli.offline { "Offline Mode" } else { "WebSocket Mode" });
                             }
                             Err(_) => {
                                 // Stats fetch failed (only happens in HTTP mode). Attempt registration.
-                                if let Err(reg_e) = api::register_address(
+                                match api::block_on(api::register_address(
                                     &context.client, &context.api_url, &address_str, &reg_message, &reg_signature.0, &hex::encode(pubkey.as_ref()),
-                                ) {
-                                    eprintln!("⚠️ Address registration failed for {}: {}. Continuing attempt to mine...", address_str, reg_e);
-                                } else {
-                                    println!("📋 Address registered successfully: {}", address_str);
-                                    // Re-fetch stats after successful registration, discarding the result with `let _ = ...`
-                                    let _ = api::fetch_statistics(&context.client, &context.api_url, &address_str);
+                                )) {
+                                    Ok(()) => {
+                                        cprintln!("📋 Address registered successfully: {}", address_str);
+                                        // Re-fetch stats after successful registration, discarding the result with `let _ = ...`
+                                        let _ = api::block_on(api::fetch_statistics(&context.client, &context.api_url, &address_str));
+                                    }
+                                    Err(reg_e) if reg_e.is_dry_run() => {
+                                        cprintln!("🧪 Dry run: registration of {} not sent.", address_str);
+                                    }
+                                    Err(reg_e) if is_unreachable(&reg_e) => {
+                                        ceprintln!("⚠️ API unreachable while registering {}: {}. Mining continues.", address_str, reg_e);
+                                        spawn_deferred_registration(&context, &deferred_registrations, address_str.clone(), reg_signature.0.clone(), hex::encode(pubkey.as_ref()));
+                                    }
+                                    Err(reg_e) => {
+                                        ceprintln!("⚠️ Address registration failed for {}: {}. Continuing attempt to mine...", address_str, reg_e);
+                                    }
                                 }
                             }
                         }
 
                         // 4. CAPTURE KEY COMPONENTS FOR DONATION IN NEXT CYCLE (if donation is configured)
-                        last_signing_key_components = if context.donate_to_option.is_some() {
-                            let destination_address = context.donate_to_option.as_ref().unwrap();
+                        last_signing_key_components = if let Some(destination_address) = context.donate_to_option.as_ref() {
                             let donation_message = format!("Assign accumulated Scavenger rights to: {}", destination_address);
 
                             // Generate the signature for the donation message using the current key pair
-                            let (donation_signature, _) = cardano::cip8_sign(key_pair_and_address.as_ref().unwrap(), &donation_message);
+                            let (donation_signature, _) = cardano::try_cip8_sign(key_pair_and_address.as_ref().unwrap(), &donation_message)?;
 
                             Some((mining_address.clone(), donation_signature))
                         } else {
@@ -339,49 +848,211 @@ pub fn run_challenge_manager(
 
                     // 5. Spawn new miner threads
                     if key_pair_and_address.is_some() {
-                        match mining::spawn_miner_workers(challenge.clone(), context.threads, mining_address.clone(), manager_tx.clone()) {
-                            Ok(signal) => {
+                        // Resume the nonce search where the previous run for this (address, challenge) left off.
+                        let cursor_key = nonce_cursor_key(&mining_address, &challenge.challenge_id);
+                        let start_nonce = match sync_get_state(&submitter_tx, &cursor_key) {
+                            Ok(Some(cursor_str)) => cursor_str.parse::<u64>().unwrap_or(0),
+                            Ok(None) => 0,
+                            Err(e) => {
+                                ceprintln!("⚠️ Failed to read nonce cursor for {}: {}. Starting from 0.", mining_address, e);
+                                0
+                            }
+                        };
+                        if start_nonce > 0 {
+                            cprintln!("⏩ Resuming nonce search at {} for address {}.", start_nonce, mining_address);
+                        }
+
+                        // With --max-threads, spawn every worker that may hash; the scaler parks the rest.
+                        let threads = if scaler.enabled() { scaler.spawn_threads() } else { context.threads.max(1) };
+
+                        let prebuilt = prebuilt_rom.lock().unwrap().take_if(|(id, _)| *id == rom_id);
+                        // A prebuilt ROM lands in the store first, so it shows up as reused below.
+                        let freshly_prebuilt = prebuilt.is_some();
+                        if let Some((prebuilt_id, prebuilt)) = prebuilt {
+                            prebuild_key = None;
+                            for evicted in rom_store.insert(prebuilt_id, prebuilt) {
+                                cprintln!("🗑️ Unloaded ROM {} (ROM store holds {}).", evicted, rom_store.capacity());
+                            }
+                        }
+                        let (rom, reused) = mining::rom_for_challenge(&mut rom_store, &challenge, &rom_params, context.rom_cache_dir.as_deref());
+                        let digest_hex = rom.digest.to_hex();
+                        if reused && !freshly_prebuilt {
+                            cprintln!("♻️ Reusing ROM for key {}", challenge.no_pre_mine_key);
+                        } else if cli.websocket {
+                            let _ = ws_tx.send(WebSocketCommand::ReportRomDigest {
+                                challenge_id: challenge.challenge_id.clone(),
+                                rom_key: challenge.no_pre_mine_key.clone(),
+                                rom_id: rom_id.clone(),
+                                digest: digest_hex.clone(),
+                            });
+                        }
+                        verify_rom_digest(&submitter_tx, &rom_id, &digest_hex)?;
+
+                        let now = chrono::Utc::now();
+                        let mut record = CycleRecord {
+                            challenge_id: challenge.challenge_id.clone(),
+                            address: mining_address.clone(),
+                            mnemonic_index: cycle_index,
+                            skipped_indices,
+                            started_at: now.to_rfc3339(),
+                            started_at_millis: now.timestamp_millis(),
+                            ended_at: None,
+                            total_hashes: 0,
+                            outcome: "running".to_string(),
+                            trigger: trigger.to_string(),
+                            submissions: Vec::new(),
+                        };
+                        match mining::spawn_miner_workers(challenge.clone(), Arc::new(CpuHasher::new(rom.clone())), threads, context.solutions_per_cycle, mining_address.clone(), start_nonce, manager_tx.clone(), submitter_tx.clone()) {
+                            Ok((signal, dispatcher)) => {
+                                save_cycle(&submitter_tx, &record)?;
+                                current_cycle = Some(record);
+                                if let Some(secs) = context.rom_check_interval {
+                                    mining::spawn_rom_integrity_checker(rom, challenge.challenge_id.clone(), Duration::from_secs(secs), signal.clone(), manager_tx.clone());
+                                }
                                 current_stop_signal = Some(signal);
+                                current_dispatcher = Some(dispatcher);
                                 last_processed_address = Some(mining_address.clone());
-                                println!("⛏️ Started mining for address: {}", last_processed_address.as_ref().unwrap());
+                                cprintln!("⛏️ Started mining for address: {}", last_processed_address.as_ref().unwrap());
+                                crate::console::event("challenge_started", serde_json::json!({
+                                    "challenge_id": challenge.challenge_id,
+                                    "difficulty": challenge.difficulty,
+                                    "address": mining_address,
+                                }));
+                            }
+                            Err(e) => {
+                                ceprintln!("❌ Failed to spawn miner workers: {}", e);
+                                record.outcome = format!("failed: {}", e);
+                                record.ended_at = Some(record.started_at.clone());
+                                save_cycle(&submitter_tx, &record)?;
                             }
-                            Err(e) => eprintln!("❌ Failed to spawn miner workers: {}", e),
                         }
                     }
+                    current_key_pair = key_pair_and_address;
 
                     Ok(())
                 }
 
-                ManagerCommand::SolutionFound(mut solution, total_hashes, elapsed_secs) => {
-                    // 1. Stop the current mining cycle to prevent further hashing
-                    stop_current_miner(&mut current_stop_signal);
+                ManagerCommand::SolutionFound(mut solution, total_hashes, elapsed_secs, cycle_complete) => {
+                    // 0. Recompute the hash before anything is queued; a solution the server would reject never leaves.
+                    let found_challenge = current_challenge.as_ref().filter(|c| c.challenge_id == solution.challenge_id);
+                    if cli.difficulty_cross_check {
+                        cross_check_difficulty(&solution, found_challenge);
+                    }
+                    match validate_found_solution(&context, &mut rom_store, found_challenge, &solution) {
+                        Ok(Some(_)) => {}
+                        Ok(None) => ceprintln!("⚠️ Could not re-check the solution for {} (challenge or ROM no longer loaded); queueing it unchecked.", solution.challenge_id),
+                        Err(reason) => {
+                            print_invalid_solution(&solution, found_challenge, &reason);
+                            crate::console::event("solution_invalid", serde_json::json!({
+                                "challenge_id": solution.challenge_id,
+                                "address": solution.address,
+                                "nonce": solution.nonce,
+                                "preimage": solution.preimage,
+                                "hash": solution.hash_output,
+                                "reason": reason,
+                            }));
+                            if cycle_complete {
+                                stop_current_miner(&mut current_stop_signal);
+                                finish_cycle(&submitter_tx, &mut current_cycle, "invalid");
+                            }
+                            return Ok(());
+                        }
+                    }
 
-                    // 2. Add donation address to the solution if configured (Submitter needs this)
+                    // 1. Add donation address to the solution if configured (Submitter needs this)
                     solution.donation_address = context.donate_to_option.clone();
+                    solution.registration = current_registration.as_ref()
+                        .filter(|(address, _)| *address == solution.address)
+                        .map(|(_, proof)| proof.clone());
+                    solution.donation_signature = last_signing_key_components.as_ref()
+                        .filter(|(address, _)| *address == solution.address)
+                        .map(|(_, signature)| signature.clone());
+                    // The manager donates for this address once the cycle completes; keep the
+                    // submitter from donating it again when the receipt comes in first.
+                    if !cli.offline && context.donate_to_option.is_some() && solution.donation_signature.is_some() {
+                        state_worker::manager_donation_started(&solution.address);
+                    }
+                    session_solutions += 1;
+                    if current_challenge.as_ref().is_some_and(|c| c.challenge_id == solution.challenge_id) {
+                        challenge_solutions += 1;
+                    }
+                    crate::console::event("nonce_found", serde_json::json!({
+                        "challenge_id": solution.challenge_id,
+                        "address": solution.address,
+                        "nonce": solution.nonce,
+                        "total_hashes": total_hashes,
+                        "elapsed_secs": elapsed_secs,
+                    }));
+                    crate::notify::send(format!(
+                        "✅ Solution found for challenge {} ({}, nonce {}).",
+                        solution.challenge_id, solution.address, solution.nonce
+                    ));
+
+                    // 2. Queue for submission (State Worker handles network submission and receipt saving),
+                    // or sign and export it for a connected machine in offline mode.
+                    if cli.offline {
+                        let challenge = current_challenge.as_ref().filter(|c| c.challenge_id == solution.challenge_id)
+                            .ok_or_else(|| format!("Solution for challenge {} found, but that challenge is no longer current. Not exported.", solution.challenge_id))?;
+                        let key_pair = current_key_pair.as_ref()
+                            .filter(|kp| kp.2.to_bech32().ok().as_deref() == Some(solution.address.as_str()))
+                            .ok_or_else(|| format!("Solution for {} found, but its key is no longer loaded. Not exported.", solution.address))?;
+                        let path = crate::offline::export_path(cli.offline_export.as_deref(), context.data_dir.as_deref());
+                        let count = crate::offline::export_solution(&path, challenge, &solution, key_pair)?;
+                        cprintln!("📤 Solution signed and exported to {} ({} solution(s) in the bundle).", path.display(), count);
+                    } else {
+                        submitter_tx.send(SubmitterCommand::SubmitSolution(solution.clone()))
+                            .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
+                    }
 
-                    // 3. Queue for submission (State Worker handles network submission and receipt saving)
-                    submitter_tx.send(SubmitterCommand::SubmitSolution(solution.clone()))
-                        .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
+                    // Link the submission to the cycle that found it.
+                    if let Some(record) = current_cycle.as_mut() {
+                        record.submissions.push(format!("{}:{}:{}:{}", SLED_KEY_PENDING, solution.address, solution.challenge_id, solution.nonce));
+                        record.total_hashes = total_hashes;
+                        if cycle_complete {
+                            finish_cycle(&submitter_tx, &mut current_cycle, "solved");
+                        } else {
+                            save_cycle(&submitter_tx, record)?;
+                        }
+                    }
 
-                    // 4. Execute synchronous Donation API call if configured (using stored key components)
-                    if let Some((original_address, donation_signature)) = last_signing_key_components.take() {
+                    // 3. In multi-solution mode the workers keep hashing until the cycle is complete.
+                    if !cycle_complete {
+                        cprintln!("📥 Solution queued for {}. Continuing to mine for more solutions.", solution.address);
+                        return Ok(());
+                    }
+                    stop_current_miner(&mut current_stop_signal);
+
+                    // 4. Execute synchronous Donation API call if configured (using stored key components).
+                    // Offline, the signature travels in the exported solution and the importing machine donates.
+                    if let Some((original_address, donation_signature)) = last_signing_key_components.take().filter(|_| !cli.offline) {
                         if original_address == solution.address {
-                            if let Some(ref destination_address) = context.donate_to_option.as_ref() {
-                                println!("🚀 Attempting synchronous donation for {}...", original_address);
-                                match api::donate_to(
+                            if let Some(destination_address) = context.donate_to_option.as_ref() {
+                                cprintln!("🚀 Attempting synchronous donation for {}...", original_address);
+                                match api::block_on(api::donate_to(
                                     &context.client,
                                     &context.api_url,
                                     &original_address,
                                     destination_address,
                                     &donation_signature,
-                                ) {
-                                    Ok(id) => println!("✅ Donation initiated successfully. ID: {}", id),
-                                    Err(e) => eprintln!("⚠️ Donation failed (manager attempt): {}", e),
+                                )) {
+                                    Ok(id) => {
+                                        cprintln!("✅ Donation initiated successfully. ID: {}", id);
+                                        state_worker::manager_donation_finished(&original_address, Some(chrono::Utc::now()));
+                                        save_donation(&submitter_tx, &DonationRecord::new(&original_address, destination_address, &id))?;
+                                    }
+                                    Err(e) => {
+                                        if e.is_dry_run() {
+                                            cprintln!("🧪 Dry run: donation of {} to {} not sent.", original_address, destination_address);
+                                        } else {
+                                            ceprintln!("⚠️ Donation failed (manager attempt): {}", e);
+                                        }
+                                        state_worker::manager_donation_finished(&original_address, None);
+                                    }
                                 }
                             }
                         } else {
                             // This should only happen if the address was somehow replaced mid-cycle (e.g., mnemonic mode loop bug)
-                            eprintln!("⚠️ Warning: Found solution for address {} but stored key is for {}. Skipping donation.", solution.address, original_address);
+                            ceprintln!("⚠️ Warning: Found solution for address {} but stored key is for {}. Skipping donation.", solution.address, original_address);
                         }
                     }
 
@@ -389,11 +1060,11 @@ pub fn run_challenge_manager(
                     let address = solution.address.clone();
 
                     // Stats fetch is still needed here for printing, but we must check WS mode
-                    let stats_result = if !cli.websocket { // Check WS mode flag
-                        api::fetch_statistics(&context.client, &context.api_url, &address)
+                    let stats_result = if !cli.websocket && !cli.offline {
+                        api::block_on(api::fetch_statistics(&context.client, &context.api_url, &address)).map_err(String::from)
                     } else {
-                        // Return dummy error in WS mode to avoid API contact
-                        Err("WebSocket mode: API contact skipped.".to_string())
+                        // Return dummy error in WS/offline mode to avoid API contact
+                        Err(API_CONTACT_SKIPPED.to_string())
                     };
 
                     // Use a safe match statement instead of unwrap_err() on Result
@@ -402,9 +1073,9 @@ pub fn run_challenge_manager(
                             // Stats were successfully fetched (HTTP mode)
                             utils::print_statistics(Ok(stats), total_hashes, elapsed_secs);
                         }
-                        Err(e) if e == "WebSocket mode: API contact skipped." => {
-                            // Stats were intentionally skipped (WS mode)
-                            println!("📈 Statistics printing skipped (WebSocket Mode).");
+                        Err(e) if e == API_CONTACT_SKIPPED => {
+                            // Stats were intentionally skipped (WS/offline mode)
+                            cprintln!("📈 Statistics printing skipped ({}).", if cli.offline { "Offline Mode" } else { "WebSocket Mode" });
                         }
                         Err(e) => {
                             // A real error occurred during stats fetch (HTTP mode)
@@ -417,36 +1088,259 @@ pub fn run_challenge_manager(
 
                     // 6. Handle Mnemonic Index Advancement (for next cycle)
                     if initial_mode == "mnemonic" {
-
-                        // Construct the challenge-specific key
-                        let challenge_id = current_challenge.as_ref().map(|c| c.challenge_id.clone())
+                        let challenge_data = current_challenge.clone()
                             .ok_or_else(|| "FATAL: Solution found but challenge context missing.".to_string())?;
-                        let mnemonic_index_key = format!("{}:{}", SLED_KEY_MNEMONIC_INDEX, challenge_id);
+                        advance_mnemonic_index(&submitter_tx, &challenge_data.challenge_id)?;
 
+                        // Self-trigger the next cycle immediately to pick up the new index/address.
+                        next_trigger = Some("next_index");
+                        manager_tx.send(ManagerCommand::NewChallenge(challenge_data)).unwrap();
+                    }
 
-                        // Get and advance the index using the challenge-specific key
-                        if let Ok(Some(index_str)) = sync_get_state(&submitter_tx, &mnemonic_index_key) {
-                            if let Ok(mut index) = index_str.parse::<u32>() {
-                                index = index.wrapping_add(1);
+                    Ok(())
+                }
 
-                                // Save the advanced index back to the challenge-specific key
-                                submitter_tx.send(SubmitterCommand::SaveState(mnemonic_index_key, index.to_string()))
-                                    .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
-                            }
-                        }
+                ManagerCommand::SpaceExhausted(challenge_id, address, total_hashes, elapsed_secs) => {
+                    let Some(challenge) = current_challenge.clone().filter(|c| c.challenge_id == challenge_id) else {
+                        return Ok(());
+                    };
 
-                        // Self-trigger the next cycle immediately to pick up the new index/address.
-                        if let Some(challenge_data) = current_challenge.clone() {
-                            manager_tx.send(ManagerCommand::NewChallenge(challenge_data)).unwrap();
-                        }
+                    cprintln!("🔚 Searched every nonce below {:#x} for {} on challenge {} without completing the cycle ({} hashes).",
+                        shadow_harvester_lib::nonce_space(), address, challenge_id, total_hashes);
+                    crate::console::event("space_exhausted", serde_json::json!({
+                        "challenge_id": challenge_id,
+                        "address": address,
+                        "nonce_space": shadow_harvester_lib::nonce_space(),
+                        "total_hashes": total_hashes,
+                        "elapsed_secs": elapsed_secs,
+                    }));
+                    stop_current_miner(&mut current_stop_signal);
+                    if let Some(record) = current_cycle.as_mut() {
+                        record.total_hashes = total_hashes;
                     }
+                    finish_cycle(&submitter_tx, &mut current_cycle, "space_exhausted");
 
+                    // The same address would search the same nonces again: move on to the next
+                    // mnemonic index, or wait for the next challenge.
+                    if initial_mode == "mnemonic" {
+                        advance_mnemonic_index(&submitter_tx, &challenge_id)?;
+                        next_trigger = Some("next_index");
+                        manager_tx.send(ManagerCommand::NewChallenge(challenge)).unwrap();
+                    } else {
+                        cprintln!("ℹ️ Waiting for the next challenge.");
+                    }
+                    Ok(())
+                }
+
+                ManagerCommand::ChallengeMilestone(challenge_id, percent) => {
+                    let Some(challenge) = current_challenge.as_ref().filter(|c| c.challenge_id == challenge_id) else {
+                        return Ok(());
+                    };
+                    let remaining = chrono::DateTime::parse_from_rfc3339(&challenge.latest_submission)
+                        .map(|deadline| (deadline.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds().max(0) as f64)
+                        .unwrap_or(0.0);
+                    if challenge_solutions == 0 {
+                        ceprintln!(
+                            "⚠️ Challenge {}: {}% of the submission window used, {} left, and NO solution found yet. Consider more threads (--threads) or skipping this challenge.",
+                            challenge_id, percent, utils::format_duration(remaining)
+                        );
+                    } else {
+                        cprintln!(
+                            "⏳ Challenge {}: {}% of the submission window used, {} left. Solutions found: {}.",
+                            challenge_id, percent, utils::format_duration(remaining), challenge_solutions
+                        );
+                    }
+                    Ok(())
+                }
+
+                ManagerCommand::ScaleCheck(challenge_id) => {
+                    let Some(challenge) = current_challenge.as_ref().filter(|c| c.challenge_id == challenge_id) else {
+                        return Ok(());
+                    };
+                    if current_stop_signal.is_none() {
+                        return Ok(());
+                    }
+                    let Some(remaining) = autoscale::remaining_percent(challenge, chrono::Utc::now()) else {
+                        return Ok(());
+                    };
+                    if let Some(threads) = scaler.evaluate(remaining, challenge_solutions > 0) {
+                        let reason = if threads > context.threads {
+                            format!("{:.0}% of the window left, no solution yet", remaining)
+                        } else if challenge_solutions > 0 {
+                            "solution found".to_string()
+                        } else {
+                            format!("{:.0}% of the window left", remaining)
+                        };
+                        apply_thread_scaling(&challenge_id, threads, &reason);
+                    }
+
+                    if std::time::Instant::now() < next_rebalance {
+                        return Ok(());
+                    }
+                    next_rebalance = std::time::Instant::now() + REBALANCE_INTERVAL;
+                    // Chance of a solution on the base workers alone, from the per-worker rate.
+                    let miner = utils::miner_stats();
+                    let base_rate = miner.rolling_rate / scaler.active_threads() as f64 * scaler.base_threads() as f64;
+                    let Some(base_chance) = miner.solution_chance_at(base_rate) else {
+                        return Ok(());
+                    };
+                    if let Some(threads) = scaler.rebalance(base_chance, challenge_solutions > 0) {
+                        let reason = format!("{:.0}% chance of a solution on {} threads", base_chance * 100.0, scaler.base_threads());
+                        apply_thread_scaling(&challenge_id, threads, &reason);
+                    }
+                    Ok(())
+                }
+
+                ManagerCommand::DeadlinePassed(challenge_id) => {
+                    // Ignore late reports from a miner of a challenge we already moved past.
+                    if current_challenge.as_ref().is_none_or(|c| c.challenge_id != challenge_id) {
+                        return Ok(());
+                    }
+
+                    cprintln!("⏰ Submission window for challenge {} closed. Waiting for the next challenge.", challenge_id);
+                    stop_current_miner(&mut current_stop_signal);
+                    if let Some(threads) = scaler.reset() {
+                        apply_thread_scaling(&challenge_id, threads, "challenge ended");
+                    }
+                    finish_cycle(&submitter_tx, &mut current_cycle, "deadline_passed");
+                    current_challenge = None;
+                    drop_donation_components(&mut last_signing_key_components);
+
+                    if poller_tx.send(PollerCommand::PollNow).is_err() {
+                        cprintln!("ℹ️ No HTTP poller running; the next challenge will arrive via WebSocket or CLI.");
+                    }
+                    Ok(())
+                }
+
+                ManagerCommand::RomCorrupted(challenge_id) => {
+                    let Some(challenge) = current_challenge.clone().filter(|c| c.challenge_id == challenge_id) else {
+                        return Ok(());
+                    };
+
+                    // The in-memory ROM can no longer be trusted: stop the workers (their progress
+                    // is kept in the nonce cursor) and re-dispatch the challenge to rebuild the ROM.
+                    cprintln!("♻️ Rebuilding ROM for challenge {} after integrity check failure.", challenge_id);
+                    stop_current_miner(&mut current_stop_signal);
+                    finish_cycle(&submitter_tx, &mut current_cycle, "rom_corrupted");
+                    rom_store.remove(&context.rom_overrides.resolve(Some(&challenge)).rom_id(&challenge.no_pre_mine_key));
+                    // Hash outputs computed on the corrupted ROM are keyed by its unchanged digest.
+                    shadow_harvester_lib::clear_hash_cache();
+                    current_challenge = None;
+                    next_trigger = Some("rom_rebuild");
+                    if manager_tx.send(ManagerCommand::NewChallenge(challenge)).is_err() {
+                        return Err("Manager channel closed while rebuilding the ROM.".to_string());
+                    }
+                    Ok(())
+                }
+
+                ManagerCommand::Heartbeat => {
+                    watchdog::beat(watchdog::Component::Manager);
+                    Ok(())
+                }
+
+                ManagerCommand::Control(request, reply_tx) => {
+                    let reply = match request {
+                        ControlRequest::Status => {
+                            let miner = utils::miner_stats();
+                            Ok(serde_json::json!({
+                                "mode": initial_mode,
+                                "paused": paused,
+                                "mining": current_stop_signal.is_some(),
+                                "challenge_id": current_challenge.as_ref().map(|c| &c.challenge_id),
+                                "difficulty": current_challenge.as_ref().map(|c| &c.difficulty),
+                                "latest_submission": current_challenge.as_ref().map(|c| &c.latest_submission),
+                                "address": last_processed_address,
+                                "challenge_solutions": challenge_solutions,
+                                "solutions_found": session_solutions,
+                                "queued_challenge": paused_challenge.as_ref().map(|c| &c.challenge_id),
+                                "threads": context.threads,
+                                "active_threads": scaler.active_threads(),
+                                "max_threads": scaler.enabled().then(|| scaler.spawn_threads()),
+                                "hash_rate": miner.rolling_rate,
+                                "thread_hash_rates": miner.thread_rates,
+                                "expected_secs_to_solution": miner.expected_secs_to_solution(),
+                                "offline_start": context.offline_start,
+                                "api_schema": crate::api_schema::last_report(),
+                                "challenge_priority": cli.challenge_priority.and_then(|p| clap::ValueEnum::to_possible_value(&p)).map(|v| v.get_name().to_string()),
+                                "challenge_sources": source_arbiter.snapshot(),
+                                "deferred_registrations": deferred_registrations.lock().unwrap().len(),
+                            }))
+                        }
+                        ControlRequest::Pause if paused => Err("Mining is already paused.".to_string()),
+                        ControlRequest::Pause => {
+                            stop_current_miner(&mut current_stop_signal);
+                            finish_cycle(&submitter_tx, &mut current_cycle, "paused");
+                            paused = true;
+                            cprintln!("⏸️ Mining paused from the control socket.");
+                            Ok(serde_json::json!({ "paused": true }))
+                        }
+                        ControlRequest::Resume if !paused => Err("Mining is not paused.".to_string()),
+                        ControlRequest::Resume => {
+                            paused = false;
+                            cprintln!("▶️ Mining resumed from the control socket.");
+                            match paused_challenge.take().or_else(|| current_challenge.clone()) {
+                                Some(challenge) => {
+                                    resume_pending = true;
+                                    next_trigger = Some("control");
+                                    manager_tx.send(ManagerCommand::NewChallenge(challenge))
+                                        .map_err(|_| "Manager channel closed.".to_string())
+                                        .map(|_| serde_json::json!({ "paused": false }))
+                                }
+                                None => Ok(serde_json::json!({ "paused": false })),
+                            }
+                        }
+                        ControlRequest::Switch(challenge_id) => {
+                            let challenge_key = format!("{}:{}", SLED_KEY_CHALLENGE, challenge_id);
+                            sync_get_state(&submitter_tx, &challenge_key)
+                                .and_then(|json| json.ok_or_else(|| format!("Challenge '{}' is not in the local database.", challenge_id)))
+                                .and_then(|json| serde_json::from_str::<ChallengeData>(&json).map_err(|e| format!("Stored challenge is unreadable: {}", e)))
+                                .and_then(utils::check_submission_deadline)
+                                .and_then(|challenge| {
+                                    cprintln!("🔀 Switching to challenge {} from the control socket.", challenge.challenge_id);
+                                    next_trigger = Some("control");
+                                    manager_tx.send(ManagerCommand::NewChallenge(challenge))
+                                        .map_err(|_| "Manager channel closed.".to_string())
+                                })
+                                .map(|_| serde_json::json!({ "challenge_id": challenge_id, "paused": paused }))
+                        }
+                        ControlRequest::SetThreads(0) => Err("Thread count must be at least 1.".to_string()),
+                        ControlRequest::SetThreads(threads) => {
+                            context.threads = threads;
+                            shadow_harvester_lib::set_active_workers(scaler.set_base(threads) as usize);
+                            cprintln!("🧵 Worker threads set to {} from the control interface.", threads);
+                            // Restart the running miner so the new count applies now, not at the next challenge.
+                            match current_challenge.clone() {
+                                Some(challenge) if current_stop_signal.is_some() && !paused => {
+                                    stop_current_miner(&mut current_stop_signal);
+                                    finish_cycle(&submitter_tx, &mut current_cycle, "threads_changed");
+                                    resume_pending = true;
+                                    next_trigger = Some("control");
+                                    manager_tx.send(ManagerCommand::NewChallenge(challenge))
+                                        .map_err(|_| "Manager channel closed.".to_string())
+                                        .map(|_| serde_json::json!({ "threads": threads, "restarted": true }))
+                                }
+                                _ => Ok(serde_json::json!({ "threads": threads, "restarted": false })),
+                            }
+                        }
+                        ControlRequest::Verify { nonce, address } => {
+                            verify_nonce(&context, &mut rom_store, current_challenge.as_ref(), address.or_else(|| last_processed_address.clone()), &nonce)
+                        }
+                    };
+                    let _ = reply_tx.send(reply);
                     Ok(())
                 }
 
                 ManagerCommand::Shutdown => {
-                    println!("🚨 Manager received shutdown signal. Stopping miner and exiting.");
+                    cprintln!("🚨 Manager received shutdown signal. Stopping miner and exiting.");
                     stop_current_miner(&mut current_stop_signal);
+                    finish_cycle(&submitter_tx, &mut current_cycle, "shutdown");
+                    // The dispatcher queues its final nonce cursor save on exit; join it so that save
+                    // reaches the submitter before its Shutdown.
+                    if let Some(dispatcher) = current_dispatcher.take()
+                        && dispatcher.join().is_err()
+                    {
+                        ceprintln!("⚠️ Miner dispatcher panicked; its nonce cursor may not be saved.");
+                    }
                     submitter_tx.send(SubmitterCommand::Shutdown)
                         .map_err(|_| SUBMITTER_SEND_FAIL.to_string())?;
                     Err("Manager received Shutdown command.".to_string())// Signal main thread to exit gracefully
@@ -464,15 +1358,16 @@ pub fn run_challenge_manager(
 
             // Check for the specific fatal Sled error and exit the Manager thread if found.
             if e.contains(SUBMITTER_SEND_FAIL) {
-                eprintln!("❌ Manager Cycle Failed (FATAL): {}", e);
+                ceprintln!("❌ Manager Cycle Failed (FATAL): {}", e);
                 // Propagate the error out of run_challenge_manager, forcing the application to exit.
                 return Err(e);
             }
 
-            eprintln!("❌ Manager Cycle Failed (Non-Fatal): {}", e);
+            ceprintln!("❌ Manager Cycle Failed (Non-Fatal): {}", e);
 
             // To be extra cautious, stop current mining if an error occurred in the cycle
             stop_current_miner(&mut current_stop_signal);
+            finish_cycle(&submitter_tx, &mut current_cycle, &format!("failed: {}", e));
         }
     }
 
@@ -0,0 +1,227 @@
+// src/challenge_sources.rs
+
+use crate::cli::{ChallengePriority, Cli};
+use crate::data_types::ChallengeData;
+use std::collections::BTreeMap;
+
+// ===============================================
+// CHALLENGE SOURCE PRIORITY (--challenge-priority)
+// ===============================================
+//
+// Challenges reach the manager from a fixed --challenge, the HTTP poller and the WebSocket
+// server. Without --challenge-priority only one of them runs and every challenge it reports is
+// mined. With it, the poller also runs next to --websocket/--challenge when an API URL is
+// available, and the manager picks the active challenge from the latest report of each source:
+//
+//   prefer-websocket   fixed > websocket > api
+//   prefer-api         fixed > api > websocket
+//   require-match      mine only once every enabled source reports the same challenge
+//
+// Reports past their submission deadline are dropped; disagreements are logged.
+
+/// Where a challenge came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChallengeSource {
+    /// The --challenge flag.
+    Fixed,
+    /// The HTTP poller.
+    Api,
+    /// The WebSocket server.
+    WebSocket,
+}
+
+impl ChallengeSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChallengeSource::Fixed => "fixed",
+            ChallengeSource::Api => "api",
+            ChallengeSource::WebSocket => "websocket",
+        }
+    }
+
+    /// Trigger recorded in the cycle history for challenges from this source.
+    pub fn trigger(self) -> &'static str {
+        match self {
+            ChallengeSource::Fixed => "fixed_challenge",
+            ChallengeSource::Api => "poller",
+            ChallengeSource::WebSocket => "websocket",
+        }
+    }
+}
+
+/// The challenge sources this run starts.
+pub fn enabled_sources(cli: &Cli) -> Vec<ChallengeSource> {
+    let mut sources = Vec::new();
+    if cli.challenge.is_some() {
+        sources.push(ChallengeSource::Fixed);
+    }
+    let has_api_url = cli.api_url.is_some() || cli.mock_api_port.is_some();
+    let legacy_poller = !cli.websocket && cli.challenge.is_none();
+    if !cli.offline && (legacy_poller || (cli.challenge_priority.is_some() && has_api_url)) {
+        sources.push(ChallengeSource::Api);
+    }
+    if cli.websocket {
+        sources.push(ChallengeSource::WebSocket);
+    }
+    sources
+}
+
+/// Decides which reported challenge the manager mines.
+pub struct SourceArbiter {
+    priority: Option<ChallengePriority>,
+    enabled: Vec<ChallengeSource>,
+    latest: BTreeMap<ChallengeSource, ChallengeData>,
+    /// Last disagreement logged, so a source repeating itself does not flood the log.
+    last_conflict: Option<String>,
+}
+
+impl SourceArbiter {
+    pub fn new(priority: Option<ChallengePriority>, enabled: Vec<ChallengeSource>) -> Self {
+        SourceArbiter { priority, enabled, latest: BTreeMap::new(), last_conflict: None }
+    }
+
+    /// Records `challenge` from `source`. Returns the challenge to switch to, with its source, or
+    /// None when the manager should keep mining `current_id`. Without a priority every report
+    /// is passed through, as before the option existed.
+    pub fn offer(&mut self, source: ChallengeSource, challenge: ChallengeData, current_id: Option<&str>) -> Option<(ChallengeSource, ChallengeData)> {
+        self.latest.insert(source, challenge.clone());
+        let Some(priority) = self.priority else {
+            return Some((source, challenge));
+        };
+        self.latest.retain(|_, c| !shadow_harvester_lib::submission_deadline_passed(&c.latest_submission));
+        self.log_conflict();
+
+        let (chosen_source, chosen) = match priority {
+            ChallengePriority::RequireMatch => {
+                let missing: Vec<&str> = self.enabled.iter().filter(|s| !self.latest.contains_key(s)).map(|s| s.name()).collect();
+                if !missing.is_empty() {
+                    cprintln!("⏳ Challenge {} from {} is waiting for confirmation from: {}.", challenge.challenge_id, source.name(), missing.join(", "));
+                    return None;
+                }
+                if self.last_conflict.is_some() {
+                    return None;
+                }
+                (source, self.latest.get(&source)?.clone())
+            }
+            ChallengePriority::PreferWebsocket | ChallengePriority::PreferApi => {
+                let order = match priority {
+                    ChallengePriority::PreferApi => [ChallengeSource::Fixed, ChallengeSource::Api, ChallengeSource::WebSocket],
+                    _ => [ChallengeSource::Fixed, ChallengeSource::WebSocket, ChallengeSource::Api],
+                };
+                order.into_iter().find_map(|s| self.latest.get(&s).map(|c| (s, c.clone())))?
+            }
+        };
+
+        if chosen.challenge_id != challenge.challenge_id {
+            cprintln!("ℹ️ Ignoring challenge {} from {}; {} from {} takes priority.",
+                challenge.challenge_id, source.name(), chosen.challenge_id, chosen_source.name());
+        }
+        (current_id != Some(chosen.challenge_id.as_str())).then_some((chosen_source, chosen))
+    }
+
+    /// Logs when the sources start (or stop) disagreeing about the active challenge.
+    fn log_conflict(&mut self) {
+        let first = self.latest.values().next().map(|c| c.challenge_id.as_str());
+        let agree = self.latest.values().all(|c| Some(c.challenge_id.as_str()) == first);
+        let conflict = (!agree).then(|| self.describe());
+        if conflict != self.last_conflict {
+            match &conflict {
+                Some(description) => ceprintln!("⚠️ Challenge sources disagree: {}.", description),
+                None if self.latest.len() > 1 => cprintln!("✅ Challenge sources agree again: {}.", self.describe()),
+                None => {}
+            }
+        }
+        self.last_conflict = conflict;
+    }
+
+    /// "api=D01C05, websocket=D01C06"
+    fn describe(&self) -> String {
+        self.latest.iter().map(|(s, c)| format!("{}={}", s.name(), c.challenge_id)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Latest challenge ID per source, for the control status.
+    pub fn snapshot(&self) -> serde_json::Value {
+        self.latest.iter().map(|(s, c)| (s.name().to_string(), serde_json::Value::from(c.challenge_id.clone()))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ChallengeSource::{Api, Fixed, WebSocket};
+
+    fn challenge(id: &str) -> ChallengeData {
+        challenge_until(id, "2999-01-01T00:00:00Z")
+    }
+
+    fn challenge_until(id: &str, latest_submission: &str) -> ChallengeData {
+        ChallengeData {
+            challenge_id: id.to_string(),
+            difficulty: "000FFFFF".to_string(),
+            no_pre_mine_key: "key".to_string(),
+            no_pre_mine_hour_str: "1".to_string(),
+            latest_submission: latest_submission.to_string(),
+            challenge_number: 0,
+            day: 0,
+            issued_at: String::new(),
+            rom_params: None,
+        }
+    }
+
+    /// The (source, challenge ID) an offer switches to.
+    fn offer(arbiter: &mut SourceArbiter, source: ChallengeSource, id: &str, current: Option<&str>) -> Option<(ChallengeSource, String)> {
+        arbiter.offer(source, challenge(id), current).map(|(source, c)| (source, c.challenge_id))
+    }
+
+    #[test]
+    fn without_priority_every_report_passes() {
+        let mut arbiter = SourceArbiter::new(None, vec![Api, WebSocket]);
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C01", None), Some((WebSocket, "D01C01".to_string())));
+        assert_eq!(offer(&mut arbiter, Api, "D01C02", Some("D01C01")), Some((Api, "D01C02".to_string())));
+        assert_eq!(offer(&mut arbiter, Api, "D01C02", Some("D01C02")), Some((Api, "D01C02".to_string())));
+    }
+
+    #[test]
+    fn prefer_websocket_overrides_api() {
+        let mut arbiter = SourceArbiter::new(Some(ChallengePriority::PreferWebsocket), vec![Api, WebSocket]);
+        assert_eq!(offer(&mut arbiter, Api, "D01C01", None), Some((Api, "D01C01".to_string())));
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C02", Some("D01C01")), Some((WebSocket, "D01C02".to_string())));
+        // A newer API report does not displace the WebSocket's challenge.
+        assert_eq!(offer(&mut arbiter, Api, "D01C03", Some("D01C02")), None);
+    }
+
+    #[test]
+    fn prefer_api_overrides_websocket() {
+        let mut arbiter = SourceArbiter::new(Some(ChallengePriority::PreferApi), vec![Api, WebSocket]);
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C01", None), Some((WebSocket, "D01C01".to_string())));
+        assert_eq!(offer(&mut arbiter, Api, "D01C02", Some("D01C01")), Some((Api, "D01C02".to_string())));
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C03", Some("D01C02")), None);
+    }
+
+    #[test]
+    fn fixed_challenge_beats_every_source() {
+        for priority in [ChallengePriority::PreferWebsocket, ChallengePriority::PreferApi] {
+            let mut arbiter = SourceArbiter::new(Some(priority), vec![Fixed, Api, WebSocket]);
+            assert_eq!(offer(&mut arbiter, Fixed, "D01C01", None), Some((Fixed, "D01C01".to_string())));
+            assert_eq!(offer(&mut arbiter, Api, "D01C02", Some("D01C01")), None);
+            assert_eq!(offer(&mut arbiter, WebSocket, "D01C03", Some("D01C01")), None);
+        }
+    }
+
+    #[test]
+    fn expired_reports_lose_to_live_ones() {
+        let mut arbiter = SourceArbiter::new(Some(ChallengePriority::PreferWebsocket), vec![Api, WebSocket]);
+        assert!(arbiter.offer(WebSocket, challenge_until("D01C01", "2000-01-01T00:00:00Z"), None).is_none());
+        assert_eq!(offer(&mut arbiter, Api, "D01C02", None), Some((Api, "D01C02".to_string())));
+    }
+
+    #[test]
+    fn require_match_waits_for_agreement() {
+        let mut arbiter = SourceArbiter::new(Some(ChallengePriority::RequireMatch), vec![Api, WebSocket]);
+        assert_eq!(offer(&mut arbiter, Api, "D01C01", None), None);
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C02", None), None);
+        assert_eq!(offer(&mut arbiter, WebSocket, "D01C01", None), Some((WebSocket, "D01C01".to_string())));
+        // Agreement on the challenge already mined is not a switch.
+        assert_eq!(offer(&mut arbiter, Api, "D01C01", Some("D01C01")), None);
+    }
+}
@@ -1,71 +1,450 @@
 // src/cli.rs
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+const LONG_ABOUT: &str = "\
+Shadow Harvester mines Scavenger Mine challenges and submits the solutions for your Cardano address(es).
+
+Pick exactly one key mode:
+  --mnemonic-file <PATH>   (recommended) derive a new address per challenge from a BIP39 phrase stored in a file
+  --mnemonic \"<WORDS>\"     the same, with the phrase itself on the command line (not a file path)
+  --payment-key <HEX>      mine with a single Ed25519 key
+  --ephemeral-key          generate a throwaway key per cycle (not recommended)
+
+Challenges come from the API (--api-url), the local database (--challenge <ID>), or a
+WebSocket client (--websocket). Run `shadow-harvester examples` for copy-pasteable command lines.";
+
+const AFTER_HELP: &str = "\
+Examples:
+  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos --mnemonic-file wallet.mnemonic
+  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos --payment-key <HEX>
+  shadow-harvester examples";
+
+/// Copy-pasteable command lines for each mining mode, printed by the `examples` subcommand.
+pub const EXAMPLES: &str = "\
+# Persistent mining with a mnemonic file (recommended).
+# The file contains the 24 words on one line; a new address is derived for each challenge.
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos \\
+    --mnemonic-file wallet.mnemonic --data-dir state
+
+# Same, with the phrase inline. Pass the WORDS, quoted - never a file path.
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos \\
+    --mnemonic \"word1 word2 ... word24\"
+
+# Continue a mnemonic on another account / skip already used indices.
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos \\
+    --mnemonic-file wallet.mnemonic --mnemonic-account 1 --mnemonic-starting-index 10
+
+# Single persistent payment key (hex-encoded Ed25519 secret key).
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos \\
+    --payment-key <HEX>
+
+# Ephemeral keys: a new throwaway key every cycle (keys are NOT saved; not recommended).
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos --ephemeral-key \\
+    --donate-to <ADDR>
+
+# WebSocket mode: challenges arrive from a WebSocket client on --ws-port, solutions go back to it.
+shadow-harvester --websocket --ws-port 8080 --accept-tos --mnemonic-file wallet.mnemonic
+
+# Fixed challenge: import the challenge JSON once, then mine it from the local database.
+shadow-harvester challenge import --file challenge.json
+shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io --accept-tos \\
+    --mnemonic-file wallet.mnemonic --challenge D07C21
+
+# Inspect local state.
+shadow-harvester challenge list
+shadow-harvester wallet list
+shadow-harvester wallet stats --mnemonic-file wallet.mnemonic --count 50
+
+# Offline hashrate benchmark.
+shadow-harvester bench --seconds 60 --threads 8";
 
 #[derive(Parser, Debug, Clone)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = LONG_ABOUT, after_help = AFTER_HELP)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
     /// The base URL for the Scavenger Mine API (e.g., https://scavenger.gd.midnighttge.io)
-    #[arg(long)]
+    #[arg(long, env = "SH_API_URL")]
     pub api_url: Option<String>,
 
+    /// Path prefix between --api-url and the endpoints, for mirrors that mount the API under
+    /// `/api` or a versioned path such as `/api/v2`. Requests then go to e.g.
+    /// `<api-url>/api/v2/challenge`.
+    #[arg(long, env = "SH_API_BASE_PATH", value_name = "PATH")]
+    pub api_base_path: Option<String>,
+
+    /// Timeout in seconds for each request to the API, including reading the response.
+    /// 0 waits indefinitely.
+    #[arg(long, env = "SH_HTTP_TIMEOUT", default_value_t = 30, value_name = "SECS")]
+    pub http_timeout: u64,
+
+    /// Retries of a failed submission or donation (network errors, 5xx, 429) before giving up.
+    /// A submission that gives up stays in the pending queue.
+    #[arg(long, env = "SH_MAX_RETRIES", default_value_t = 10)]
+    pub max_retries: u32,
+
+    /// Longest wait in seconds between two retries; the wait doubles from 5s up to this cap.
+    #[arg(long, env = "SH_RETRY_MAX_BACKOFF", default_value_t = 300, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub retry_max_backoff: u64,
+
+    /// Maximum API requests started per second, shared by every request the process makes
+    /// (registrations, statistics, submissions, polling). Fractions are allowed; 0 for unlimited.
+    #[arg(long, env = "SH_MAX_RPS", default_value_t = 10.0, value_name = "RPS")]
+    pub max_rps: f64,
+
+    /// PEM file with extra CA certificates to trust for API connections, e.g. the internal CA
+    /// of a TLS-intercepting corporate proxy. The system roots stay trusted.
+    #[arg(long, env = "SH_TLS_CA_CERT", value_name = "PATH")]
+    pub tls_ca_cert: Option<String>,
+
+    /// PEM client certificate presented to the API (or a proxy) that requires one.
+    #[arg(long, env = "SH_TLS_CLIENT_CERT", value_name = "PATH", requires = "tls_client_key")]
+    pub tls_client_cert: Option<String>,
+
+    /// PKCS#8 PEM private key of --tls-client-cert.
+    #[arg(long, env = "SH_TLS_CLIENT_KEY", value_name = "PATH", requires = "tls_client_cert")]
+    pub tls_client_key: Option<String>,
+
     /// Accept the Token End User Agreement and continue mining without displaying the terms.
-    #[arg(long)]
+    #[arg(long, env = "SH_ACCEPT_TOS")]
     pub accept_tos: bool,
 
+    /// Mine without submitting: registration, solution and donation POSTs are logged instead of
+    /// sent, and each solution is validated locally. Registration status is still looked up.
+    #[arg(long, env = "SH_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Mine on an air-gapped machine: the --challenge (imported with `challenge import`) is mined
+    /// without any API connectivity, and each solution is signed by its address key and written
+    /// to --offline-export instead of being submitted. Registration uses the cached T&C, if any.
+    #[arg(long, env = "SH_OFFLINE", requires = "challenge", conflicts_with_all = ["websocket", "dry_run", "challenge_priority"])]
+    pub offline: bool,
+
+    /// Solution bundle written by --offline mining [default: <data-dir>/offline-solutions.json].
+    #[arg(long, env = "SH_OFFLINE_EXPORT", value_name = "PATH")]
+    pub offline_export: Option<String>,
+
     /// Registered Cardano address to submit solutions for.
-    #[arg(long)]
+    #[arg(long, env = "SH_ADDRESS")]
     pub address: Option<String>,
 
     /// Number of worker threads to use for mining.
-    #[arg(long, default_value_t = std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(24))]
+    #[arg(long, env = "SH_THREADS", default_value_t = std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(24))]
     pub threads: u32,
 
+    /// Scale up to this many worker threads when less than --scale-up-at percent of the
+    /// submission window is left and the challenge has no solution yet, e.g. borrowing cores
+    /// normally left to other work. Before that, part of them is lent every few minutes by the
+    /// chance that --threads misses a solution. Back to --threads once solved or when the
+    /// challenge ends.
+    #[arg(long, env = "SH_MAX_THREADS", value_name = "N")]
+    pub max_threads: Option<u32>,
+
+    /// Share of the submission window (in percent) left at which --max-threads kicks in.
+    #[arg(long, env = "SH_SCALE_UP_AT", value_name = "PERCENT", default_value_t = 25, value_parser = clap::value_parser!(u8).range(1..=99))]
+    pub scale_up_at: u8,
+
+    /// Caps worker CPU utilization to this percentage (1-100) by inserting sleep windows between hashes.
+    /// Useful on laptops or shared servers without dropping to a single thread.
+    #[arg(long, env = "SH_CPU_LIMIT", default_value_t = 100, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub cpu_limit: u8,
+
+    /// How a hash is compared against the challenge's difficulty mask: `mask` (no bit set outside
+    /// the mask, the server's rule), `leading-zero-bits`, `numeric-prefix` (first 4 bytes <= mask)
+    /// or `byte-prefix` (each of the first 4 bytes <= the mask's byte). Applies to mining and to
+    /// every local difficulty check.
+    #[arg(long, env = "SH_DIFFICULTY_RULE", value_name = "RULE", default_value = "mask")]
+    pub difficulty_rule: shadow_harvester_lib::DifficultyRule,
+
+    /// Checks every found solution against all difficulty rules and warns when they disagree.
+    #[arg(long, env = "SH_DIFFICULTY_CROSS_CHECK")]
+    pub difficulty_cross_check: bool,
+
+    /// Search only nonces below N for each address and challenge. Once every worker runs out,
+    /// the cycle ends as "search space exhausted" instead of hashing forever. Meant for tests and
+    /// tiny mock challenges; the default searches the whole 64-bit space.
+    #[arg(long, env = "SH_NONCE_SPACE", value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub nonce_space: Option<u64>,
+
+    /// Cache the hash outputs of the last N preimages and reuse them instead of hashing the same
+    /// preimage twice, e.g. when a fixed challenge is mined again for the same address from
+    /// nonce 0. CPU backend only. Disabled (0) by default.
+    #[arg(long, env = "SH_HASH_CACHE", value_name = "N", default_value_t = 0)]
+    pub hash_cache: usize,
+
+    /// Run mining workers at idle/low OS priority (nice 19 on Unix, idle priority on Windows)
+    /// so the miner yields to interactive workloads.
+    #[arg(long, env = "SH_BACKGROUND")]
+    pub background: bool,
+
+    /// Number of distinct solutions to find per address before the mining cycle ends.
+    /// Workers keep hashing after each find until this many are queued or the deadline passes.
+    #[arg(long, env = "SH_SOLUTIONS_PER_CYCLE", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub solutions_per_cycle: u32,
+
+    /// Periodically re-hash random ROM chunks against checksums taken at generation time,
+    /// every SECS seconds, and rebuild the ROM on mismatch. Guards against silent memory
+    /// corruption on long runs on non-ECC machines. Disabled by default.
+    #[arg(long, env = "SH_ROM_CHECK_INTERVAL", value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub rom_check_interval: Option<u64>,
+
+    /// Do not cache generated ROMs under <data-dir>/roms/. By default each 1 GiB ROM is written
+    /// there once and memory-mapped on later runs instead of being regenerated.
+    #[arg(long, env = "SH_NO_ROM_CACHE")]
+    pub no_rom_cache: bool,
+
+    /// Number of ROM files kept in <data-dir>/roms/. Caching another ROM deletes the least
+    /// recently used ones beyond this.
+    #[arg(long, env = "SH_ROM_CACHE_MAX", value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rom_cache_max: u64,
+
+    /// Do not build the next challenge's ROM in the background. By default, when a challenge with
+    /// a new ROM key arrives, the current challenge keeps mining until the new ROM is ready,
+    /// which briefly needs memory for two ROMs. Always off with --low-memory.
+    #[arg(long, env = "SH_NO_ROM_PREBUILD")]
+    pub no_rom_prebuild: bool,
+
+    /// Back the ROM with huge pages to cut TLB misses on its random accesses. Tries explicit
+    /// 2 MiB pages (vm.nr_hugepages) first, then transparent huge pages, then falls back to
+    /// standard pages; the setup banner shows which one was used. Linux only.
+    #[arg(long, env = "SH_HUGE_PAGES")]
+    pub huge_pages: bool,
+
+    /// Number of ROMs kept loaded at once, least recently used unloaded first. Each protocol
+    /// ROM takes 1 GiB. Raise it when alternating between challenges with different ROM keys,
+    /// so switching back does not regenerate the ROM.
+    #[arg(long, env = "SH_ROM_STORE_SIZE", value_name = "N", default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=16))]
+    pub rom_store_size: usize,
+
+    /// Low-memory ROM mode for devices that cannot hold the 1 GiB ROM (e.g. 2 GB RAM). Only the
+    /// pre-buffer and offsets (~32 MiB) stay resident and every ROM access regenerates its chunk,
+    /// so hashing is much slower. A cached ROM file (--data-dir) is memory-mapped instead, which
+    /// the kernel can page out under memory pressure.
+    #[arg(long, env = "SH_LOW_MEMORY")]
+    pub low_memory: bool,
+
+    /// Linux only: when the ROM is memory-mapped from the disk cache, announce each VM loop's
+    /// ROM reads to the kernel in one io_uring batch before executing it, so workers stall less
+    /// on page faults. Most useful with --low-memory on slow storage. Requires Linux 5.6+.
+    #[arg(long, env = "SH_ROM_PREFETCH")]
+    pub rom_prefetch: bool,
+
+    /// Override the ROM size in bytes (protocol default: 1073741824). Takes precedence over
+    /// `rom_params` in the challenge JSON. Only change this if the protocol parameters change.
+    #[arg(long, env = "SH_ROM_SIZE", value_name = "BYTES")]
+    pub rom_size: Option<usize>,
+
+    /// Override the ROM pre-buffer size in bytes, a power of two (protocol default: 16777216).
+    #[arg(long, env = "SH_ROM_PRE_SIZE", value_name = "BYTES")]
+    pub rom_pre_size: Option<usize>,
+
+    /// Override the number of pre-buffer chunks mixed into each ROM chunk (protocol default: 4).
+    #[arg(long, env = "SH_ROM_MIXING_NUMBERS", value_name = "N")]
+    pub rom_mixing_numbers: Option<usize>,
+
+    /// Fail at startup instead of warning when options are ambiguous or would be ignored
+    /// (e.g. --address, --challenge together with --websocket, --ws-port without --websocket).
+    #[arg(long, env = "SH_STRICT")]
+    pub strict: bool,
+
     /// Optional secret key (hex-encoded) to mine with.
-    #[arg(long)]
+    #[arg(long, env = "SH_PAYMENT_KEY", hide_env_values = true)]
     pub payment_key: Option<String>,
 
     /// Automatically generate a new ephemeral key pair for every mining cycle.
-    #[arg(long)]
+    #[arg(long, env = "SH_EPHEMERAL_KEY")]
     pub ephemeral_key: bool,
 
+    /// Cardano network the derived addresses belong to; test networks use `addr_test` addresses.
+    /// Use it to mine against a test deployment of the API.
+    #[arg(long, env = "SH_NETWORK", value_enum, default_value_t = CardanoNetwork::Mainnet)]
+    pub network: CardanoNetwork,
+
+    /// Mine with a payment key held by a Ledger (Cardano app 7.1+). Registration and donation
+    /// messages are signed on the device; no secret key material is needed on this machine.
+    #[arg(long, env = "SH_LEDGER")]
+    pub ledger: bool,
+
+    /// hidraw device of the Ledger (e.g. /dev/hidraw3). Found automatically on Linux by default.
+    #[arg(long, env = "SH_LEDGER_DEVICE", value_name = "PATH")]
+    pub ledger_device: Option<String>,
+
+    /// Ledger key to mine with: 1852'/1815'/<account>'/0/<index>.
+    #[arg(long, env = "SH_LEDGER_ACCOUNT", default_value_t = 0)]
+    pub ledger_account: u32,
+
+    /// Address index of the Ledger key to mine with.
+    #[arg(long, env = "SH_LEDGER_INDEX", default_value_t = 0)]
+    pub ledger_index: u32,
+
     /// Cardano address (bech32) to donate all accumulated rewards to.
-    #[arg(long)]
+    #[arg(long, env = "SH_DONATE_TO")]
     pub donate_to: Option<String>,
 
     /// 24-word BIP39 mnemonic phrase for sequential address generation.
-    #[arg(long)]
+    /// Pass the quoted words themselves; to read them from a file use --mnemonic-file.
+    #[arg(long, env = "SH_MNEMONIC", hide_env_values = true, value_name = "WORDS")]
     pub mnemonic: Option<String>,
 
-    #[arg(long)]
+    /// Path to a file containing the BIP39 mnemonic phrase (recommended over --mnemonic).
+    #[arg(long, env = "SH_MNEMONIC_FILE", value_name = "PATH")]
     pub mnemonic_file: Option<String>,
 
-    #[arg(long, default_value_t = 0)]
+    /// Optional BIP39 passphrase ("25th word") of the mnemonic. Wallets created with one derive
+    /// different addresses; also used by the `wallet` commands.
+    #[arg(long, env = "SH_MNEMONIC_PASSPHRASE", hide_env_values = true, value_name = "PASSPHRASE")]
+    pub mnemonic_passphrase: Option<String>,
+
+    /// BIP44 account index used when deriving addresses from the mnemonic.
+    #[arg(long, env = "SH_MNEMONIC_ACCOUNT", default_value_t = 0)]
     pub mnemonic_account: u32,
 
-    #[arg(long, default_value_t = 0)]
+    /// First address index to derive from the mnemonic (skips earlier, already used indices).
+    #[arg(long, env = "SH_MNEMONIC_STARTING_INDEX", default_value_t = 0)]
     pub mnemonic_starting_index: u32,
 
+    /// Mine with base addresses (payment key + stake key 1852'/1815'/<account>'/2/<stake-index>)
+    /// instead of enterprise addresses, for wallets registered with their base address.
+    /// Applies to mnemonic and --ledger mining and to `db check`.
+    #[arg(long, env = "SH_BASE_ADDRESS")]
+    pub base_address: bool,
+
+    /// Stake key index of base addresses. Wallets use 0 for every address.
+    #[arg(long, env = "SH_STAKE_INDEX", default_value_t = 0)]
+    pub stake_index: u32,
+
     /// The name of the challenge to mine (e.g., D07C21). The challenge details are loaded from the Sled DB.
-    #[arg(long)]
+    #[arg(long, env = "SH_CHALLENGE")]
     pub challenge: Option<String>,
 
     /// Where to store state (like the mnemonic starting index) and receipts
-    #[arg(long, default_value = ".")]
+    #[arg(long, env = "SH_DATA_DIR", default_value = ".")]
     pub data_dir: Option<String>,
 
+    /// Set by `main` when --data-dir was passed on the command line rather than defaulted.
+    #[arg(skip)]
+    pub data_dir_explicit: bool,
+
+    /// Compress large database values (receipts, error records) with zstd when writing.
+    /// Existing uncompressed values stay readable, so this can be enabled on an existing data dir.
+    #[arg(long, env = "SH_DB_COMPRESS")]
+    pub db_compress: bool,
+
+    /// Encrypt database values (pending solutions, receipts, addresses) at rest with a key
+    /// derived from the passphrase on the first line of this file. The passphrase can also be
+    /// given in the SHADOW_HARVESTER_DB_PASSPHRASE environment variable. Existing plain values
+    /// stay readable, but once values are encrypted every run and command needs the passphrase.
+    /// Database keys (which include addresses and challenge IDs) are not encrypted.
+    #[arg(long, env = "SH_DB_PASSPHRASE_FILE", value_name = "PATH")]
+    pub db_passphrase_file: Option<String>,
+
+    /// Encrypt only the sensitive database entries (the mnemonic's derived address map and
+    /// donation progress) instead of every value. `password` uses the database passphrase
+    /// (--db-passphrase-file, SHADOW_HARVESTER_DB_PASSPHRASE or --keyring); any other value is
+    /// the path of a key file of at least 32 bytes (e.g. `head -c 32 /dev/urandom > db.key`).
+    /// Plain entries under the sensitive prefixes are encrypted when the database is opened.
+    #[arg(long, env = "SH_DB_ENCRYPT", value_name = "password|KEYFILE")]
+    pub db_encrypt: Option<String>,
+
+    /// Read key material the command line leaves unset from the OS keyring: the mnemonic (or,
+    /// if none is stored, the payment key) and the database passphrase. Store them first with
+    /// `keyring set`.
+    #[arg(long, env = "SH_KEYRING")]
+    pub keyring: bool,
+
+    /// Where the state database lives. `memory` keeps everything in RAM and writes nothing to
+    /// disk (for read-only containers); pending solutions and receipts are lost on exit.
+    #[arg(long, env = "SH_DB_BACKEND", value_enum, default_value_t = DbBackend::Sled)]
+    pub db_backend: DbBackend,
+
     /// Enable WebSocket mode for receiving challenges and posting solutions.
-    #[arg(long)]
+    #[arg(long, env = "SH_WEBSOCKET")]
     pub websocket: bool,
     /// The port for the internal WebSocket server to listen on for new challenges.
-    #[arg(long, default_value_t = 8080)]
+    #[arg(long, env = "SH_WS_PORT", default_value_t = 8080)]
     pub ws_port: u16,
+    /// How to choose between challenge sources (--challenge, HTTP poller, WebSocket). With this
+    /// set, the poller also runs next to --websocket or --challenge when --api-url is given.
+    #[arg(long, env = "SH_CHALLENGE_PRIORITY", value_enum, value_name = "POLICY")]
+    pub challenge_priority: Option<ChallengePriority>,
+    /// Listen on this Unix socket for control commands (status, pause, resume, switch,
+    /// receipts, verify) from `shadow-harvester shell`. Defaults to `control.sock` in the data
+    /// directory, which is also where `shell` and one-shot commands look for it.
+    #[arg(long, env = "SH_CONTROL_SOCKET", value_name = "PATH")]
+    pub control_socket: Option<String>,
+    /// Do not open the control socket, and do not look for a running miner's socket.
+    #[arg(long, env = "SH_NO_CONTROL_SOCKET", conflicts_with = "control_socket")]
+    pub no_control_socket: bool,
+    /// Serve the REST control API (GET /status, POST /pause, POST /resume, POST /threads/{n},
+    /// POST /pending/retry) on this port, plus the web dashboard at / when built with the
+    /// "web-ui" feature.
+    #[arg(long, env = "SH_CONTROL_PORT")]
+    pub control_port: Option<u16>,
+    /// Address the REST control API binds to. Use 0.0.0.0 to manage the miner from other
+    /// hosts; any non-loopback address requires --control-token.
+    #[arg(long, env = "SH_CONTROL_BIND", default_value = "127.0.0.1")]
+    pub control_bind: std::net::IpAddr,
+    /// Require `Authorization: Bearer <TOKEN>` (or `?token=<TOKEN>`) on every REST control API
+    /// request. The web dashboard page itself loads without it.
+    #[arg(long, env = "SH_CONTROL_TOKEN", hide_env_values = true, value_name = "TOKEN")]
+    pub control_token: Option<String>,
+    /// JSON file configuring notification sinks, e.g.
+    /// `{"telegram": {"bot_token": "...", "chat_id": "..."}, "discord": {"webhook_url": "..."}, "hashrate_drop_percent": 30}`.
+    /// Keeps tokens out of the process arguments; the flags below override it.
+    #[arg(long, env = "SH_NOTIFY_CONFIG", value_name = "PATH")]
+    pub notify_config: Option<String>,
+    /// Telegram bot token for notifications about found solutions, rejected submissions and
+    /// hashrate drops. Requires --telegram-chat-id.
+    #[arg(long, env = "SH_TELEGRAM_BOT_TOKEN", hide_env_values = true, value_name = "TOKEN")]
+    pub telegram_bot_token: Option<String>,
+    /// Telegram chat the bot posts notifications to.
+    #[arg(long, env = "SH_TELEGRAM_CHAT_ID", value_name = "CHAT_ID")]
+    pub telegram_chat_id: Option<String>,
+    /// Discord webhook URL to post notifications to.
+    #[arg(long, env = "SH_DISCORD_WEBHOOK", hide_env_values = true, value_name = "URL")]
+    pub discord_webhook: Option<String>,
+    /// Notify when the hashrate over a minute falls this many percent below the best minute
+    /// seen while mining the challenge (default 30).
+    #[arg(long, env = "SH_NOTIFY_HASHRATE_DROP", value_name = "PERCENT")]
+    pub notify_hashrate_drop: Option<u8>,
+    /// Append a CSV row with state, hashrate, memory usage, challenge and solution counts to this
+    /// file every --telemetry-interval seconds.
+    #[arg(long, env = "SH_TELEMETRY_FILE", value_name = "PATH")]
+    pub telemetry_file: Option<String>,
+    /// Seconds between two rows of --telemetry-file.
+    #[arg(long, env = "SH_TELEMETRY_INTERVAL", value_name = "SECS", default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub telemetry_interval: u64,
+    /// Cancel long one-shot commands (challenge hash/verify/retry-error, wallet stats,
+    /// donate-all) after this many seconds, as if Ctrl-C was pressed. Work done so far is kept.
+    #[arg(long, env = "SH_COMMAND_TIMEOUT", value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub command_timeout: Option<u64>,
     /// The port to run the Mock API server on for testing.**
-    #[arg(long)]
+    #[arg(long, env = "SH_MOCK_API_PORT")]
     pub mock_api_port: Option<u16>,
+
+    /// Print progress as a plain-text line every 30 seconds instead of drawing a progress bar.
+    /// Implied when stdout is not a terminal (systemd, Docker, redirected output).
+    #[arg(long, env = "SH_NO_PROGRESS")]
+    pub no_progress: bool,
+
+    /// Language of the mining summaries and prompts (en, es). Defaults to the locale from
+    /// LC_ALL, LC_MESSAGES or LANG. Warnings, errors and logs are always in English.
+    #[arg(long, env = "SH_LANG", value_enum, value_name = "LANG")]
+    pub lang: Option<Lang>,
+
+    /// `json` writes one JSON object per line to stdout for key events (challenge_started,
+    /// nonce_found, submission_result, stats) and moves all other output to stderr.
+    #[arg(long, env = "SH_LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Log filter for the mining, submission and API modules: a level (error, warn, info,
+    /// debug, trace) optionally followed by per-module overrides, e.g.
+    /// `info,shadow_harvester::api=debug` to log full API response bodies. A bare `debug` also
+    /// enables the HTTP libraries' debug output. Defaults to RUST_LOG, or `info`.
+    #[arg(long, env = "SH_LOG_LEVEL", value_name = "FILTER")]
+    pub log_level: Option<String>,
 }
 
 
@@ -83,23 +462,273 @@ pub enum Commands {
         old_data_dir: String,
     },
 
+    /// Writes challenges, receipts and pending solutions from the Sled database back out in the
+    /// old file-based layout (the reverse of `migrate-state`), e.g. to downgrade or to inspect
+    /// state with standard tools. Other records (error records, notes, history) are not exported.
+    #[command(author, about = "Export Sled DB state to the old file-based layout", after_help = "Examples:\n  shadow-harvester migrate-export --out-dir state-files && tar czf state-files.tar.gz state-files")]
+    MigrateExport {
+        /// Directory to write the file layout into; must not exist or be empty.
+        #[arg(long, default_value = "state-files")]
+        out_dir: String,
+    },
+
     /// Commands for managing stored challenges (list, import, info).
-    #[command(subcommand, author, about = "Manage local challenge state (list, import, info)")]
+    #[command(
+        subcommand,
+        author,
+        about = "Manage local challenge state (list, import, info, solution bundles)",
+        after_help = "Examples:\n  shadow-harvester challenge list\n  shadow-harvester challenge import --file challenge.json\n  shadow-harvester challenge details --id D07C21\n  shadow-harvester challenge note D07C21 \"API was flaky today\" --tag flaky"
+    )]
     Challenge(ChallengeCommands),
 
     /// Commands for inspecting known wallet addresses and derivations.
-    #[command(subcommand, author, about = "Inspect known wallet addresses")]
+    #[command(
+        subcommand,
+        author,
+        about = "Inspect known wallet addresses",
+        after_help = "Examples:\n  shadow-harvester wallet list\n  shadow-harvester wallet addresses --wallet 16886378742194182050:0\n  shadow-harvester wallet stats --mnemonic-file wallet.mnemonic --count 50"
+    )]
     Wallet(WalletCommands),
 
     /// Commands for backing up and restoring the Sled database.
-    #[command(subcommand, author, about = "Manage Sled database backup and restore")]
+    #[command(
+        subcommand,
+        author,
+        about = "Manage Sled database backup and restore",
+        after_help = "Examples:\n  shadow-harvester db export --file backup.json\n  shadow-harvester --data-dir new-state db import --file backup.json"
+    )]
     Db(DbCommands),
+
+    /// Commands for inspecting what the miner did in past runs.
+    #[command(
+        subcommand,
+        author,
+        about = "Inspect past mining cycles",
+        after_help = "Examples:\n  shadow-harvester history cycles\n  shadow-harvester history cycles --challenge D07C21 --limit 200"
+    )]
+    History(HistoryCommands),
+
+    /// Reports computed from the local database.
+    #[command(
+        subcommand,
+        author,
+        about = "Cross-check the local database",
+        after_help = "Examples:\n  shadow-harvester --donate-to addr1... report audit"
+    )]
+    Report(ReportCommands),
+
+    /// Read-only requests against the Scavenger Mine API, for debugging server behavior.
+    #[command(
+        subcommand,
+        author,
+        about = "Query API endpoints without touching local state",
+        after_help = "Examples:\n  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io api challenge\n  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io api stats addr1...\n  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io api tandc --json | jq .\n  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io api schema"
+    )]
+    Api(ApiCommands),
+
+    /// Offline ROM tools for cross-checking ROM generation against other implementations.
+    #[command(
+        subcommand,
+        author,
+        about = "Generate a ROM and print its digest",
+        after_help = "Examples:\n  shadow-harvester rom digest fd651ac2725e3b9d804cc8b161c0709af14d6264f93e8d4afef0fd1142a3f011\n  shadow-harvester rom digest <KEY> --output rom.bin"
+    )]
+    Rom(RomCommands),
+
+    /// Measures local hashrate on a throwaway ROM without contacting the API.
+    #[command(
+        author,
+        alias = "benchmark",
+        args_conflicts_with_subcommands = true,
+        about = "Benchmark ROM generation and hashrate offline",
+        after_help = "Examples:\n  shadow-harvester bench\n  shadow-harvester bench --seconds 30 --threads 4\n  shadow-harvester bench compare --config-a a.toml --config-b b.toml --duration 120"
+    )]
+    Bench {
+        #[command(subcommand)]
+        command: Option<BenchCommands>,
+
+        /// How long to hash, in seconds (after ROM generation).
+        #[arg(long, default_value_t = 60)]
+        seconds: u64,
+
+        /// Number of worker threads (defaults to the top-level --threads value).
+        #[arg(long)]
+        threads: Option<u32>,
+    },
+
+    /// Stores secrets in the OS keyring so they never sit in files or process arguments.
+    #[command(
+        subcommand,
+        author,
+        about = "Manage secrets in the OS keyring",
+        after_help = "Examples:\n  shadow-harvester keyring set mnemonic --from-file wallet.mnemonic && shred -u wallet.mnemonic\n  shadow-harvester keyring set db-passphrase\n  shadow-harvester --keyring --accept-tos --api-url https://scavenger.prod.gd.midnighttge.io"
+    )]
+    Keyring(KeyringCommands),
+
+    /// Opens a REPL against a running miner through its control socket.
+    #[command(
+        author,
+        about = "Control a running miner interactively",
+        after_help = "The miner listens on <data-dir>/control.sock unless started with --control-socket PATH\nor --no-control-socket. Pass the same --data-dir or --control-socket to the shell.\n\nExamples:\n  shadow-harvester --data-dir /var/lib/shadow-harvester shell\n  shadow-harvester --control-socket /run/shadow-harvester.sock shell\n  echo status | shadow-harvester --control-socket /run/shadow-harvester.sock shell"
+    )]
+    Shell,
+
+    /// Runs embedded known-answer vectors (ROM digest, VM hash, difficulty check) and reports pass/fail.
+    #[command(author, about = "Verify this binary computes hashes correctly")]
+    Selftest,
+
+    /// Runs the full miner against the built-in mock API with rapid challenges and injected
+    /// API faults, asserting database invariants periodically.
+    #[command(
+        author,
+        about = "Reliability soak test against the mock API",
+        after_help = "Uses an ephemeral key and a small ROM unless overridden, and a fresh temporary data directory\nunless --data-dir is given. Exits non-zero on the first invariant violation, keeping the data directory.\n\nExamples:\n  shadow-harvester soak --hours 24\n  shadow-harvester --threads 2 soak --hours 0.5 --challenge-secs 20 --fault-rate 0.2"
+    )]
+    Soak {
+        /// How long to run before shutting down cleanly.
+        #[arg(long, default_value_t = 24.0)]
+        hours: f64,
+
+        /// Port of the mock API server.
+        #[arg(long, default_value_t = 18080)]
+        port: u16,
+
+        /// Lifetime of each mock challenge in seconds.
+        #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(i64).range(5..))]
+        challenge_secs: i64,
+
+        /// Share of API requests (0.0-1.0) answered with a 503, a 429 or a delayed reply.
+        #[arg(long, default_value_t = 0.05)]
+        fault_rate: f64,
+
+        /// Seconds between database invariant checks.
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        check_interval: u64,
+    },
+
+    /// Prints copy-pasteable command lines for each mining mode.
+    #[command(author, about = "Show example command lines for each mode")]
+    Examples,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BenchCommands {
+    /// Runs alternating timed trials of two configurations on the same ROM and compares them.
+    #[command(
+        author,
+        about = "Compare the hashrate of two configurations (A/B)",
+        after_help = "Each config is a TOML file:\n  name = \"8 threads, pinned\"\n  threads = 8\n  affinity = [0, 1, 2, 3, 4, 5, 6, 7]   # optional, Linux only\n  backend = \"cpu\"                      # optional\n\nExamples:\n  shadow-harvester bench compare --config-a a.toml --config-b b.toml --duration 120"
+    )]
+    Compare {
+        /// TOML file with configuration A.
+        #[arg(long, value_name = "FILE")]
+        config_a: String,
+
+        /// TOML file with configuration B.
+        #[arg(long, value_name = "FILE")]
+        config_b: String,
+
+        /// Total hashing time per configuration, in seconds, split evenly across the trials.
+        #[arg(long, default_value_t = 120)]
+        duration: u64,
+
+        /// Number of trials per configuration. A and B alternate, so drift affects both alike.
+        #[arg(long, default_value_t = 6)]
+        trials: u32,
+    },
+}
+
+/// Output ordering for the listing commands (`challenge list`, `wallet list`, `wallet addresses`).
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Numeric ordering: challenges by day and challenge number, wallets and addresses by index.
+    #[default]
+    Natural,
+    /// Raw Sled key (byte) order, e.g. D10 before D2.
+    Key,
+}
+
+/// Storage backend for the state database used while mining.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DbBackend {
+    /// On-disk Sled database under `--data-dir`.
+    #[default]
+    Sled,
+    /// Temporary in-memory Sled database, discarded on exit.
+    Memory,
+}
+
+/// Which challenge source wins when several report one. A fixed --challenge always wins
+/// over the others, except under `require-match`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengePriority {
+    /// Mine the WebSocket's challenge; fall back to the API's.
+    PreferWebsocket,
+    /// Mine the API's challenge; fall back to the WebSocket's.
+    PreferApi,
+    /// Mine only when every enabled source reports the same challenge.
+    RequireMatch,
+}
+
+/// Cardano network the mining addresses belong to.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CardanoNetwork {
+    /// Mainnet (`addr1...` addresses).
+    #[default]
+    Mainnet,
+    /// A test network (`addr_test1...` addresses).
+    Testnet,
+    /// The pre-production test network (`addr_test1...` addresses).
+    Preprod,
+}
+
+impl CardanoNetwork {
+    /// Network id in the address header.
+    pub fn network_id(self) -> u8 {
+        match self {
+            CardanoNetwork::Mainnet => 1,
+            CardanoNetwork::Testnet | CardanoNetwork::Preprod => 0,
+        }
+    }
+}
+
+/// Language of user-facing messages.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    /// English.
+    En,
+    /// Spanish.
+    Es,
+}
+
+/// Format of files written by export commands.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+    /// A JSON array of objects.
+    Json,
+}
+
+/// Output format of the miner's log.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// Structured JSON events on stdout, text on stderr.
+    Json,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum ChallengeCommands {
     /// Lists all challenge IDs stored in the local Sled database.
-    List,
+    List {
+        /// Ordering of the listed challenges.
+        #[arg(long, value_enum, default_value_t = SortOrder::Natural)]
+        sort: SortOrder,
+    },
 
     /// Imports a challenge JSON file into the local Sled database for offline/custom mining.
     Import {
@@ -108,6 +737,25 @@ pub enum ChallengeCommands {
         file: String,
     },
 
+    /// Writes the solutions found for a challenge (pending queue and --offline bundle) to a
+    /// solution bundle, to be queued on a connected machine with `challenge import-solutions`.
+    #[command(after_help = "Examples:\n  shadow-harvester challenge export-solutions D07C21 --out d07c21-solutions.json\n  shadow-harvester --offline-export /mnt/usb/solutions.json challenge export-solutions D07C21 --out d07c21-solutions.json")]
+    ExportSolutions {
+        /// The ID of the challenge (e.g., D07C21).
+        id: String,
+        /// Path of the bundle file to write.
+        #[arg(long)]
+        out: String,
+    },
+
+    /// Queues the solutions of a bundle written by `challenge export-solutions` or --offline
+    /// mining for submission. Solutions that already have a receipt, are already queued or
+    /// whose submission window closed are skipped.
+    ImportSolutions {
+        /// Path of the solution bundle file.
+        file: String,
+    },
+
     /// Dumps the full JSON details of a specific challenge loaded from the Sled DB.
     Info {
         /// The ID of the challenge to display (e.g., D07C21).
@@ -146,6 +794,29 @@ pub enum ChallengeCommands {
         nonce: String,
     },
     Errors,
+
+    /// Re-validates a permanently failed solution (preimage rebuilt, hash recomputed against the
+    /// difficulty) and queues it for submission again, e.g. after the server rejected it
+    /// because of a bug on its side.
+    #[command(after_help = "Examples:\n  shadow-harvester challenge retry-error D07C21 addr1v...\n  shadow-harvester challenge retry-error D07C21 addr1v... --nonce 00000000deadbeef")]
+    RetryError {
+        /// The ID of the challenge (e.g., D07C21).
+        challenge_id: String,
+        /// The Cardano address of the failed solution.
+        address: String,
+        /// The nonce of the failed solution; needed when the address has several.
+        #[arg(long)]
+        nonce: Option<String>,
+    },
+
+    /// Prints the required zero bits of each day's difficulty mask and a naive prediction for
+    /// the next day. Days seen before difficulty tracking existed are filled in from stored
+    /// challenges.
+    DifficultyTrend,
+
+    /// Recomputes the hash of a stored receipt or error record, or of any `--nonce` for the
+    /// address, and states whether it meets the challenge's difficulty.
+    #[command(after_help = "Examples:\n  shadow-harvester challenge hash --challenge-id D07C21 --address addr1v...\n  shadow-harvester challenge hash --challenge-id D07C21 --address addr1v... --nonce 00000000deadbeef")]
     Hash {
         /// The ID of the challenge (e.g., D07C21).
         #[arg(long)]
@@ -153,19 +824,83 @@ pub enum ChallengeCommands {
         /// The Cardano address associated with the receipt.
         #[arg(long)]
         address: String,
+        /// Hashes this nonce (hex, e.g. from a rejected submission) instead of a stored receipt
+        /// or error record; the preimage is rebuilt from the nonce, address and challenge.
+        #[arg(long)]
+        nonce: Option<String>,
     },
+
+    /// Attaches a note and tags to a challenge, shown by `challenge list` and `challenge details`.
+    /// Without a note or tag options, prints the current annotation.
+    #[command(after_help = "Examples:\n  shadow-harvester challenge note D08C02 \"API was flaky today\"\n  shadow-harvester challenge note D08C02 --tag flaky --tag retried\n  shadow-harvester challenge note D08C02 --clear")]
+    Note {
+        /// The ID of the challenge (e.g., D08C02).
+        id: String,
+        #[command(flatten)]
+        edit: NoteEdit,
+    },
+
+    /// Re-hashes stored receipts and error records in parallel: receipt hashes must meet their
+    /// challenge's difficulty, error-record hashes must match the hash recorded at the time.
+    Verify {
+        /// Only verify entries for this challenge ID.
+        #[arg(long)]
+        challenge_id: Option<String>,
+        /// Which stored entries to verify.
+        #[arg(long, value_enum, default_value_t = VerifySource::All)]
+        source: VerifySource,
+        /// Number of hashing threads (defaults to the top-level --threads value).
+        #[arg(long)]
+        threads: Option<u32>,
+    },
+}
+
+/// Changes made by the `note` subcommands.
+#[derive(Args, Debug, Clone)]
+pub struct NoteEdit {
+    /// Freeform note; replaces the previous one.
+    pub text: Option<String>,
+    /// Add a tag (repeatable).
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// Remove a tag (repeatable).
+    #[arg(long = "untag", value_name = "TAG")]
+    pub untags: Vec<String>,
+    /// Remove the note and all tags.
+    #[arg(long, conflicts_with_all = ["text", "tags", "untags"])]
+    pub clear: bool,
+}
+
+/// Stored entries `challenge verify` re-hashes.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifySource {
+    /// Receipts and error records.
+    #[default]
+    All,
+    /// Receipts of accepted solutions.
+    Receipts,
+    /// Records of permanently failed submissions.
+    Errors,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum WalletCommands {
-    /// Lists unique wallet identifiers (Mnemonic Hash:Account Index) found in the database.
-    List,
+    /// Lists wallet identifiers (Mnemonic Hash:Account Index) found in the database, each with its
+    /// first derived address, number of known indices, receipt count and last receipt time.
+    List {
+        /// Ordering of the listed wallet identifiers.
+        #[arg(long, value_enum, default_value_t = SortOrder::Natural)]
+        sort: SortOrder,
+    },
 
     /// Lists all known addresses and derivation paths (<index>:<address>) for a specific wallet hash.
     Addresses {
         /// The unique wallet identifier (Mnemonic Hash:Account Index) to inspect (e.g., 16886378742194182050:0).
         #[arg(long)]
         wallet: String,
+        /// Ordering of the listed addresses.
+        #[arg(long, value_enum, default_value_t = SortOrder::Natural)]
+        sort: SortOrder,
     },
 
     /// Lists all challenge IDs that a specific address has a receipt for.
@@ -174,6 +909,60 @@ pub enum WalletCommands {
         #[arg(long)]
         address: String,
     },
+    /// Writes the challenge ID, nonce, timestamp and receipt signature of every receipt of an
+    /// address to a CSV or JSON file, e.g. to reconcile rewards in a spreadsheet.
+    #[command(after_help = "Examples:\n  shadow-harvester wallet export-receipts --address addr1v... --format csv --file receipts.csv")]
+    ExportReceipts {
+        /// The Cardano address whose receipts to export.
+        #[arg(long)]
+        address: String,
+        /// Format of the written file.
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Path of the file to write.
+        #[arg(long)]
+        file: String,
+    },
+    /// Compares an address's local receipts with the receipt count the API reports (GET
+    /// /statistics) and flags challenges whose submission was accepted remotely without a local
+    /// receipt, or local receipts the API does not count. Exits non-zero when they disagree.
+    #[command(after_help = "Examples:\n  shadow-harvester --api-url https://scavenger.prod.gd.midnighttge.io wallet reconcile --address addr1v...")]
+    Reconcile {
+        /// The Cardano address to reconcile.
+        #[arg(long)]
+        address: String,
+    },
+    /// Attaches a note and tags to an address, shown by `wallet addresses`. Without a note or tag
+    /// options, prints the current annotation.
+    Note {
+        /// The Cardano address to annotate.
+        address: String,
+        #[command(flatten)]
+        edit: NoteEdit,
+    },
+    /// Fetches API statistics (receipts, NIGHT allocation) for a range of mnemonic-derived addresses concurrently.
+    Stats {
+        /// Use base addresses instead of enterprise
+        #[arg(long)]
+        base: bool,
+        /// 24-word BIP39 mnemonic phrase for sequential address generation.
+        #[arg(long, env = "SH_MNEMONIC", hide_env_values = true)]
+        mnemonic: Option<String>,
+        #[arg(long, env = "SH_MNEMONIC_FILE")]
+        mnemonic_file: Option<String>,
+        /// The mnemonic account index to derive addresses from.
+        #[arg(long, default_value_t = 0)]
+        mnemonic_account: u32,
+        /// The first derivation index to fetch.
+        #[arg(long, default_value_t = 0)]
+        mnemonic_starting_index: u32,
+        /// The number of sequential derivation indices to fetch.
+        #[arg(long, default_value_t = 20)]
+        count: u32,
+        /// Maximum number of statistics requests in flight at once.
+        #[arg(long, default_value_t = crate::sweep::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
     /// Iterates through mnemonic derivation indices and runs the donate_to API call until an error is returned.
     DonateAll {
         /// Use base addresses instead of enterprise
@@ -183,9 +972,9 @@ pub enum WalletCommands {
         #[arg(long)]
         donate_to: String,
         /// 24-word BIP39 mnemonic phrase for sequential address generation.
-        #[arg(long)]
+        #[arg(long, env = "SH_MNEMONIC", hide_env_values = true)]
         mnemonic: Option<String>,
-        #[arg(long)]
+        #[arg(long, env = "SH_MNEMONIC_FILE")]
         mnemonic_file: Option<String>,
         /// The mnemonic account index to start derivation from.
         #[arg(long, default_value_t = 0)]
@@ -199,6 +988,137 @@ pub enum WalletCommands {
         /// The maximum number of donate_to iterations, 0 for unlimited.
         #[arg(long, default_value_t = 0)]
         max_iteration: u32,
+        /// Continue an interrupted sweep at the index it stopped at, instead of
+        /// --mnemonic-starting-index.
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Writes a JSON document listing the addresses this mnemonic mined with (from the local
+    /// mnemonic index) and their receipts, each entry signed by the address's key, to prove
+    /// control over the mined addresses to a third party.
+    Attest {
+        /// 24-word BIP39 mnemonic phrase the addresses were derived from.
+        #[arg(long, env = "SH_MNEMONIC", hide_env_values = true)]
+        mnemonic: Option<String>,
+        #[arg(long, env = "SH_MNEMONIC_FILE")]
+        mnemonic_file: Option<String>,
+        /// The mnemonic account index to attest.
+        #[arg(long, default_value_t = 0)]
+        mnemonic_account: u32,
+        /// Free text included in every signed message, e.g. "For pool XYZ, 2026-10".
+        #[arg(long)]
+        statement: Option<String>,
+        /// Leave out addresses without receipts.
+        #[arg(long)]
+        with_receipts_only: bool,
+        /// Path of the attestation file to write.
+        #[arg(long)]
+        file: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum HistoryCommands {
+    /// Lists recorded mining cycles (oldest first) with their trigger, outcome, skipped indices
+    /// and the current state of each submission they produced.
+    Cycles {
+        /// Only show cycles for this challenge ID.
+        #[arg(long)]
+        challenge: Option<String>,
+        /// Only show cycles for this address.
+        #[arg(long)]
+        address: Option<String>,
+        /// Show at most this many of the most recent cycles.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReportCommands {
+    /// Cross-references every receipt with a donation record and lists addresses whose rewards
+    /// are not assigned to the --donate-to target (or to any address without --donate-to).
+    /// Exits with an error when any address is unassigned, so scripts can alert on it.
+    Audit,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RomCommands {
+    /// Generates the ROM for a challenge's ROM key (`no_pre_mine`) and prints its digest.
+    /// Honors the top-level --rom-size/--rom-pre-size/--rom-mixing-numbers overrides.
+    Digest {
+        /// The ROM key as published in the challenge (hex). Its text is used as the seed input,
+        /// exactly as the miner does.
+        key: String,
+        /// Also write the raw ROM bytes (no header) to this file.
+        #[arg(long, value_name = "PATH")]
+        output: Option<String>,
+    },
+}
+
+/// Secrets that can be kept in the OS keyring.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyringSecret {
+    /// BIP39 mnemonic phrase (used like --mnemonic).
+    Mnemonic,
+    /// Hex-encoded payment secret key (used like --payment-key).
+    PaymentKey,
+    /// Database encryption passphrase (used like --db-passphrase-file).
+    DbPassphrase,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum KeyringCommands {
+    /// Stores a secret, read from the first line of stdin unless --from-file is given.
+    Set {
+        secret: KeyringSecret,
+        /// Read the secret from the first line of this file instead of stdin.
+        #[arg(long, value_name = "PATH")]
+        from_file: Option<String>,
+    },
+    /// Removes a stored secret.
+    Delete {
+        secret: KeyringSecret,
+    },
+    /// Shows which secrets are stored (never their values).
+    Status,
+}
+
+/// Each command sends one GET with the miner's HTTP client and headers and prints the HTTP
+/// status and the response body, including error bodies from non-2xx responses.
+#[derive(Subcommand, Debug, Clone)]
+pub enum ApiCommands {
+    /// GET /challenge: the current challenge status and parameters.
+    Challenge {
+        /// Print only the raw JSON body (for piping into other tools).
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// GET /statistics/<ADDRESS>: the server's statistics for one address.
+    Stats {
+        /// The registered address to look up.
+        address: String,
+        /// Print only the raw JSON body (for piping into other tools).
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// GET /TandC/<VERSION>: the Terms and Conditions and the message signed at registration.
+    Tandc {
+        /// Terms and Conditions version to fetch.
+        #[arg(long, default_value = "1-0")]
+        version: String,
+        /// Print only the raw JSON body (for piping into other tools).
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// GET /challenge and compare its fields against the ones this build reads.
+    Schema {
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -217,4 +1137,45 @@ pub enum DbCommands {
         #[arg(long, default_value = "backup.json")]
         file: String,
     },
+
+    /// Writes a checksummed snapshot of the whole database, e.g. before upgrading or moving
+    /// machines. While the miner runs, the snapshot is taken by its state worker in one step.
+    #[command(after_help = "Examples:\n  shadow-harvester db backup state-2026-10-16.backup.json\n  shadow-harvester --data-dir new-state db restore state-2026-10-16.backup.json")]
+    Backup {
+        /// Path of the backup file to write.
+        path: String,
+    },
+
+    /// Restores a `db backup` snapshot (or a `db export` file) into the database. The miner must
+    /// be stopped, and the database must be empty unless --force is given.
+    Restore {
+        /// Path of the backup file to read.
+        path: String,
+        /// Replace every existing entry. The old entries are removed and the backup written in
+        /// one atomic batch.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Merges another miner's state directory into this database: challenges, receipts,
+    /// pending solutions, mnemonic index records, error records, donations and cycle records.
+    /// On a receipt collision the entry with a receipt wins, then the newer receipt; pending
+    /// solutions of addresses with a receipt are dropped. Other records are added when missing.
+    /// The other database is only read; its miner must be stopped.
+    #[command(after_help = "Examples:\n  shadow-harvester db merge /mnt/rig2/state\n  for dir in rig2 rig3; do shadow-harvester db merge \"$dir/state\"; done")]
+    Merge {
+        /// The other miner's --data-dir (the directory holding state.sled).
+        other_state_dir: String,
+    },
+
+    /// Scans the database for inconsistent entries: pending solutions of unknown challenges,
+    /// unparsable records, nonces stored twice and gaps in the mnemonic address index. Exits
+    /// non-zero while problems remain.
+    #[command(after_help = "Examples:\n  shadow-harvester db check\n  shadow-harvester --mnemonic-file wallet.mnemonic db check --fix")]
+    Check {
+        /// Repair what can be repaired: remove redundant or undecodable entries and, with
+        /// --mnemonic or --mnemonic-file, fill index gaps of that wallet.
+        #[arg(long)]
+        fix: bool,
+    },
 }
@@ -1,33 +1,136 @@
 // src/cli_commands.rs
 
-use crate::cli::{Cli, Commands, ChallengeCommands, WalletCommands, DbCommands};
-use crate::persistence::Persistence;
-use crate::data_types::{ChallengeData, FailedSolution, BackupEntry};
+use crate::cli::{Cli, Commands, ChallengeCommands, WalletCommands, DbCommands, ExportFormat, HistoryCommands, ReportCommands, NoteEdit, SortOrder, VerifySource};
+use crate::state_store::StateStore;
+use crate::data_types::{Annotation, Attestation, AttestedAddress, AttestedReceipt, ATTESTATION_FORMAT, ChallengeData, CycleRecord, DifficultyRecord, DonationRecord, BackupEntry, DbBackup, SLED_KEY_CYCLE, SLED_KEY_DIFFICULTY, SLED_KEY_DONATION};
 use crate::utils;
 use crate::cardano;
 use crate::api;
-use crate::data_types::SLED_KEY_FAILED_SOLUTION;
-use regex::Regex;
-use std::collections::{HashSet, HashMap};
+use crate::sweep;
+use crate::verify::{self, Verdict, VerifyEntry};
+use crate::exit_codes::{self, ExitError, WithExitCode};
+use crate::data_types::{PendingSolution, SignedSolution, SolutionBundle};
+use crate::offline;
+use crate::persistence::{Persistence, SLED_KEY_DB_ENCRYPTION};
+use crate::schema::{challenge_key, FailedSolutionKey, MnemonicIndexKey, PendingKey, ReceiptKey, StateSchema, RECEIPT_STATUS_CONSUMED_MARKER, SLED_KEY_CHALLENGE, SLED_KEY_DONATE_PROGRESS};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-
-// Key prefixes for SLED to organize data
-const SLED_KEY_CHALLENGE: &str = "challenge";
-const SLED_KEY_RECEIPT: &str = "receipt";
-const SLED_KEY_PENDING: &str = "pending";
-const SLED_KEY_MNEMONIC_INDEX: &str = "mnemonic_index";
-const SLED_DB_FILENAME: &str = "state.sled";
-
-fn http_code_from_err(e: &str) -> Option<u16> {
-    let re = Regex::new(r"\b(\d{3})\b").unwrap();
-    re.captures(e)
-        .and_then(|c| c.get(1))
-        .and_then(|m| m.as_str().parse::<u16>().ok())
+
+/// Resolves the mnemonic phrase from either `--mnemonic` or `--mnemonic-file` (exactly one is required).
+fn resolve_mnemonic(mnemonic: Option<String>, mnemonic_file: Option<String>) -> Result<String, ExitError> {
+    match (mnemonic, mnemonic_file) {
+        (Some(_), Some(_)) => Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use both '--mnemonic' and '--mnemonic-file' flags simultaneously.")),
+        (None, Some(file_path)) => fs::read_to_string(&file_path)
+            .map(|content| content.trim().to_string())
+            .map_err(|e| format!("🚨 Failed to read mnemonic file {}: {}", file_path, e))
+            .exit_code(exit_codes::INVALID_KEY_MATERIAL),
+        (Some(phrase), None) => Ok(phrase),
+        (None, None) => Err(ExitError::new(exit_codes::USAGE_ERROR, "FATAL: Either '--mnemonic' or '--mnemonic-file' must be specified.")),
+    }
+}
+
+/// Records a successful donation for `report audit`.
+fn save_donation(persistence: &StateStore, record: &DonationRecord) -> Result<(), String> {
+    let json = serde_json::to_string(record).map_err(|e| format!("Failed to serialize donation record: {}", e))?;
+    persistence.set(&record.sled_key(), &json)
+}
+
+/// Current state of a submission key recorded in a cycle: receipt, still pending, failed, or unknown.
+fn submission_state(persistence: &StateStore, pending_key: &str) -> Result<String, String> {
+    let Some(key) = PendingKey::parse(pending_key) else {
+        return Ok("unknown".to_string());
+    };
+
+    if persistence.has_receipt(&key.address, &key.challenge_id)? {
+        let status = persistence.get_receipt(&key.address, &key.challenge_id).ok().flatten()
+            .and_then(|v| v.get("status").and_then(|s| s.as_str()).map(str::to_string));
+        return Ok(match status {
+            Some(status) => format!("receipt ({})", status),
+            None => "receipt".to_string(),
+        });
+    }
+    if persistence.get_pending(&key).is_ok_and(|pending| pending.is_some()) {
+        return Ok("pending".to_string());
+    }
+    for entry in persistence.iter_failed(Some(&key.address), Some(&key.challenge_id))? {
+        if let Ok(failed) = entry.value
+            && failed.nonce == key.nonce
+        {
+            return Ok(format!("failed ({})", failed.error_message));
+        }
+    }
+    Ok("unknown".to_string())
+}
+
+/// Sort key for a stored challenge: (day, challenge_number, id).
+/// Challenges whose JSON cannot be parsed sort after all parseable ones.
+fn challenge_sort_key(challenge_id: &str, challenge_json: &str) -> (u8, u16, String) {
+    match serde_json::from_str::<ChallengeData>(challenge_json) {
+        Ok(data) => (data.day, data.challenge_number, challenge_id.to_string()),
+        Err(_) => (u8::MAX, u16::MAX, challenge_id.to_string()),
+    }
+}
+
+/// Sort key for a wallet identifier (<HASH>:<ACCOUNT>), comparing both parts numerically.
+fn wallet_identifier_sort_key(identifier: &str) -> (u64, u32, String) {
+    let mut parts = identifier.split(':');
+    let hash = parts.next().and_then(|h| h.parse::<u64>().ok()).unwrap_or(u64::MAX);
+    let account = parts.next().and_then(|a| a.parse::<u32>().ok()).unwrap_or(u32::MAX);
+    (hash, account, identifier.to_string())
+}
+
+/// Reads the annotation of a challenge or address (`kind` is "challenge" or "address").
+fn load_annotation(persistence: &StateStore, kind: &str, id: &str) -> Result<Option<Annotation>, String> {
+    persistence.get(&Annotation::sled_key(kind, id))?
+        .map(|json| serde_json::from_str(&json).map_err(|e| format!("Failed to parse note for {} {}: {}", kind, id, e)))
+        .transpose()
+}
+
+/// Applies a `note` subcommand and prints the resulting annotation.
+fn run_note_command(persistence: &StateStore, kind: &str, id: &str, edit: NoteEdit) -> Result<(), String> {
+    let key = Annotation::sled_key(kind, id);
+    let mut annotation = load_annotation(persistence, kind, id)?.unwrap_or_default();
+
+    if edit.clear {
+        persistence.remove(&key)?;
+        cprintln!("🗑️ Cleared the note and tags of {} {}.", kind, id);
+        return Ok(());
+    }
+
+    let changed = edit.text.is_some() || !edit.tags.is_empty() || !edit.untags.is_empty();
+    if changed {
+        if let Some(text) = edit.text {
+            annotation.note = Some(text).filter(|text| !text.trim().is_empty());
+        }
+        for tag in edit.tags {
+            if !annotation.tags.contains(&tag) {
+                annotation.tags.push(tag);
+            }
+        }
+        annotation.tags.retain(|tag| !edit.untags.contains(tag));
+        annotation.updated_at = chrono::Utc::now().to_rfc3339();
+
+        if annotation.is_empty() {
+            persistence.remove(&key)?;
+        } else {
+            let json = serde_json::to_string(&annotation).map_err(|e| format!("Failed to serialize note: {}", e))?;
+            persistence.set(&key, &json)?;
+        }
+    }
+
+    if annotation.is_empty() {
+        cprintln!("No note or tags on {} {}.", kind, id);
+    } else {
+        cprintln!("📝 {} {}", kind, id);
+        cprintln!("  Note:    {}", annotation.note.as_deref().unwrap_or("-"));
+        cprintln!("  Tags:    {}", if annotation.tags.is_empty() { "-".to_string() } else { annotation.tags.join(", ") });
+        cprintln!("  Updated: {}", annotation.updated_at);
+    }
+    Ok(())
 }
 
 /// Helper function to insert a key-value pair only if the key is NOT already present.
-fn sync_insert_if_not_exists(persistence: &Persistence, key: &str, value: &str) -> Result<bool, String> {
+fn sync_insert_if_not_exists(persistence: &StateStore, key: &str, value: &str) -> Result<bool, String> {
     // Check if the key exists using the Persistence method.
     match persistence.get(key)? {
         Some(_) => Ok(false), // Key exists, return false (did not insert)
@@ -39,77 +142,749 @@ fn sync_insert_if_not_exists(persistence: &Persistence, key: &str, value: &str)
     }
 }
 
+/// Days of history the difficulty prediction averages over.
+const DIFFICULTY_TREND_WINDOW: usize = 7;
+
+/// Naive prediction of the next day's required zero bits: the last value plus the average
+/// day-over-day change across the last `DIFFICULTY_TREND_WINDOW` days.
+fn predict_zero_bits(bits: &[u32]) -> Option<f64> {
+    let last = *bits.last()?;
+    let recent = &bits[bits.len().saturating_sub(DIFFICULTY_TREND_WINDOW)..];
+    let average_change = if recent.len() < 2 {
+        0.0
+    } else {
+        (recent[recent.len() - 1] as f64 - recent[0] as f64) / (recent.len() - 1) as f64
+    };
+    Some((last as f64 + average_change).clamp(0.0, 32.0))
+}
+
+/// Loads one difficulty record per day: persisted records, plus the latest stored challenge
+/// of each day that has no record.
+fn load_difficulty_history(persistence: &StateStore) -> Result<BTreeMap<u8, DifficultyRecord>, String> {
+    let mut days = BTreeMap::new();
+    for entry in persistence.iter_challenges()? {
+        let Ok(challenge) = entry.value else {
+            continue;
+        };
+        let newer = days.get(&challenge.day).is_none_or(|(number, _): &(u16, DifficultyRecord)| challenge.challenge_number >= *number);
+        if newer {
+            days.insert(challenge.day, (challenge.challenge_number, DifficultyRecord::from_challenge(&challenge)));
+        }
+    }
+    let mut history: BTreeMap<u8, DifficultyRecord> = days.into_iter().map(|(day, (_, record))| (day, record)).collect();
+
+    for (_, value) in persistence.scan_prefix(&format!("{}:", SLED_KEY_DIFFICULTY))? {
+        let record: DifficultyRecord = serde_json::from_str(&value)
+            .map_err(|e| format!("Failed to parse difficulty record: {}", e))?;
+        history.insert(record.day, record);
+    }
+    Ok(history)
+}
+
+/// Writes the solutions found for `challenge_id` to a bundle at `out`: those waiting in the
+/// pending queue (unsigned) and those in the --offline bundle (signed).
+fn run_export_solutions(cli: &Cli, persistence: &StateStore, challenge_id: &str, out: &str) -> Result<(), ExitError> {
+    let mut bundle = SolutionBundle::default();
+    if let Some(challenge) = persistence.get_challenge(challenge_id)? {
+        bundle.add_challenge(&challenge);
+    }
+
+    // The offline bundle holds signed copies; take them first so they win over queue entries.
+    let offline_path = offline::export_path(cli.offline_export.as_deref(), cli.data_dir.as_deref());
+    if offline_path.exists() {
+        let offline_bundle = offline::read_bundle(&offline_path)?;
+        for challenge in offline_bundle.challenges.iter().filter(|c| c.challenge_id == challenge_id) {
+            bundle.add_challenge(challenge);
+        }
+        for signed in offline_bundle.solutions.into_iter().filter(|s| s.solution.challenge_id == challenge_id) {
+            bundle.add_solution(signed);
+        }
+    }
+
+    for entry in persistence.iter_pending(None)?.into_iter().filter(|entry| entry.key.challenge_id == challenge_id) {
+        match entry.value {
+            Ok(solution) => { bundle.add_solution(SignedSolution::unsigned(solution)); }
+            Err(e) => ceprintln!("⚠️ Skipping pending entry: {}", e),
+        }
+    }
+
+    if bundle.solutions.is_empty() {
+        return Err(ExitError::new(exit_codes::NOT_FOUND, format!("No solutions found for challenge '{}' (pending queue and {}).", challenge_id, offline_path.display())));
+    }
+    if bundle.challenges.is_empty() {
+        ceprintln!("⚠️ Challenge '{}' is not stored locally; the bundle carries its solutions only.", challenge_id);
+    }
+    offline::write_bundle(std::path::Path::new(out), &bundle)?;
+    cprintln!("✅ Exported {} solution(s) for challenge '{}' to {}.", bundle.solutions.len(), challenge_id, out);
+    Ok(())
+}
+
+/// Queues the solutions of the bundle at `file` for submission, storing the challenges it
+/// carries when they are not known yet.
+fn run_import_solutions(persistence: &StateStore, file: &str) -> Result<(), String> {
+    let bundle = offline::read_bundle(std::path::Path::new(file))?;
+
+    for challenge in &bundle.challenges {
+        if persistence.read(&challenge_key(&challenge.challenge_id))?.is_none() {
+            persistence.put_challenge(challenge)?;
+            cprintln!("📥 Stored challenge '{}' from the bundle.", challenge.challenge_id);
+        }
+    }
+
+    let (mut queued, mut skipped) = (0, 0);
+    for SignedSolution { solution, .. } in bundle.solutions {
+        let label = format!("{} on {} (nonce {})", solution.address, solution.challenge_id, solution.nonce);
+        let deadline = persistence.get_challenge(&solution.challenge_id).ok().flatten()
+            .map(|challenge| challenge.latest_submission);
+
+        let skip_reason = if persistence.has_receipt(&solution.address, &solution.challenge_id)? {
+            Some("already has a receipt")
+        } else if persistence.read(&PendingKey::of(&solution).to_string())?.is_some() {
+            Some("already queued")
+        } else if deadline.as_deref().is_some_and(shadow_harvester_lib::submission_deadline_passed) {
+            Some("submission window closed")
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            cprintln!("⏭️ Skipping {}: {}.", label, reason);
+            skipped += 1;
+            continue;
+        }
+
+        persistence.put_pending(&solution)?;
+        cprintln!("📦 Queued {}.", label);
+        queued += 1;
+    }
+
+    cprintln!("✅ Queued {} solution(s) from {}, skipped {}.", queued, file, skipped);
+    if queued > 0 {
+        submit_queued(persistence)?;
+    }
+    Ok(())
+}
+
+/// Has a running miner submit the pending queue, or says how to once it runs.
+fn submit_queued(persistence: &StateStore) -> Result<(), String> {
+    match persistence.retry_pending()? {
+        Some(started) => cprintln!("🔁 The running miner started {} submission(s).", started),
+        None => cprintln!("ℹ️ Start the miner and send it `retry-pending` (shell or POST /pending/retry) to submit the queue."),
+    }
+    Ok(())
+}
+
+/// Recomputes the hash of `preimage` on the ROM of `challenge`.
+fn rehash_preimage(cli: &Cli, challenge: &ChallengeData, preimage: &str) -> Result<[u8; 64], String> {
+    use shadow_harvester_lib::{ChallengeHasher, CpuHasher, Rom};
+
+    let rom_params = utils::rom_overrides(cli)?.resolve(Some(challenge));
+    rom_params.validate()?;
+    let rom_key = challenge.no_pre_mine_key.clone();
+    let rom = crate::shutdown::run_cancellable(move || Rom::from_params(rom_key.as_bytes(), &rom_params))?;
+    Ok(CpuHasher::new(std::sync::Arc::new(rom)).hash(preimage.as_bytes()))
+}
+
+/// Re-validates a permanently failed solution and moves it from the error records back to the
+/// pending queue.
+fn run_retry_error(cli: &Cli, persistence: &StateStore, challenge_id: &str, address: &str, nonce: Option<&str>) -> Result<(), ExitError> {
+    let challenge = persistence.get_challenge(challenge_id)?
+        .ok_or_else(|| ExitError::new(exit_codes::NOT_FOUND, format!("Challenge ID '{}' not found in Sled DB.", challenge_id)))?;
+
+    if persistence.has_receipt(address, challenge_id)? {
+        return Err(format!("Address {} already has a receipt for challenge '{}'.", address, challenge_id).into());
+    }
+    if shadow_harvester_lib::submission_deadline_passed(&challenge.latest_submission) {
+        return Err(ExitError::new(exit_codes::DEADLINE_PASSED, format!("The submission window of challenge '{}' closed at {}.", challenge_id, challenge.latest_submission)));
+    }
+
+    let mut records = Vec::new();
+    for entry in persistence.iter_failed(Some(address), Some(challenge_id))? {
+        let failed = entry.value?;
+        if nonce.is_none_or(|wanted| wanted.eq_ignore_ascii_case(&failed.nonce)) {
+            records.push((entry.key, failed));
+        }
+    }
+    let (error_key, failed) = match records.len() {
+        0 => return Err(ExitError::new(exit_codes::NOT_FOUND, format!("No permanent Error Record found for challenge '{}' and address '{}'{}.", challenge_id, address,
+            nonce.map(|n| format!(" with nonce {}", n)).unwrap_or_default()))),
+        1 => records.remove(0),
+        _ => return Err(format!("Address {} has {} Error Records for challenge '{}'; pick one with --nonce ({}).", address, records.len(), challenge_id,
+            records.iter().map(|(_, f)| f.nonce.as_str()).collect::<Vec<_>>().join(", ")).into()),
+    };
+
+    // 1. The preimage must be the one the API rebuilds from nonce, address and challenge.
+    let mask = u32::from_str_radix(&challenge.difficulty, 16)
+        .map_err(|_| format!("Challenge {} has a non-hex difficulty '{}'.", challenge_id, challenge.difficulty))?;
+    let nonce_value = u64::from_str_radix(&failed.nonce, 16)
+        .map_err(|_| format!("Stored nonce '{}' is not hex.", failed.nonce))?;
+    let expected_preimage = shadow_harvester_lib::build_preimage(
+        nonce_value, address, &challenge.challenge_id, mask,
+        &challenge.no_pre_mine_key, &challenge.latest_submission, &challenge.no_pre_mine_hour_str,
+    );
+    if failed.preimage != expected_preimage {
+        return Err(format!("Stored preimage does not match the nonce, address and challenge; not resubmitting.\n  stored:   {}\n  expected: {}", failed.preimage, expected_preimage).into());
+    }
+
+    // 2. The recomputed hash must meet the difficulty.
+    cprintln!("🔎 Recomputing the hash of nonce {} (building the ROM)...", failed.nonce);
+    let hash = rehash_preimage(cli, &challenge, &failed.preimage)?;
+    let hash_hex = hex::encode(hash);
+    if hash_hex != failed.hash_output {
+        ceprintln!("⚠️ Recomputed hash differs from the one recorded at submission time ({}); using the recomputed hash.", failed.hash_output);
+    }
+    if !shadow_harvester_lib::hash_structure_good(&hash, mask) {
+        return Err(format!("Recomputed hash {} does not meet difficulty {}; the rejection stands.", hash_hex, challenge.difficulty).into());
+    }
+    cprintln!("✅ Solution passes local validation (hash {}).", hash_hex);
+
+    // 3. Back into the pending queue; the error record goes, a new rejection records it again.
+    let solution = PendingSolution {
+        address: failed.address,
+        challenge_id: failed.challenge_id,
+        nonce: failed.nonce,
+        donation_address: None,
+        preimage: failed.preimage,
+        hash_output: hash_hex,
+        registration: None,
+        donation_signature: None,
+    };
+    persistence.put_pending(&solution)?;
+    persistence.remove_failed(&error_key)?;
+    cprintln!("📦 Queued {} for submission.", PendingKey::of(&solution));
+    submit_queued(persistence).map_err(ExitError::from)
+}
+
+/// One row of `wallet export-receipts`.
+#[derive(serde::Serialize)]
+struct ReceiptRow {
+    challenge_id: String,
+    nonce: String,
+    timestamp: String,
+    signature: String,
+    /// "accepted", or the status of a marker receipt (e.g. solved_by_network).
+    status: String,
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Nonce of a stored receipt. The API's crypto_receipt preimage starts with it; markers carry it
+/// as a field.
+fn receipt_nonce(receipt: &serde_json::Value) -> Option<&str> {
+    const NONCE_HEX_LENGTH: usize = 16;
+    receipt["nonce"].as_str()
+        .or_else(|| receipt["preimage"].as_str().and_then(|p| p.get(..NONCE_HEX_LENGTH)))
+}
+
+/// Writes every receipt of `address` to `file`, sorted by timestamp.
+fn run_export_receipts(persistence: &StateStore, address: &str, format: ExportFormat, file: &str) -> Result<(), String> {
+    let mut rows = Vec::new();
+    for entry in persistence.iter_receipts(Some(address))? {
+        let receipt = match entry.value {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                ceprintln!("⚠️ Skipping receipt: {}", e);
+                continue;
+            }
+        };
+        let text = |field: &str| receipt[field].as_str().unwrap_or_default().to_string();
+        let nonce = receipt_nonce(&receipt).unwrap_or_default().to_string();
+        rows.push(ReceiptRow {
+            challenge_id: entry.key.challenge_id,
+            nonce,
+            timestamp: text("timestamp"),
+            signature: text("signature"),
+            status: receipt["status"].as_str().unwrap_or("accepted").to_string(),
+        });
+    }
+    rows.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.challenge_id.cmp(&b.challenge_id)));
+
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&rows).map_err(|e| format!("Failed to serialize receipts: {}", e))?,
+        ExportFormat::Csv => {
+            let mut csv = String::from("challenge_id,nonce,timestamp,signature,status\n");
+            for row in &rows {
+                let fields = [&row.challenge_id, &row.nonce, &row.timestamp, &row.signature, &row.status];
+                csv.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+    };
+    fs::write(file, content).map_err(|e| format!("Failed to write {}: {}", file, e))?;
+    cprintln!("✅ Exported {} receipt(s) of {} to {}.", rows.len(), address, file);
+    Ok(())
+}
+
+/// One inconsistency found by `db check`, with the writes (None: remove) that repair it, if any.
+struct CheckFinding {
+    check: &'static str,
+    detail: String,
+    repair: Vec<(String, Option<String>)>,
+}
+
+/// Scans the database for inconsistent entries and, with `fix`, repairs them. Repairs only
+/// drop entries that can never be submitted or that repeat a nonce the API already answered,
+/// and fill mnemonic index gaps from the mnemonic given on the command line.
+fn run_db_check(cli: &Cli, persistence: &StateStore, fix: bool) -> Result<(), String> {
+    let mut findings = Vec::new();
+    let mut finding = |check, detail, repair| findings.push(CheckFinding { check, detail, repair });
+
+    let mut challenges = HashSet::new();
+    for entry in persistence.iter_challenges()? {
+        match entry.value {
+            Ok(_) => { challenges.insert(entry.key); }
+            Err(e) => finding("Unparsable challenges", e, Vec::new()),
+        }
+    }
+
+    // A receipt that does not parse still marks its address as done, so it is only reported.
+    let mut receipt_nonces = HashMap::new();
+    for entry in persistence.iter_receipts(None)? {
+        match entry.value {
+            Ok(receipt) => {
+                let nonce = receipt_nonce(&receipt).unwrap_or_default().to_string();
+                receipt_nonces.insert((entry.key.address, entry.key.challenge_id), nonce);
+            }
+            Err(e) => finding("Unparsable receipts", e, Vec::new()),
+        }
+    }
+    let receipted = |key: &PendingKey| receipt_nonces.get(&(key.address.clone(), key.challenge_id.clone())) == Some(&key.nonce);
+
+    let mut failed_nonces = HashSet::new();
+    for entry in persistence.iter_failed(None, None)? {
+        let stored_key = entry.key.to_string();
+        match entry.value {
+            Ok(failed) => {
+                let key = PendingKey::new(&failed.address, &failed.challenge_id, &failed.nonce);
+                if receipted(&key) {
+                    finding("Duplicate nonces", format!("{}: the nonce also has a receipt", stored_key), vec![(stored_key, None)]);
+                } else {
+                    failed_nonces.insert(key.to_string());
+                }
+            }
+            Err(e) => finding("Unparsable error records", e, vec![(stored_key, None)]),
+        }
+    }
+
+    for entry in persistence.iter_pending(None)? {
+        let stored_key = entry.key.to_string();
+        if let Err(e) = entry.value {
+            finding("Unparsable pending solutions", e, vec![(stored_key, None)]);
+        } else if !challenges.contains(&entry.key.challenge_id) {
+            // Without the challenge its deadline is unknown, so the solution may still be submittable.
+            finding("Orphaned pending solutions", format!("{}: challenge {} is not stored", stored_key, entry.key.challenge_id), Vec::new());
+        } else if receipted(&entry.key) {
+            finding("Duplicate nonces", format!("{}: the nonce already has a receipt", stored_key), vec![(stored_key, None)]);
+        } else if failed_nonces.contains(&stored_key) {
+            finding("Duplicate nonces", format!("{}: the nonce was already rejected permanently", stored_key), vec![(stored_key, None)]);
+        }
+    }
+
+    // Each wallet account's derived addresses are recorded index by index as they are mined.
+    let mnemonic = match (&cli.mnemonic, &cli.mnemonic_file) {
+        (None, None) => None,
+        (mnemonic, mnemonic_file) => Some(resolve_mnemonic(mnemonic.clone(), mnemonic_file.clone()).map_err(|e| e.message)?),
+    };
+    let mut wallets: BTreeMap<(String, u32), Vec<u32>> = BTreeMap::new();
+    for (key, _) in persistence.iter_mnemonic_indices(None)? {
+        wallets.entry((key.wallet_hash, key.account)).or_default().push(key.index);
+    }
+    for ((wallet_hash, account), mut indices) in wallets {
+        indices.sort_unstable();
+        let missing: Vec<u32> = indices.windows(2).flat_map(|pair| pair[0] + 1..pair[1]).collect();
+        let (Some(first), Some(last)) = (missing.first(), missing.last()) else { continue };
+        let repair = match &mnemonic {
+            Some(phrase) if cardano::mnemonic_hash(phrase, cli.mnemonic_passphrase.as_deref()).to_string() == wallet_hash => missing.iter()
+                .map(|index| {
+                    let key_pair = if cli.base_address {
+                        cardano::derive_key_pair_from_mnemonic_base(phrase, cli.mnemonic_passphrase.as_deref(), account, *index, cli.stake_index)
+                    } else {
+                        cardano::derive_key_pair_from_mnemonic(phrase, cli.mnemonic_passphrase.as_deref(), account, *index)
+                    };
+                    let address = key_pair.2.to_bech32()
+                        .map_err(|e| format!("Failed to encode address of index {}: {}", index, e))?;
+                    Ok((MnemonicIndexKey { wallet_hash: wallet_hash.clone(), account, index: *index }.to_string(), Some(address)))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+            _ => Vec::new(),
+        };
+        finding("Mnemonic index gaps", format!("wallet {}:{}: {} index(es) missing between {} and {}", wallet_hash, account, missing.len(), first, last), repair);
+    }
+
+    cprintln!("\n==============================================");
+    cprintln!("🩺 Database Check{}", if fix { " (fixing)" } else { "" });
+    cprintln!("==============================================");
+    let mut by_check: BTreeMap<&str, Vec<&CheckFinding>> = BTreeMap::new();
+    for finding in &findings {
+        by_check.entry(finding.check).or_default().push(finding);
+    }
+    let (mut fixed, mut fixable) = (0, 0);
+    for (check, findings) in by_check {
+        cprintln!("{}: {}", check, findings.len());
+        for finding in findings {
+            let status = match (finding.repair.is_empty(), fix) {
+                (true, _) => "not fixable",
+                (false, false) => {
+                    fixable += 1;
+                    "fixable with --fix"
+                }
+                (false, true) => {
+                    for (key, value) in &finding.repair {
+                        match value {
+                            Some(value) => persistence.set(key, value)?,
+                            None => persistence.remove(key)?,
+                        }
+                    }
+                    fixed += 1;
+                    "fixed"
+                }
+            };
+            cprintln!("  {} [{}]", finding.detail, status);
+        }
+    }
+    if findings.is_empty() {
+        cprintln!("✅ No problems found.");
+    }
+    cprintln!("----------------------------------------------");
+    cprintln!("Problems: {}   Fixed: {}", findings.len(), fixed);
+    cprintln!("==============================================");
+
+    let left = findings.len() - fixed;
+    match (left, fixable) {
+        (0, _) => Ok(()),
+        (_, 0) => Err(format!("{} problem(s) left that need manual attention.", left)),
+        _ => Err(format!("{} problem(s) left; `db check --fix` repairs {} of them.", left, fixable)),
+    }
+}
+
+/// Compares the receipts stored for `address` with the count GET /statistics reports. The API
+/// only reports a total, so a surplus on either side is pinned to challenges where possible:
+/// "solved by network" markers and unsettled (pending or rejected) submissions.
+fn run_wallet_reconcile(cli: &Cli, persistence: &StateStore, address: &str) -> Result<(), ExitError> {
+    let api_url = cli.api_url.as_ref()
+        .ok_or_else(|| ExitError::new(exit_codes::USAGE_ERROR, "FATAL: --api-url must be specified for wallet reconcile."))?;
+    let client = utils::create_api_client()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    // Local state of every challenge this address has a record for.
+    let mut states: BTreeMap<String, &str> = BTreeMap::new();
+    for entry in persistence.iter_receipts(Some(address))? {
+        let marker = entry.value.is_ok_and(|receipt| receipt["status"].as_str() == Some(RECEIPT_STATUS_CONSUMED_MARKER));
+        states.insert(entry.key.challenge_id, if marker { "marker" } else { "receipt" });
+    }
+    for entry in persistence.iter_pending(Some(address))? {
+        states.entry(entry.key.challenge_id).or_insert("pending");
+    }
+    for entry in persistence.iter_failed(Some(address), None)? {
+        states.entry(entry.key.challenge_id).or_insert("rejected");
+    }
+    let count = |state: &str| states.values().filter(|s| **s == state).count() as i64;
+    let (receipts, markers) = (count("receipt"), count("marker"));
+
+    let stats = api::block_on(api::fetch_statistics(&client, api_url, address)).exit_code(exit_codes::NETWORK_ERROR)?;
+    // Markers stand for submissions the API accepted earlier, so it counts them too.
+    let surplus = stats.crypto_receipts as i64 - receipts - markers;
+
+    cprintln!("\n==============================================");
+    cprintln!("🧮 Receipt Reconciliation: {}", address);
+    cprintln!("==============================================");
+    cprintln!("Local receipts:             {}", receipts);
+    cprintln!("Solved-by-network markers:  {}", markers);
+    cprintln!("API crypto receipts:        {}", stats.crypto_receipts);
+    cprintln!("----------------------------------------------");
+    let mut flagged = 0;
+    for (challenge_id, state) in &states {
+        let flag = match *state {
+            "marker" => "⚠️ accepted remotely, no local receipt",
+            "pending" | "rejected" if surplus > 0 => "❓ may have been accepted remotely",
+            _ => "",
+        };
+        if !flag.is_empty() {
+            flagged += 1;
+        }
+        cprintln!("{}", format!("{:<20} {:<9} {}", challenge_id, state, flag).trim_end());
+    }
+    if states.is_empty() {
+        cprintln!("No local records for this address.");
+    }
+    cprintln!("----------------------------------------------");
+    match surplus {
+        0 if markers == 0 => cprintln!("✅ Local receipts match the API."),
+        0 => cprintln!("⚠️ The API's count matches, but {} accepted submission(s) have no local receipt.", markers),
+        n if n > 0 => cprintln!("⚠️ The API counts {} accepted submission(s) without a local record. If the address was mined elsewhere, `db merge` that state.", n),
+        n => cprintln!("⚠️ {} local receipt(s) are not counted by the API.", -n),
+    }
+    cprintln!("==============================================");
+
+    if surplus != 0 || flagged > 0 {
+        return Err(format!("Local receipts of {} do not match the API.", address).into());
+    }
+    Ok(())
+}
+
+/// Orders receipts for `db merge`: API receipts above "solved by network" markers, then by
+/// timestamp (RFC 3339, so newer sorts higher).
+fn receipt_rank(receipt: &serde_json::Value) -> (bool, String) {
+    (
+        receipt["status"].as_str() != Some(RECEIPT_STATUS_CONSUMED_MARKER),
+        receipt["timestamp"].as_str().unwrap_or_default().to_string(),
+    )
+}
+
+/// Copies the records under `prefix` that `persistence` does not have yet; where both sides
+/// hold a key, ours is kept. Returns how many were copied.
+fn merge_missing(persistence: &StateStore, other: &Persistence, prefix: &str) -> Result<usize, String> {
+    let mut added = 0;
+    for (key, json) in other.scan(prefix)? {
+        if persistence.read(&key)?.is_none() {
+            persistence.write(&key, &json)?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// Merges the database under `other_dir` into `persistence`. Values are copied as stored.
+fn run_db_merge(cli: &Cli, persistence: &StateStore, other_dir: &str) -> Result<(), String> {
+    let own_dir = cli.data_dir.as_deref().unwrap_or("state");
+    if fs::canonicalize(own_dir).is_ok_and(|own| fs::canonicalize(other_dir).is_ok_and(|other| other == own)) {
+        return Err("Cannot merge a state directory into itself.".to_string());
+    }
+    let other = StateStore::open_local(cli, other_dir)?;
+
+    cprintln!("\n==============================================");
+    cprintln!("🔀 Merging {} into this database", other_dir);
+    cprintln!("==============================================");
+
+    // Challenges first, so merged pending solutions are not orphaned.
+    let challenges = merge_missing(persistence, &other, &format!("{}:", SLED_KEY_CHALLENGE))?;
+
+    let (mut receipts_added, mut receipts_replaced, mut pending_dropped) = (0, 0, 0);
+    for (key, json) in other.scan(&ReceiptKey::prefix(None))? {
+        let Some(receipt_key) = ReceiptKey::parse(&key) else { continue };
+        let theirs: serde_json::Value = match serde_json::from_str(&json) {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                ceprintln!("⚠️ Skipping receipt {} that does not parse: {}", key, e);
+                continue;
+            }
+        };
+        match persistence.read(&key)? {
+            None => receipts_added += 1,
+            // An unparsable receipt of ours loses to a parsable one.
+            Some(ours) => match serde_json::from_str::<serde_json::Value>(&ours) {
+                Ok(ours) if receipt_rank(&ours) >= receipt_rank(&theirs) => continue,
+                _ => receipts_replaced += 1,
+            },
+        }
+        persistence.write(&key, &json)?;
+
+        // The address is done with this challenge; its queued solutions would be rejected.
+        for entry in persistence.iter_pending(Some(&receipt_key.address))? {
+            if entry.key.challenge_id == receipt_key.challenge_id {
+                persistence.remove_pending(&entry.key)?;
+                pending_dropped += 1;
+            }
+        }
+    }
+
+    let (mut pending_added, mut pending_skipped) = (0, 0);
+    for (key, json) in other.scan(&PendingKey::prefix(None, None))? {
+        let Some(pending_key) = PendingKey::parse(&key) else { continue };
+        if persistence.has_receipt(&pending_key.address, &pending_key.challenge_id)? || persistence.read(&key)?.is_some() {
+            pending_skipped += 1;
+            continue;
+        }
+        persistence.write(&key, &json)?;
+        pending_added += 1;
+    }
+
+    // Index records derive from the mnemonic, so both sides must agree.
+    let (mut indices_added, mut index_conflicts) = (0, 0);
+    for (key, address) in other.iter_mnemonic_indices(None)? {
+        match persistence.read(&key.to_string())? {
+            None => {
+                persistence.write(&key.to_string(), &address)?;
+                indices_added += 1;
+            }
+            Some(ours) if ours != address => {
+                ceprintln!("⚠️ Kept {} = {}; the other database has {}.", key, ours, address);
+                index_conflicts += 1;
+            }
+            Some(_) => {}
+        }
+    }
+
+    // History records: `report audit` cross-checks receipts against donations and error records.
+    let failed_added = merge_missing(persistence, &other, &FailedSolutionKey::prefix(None, None))?;
+    let donations_added = merge_missing(persistence, &other, &format!("{}:", SLED_KEY_DONATION))?;
+    let cycles_added = merge_missing(persistence, &other, &format!("{}:", SLED_KEY_CYCLE))?;
+    other.close().map_err(|e| format!("Failed to close {}: {}", other_dir, e))?;
+
+    cprintln!("Challenges added:         {}", challenges);
+    cprintln!("Receipts added:           {}", receipts_added);
+    cprintln!("Receipts replaced:        {}", receipts_replaced);
+    cprintln!("Pending solutions added:  {} ({} skipped, {} of ours dropped for a merged receipt)", pending_added, pending_skipped, pending_dropped);
+    cprintln!("Mnemonic indices added:   {}{}", indices_added, if index_conflicts > 0 { format!(" ({} conflicting, kept ours)", index_conflicts) } else { String::new() });
+    cprintln!("Error records added:      {}", failed_added);
+    cprintln!("Donations added:          {}", donations_added);
+    cprintln!("Cycle records added:      {}", cycles_added);
+    cprintln!("==============================================");
+    if pending_added > 0 {
+        submit_queued(persistence)?;
+    }
+    Ok(())
+}
+
+/// Writes a checksummed snapshot of every entry to `path`. The encryption record is left out:
+/// it belongs to the database, and values are stored decoded.
+fn run_db_backup(persistence: &StateStore, path: &str) -> Result<(), String> {
+    cprintln!("\n==============================================");
+    cprintln!("Backing up Sled DB to: {}", path);
+    cprintln!("==============================================");
+
+    // One scan: while the miner runs, its state worker answers it as a single request.
+    let entries: Vec<BackupEntry> = persistence.scan_prefix("")?
+        .into_iter()
+        .filter(|(key, _)| key != SLED_KEY_DB_ENCRYPTION)
+        .map(|(key, value)| BackupEntry { key, value })
+        .collect();
+    let backup = DbBackup::new(entries);
+
+    let json = serde_json::to_string_pretty(&backup).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write backup file {}: {}", tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to move backup into place at {}: {}", path, e))?;
+
+    cprintln!("✅ Backup complete. {} key-value pairs, checksum {}.", backup.entry_count, backup.checksum);
+    if matches!(persistence, StateStore::Local(p) if p.is_encrypted()) {
+        ceprintln!("⚠️ The database is encrypted, but the backup holds its values in plain text. Store it safely.");
+    }
+    Ok(())
+}
+
+/// Restores a `db backup` file (or a plain `db export` array) into the local database.
+fn run_db_restore(persistence: &StateStore, path: &str, force: bool) -> Result<(), String> {
+    let StateStore::Local(db) = persistence else {
+        return Err("A running miner holds the database. Stop it before restoring.".to_string());
+    };
+    cprintln!("\n==============================================");
+    cprintln!("Restoring Sled DB from: {}", path);
+    cprintln!("==============================================");
+
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read backup file {}: {}", path, e))?;
+    let entries = match serde_json::from_str::<DbBackup>(&content) {
+        Ok(backup) => {
+            backup.validate()?;
+            cprintln!("Backup of {} taken {} by v{}: {} entries, checksum OK.", path, backup.created_at, backup.version, backup.entry_count);
+            backup.entries
+        }
+        Err(_) => {
+            let entries: Vec<BackupEntry> = serde_json::from_str(&content)
+                .map_err(|e| format!("{} is neither a `db backup` nor a `db export` file: {}", path, e))?;
+            ceprintln!("⚠️ {} is a `db export` file without a checksum; restoring it as is.", path);
+            entries
+        }
+    };
+
+    let existing: Vec<String> = persistence.scan_prefix("")?
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| key != SLED_KEY_DB_ENCRYPTION)
+        .collect();
+    if !existing.is_empty() && !force {
+        return Err(format!(
+            "The database already holds {} entries. Restore into an empty --data-dir, or pass --force to replace them (run `db backup` first to keep them).",
+            existing.len()
+        ));
+    }
+
+    // One batch, so a failure halfway leaves the old entries in place instead of a partial database.
+    let restored: Vec<(String, String)> = entries
+        .into_iter()
+        .filter(|entry| entry.key != SLED_KEY_DB_ENCRYPTION)
+        .map(|entry| (entry.key, entry.value))
+        .collect();
+    db.apply_batch(&existing, &restored)?;
+    db.db.flush().map_err(|e| format!("Failed to flush the restored database: {}", e))?;
+    if !existing.is_empty() {
+        cprintln!("🗑️ Replaced {} existing entries.", existing.len());
+    }
+    cprintln!("✅ Restore complete. {} key-value pairs restored.", restored.len());
+    Ok(())
+}
+
+/// Commands whose long loops check `shutdown::check_cancelled` and so can stop cleanly.
+fn is_cancellable(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Challenge(ChallengeCommands::Hash { .. } | ChallengeCommands::Verify { .. } | ChallengeCommands::RetryError { .. })
+            | Commands::Wallet(WalletCommands::Stats { .. } | WalletCommands::DonateAll { .. })
+    )
+}
+
 /// Handles all synchronous persistence-related commands (List, Import, Info, ReceiptInfo, PendingInfo, Wallet, Db).
 /// These commands run before the main application loop starts.
-pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
+pub fn handle_sync_commands(cli: &Cli) -> Result<(), ExitError> {
 
-    // 1. Initialize Sled DB based on CLI data_dir
-    let db_path = PathBuf::from(cli.data_dir.as_deref().unwrap_or("state")).join(SLED_DB_FILENAME);
-    let persistence = Persistence::open(&db_path)
-        .map_err(|e| format!("FATAL: Could not open Sled DB at {}: {}", db_path.display(), e))?;
+    // 1. Open the state: through a running miner's control socket, or the Sled DB under data_dir
+    let persistence = StateStore::open(cli).exit_code(exit_codes::DATABASE_ERROR)?;
+
+    // Ctrl-C / --command-timeout stop long commands between steps; the DB is closed below either way.
+    // Other commands keep the default Ctrl-C behaviour and exit immediately.
+    if cli.command.as_ref().is_some_and(is_cancellable) {
+        crate::shutdown::spawn_cancel_handler(cli.command_timeout.map(std::time::Duration::from_secs));
+    }
 
-    if let Some(command) = cli.command.clone() {
+    let result: Result<(), ExitError> = if let Some(command) = cli.command.clone() {
         match command {
             Commands::Challenge(cmd) => {
                 match cmd {
-                    ChallengeCommands::List => {
-                        println!("\n==============================================");
-                        println!("Stored Challenge IDs and Solutions");
-                        println!("==============================================");
+                    ChallengeCommands::List { sort } => {
+                        cprintln!("\n==============================================");
+                        cprintln!("Stored Challenge IDs and Solutions");
+                        cprintln!("==============================================");
 
                         // 1. Calculate receipt counts for all challenges
                         let mut challenge_receipt_counts = HashMap::new();
-                        let completed_prefix_base = format!("{}:", SLED_KEY_RECEIPT);
-
-                        // Iterate over all receipts
-                        for entry_result in persistence.db.scan_prefix(completed_prefix_base.as_bytes()) {
-                            match entry_result {
-                                Ok((key_ivec, _value_ivec)) => {
-                                    let key = String::from_utf8_lossy(&key_ivec);
-                                    // Key format: receipt:<ADDRESS>:<CHALLENGE_ID>
-                                    let parts: Vec<&str> = key.split(':').collect();
-
-                                    // parts[2] is CHALLENGE_ID
-                                    if parts.len() == 3 {
-                                        let challenge_id = parts[2].to_string();
-                                        // Increment count for this challenge ID
-                                        *challenge_receipt_counts.entry(challenge_id).or_insert(0) += 1;
-                                    }
-                                }
-                                Err(e) => {
-                                    // Handle iteration failure
-                                    return Err(format!("Sled receipt iteration error: {}", e));
-                                }
+                        for entry in persistence.iter_receipts(None)? {
+                            *challenge_receipt_counts.entry(entry.key.challenge_id).or_insert(0) += 1;
+                        }
+
+                        // 2. Collect stored challenge IDs (Sled returns them in byte order)
+                        let mut challenges: Vec<(String, String)> = Vec::new();
+                        for (key, value) in persistence.scan(&format!("{}:", SLED_KEY_CHALLENGE))? {
+                            if let Some(challenge_id) = key.strip_prefix(format!("{}:", SLED_KEY_CHALLENGE).as_str()) {
+                                challenges.push((challenge_id.to_string(), value));
                             }
                         }
 
-                        // 2. Iterate over stored challenge IDs and print with count
-                        let mut found = false;
-                        let iter = persistence.db.scan_prefix(format!("{}:", SLED_KEY_CHALLENGE).as_bytes());
-
-                        for entry_result in iter {
-                            match entry_result {
-                                Ok((key_ivec, _value_ivec)) => {
-                                    let key = String::from_utf8_lossy(&key_ivec);
-                                    if let Some(challenge_id) = key.strip_prefix(format!("{}:", SLED_KEY_CHALLENGE).as_str()) {
-                                        // Get the count, defaulting to 0
-                                        let count = challenge_receipt_counts.get(challenge_id).unwrap_or(&0);
-                                        // Print in a formatted way
-                                        println!("{:<20} Solutions: {}", challenge_id, count);
-                                        found = true;
-                                    }
-                                }
-                                Err(e) => {
-                                    // Handle iteration failure
-                                    return Err(format!("Sled challenge iteration error: {}", e));
-                                }
+                        if sort == SortOrder::Natural {
+                            challenges.sort_by_cached_key(|(id, json)| challenge_sort_key(id, json));
+                        }
+
+                        // 3. Print with receipt count
+                        for (challenge_id, _) in &challenges {
+                            // Get the count, defaulting to 0
+                            let count = challenge_receipt_counts.get(challenge_id).unwrap_or(&0);
+                            // Print in a formatted way
+                            match load_annotation(&persistence, "challenge", challenge_id)? {
+                                Some(annotation) => cprintln!("{:<20} Solutions: {:<5} {}", challenge_id, count, annotation.summary()),
+                                None => cprintln!("{:<20} Solutions: {}", challenge_id, count),
                             }
                         }
 
-                        if !found {
-                            println!("No challenges found in local state.");
+                        if challenges.is_empty() {
+                            cprintln!("No challenges found in local state.");
                         }
-                        println!("==============================================");
+                        cprintln!("==============================================");
                         Ok(())
                     }
                     ChallengeCommands::Import { file } => {
@@ -118,162 +893,152 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
                         let challenge_data: ChallengeData = serde_json::from_str(&content)
                             .map_err(|e| format!("Failed to parse JSON file {}: {}", file, e))?;
 
-                        let key = format!("{}:{}", SLED_KEY_CHALLENGE, challenge_data.challenge_id);
-                        persistence.set(&key, &content)?;
+                        persistence.write(&challenge_key(&challenge_data.challenge_id), &content)?;
 
-                        println!("✅ Challenge '{}' imported successfully into Sled DB.", challenge_data.challenge_id);
+                        cprintln!("✅ Challenge '{}' imported successfully into Sled DB.", challenge_data.challenge_id);
                         Ok(())
                     }
+                    ChallengeCommands::ExportSolutions { id, out } => run_export_solutions(cli, &persistence, &id, &out),
+                    ChallengeCommands::ImportSolutions { file } => run_import_solutions(&persistence, &file).map_err(ExitError::from),
                     ChallengeCommands::Info { id } => {
-                        let key = format!("{}:{}", SLED_KEY_CHALLENGE, id);
-                        match persistence.get(&key)? {
+                        match persistence.read(&challenge_key(&id))? {
                             Some(json) => {
-                                println!("\n==============================================");
-                                println!("Challenge Details: {}", id);
-                                println!("==============================================");
-                                println!("{}", json);
+                                cprintln!("\n==============================================");
+                                cprintln!("Challenge Details: {}", id);
+                                cprintln!("==============================================");
+                                cprintln!("{}", json);
                                 Ok(())
                             }
                             None => {
-                                Err(format!("Challenge ID '{}' not found in Sled DB.", id))
+                                Err(ExitError::new(exit_codes::NOT_FOUND, format!("Challenge ID '{}' not found in Sled DB.", id)))
                             }
                         }
                     }
                     ChallengeCommands::Details { id } => {
-                        let key = format!("{}:{}", SLED_KEY_CHALLENGE, id);
-                        let json = persistence.get(&key)?.ok_or_else(|| format!("Challenge ID '{}' not found in Sled DB.", id))?;
-                        let challenge_data: ChallengeData = serde_json::from_str(&json)
-                            .map_err(|e| format!("Failed to deserialize challenge data: {}", e))?;
-
-                        // --- Aggregation: FIX Logic to count SPECIFICALLY for this challenge ID ---
-
-                        // Completed Key format: receipt:<ADDRESS>:<ID>
-                        let completed_prefix_base = format!("{}:", SLED_KEY_RECEIPT);
-                        let mut completed_count = 0;
-
-                        // Iterate over all receipts and manually filter by CHALLENGE_ID
-                        for entry_result in persistence.db.scan_prefix(completed_prefix_base.as_bytes()) {
-                            if let Ok((key_ivec, _value_ivec)) = entry_result {
-                                let key = String::from_utf8_lossy(&key_ivec);
-                                // The key is receipt:<ADDRESS>:<CHALLENGE_ID>
-                                let parts: Vec<&str> = key.split(':').collect();
-                                // parts[2] is CHALLENGE_ID
-                                if parts.len() == 3 && parts[2] == id {
-                                    completed_count += 1;
-                                }
-                            }
-                            else if let Err(e) = entry_result {
-                                return Err(format!("Sled iteration error: {}", e));
-                            }
-                        }
-
-                        // Pending Key format: pending:<ADDRESS>:<ID>:<NONCE>
-                        let pending_prefix_base = format!("{}:", SLED_KEY_PENDING);
-                        let mut pending_count = 0;
-
-                        // Iterate over all pending solutions and manually filter by CHALLENGE_ID
-                        for entry_result in persistence.db.scan_prefix(pending_prefix_base.as_bytes()) {
-                            if let Ok((key_ivec, _value_ivec)) = entry_result {
-                                let key = String::from_utf8_lossy(&key_ivec);
-                                // The key is pending:<ADDRESS>:<CHALLENGE_ID>:<NONCE>
-                                let parts: Vec<&str> = key.split(':').collect();
-                                // parts[2] is CHALLENGE_ID
-                                if parts.len() == 4 && parts[2] == id {
-                                    pending_count += 1;
-                                }
-                            }
-                            else if let Err(e) = entry_result {
-                                return Err(format!("Sled iteration error: {}", e));
-                            }
-                        }
+                        let challenge_data = persistence.get_challenge(&id)?
+                            .ok_or_else(|| ExitError::new(exit_codes::NOT_FOUND, format!("Challenge ID '{}' not found in Sled DB.", id)))?;
+
+                        // --- Aggregation: count receipts and queued solutions of this challenge ID ---
+                        let completed_count = persistence.iter_receipts(None)?
+                            .iter()
+                            .filter(|entry| entry.key.challenge_id == id)
+                            .count();
+                        let pending_count = persistence.iter_pending(None)?
+                            .iter()
+                            .filter(|entry| entry.key.challenge_id == id)
+                            .count();
 
                         // --- Output ---
-                        println!("\n==============================================");
-                        println!("⛏️  Challenge Details: {}", id);
-                        println!("==============================================");
-                        println!("  ID:               {}", challenge_data.challenge_id);
-                        println!("  Day:              {}", challenge_data.day);
-                        println!("  Difficulty Mask:  {}", challenge_data.difficulty);
-                        println!("  Submission Deadline: {}", challenge_data.latest_submission);
-                        println!("  ROM Key:          {}", challenge_data.no_pre_mine_key);
-                        println!("  Hash Input Hour:  {}", challenge_data.no_pre_mine_hour_str);
-                        println!("----------------------------------------------");
-                        println!("  Local Completed Solutions: {}", completed_count);
-                        println!("  Local Pending Submissions: {}", pending_count);
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("⛏️  Challenge Details: {}", id);
+                        cprintln!("==============================================");
+                        cprintln!("  ID:               {}", challenge_data.challenge_id);
+                        cprintln!("  Day:              {}", challenge_data.day);
+                        cprintln!("  Difficulty Mask:  {}", challenge_data.difficulty);
+                        cprintln!("  Submission Deadline: {}", challenge_data.latest_submission);
+                        cprintln!("  ROM Key:          {}", challenge_data.no_pre_mine_key);
+                        cprintln!("  Hash Input Hour:  {}", challenge_data.no_pre_mine_hour_str);
+                        cprintln!("----------------------------------------------");
+                        cprintln!("  Local Completed Solutions: {}", completed_count);
+                        cprintln!("  Local Pending Submissions: {}", pending_count);
+                        if let Some(annotation) = load_annotation(&persistence, "challenge", &id)? {
+                            cprintln!("----------------------------------------------");
+                            cprintln!("  Note: {}", annotation.note.as_deref().unwrap_or("-"));
+                            cprintln!("  Tags: {}", if annotation.tags.is_empty() { "-".to_string() } else { annotation.tags.join(", ") });
+                        }
+                        cprintln!("==============================================");
 
                         Ok(())
                     }
                     ChallengeCommands::ReceiptInfo { challenge_id, address } => {
-                        // Key format: receipt:<ADDRESS>:<CHALLENGE_ID>
-                        let key = format!("{}:{}:{}", SLED_KEY_RECEIPT, address, challenge_id);
-                        match persistence.get(&key)? {
+                        match persistence.read(&ReceiptKey::new(&address, &challenge_id).to_string())? {
                             Some(json) => {
-                                println!("\n==============================================");
-                                println!("Receipt Info: {} for {}", challenge_id, address);
-                                println!("==============================================");
-                                println!("{}", json);
+                                cprintln!("\n==============================================");
+                                cprintln!("Receipt Info: {} for {}", challenge_id, address);
+                                cprintln!("==============================================");
+                                cprintln!("{}", json);
                                 Ok(())
                             }
                             None => {
-                                Err(format!("Receipt not found for Challenge ID '{}' and Address '{}'.", challenge_id, address))
+                                Err(ExitError::new(exit_codes::NOT_FOUND, format!("Receipt not found for Challenge ID '{}' and Address '{}'.", challenge_id, address)))
                             }
                         }
                     }
                     ChallengeCommands::PendingInfo { challenge_id, address, nonce } => {
-                        // Key format: pending:<ADDRESS>:<CHALLENGE_ID>:<NONCE>
-                        let key = format!("{}:{}:{}:{}", SLED_KEY_PENDING, address, challenge_id, nonce);
-
-                        match persistence.get(&key)? {
+                        match persistence.read(&PendingKey::new(&address, &challenge_id, &nonce).to_string())? {
                             Some(json) => {
-                                println!("\n==============================================");
-                                println!("Pending Solution: {} for {}", nonce, address);
-                                println!("==============================================");
-                                println!("{}", json);
+                                cprintln!("\n==============================================");
+                                cprintln!("Pending Solution: {} for {}", nonce, address);
+                                cprintln!("==============================================");
+                                cprintln!("{}", json);
                                 Ok(())
                             }
                             None => {
-                                Err(format!("Pending solution not found for Nonce '{}', Challenge '{}', and Address '{}'.", nonce, challenge_id, address))
+                                Err(ExitError::new(exit_codes::NOT_FOUND, format!("Pending solution not found for Nonce '{}', Challenge '{}', and Address '{}'.", nonce, challenge_id, address)))
                             }
                         }
                     }
                     ChallengeCommands::Errors => {
-                        println!("\n==============================================");
-                        println!("Stored Permanent Submission Errors");
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("Stored Permanent Submission Errors");
+                        cprintln!("==============================================");
 
-                        let prefix = format!("{}:", SLED_KEY_FAILED_SOLUTION);
                         let mut found = false;
 
-                        // Scan Sled for the failed solution prefix
-                        for entry_result in persistence.db.scan_prefix(prefix.as_bytes()) {
-                            match entry_result {
-                                Ok((_key_ivec, value_ivec)) => {
-                                    let error_json = String::from_utf8_lossy(&value_ivec);
-
-                                    // Print the entire stored JSON object
-                                    println!("{}", error_json);
-                                    println!("----------------------------------------------");
-                                    found = true;
-                                }
-                                Err(e) => {
-                                    return Err(format!("Sled iteration error while dumping errors: {}", e));
-                                }
-                            }
+                        // Print each stored error record as stored
+                        for (_, error_json) in persistence.scan(&FailedSolutionKey::prefix(None, None))? {
+                            // Print the entire stored JSON object
+                            cprintln!("{}", error_json);
+                            cprintln!("----------------------------------------------");
+                            found = true;
                         }
 
                         if !found {
-                            println!("No permanent submission errors found in local state.");
+                            cprintln!("No permanent submission errors found in local state.");
                         }
-                        println!("==============================================");
+                        cprintln!("==============================================");
                         Ok(())
                     }
-                    ChallengeCommands::Hash { challenge_id, address } => {
+                    ChallengeCommands::RetryError { challenge_id, address, nonce } => run_retry_error(cli, &persistence, &challenge_id, &address, nonce.as_deref()),
+                    ChallengeCommands::DifficultyTrend => {
+                        let history = load_difficulty_history(&persistence)?;
+                        cprintln!("\n==============================================");
+                        cprintln!("📈 Difficulty Trend");
+                        cprintln!("==============================================");
+                        if history.is_empty() {
+                            cprintln!("No difficulty history found in local state.");
+                            cprintln!("==============================================");
+                            return Ok(());
+                        }
+
+                        cprintln!("{:<6} {:<10} {:>9} {:>7}  Challenge", "Day", "Mask", "Zero Bits", "Change");
+                        let mut bits = Vec::new();
+                        for record in history.values() {
+                            let Some(zero_bits) = record.zero_bits() else {
+                                cprintln!("{:<6} {:<10} {:>9} {:>7}  {}", record.day, record.difficulty, "invalid", "", record.challenge_id);
+                                continue;
+                            };
+                            let change = bits.last().map_or(String::new(), |&prev: &u32| format!("{:+}", zero_bits as i64 - prev as i64));
+                            cprintln!("{:<6} {:<10} {:>9} {:>7}  {}", record.day, record.difficulty, zero_bits, change, record.challenge_id);
+                            bits.push(zero_bits);
+                        }
+
+                        cprintln!("----------------------------------------------");
+                        if let (Some(predicted), Some(last_day)) = (predict_zero_bits(&bits), history.keys().next_back()) {
+                            let rounded = predicted.round() as u32;
+                            cprintln!(
+                                "Prediction for day {}: ~{:.1} zero bits (mask {:08X}, ~2^{} = {:.0} hashes per solution).",
+                                last_day.saturating_add(1), predicted, u32::MAX.checked_shr(rounded).unwrap_or(0), rounded, 2f64.powi(rounded as i32)
+                            );
+                            cprintln!("Naive linear extrapolation over the last {} day(s); the protocol may change difficulty at any time.", bits.len().min(DIFFICULTY_TREND_WINDOW));
+                        }
+                        cprintln!("==============================================");
+                        Ok(())
+                    }
+                    ChallengeCommands::Hash { challenge_id, address, nonce } => {
                         // Import necessary library functions
-                        use shadow_harvester_lib::{Rom, RomGenerationType, hash};
+                        use shadow_harvester_lib::{Rom, hash};
 
-                        const MB: usize = 1024 * 1024;
-                        const GB: usize = 1024 * MB;
                         const NONCE_HEX_LENGTH: usize = 16;
                         const NB_LOOPS: u32 = 8;
                         const NB_INSTRS: u32 = 256;
@@ -282,23 +1047,26 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
                         let preimage_str: String;
                         let stored_hash: Option<String>; // Hash found in the FailedSolution record
 
-                        let key_challenge = format!("{}:{}", SLED_KEY_CHALLENGE, challenge_id);
-                        let key_receipt = format!("{}:{}:{}", SLED_KEY_RECEIPT, address, challenge_id);
-                        let prefix_error = format!("{}:{}:{}:", SLED_KEY_FAILED_SOLUTION, address, challenge_id);
-
-
                         // 1. Get Challenge Data (needed for ROM and preimage)
-                        let challenge_json = persistence.get(&key_challenge)?
-                            .ok_or_else(|| format!("Challenge ID '{}' not found in Sled DB.", challenge_id))?;
-                        let challenge_data: ChallengeData = serde_json::from_str(&challenge_json)
-                            .map_err(|e| format!("Failed to deserialize challenge data: {}", e))?;
-
-                        // 2. Try to get Receipt or Error Record
-                        if let Some(receipt_json_value) = persistence.get(&key_receipt)? {
+                        let challenge_data = persistence.get_challenge(&challenge_id)?
+                            .ok_or_else(|| ExitError::new(exit_codes::NOT_FOUND, format!("Challenge ID '{}' not found in Sled DB.", challenge_id)))?;
+                        let difficulty_mask = u32::from_str_radix(&challenge_data.difficulty, 16)
+                            .map_err(|_| format!("Challenge {} has a non-hex difficulty '{}'.", challenge_id, challenge_data.difficulty))?;
+
+                        // 2. Rebuild the preimage from an explicit nonce, or get the Receipt or Error Record
+                        if let Some(nonce) = nonce {
+                            source = "Explicit Nonce (Rebuilt Preimage)";
+                            let nonce_value = u64::from_str_radix(nonce.trim_start_matches("0x"), 16)
+                                .map_err(|_| format!("Nonce '{}' is not a 64-bit hex number.", nonce))?;
+                            preimage_str = shadow_harvester_lib::build_preimage(
+                                nonce_value, &address, &challenge_data.challenge_id, difficulty_mask,
+                                &challenge_data.no_pre_mine_key, &challenge_data.latest_submission, &challenge_data.no_pre_mine_hour_str,
+                            );
+                            stored_hash = None;
+                        }
+                        else if let Some(full_receipt) = persistence.get_receipt(&address, &challenge_id)? {
                             // --- FOUND RECEIPT ---
                             source = "Receipt (Successful Submission)";
-                            let full_receipt: serde_json::Value = serde_json::from_str(&receipt_json_value)
-                                .map_err(|e| format!("Failed to parse receipt JSON from Sled: {}", e))?;
 
                             preimage_str = full_receipt.get("preimage")
                                 .and_then(|v| v.as_str())
@@ -307,275 +1075,453 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
 
                             stored_hash = None; // Receipt does not store the hash output
                         }
-                        else if let Some(error_entry) = persistence.db.scan_prefix(prefix_error.as_bytes()).next().and_then(|r| r.ok()) {
+                        else if let Some(entry) = persistence.iter_failed(Some(&address), Some(&challenge_id))?.into_iter().next() {
                             // --- FOUND ERROR RECORD ---
                             source = "Error Record (Non-Recoverable Failure)";
-                            let error_json = String::from_utf8_lossy(&error_entry.1);
-
-                            let failed_solution: FailedSolution = serde_json::from_str(&error_json)
-                                .map_err(|e| format!("Failed to deserialize Error JSON: {}", e))?;
+                            let failed_solution = entry.value?;
 
                             preimage_str = failed_solution.preimage;
                             stored_hash = Some(failed_solution.hash_output);
                         }
                         else {
-                            return Err(format!("Neither a Receipt nor a permanent Error Record found for challenge '{}' and address '{}'.", challenge_id, address));
+                            return Err(ExitError::new(exit_codes::NOT_FOUND, format!("Neither a Receipt nor a permanent Error Record found for challenge '{}' and address '{}'.", challenge_id, address)));
                         }
 
                         let nonce_hex = preimage_str.get(0..NONCE_HEX_LENGTH)
                             .ok_or_else(|| "Preimage is too short to extract 16-char nonce.".to_string())?;
 
                         // 3. Initialize ROM
-                        let rom = Rom::new(
-                            challenge_data.no_pre_mine_key.as_bytes(),
-                            RomGenerationType::TwoStep {
-                                pre_size: 16 * MB,
-                                mixing_numbers: 4,
-                            },
-                            GB,
-                        );
+                        let rom_params = crate::utils::rom_overrides(cli)?.resolve(Some(&challenge_data));
+                        rom_params.validate()?;
+                        let rom_key = challenge_data.no_pre_mine_key.clone();
+                        let rom = crate::shutdown::run_cancellable(move || Rom::from_params(rom_key.as_bytes(), &rom_params))?;
 
                         // 4. Compute the Hash
                         let h = hash(preimage_str.as_bytes(), &rom, NB_LOOPS, NB_INSTRS);
 
 
                         // 5. Output Result
-                        println!("\n==============================================");
-                        println!("Hash Verification for Challenge: {}", challenge_id);
-                        println!("  Source: {}", source);
-                        println!("==============================================");
-                        println!("Address: {}", address);
-                        println!("Nonce: {}", nonce_hex);
-                        println!("Difficulty Mask: {}", challenge_data.difficulty);
-                        println!("Reconstructed Preimage (Full): {}", preimage_str);
-                        println!("----------------------------------------------");
-                        println!("ROM Key: {}", challenge_data.no_pre_mine_key);
-                        println!("ROM Digest: {}", hex::encode(rom.digest.0));
-                        println!("Computed Final Hash (Blake2b-512):");
-                        println!("{}", hex::encode(h));
+                        cprintln!("\n==============================================");
+                        cprintln!("Hash Verification for Challenge: {}", challenge_id);
+                        cprintln!("  Source: {}", source);
+                        cprintln!("==============================================");
+                        cprintln!("Address: {}", address);
+                        cprintln!("Nonce: {}", nonce_hex);
+                        cprintln!("Difficulty Mask: {}", challenge_data.difficulty);
+                        cprintln!("Reconstructed Preimage (Full): {}", preimage_str);
+                        cprintln!("----------------------------------------------");
+                        cprintln!("ROM Key: {}", challenge_data.no_pre_mine_key);
+                        cprintln!("ROM Digest: {}", hex::encode(rom.digest.0));
+                        cprintln!("Computed Final Hash (Blake2b-512):");
+                        cprintln!("{}", hex::encode(h));
 
                         if let Some(stored_hash) = stored_hash {
-                            println!("----------------------------------------------");
-                            println!("Stored Hash (from Error Record):");
-                            println!("{}", stored_hash);
+                            cprintln!("----------------------------------------------");
+                            cprintln!("Stored Hash (from Error Record):");
+                            cprintln!("{}", stored_hash);
                             if stored_hash == hex::encode(h) {
-                                println!("✅ Stored Hash MATCHES Computed Hash.");
+                                cprintln!("✅ Stored Hash MATCHES Computed Hash.");
                             } else {
-                                println!("❌ Stored Hash DOES NOT MATCH Computed Hash. Logic error or data corruption.");
+                                cprintln!("❌ Stored Hash DOES NOT MATCH Computed Hash. Logic error or data corruption.");
                             }
                         }
-                        println!("==============================================");
+                        cprintln!("----------------------------------------------");
+                        if shadow_harvester_lib::hash_structure_good(&h, difficulty_mask) {
+                            cprintln!("✅ Computed Hash MEETS difficulty {}.", challenge_data.difficulty);
+                        } else {
+                            cprintln!("❌ Computed Hash DOES NOT MEET difficulty {}. The server rejects this solution.", challenge_data.difficulty);
+                        }
+                        cprintln!("==============================================");
 
                         Ok(())
                     }
+                    ChallengeCommands::Note { id, edit } => run_note_command(&persistence, "challenge", &id, edit).map_err(ExitError::from),
+                    ChallengeCommands::Verify { challenge_id, source, threads } => {
+                        let matches_filter = |id: &str| challenge_id.as_deref().is_none_or(|wanted| wanted == id);
+
+                        let mut challenges = BTreeMap::new();
+                        for entry in persistence.iter_challenges()? {
+                            if let Ok(challenge) = entry.value {
+                                challenges.insert(challenge.challenge_id.clone(), challenge);
+                            }
+                        }
+
+                        let mut entries = Vec::new();
+                        if source != VerifySource::Errors {
+                            for (key, value) in persistence.scan(&ReceiptKey::prefix(None))? {
+                                if let Some(key) = ReceiptKey::parse(&key).filter(|key| matches_filter(&key.challenge_id)) {
+                                    entries.push(VerifyEntry::from_receipt(&key.address, &key.challenge_id, &value));
+                                }
+                            }
+                        }
+                        if source != VerifySource::Receipts {
+                            for entry in persistence.iter_failed(None, None)? {
+                                if matches_filter(&entry.key.challenge_id) {
+                                    entries.push(VerifyEntry::from_failed_solution(entry.value?));
+                                }
+                            }
+                        }
+
+                        let threads = threads.unwrap_or(cli.threads).max(1) as usize;
+                        cprintln!("\n==============================================");
+                        cprintln!("Verifying {} stored hash(es) with {} thread(s)", entries.len(), threads);
+                        cprintln!("==============================================");
+
+                        let started = std::time::Instant::now();
+                        let verdicts = verify::verify_entries(&entries, &challenges, &utils::rom_overrides(cli)?, threads)?;
+
+                        let (mut ok, mut failed, mut skipped) = (0, 0, 0);
+                        for (entry, verdict) in entries.iter().zip(&verdicts) {
+                            match verdict {
+                                Verdict::Ok => ok += 1,
+                                Verdict::BelowDifficulty(hash) => {
+                                    failed += 1;
+                                    cprintln!("❌ {} {}: hash {} does not meet the difficulty", entry.challenge_id, entry.address, hash);
+                                }
+                                Verdict::HashMismatch { computed, stored } => {
+                                    failed += 1;
+                                    cprintln!("❌ {} {}: computed {} but stored {}", entry.challenge_id, entry.address, computed, stored);
+                                }
+                                Verdict::Skipped(reason) => {
+                                    skipped += 1;
+                                    cprintln!("⚠️ {} {}: skipped, {}", entry.challenge_id, entry.address, reason);
+                                }
+                            }
+                        }
+                        cprintln!("----------------------------------------------");
+                        cprintln!("✅ OK: {}   ❌ Failed: {}   ⚠️ Skipped: {}   ({})", ok, failed, skipped, utils::format_duration(started.elapsed().as_secs_f64()));
+                        cprintln!("==============================================");
+
+                        crate::shutdown::check_cancelled()?;
+                        if failed > 0 {
+                            return Err(format!("{} stored hash(es) failed verification.", failed).into());
+                        }
+                        Ok(())
+                    }
                 }
             }
             Commands::Wallet(cmd) => {
                 match cmd {
-                    WalletCommands::List => {
-                        println!("\n==============================================");
-                        println!("Stored Wallet Identifiers (Hash:Account)");
-                        println!("==============================================");
-
-                        let mut identifiers = HashSet::new();
-                        let prefix = format!("{}:", SLED_KEY_MNEMONIC_INDEX);
-
-                        let iter = persistence.db.scan_prefix(prefix.as_bytes());
-
-                        for entry_result in iter {
-                            match entry_result {
-                                Ok((key_ivec, _value_ivec)) => {
-                                    let key = String::from_utf8_lossy(&key_ivec);
-
-                                    // Key format: mnemonic_index:<HASH>:<ACCOUNT>:<INDEX>
-                                    let parts: Vec<&str> = key.split(':').collect();
+                    WalletCommands::List { sort } => {
+                        cprintln!("\n==============================================");
+                        cprintln!("Stored Wallets (Hash:Account)");
+                        cprintln!("==============================================");
+
+                        // Identifier (HASH:ACCOUNT) -> (index, address) pairs from the mnemonic_index tree.
+                        let mut wallets: BTreeMap<String, Vec<(u32, String)>> = BTreeMap::new();
+                        for (key, address) in persistence.iter_mnemonic_indices(None)? {
+                            wallets.entry(key.wallet()).or_default().push((key.index, address));
+                        }
 
-                                    // Need to confirm key starts with prefix and has enough parts
-                                    if parts.len() >= 3 && parts[0] == SLED_KEY_MNEMONIC_INDEX {
-                                        // Identifier is HASH:ACCOUNT
-                                        let identifier = format!("{}:{}", parts[1], parts[2]);
-                                        identifiers.insert(identifier);
+                        let mut summaries = Vec::with_capacity(wallets.len());
+                        for (identifier, mut addresses) in wallets {
+                            addresses.sort();
+                            let mut receipts = 0usize;
+                            let mut last_used: Option<String> = None;
+                            for (_, address) in &addresses {
+                                for entry in persistence.iter_receipts(Some(address))? {
+                                    receipts += 1;
+                                    // Receipts store the API's crypto_receipt, whose timestamp is RFC 3339.
+                                    let timestamp = entry.value.ok()
+                                        .and_then(|receipt| receipt["timestamp"].as_str().map(str::to_string));
+                                    if timestamp > last_used {
+                                        last_used = timestamp;
                                     }
                                 }
-                                Err(e) => {
-                                    return Err(format!("Sled iteration error: {}", e));
-                                }
                             }
+                            let first_address = addresses.first().map(|(_, address)| address.clone()).unwrap_or_default();
+                            summaries.push((identifier, first_address, addresses.len(), receipts, last_used));
+                        }
+
+                        if sort == SortOrder::Natural {
+                            summaries.sort_by_cached_key(|(id, ..)| wallet_identifier_sort_key(id));
                         }
 
-                        if identifiers.is_empty() {
-                            println!("No wallet identifiers found in local state.");
+                        if summaries.is_empty() {
+                            cprintln!("No wallet identifiers found in local state.");
                         } else {
-                            for id in identifiers {
-                                println!("{}", id);
+                            for (identifier, first_address, indices, receipts, last_used) in summaries {
+                                cprintln!("{}", identifier);
+                                cprintln!("  First address: {}", first_address);
+                                cprintln!("  Indices:       {}", indices);
+                                cprintln!("  Receipts:      {}", receipts);
+                                cprintln!("  Last used:     {}", last_used.as_deref().unwrap_or("never"));
                             }
                         }
-                        println!("==============================================");
+                        cprintln!("==============================================");
                         Ok(())
                     }
 
-                    WalletCommands::Addresses { wallet } => {
-                        let parts: Vec<&str> = wallet.split(':').collect();
-                        if parts.len() != 2 {
-                             return Err("Invalid wallet format. Expected <Hash>:<AccountIndex> (e.g., 16886378742194182050:0)".to_string());
-                        }
-                        let (hash, account) = (parts[0], parts[1]);
-
-                        println!("\n==============================================");
-                        println!("Addresses for Wallet: {} (Account {})", hash, account);
-                        println!("==============================================");
-
-                        let prefix = format!("{}:{}:{}:", SLED_KEY_MNEMONIC_INDEX, hash, account);
-                        let mut addresses_found = false;
+                    WalletCommands::Addresses { wallet, sort } => {
+                        let Some((hash, account)) = wallet.split_once(':').and_then(|(hash, account)| Some((hash, account.parse::<u32>().ok()?))) else {
+                             return Err(ExitError::new(exit_codes::USAGE_ERROR, "Invalid wallet format. Expected <Hash>:<AccountIndex> (e.g., 16886378742194182050:0)"));
+                        };
 
-                        let iter = persistence.db.scan_prefix(prefix.as_bytes());
+                        cprintln!("\n==============================================");
+                        cprintln!("Addresses for Wallet: {} (Account {})", hash, account);
+                        cprintln!("==============================================");
 
-                        for entry_result in iter { // Iterates over Result<(IVec, IVec), E>
-                            match entry_result {
-                                Ok((key_ivec, value_ivec)) => {
-                                    let key = String::from_utf8_lossy(&key_ivec);
-                                    let address = String::from_utf8_lossy(&value_ivec);
+                        // Sled returns the indices in byte order ("10" before "2").
+                        let mut addresses: Vec<(u32, String)> = persistence.iter_mnemonic_indices(Some((hash, account)))?
+                            .into_iter()
+                            .map(|(key, address)| (key.index, address))
+                            .collect();
 
-                                    // Key format: mnemonic_index:HASH:ACCOUNT:INDEX
-                                    let key_parts: Vec<&str> = key.split(':').collect();
-
-                                    // We know length must be 4 based on key format
-                                    if key_parts.len() == 4 {
-                                        let index = key_parts[3];
+                        if sort == SortOrder::Natural {
+                            addresses.sort_by_key(|(index, _)| *index);
+                        }
 
-                                        // Output format: <INDEX>:<ADDRESS>
-                                        println!("{}: {}", index, address);
-                                        addresses_found = true;
-                                    }
-                                }
-                                Err(e) => {
-                                    return Err(format!("Sled iteration error: {}", e));
-                                }
+                        // Output format: <INDEX>:<ADDRESS>
+                        for (index, address) in &addresses {
+                            match load_annotation(&persistence, "address", address)? {
+                                Some(annotation) => cprintln!("{}: {}  {}", index, address, annotation.summary()),
+                                None => cprintln!("{}: {}", index, address),
                             }
                         }
 
-                        if !addresses_found {
-                            println!("No addresses found for this wallet identifier.");
+                        if addresses.is_empty() {
+                            cprintln!("No addresses found for this wallet identifier.");
                         }
-                        println!("==============================================");
+                        cprintln!("==============================================");
                         Ok(())
                     }
 
                     WalletCommands::ListChallenges { address } => {
-                        println!("\n==============================================");
-                        println!("Completed Challenges for Address: {}", address);
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("Completed Challenges for Address: {}", address);
+                        cprintln!("==============================================");
 
-                        // Key format: receipt:<ADDRESS>:<ID>
-                        let prefix = format!("{}:{}:", SLED_KEY_RECEIPT, address);
                         let mut challenges_found = false;
 
-                        let iter = persistence.db.scan_prefix(prefix.as_bytes());
-
-                        for entry_result in iter {
-                            if let Ok((key_ivec, _value_ivec)) = entry_result {
-                                let key = String::from_utf8_lossy(&key_ivec);
-                                // Key format: receipt:<ADDRESS>:<CHALLENGE_ID>
-                                let parts: Vec<&str> = key.split(':').collect();
-
-                                if parts.len() == 3 && parts[0] == SLED_KEY_RECEIPT {
-                                    println!("{}", parts[2]); // parts[2] is the CHALLENGE_ID
-                                    challenges_found = true;
+                        for (key, _) in persistence.scan(&ReceiptKey::prefix(Some(&address)))? {
+                            if let Some(ReceiptKey { challenge_id, .. }) = ReceiptKey::parse(&key) {
+                                match load_annotation(&persistence, "challenge", &challenge_id)? {
+                                    Some(annotation) => cprintln!("{:<20} {}", challenge_id, annotation.summary()),
+                                    None => cprintln!("{}", challenge_id),
                                 }
-                            } else {
-                                // If the iteration itself fails, return the error.
-                                return Err(format!("Sled iteration error: {}", entry_result.unwrap_err()));
+                                challenges_found = true;
                             }
                         }
 
                         if !challenges_found {
-                            println!("No completed challenges found for this address.");
+                            cprintln!("No completed challenges found for this address.");
                         }
-                        println!("==============================================");
+                        cprintln!("==============================================");
                         Ok(())
                     }
-                    WalletCommands::DonateAll { base, donate_to, mnemonic, mnemonic_file, mnemonic_account, mnemonic_starting_index, tolerance, max_iteration } => {
-                        println!("\n==============================================");
-                        println!("💸 Starting Donation Sweep Mode");
-                        println!("==============================================");
-
-                        // 1) Mnemonic resolution (unchanged)
-                        let mnemonic_phrase: String;
-                        if mnemonic.is_some() && mnemonic_file.is_some() {
-                            return Err("Cannot use both '--mnemonic' and '--mnemonic-file' flags simultaneously.".to_string());
-                        } else if let Some(file_path) = mnemonic_file.as_ref() {
-                            match fs::read_to_string(file_path) {
-                                Ok(content) => { mnemonic_phrase = content.trim().to_string(); }
-                                Err(e) => { return Err(format!("🚨 Failed to read mnemonic file {}: {}", file_path, e)); }
+                    WalletCommands::ExportReceipts { address, format, file } => run_export_receipts(&persistence, &address, format, &file).map_err(ExitError::from),
+                    WalletCommands::Reconcile { address } => run_wallet_reconcile(cli, &persistence, &address),
+                    WalletCommands::Note { address, edit } => run_note_command(&persistence, "address", &address, edit).map_err(ExitError::from),
+                    WalletCommands::Stats { base, mnemonic, mnemonic_file, mnemonic_account, mnemonic_starting_index, count, concurrency } => {
+                        let mnemonic_phrase = resolve_mnemonic(mnemonic, mnemonic_file)?;
+                        let api_url = cli.api_url.as_ref()
+                            .ok_or_else(|| "FATAL: --api-url must be specified for wallet stats.".to_string())?;
+                        let client = utils::create_api_client()
+                            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+                        cprintln!("\n==============================================");
+                        cprintln!("📊 Statistics Sweep: account {}, indices {}..{} ({} in flight, {} req/s max)",
+                            mnemonic_account, mnemonic_starting_index, mnemonic_starting_index.saturating_add(count), concurrency, cli.max_rps);
+                        cprintln!("==============================================");
+
+                        let targets: Vec<(u32, String)> = (mnemonic_starting_index..mnemonic_starting_index.saturating_add(count))
+                            .map(|index| {
+                                let key_pair = if base || cli.base_address {
+                                    cardano::derive_key_pair_from_mnemonic_base(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index, cli.stake_index)
+                                } else {
+                                    cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index)
+                                };
+                                (index, key_pair.2.to_bech32().unwrap())
+                            })
+                            .collect();
+
+                        let results = sweep::fetch_statistics_concurrent(&client, api_url, &targets, concurrency);
+
+                        let mut total_receipts: u64 = 0;
+                        let mut total_night: u64 = 0;
+                        let mut failures = 0;
+                        for (index, address, result) in results {
+                            match result {
+                                Ok(stats) => {
+                                    total_receipts += stats.crypto_receipts as u64;
+                                    total_night += stats.night_allocation as u64;
+                                    cprintln!("Index {:<6} {} Receipts: {:<5} NIGHT: {}", index, address, stats.crypto_receipts, stats.night_allocation);
+                                }
+                                Err(e) => {
+                                    failures += 1;
+                                    cprintln!("Index {:<6} {} ❌ {}", index, address, e);
+                                }
                             }
-                        } else if let Some(phrase) = mnemonic {
-                            mnemonic_phrase = phrase;
-                        } else {
-                            return Err("FATAL: Either '--mnemonic' or '--mnemonic-file' must be specified.".to_string());
                         }
 
+                        cprintln!("----------------------------------------------");
+                        cprintln!("Total Receipts: {}", total_receipts);
+                        cprintln!("Total NIGHT Allocation: {}", total_night);
+                        if failures > 0 {
+                            cprintln!("Failed Lookups: {}", failures);
+                        }
+                        crate::shutdown::check_cancelled().map_err(ExitError::from)
+                    }
+                    WalletCommands::Attest { mnemonic, mnemonic_file, mnemonic_account, statement, with_receipts_only, file } => {
+                        let mnemonic_phrase = resolve_mnemonic(mnemonic, mnemonic_file)?;
+                        let mnemonic_hash = cardano::mnemonic_hash(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref());
+
+                        cprintln!("\n==============================================");
+                        cprintln!("🔏 Attesting Wallet {}:{}", mnemonic_hash, mnemonic_account);
+                        cprintln!("==============================================");
+
+                        // Addresses this mnemonic mined with, by derivation index
+                        let mut indexed: Vec<(u32, String)> = persistence.iter_mnemonic_indices(Some((&mnemonic_hash.to_string(), mnemonic_account)))?
+                            .into_iter()
+                            .map(|(key, address)| (key.index, address))
+                            .collect();
+                        indexed.sort();
+                        if indexed.is_empty() {
+                            return Err(ExitError::new(exit_codes::NOT_FOUND, format!("No addresses of this mnemonic (wallet {}:{}) are in the local database.", mnemonic_hash, mnemonic_account)));
+                        }
+
+                        let issued_at = chrono::Utc::now().to_rfc3339();
+                        let mut addresses = Vec::new();
+                        for (index, stored_address) in indexed {
+                            // The miner derives enterprise addresses; accept base ones from older runs too.
+                            let key_pair = [
+                                cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index),
+                                cardano::derive_key_pair_from_mnemonic_base(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index, cli.stake_index),
+                                // Older releases used the stake key of the same index.
+                                cardano::derive_key_pair_from_mnemonic_base(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index, index),
+                            ]
+                            .into_iter()
+                            .find(|key_pair| key_pair.2.to_bech32().ok().as_deref() == Some(stored_address.as_str()));
+                            let Some(key_pair) = key_pair else {
+                                cprintln!("⚠️ Index {}: stored address {} does not derive from this mnemonic. Skipped.", index, stored_address);
+                                continue;
+                            };
+
+                            let mut receipts = Vec::new();
+                            for (key, json) in persistence.scan(&ReceiptKey::prefix(Some(&stored_address)))? {
+                                let Some(ReceiptKey { challenge_id, .. }) = ReceiptKey::parse(&key) else { continue };
+                                let receipt = serde_json::from_str(&json).unwrap_or(serde_json::Value::String(json));
+                                receipts.push(AttestedReceipt { challenge_id, receipt });
+                            }
+                            if with_receipts_only && receipts.is_empty() {
+                                continue;
+                            }
+
+                            let message = AttestedAddress::message(&stored_address, &issued_at, statement.as_deref(), &receipts);
+                            let (signature, _) = cardano::cip8_sign(&key_pair, &message);
+                            cprintln!("Index {:<6} {} Receipts: {}", index, stored_address, receipts.len());
+                            addresses.push(AttestedAddress {
+                                account: mnemonic_account,
+                                index,
+                                address: stored_address,
+                                receipts,
+                                message,
+                                signature,
+                                public_key: hex::encode(key_pair.1.as_ref()),
+                            });
+                        }
+
+                        let receipt_count: usize = addresses.iter().map(|address| address.receipts.len()).sum();
+                        let attestation = Attestation { format: ATTESTATION_FORMAT.to_string(), issued_at, statement, addresses };
+                        let json = serde_json::to_string_pretty(&attestation)
+                            .map_err(|e| format!("Failed to serialize attestation: {}", e))?;
+                        fs::write(&file, json).map_err(|e| format!("Failed to write attestation file {}: {}", file, e))?;
+
+                        cprintln!("----------------------------------------------");
+                        cprintln!("✅ Wrote {} signed address(es) with {} receipt(s) to {}.", attestation.addresses.len(), receipt_count, file);
+                        cprintln!("==============================================");
+                        Ok(())
+                    }
+
+                    WalletCommands::DonateAll { base, donate_to, mnemonic, mnemonic_file, mnemonic_account, mnemonic_starting_index, tolerance, max_iteration, resume } => {
+                        cprintln!("\n==============================================");
+                        cprintln!("💸 Starting Donation Sweep Mode");
+                        cprintln!("==============================================");
+
+                        // 1) Mnemonic resolution
+                        let mnemonic_phrase = resolve_mnemonic(mnemonic, mnemonic_file)?;
+
                         // 2) API setup (unchanged)
                         let api_url = cli.api_url.as_ref()
                             .ok_or_else(|| "FATAL: --api-url must be specified for donation.".to_string())?;
 
                         if !cli.accept_tos {
-                            return Err("FATAL: You must pass the '--accept-tos' flag to proceed with donation.".to_string());
+                            return Err(ExitError::new(exit_codes::USAGE_ERROR, "FATAL: You must pass the '--accept-tos' flag to proceed with donation."));
                         }
 
                         let client = utils::create_api_client()
                             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
-                        let mut index = mnemonic_starting_index;
+                        // Next index to try, saved after every step so an interrupted sweep can resume.
+                        let progress_key = format!("{}:{}:{}:{}", SLED_KEY_DONATE_PROGRESS, cardano::mnemonic_hash(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref()), mnemonic_account, donate_to);
+                        let saved_index = persistence.get(&progress_key)?.and_then(|value| value.parse::<u32>().ok());
+                        let mut index = match (resume, saved_index) {
+                            (true, Some(saved)) => {
+                                cprintln!("⏩ Resuming the previous sweep at index {}.", saved);
+                                saved
+                            }
+                            (true, None) => {
+                                cprintln!("No saved progress for this mnemonic account and destination. Starting at index {}.", mnemonic_starting_index);
+                                mnemonic_starting_index
+                            }
+                            (false, _) => mnemonic_starting_index,
+                        };
                         let donation_message = format!("Assign accumulated Scavenger rights to: {}", donate_to);
                         let mut success_count: u32 = 0;
 
-                        println!("Destination Address: {}", donate_to);
-                        println!("Starting Account Index: {}", mnemonic_account);
-                        println!("Starting Derivation Index: {}", index);
-                        println!("API URL: {}", api_url);
-                        println!("Max Iterations: {}", max_iteration);
-                        println!("Tolerance: {}", tolerance);
-                        println!("Message: \"{}\"", donation_message);
-                        println!("----------------------------------------------");
+                        cprintln!("Destination Address: {}", donate_to);
+                        cprintln!("Starting Account Index: {}", mnemonic_account);
+                        cprintln!("Starting Derivation Index: {}", index);
+                        cprintln!("API URL: {}", api_url);
+                        cprintln!("Max Iterations: {}", max_iteration);
+                        cprintln!("Tolerance: {}", tolerance);
+                        cprintln!("Message: \"{}\"", donation_message);
+                        cprintln!("----------------------------------------------");
 
                         let mut consecutive_404s: u32 = 0;
                         let mut performed: u32 = 0;
 
                         // 3) Sweep loop with max_iteration cap
                         loop {
+                            persistence.set(&progress_key, &index.to_string())?;
+                            if crate::shutdown::cancelled() {
+                                cprintln!("⏸️ Sweep interrupted before index {}. Continue later with --resume.", index);
+                                break;
+                            }
                             // Respect max_iteration (0 = unlimited)
                             if max_iteration > 0 && performed >= max_iteration {
-                                println!("⏹ Reached max_iteration limit ({}).", max_iteration);
+                                cprintln!("⏹ Reached max_iteration limit ({}).", max_iteration);
                                 break;
                             }
 
-                            let key_pair_result = if base {
-                                cardano::derive_key_pair_from_mnemonic_base(&mnemonic_phrase, mnemonic_account, index)
+                            let key_pair_result = if base || cli.base_address {
+                                cardano::derive_key_pair_from_mnemonic_base(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index, cli.stake_index)
                             } else {
-                                cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, mnemonic_account, index)
+                                cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), mnemonic_account, index)
                             };
 
                             let original_address = key_pair_result.2.to_bech32().unwrap();
 
-                            print!("Attempting donation for index {} ({})... ", index, &original_address);
+                            cprintln!("Attempting donation for index {} ({})...", index, &original_address);
 
                             let (donation_signature, _) = cardano::cip8_sign(&key_pair_result, &donation_message);
 
-                            let outcome = api::donate_to(
+                            let outcome = api::block_on(api::donate_to(
                                 &client,
                                 api_url,
                                 &original_address,   // <- original first
                                 &donate_to,          // <- destination second
                                 &donation_signature,
-                            );
+                            ));
 
                             match outcome {
                                 Ok(donation_id) => {
+                                    save_donation(&persistence, &DonationRecord::new(&original_address, &donate_to, &donation_id))?;
                                     // Treat 2xx and 409 as success (409 returns "(already-done)")
                                     if donation_id == "(already-done)" {
-                                        println!("✅ ALREADY MAPPED at index {} ({})", index, original_address);
+                                        cprintln!("✅ ALREADY MAPPED at index {} ({})", index, original_address);
                                     } else {
-                                        println!("✅ SUCCESS at index {} — Donation ID: {}", index, donation_id);
+                                        cprintln!("✅ SUCCESS at index {} — Donation ID: {}", index, donation_id);
                                     }
                                     success_count = success_count.wrapping_add(1);
                                     consecutive_404s = 0;
@@ -584,74 +1530,203 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
                                     continue;
                                 }
                                 Err(e) => {
-                                    // Prefer structured classification by HTTP code; fall back to keywords
-                                    let code = http_code_from_err(&e);
-                                    if matches!(code, Some(404)) || e.contains("NotRegistered") {
+                                    // Classify by HTTP code, then by the API's error kind
+                                    let code = e.status();
+                                    if matches!(code, Some(404)) || e.kind() == Some(api::ApiErrorKind::NotRegistered) {
                                         // 404 tolerance window
                                         consecutive_404s = consecutive_404s.saturating_add(1);
-                                        println!("⚠️ 404 ({} of {} tolerance). Continuing.", consecutive_404s, tolerance);
+                                        cprintln!("⚠️ 404 ({} of {} tolerance). Continuing.", consecutive_404s, tolerance);
                                         if consecutive_404s >= tolerance {
-                                            println!("🛑 STOP: exceeded 404 tolerance (>={}). Assuming end of registered/funded addresses.", tolerance);
+                                            cprintln!("🛑 STOP: exceeded 404 tolerance (>={}). Assuming end of registered/funded addresses.", tolerance);
                                             break;
                                         }
                                         index = index.wrapping_add(1);
                                         performed = performed.wrapping_add(1);
                                         continue;
-                                    } else if matches!(code, Some(409)) || e.contains("(already-done)") {
+                                    } else if matches!(code, Some(409)) || e.kind() == Some(api::ApiErrorKind::AlreadySubmitted) {
                                         // Be extra-safe: treat explicit 409 shape as benign success-equivalent
-                                        println!("✅ ALREADY MAPPED (409) at index {} ({})", index, original_address);
+                                        save_donation(&persistence, &DonationRecord::new(&original_address, &donate_to, "(already-done)"))?;
+                                        cprintln!("✅ ALREADY MAPPED (409) at index {} ({})", index, original_address);
                                         consecutive_404s = 0;
                                         index = index.wrapping_add(1);
                                         performed = performed.wrapping_add(1);
                                         continue;
-                                    } else if matches!(code, Some(400)) || e.contains("BadSig") {
+                                    } else if matches!(code, Some(400)) {
                                         // Bad signature → skip index, no 404 window bump
-                                        println!("❌ BAD SIG at index {}. Skipping. ({})", index, e);
+                                        cprintln!("❌ BAD SIG at index {}. Skipping. ({})", index, e);
                                         index = index.wrapping_add(1);
                                         performed = performed.wrapping_add(1);
                                         continue;
-                                    } else if e.contains("Max retries exceeded") {
-                                        println!("❌ Max retries exceeded. Stopping. ({})", e);
+                                    } else if e.is_retryable() {
+                                        cprintln!("❌ Max retries exceeded. Stopping. ({})", e);
                                         break;
                                     } else {
                                         // Other non-retryable 4xx or unexpected error → stop (like original fatal path)
-                                        println!("❌ Non-retryable/Unexpected API error. Stopping. ({})", e);
+                                        cprintln!("❌ Non-retryable/Unexpected API error. Stopping. ({})", e);
                                         break;
                                     }
                                 }
                             }
                         }
 
-                        println!("\n==============================================");
-                        println!("💸 Donation Sweep Complete. Total Successful Donations: {}", success_count);
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("💸 Donation Sweep Complete. Total Successful Donations: {}", success_count);
+                        cprintln!("==============================================");
+                        crate::shutdown::check_cancelled().map_err(ExitError::from)
+                    }
+                }
+            }
+            Commands::History(cmd) => {
+                match cmd {
+                    HistoryCommands::Cycles { challenge, address, limit } => {
+                        let prefix = match challenge.as_ref() {
+                            Some(id) => format!("{}:{}:", SLED_KEY_CYCLE, id),
+                            None => format!("{}:", SLED_KEY_CYCLE),
+                        };
+
+                        let mut cycles: Vec<CycleRecord> = Vec::new();
+                        for (_, value) in persistence.scan_prefix(&prefix)? {
+                            match serde_json::from_str::<CycleRecord>(&value) {
+                                Ok(record) if address.as_ref().is_none_or(|a| *a == record.address) => cycles.push(record),
+                                Ok(_) => {}
+                                Err(e) => ceprintln!("⚠️ Skipping unreadable cycle record: {}", e),
+                            }
+                        }
+                        cycles.sort_by_key(|c| c.started_at_millis);
+                        let skip = cycles.len().saturating_sub(limit);
+
+                        cprintln!("\n==============================================");
+                        cprintln!("📜 Mining Cycles ({} shown of {})", cycles.len() - skip, cycles.len());
+                        cprintln!("==============================================");
+                        if cycles.is_empty() {
+                            cprintln!("No cycle records found.");
+                        }
+
+                        for cycle in cycles.iter().skip(skip) {
+                            let index = cycle.mnemonic_index.map_or("-".to_string(), |i| i.to_string());
+                            cprintln!("\n[{}] {} index {} -> {}", cycle.started_at, cycle.challenge_id, index, cycle.outcome);
+                            cprintln!("  Address:  {}", cycle.address);
+                            cprintln!("  Trigger:  {}", cycle.trigger);
+                            cprintln!("  Ended:    {}", cycle.ended_at.as_deref().unwrap_or("(still running or interrupted)"));
+                            if cycle.total_hashes > 0 {
+                                cprintln!("  Hashes:   {}", cycle.total_hashes);
+                            }
+                            if !cycle.skipped_indices.is_empty() {
+                                let skipped: Vec<String> = cycle.skipped_indices.iter().map(|i| i.to_string()).collect();
+                                cprintln!("  Skipped:  {} (receipt already present)", skipped.join(", "));
+                            }
+                            for key in &cycle.submissions {
+                                cprintln!("  Solution: {} [{}]", key, submission_state(&persistence, key)?);
+                            }
+                        }
                         Ok(())
                     }
                 }
             }
+            Commands::Report(ReportCommands::Audit) => {
+                let target = cli.donate_to.as_deref();
+
+                // Receipts per address; "solved by network" markers are not rewards of ours.
+                let parse_time = |text: &str| chrono::DateTime::parse_from_rfc3339(text).ok().map(|at| at.with_timezone(&chrono::Utc));
+                let mut receipts: BTreeMap<String, usize> = BTreeMap::new();
+                let mut latest_receipt: HashMap<String, chrono::DateTime<chrono::Utc>> = HashMap::new();
+                let mut markers = 0;
+                for entry in persistence.iter_receipts(None)? {
+                    let address = entry.key.address;
+                    let receipt = entry.value.ok();
+                    if receipt.as_ref().and_then(|r| r["status"].as_str()) == Some(RECEIPT_STATUS_CONSUMED_MARKER) {
+                        markers += 1;
+                        continue;
+                    }
+                    *receipts.entry(address.to_string()).or_insert(0) += 1;
+                    if let Some(at) = receipt.as_ref().and_then(|r| r["timestamp"].as_str()).and_then(parse_time) {
+                        let latest = latest_receipt.entry(address.to_string()).or_insert(at);
+                        *latest = (*latest).max(at);
+                    }
+                }
+
+                let mut donations: HashMap<String, DonationRecord> = HashMap::new();
+                for (_, value) in persistence.scan_prefix(&format!("{}:", SLED_KEY_DONATION))? {
+                    match serde_json::from_str::<DonationRecord>(&value) {
+                        Ok(record) => { donations.insert(record.address.clone(), record); }
+                        Err(e) => ceprintln!("⚠️ Skipping unreadable donation record: {}", e),
+                    }
+                }
+
+                cprintln!("\n==============================================");
+                cprintln!("🧾 Receipt / Donation Audit");
+                cprintln!("==============================================");
+                match target {
+                    Some(target) => cprintln!("Target: {}", target),
+                    None => cprintln!("Target: any (no --donate-to given)"),
+                }
+                cprintln!("----------------------------------------------");
+                if receipts.is_empty() {
+                    cprintln!("No receipts found.");
+                }
+
+                let mut gaps: Vec<(&String, usize)> = Vec::new();
+                for (address, &count) in &receipts {
+                    let donation = donations.get(address);
+                    let status = match donation {
+                        // Rewards of the target address itself need no assignment.
+                        _ if target == Some(address.as_str()) => "✅ is the target".to_string(),
+                        // The miner re-donates after late receipts; a failed re-donation shows up here.
+                        Some(record) if latest_receipt.get(address).is_some_and(|latest| parse_time(&record.recorded_at).is_some_and(|at| at < *latest)) => {
+                            format!("❌ donated to {} at {}, before the latest receipt", record.donate_to, record.recorded_at)
+                        }
+                        Some(record) if target.is_none_or(|target| target == record.donate_to) => {
+                            format!("✅ assigned to {} (ID {}, {})", record.donate_to, record.donation_id, record.recorded_at)
+                        }
+                        Some(record) => format!("❌ assigned to {} instead", record.donate_to),
+                        None => "❌ not assigned".to_string(),
+                    };
+                    if status.starts_with('❌') {
+                        gaps.push((address, count));
+                    }
+                    cprintln!("{} Receipts: {:<4} {}", address, count, status);
+                }
+
+                let receipt_total: usize = receipts.values().sum();
+                let gap_receipts: usize = gaps.iter().map(|(_, count)| count).sum();
+                cprintln!("----------------------------------------------");
+                cprintln!("Addresses with receipts: {} ({} receipts)", receipts.len(), receipt_total);
+                cprintln!("Assigned:                {}", receipts.len() - gaps.len());
+                cprintln!("Not assigned:            {} ({} receipts)", gaps.len(), gap_receipts);
+                if markers > 0 {
+                    cprintln!("Solved by network:       {} (not counted)", markers);
+                }
+                let orphans = donations.keys().filter(|address| !receipts.contains_key(*address)).count();
+                if orphans > 0 {
+                    cprintln!("Donations without local receipts: {}", orphans);
+                }
+                cprintln!("==============================================");
+
+                if gaps.is_empty() {
+                    Ok(())
+                } else {
+                    cprintln!("Unassigned addresses (run `wallet donate-all` or donate them before the claim window closes):");
+                    for (address, count) in &gaps {
+                        cprintln!("  {} ({} receipts)", address, count);
+                    }
+                    Err(format!("Audit failed: {} address(es) with {} receipt(s) are not assigned to {}.",
+                        gaps.len(), gap_receipts, target.unwrap_or("any address")).into())
+                }
+            }
             Commands::Db(cmd) => {
                 match cmd {
                     DbCommands::Export { file } => {
-                        println!("\n==============================================");
-                        println!("Dumping Sled DB to: {}", file);
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("Dumping Sled DB to: {}", file);
+                        cprintln!("==============================================");
 
                         let mut entries: Vec<BackupEntry> = Vec::new();
                         let mut count = 0;
 
-                        // Iterate over the entire database
-                        for entry_result in persistence.db.iter() {
-                            match entry_result {
-                                Ok((key_ivec, value_ivec)) => {
-                                    let key = String::from_utf8_lossy(&key_ivec).into_owned();
-                                    let value = String::from_utf8_lossy(&value_ivec).into_owned();
-                                    entries.push(BackupEntry { key, value });
-                                    count += 1;
-                                }
-                                Err(e) => {
-                                    return Err(format!("Sled export iteration error: {}", e));
-                                }
-                            }
+                        // Iterate over the entire database (the empty prefix matches every key)
+                        for (key, value) in persistence.scan_prefix("")? {
+                            entries.push(BackupEntry { key, value });
+                            count += 1;
                         }
 
                         let json_content = serde_json::to_string_pretty(&entries)
@@ -660,14 +1735,18 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
                         fs::write(&file, json_content)
                             .map_err(|e| format!("Failed to write backup file {}: {}", file, e))?;
 
-                        println!("✅ Export complete. {} key-value pairs backed up.", count);
+                        cprintln!("✅ Export complete. {} key-value pairs backed up.", count);
                         Ok(())
                     }
 
+                    DbCommands::Backup { path } => run_db_backup(&persistence, &path).map_err(ExitError::from),
+                    DbCommands::Restore { path, force } => run_db_restore(&persistence, &path, force).map_err(ExitError::from),
+                    DbCommands::Check { fix } => run_db_check(cli, &persistence, fix).map_err(ExitError::from),
+                    DbCommands::Merge { other_state_dir } => run_db_merge(cli, &persistence, &other_state_dir).map_err(ExitError::from),
                     DbCommands::Import { file } => {
-                        println!("\n==============================================");
-                        println!("Importing Sled DB from: {}", file);
-                        println!("==============================================");
+                        cprintln!("\n==============================================");
+                        cprintln!("Importing Sled DB from: {}", file);
+                        cprintln!("==============================================");
 
                         let content = fs::read_to_string(&file)
                             .map_err(|e| format!("Failed to read backup file {}: {}", file, e))?;
@@ -687,27 +1766,73 @@ pub fn handle_sync_commands(cli: &Cli) -> Result<(), String> {
                                     skipped_count += 1;
                                 }
                                 Err(e) => {
-                                    eprintln!("⚠️ Import stopped due to Sled error: {}", e);
+                                    ceprintln!("⚠️ Import stopped due to Sled error: {}", e);
                                     break;
                                 }
                             }
                         }
 
-                        println!("✅ Import complete.");
-                        println!("  Imported new items: {}", imported_count);
-                        println!("  Skipped existing items: {}", skipped_count);
+                        cprintln!("✅ Import complete.");
+                        cprintln!("  Imported new items: {}", imported_count);
+                        cprintln!("  Skipped existing items: {}", skipped_count);
                         Ok(())
                     }
                 }
             }
-            _ => return Err("Invalid command passed to handle_persistence_commands.".to_string()),
+            Commands::MigrateExport { out_dir } => crate::migrate::run_export(&persistence, &out_dir).map_err(ExitError::from),
+            _ => return Err(ExitError::new(exit_codes::USAGE_ERROR, "Invalid command passed to handle_persistence_commands.")),
         }
     } else {
         // This case should not be reachable if logic in main.rs is correct,
         // but acts as a fallback.
-        Err("Invalid command passed to handle_persistence_commands.".to_string())
-    }?; // Use ? to propagate the error from the match block
+        Err(ExitError::new(exit_codes::USAGE_ERROR, "Invalid command passed to handle_persistence_commands."))
+    };
 
-    persistence.close().map_err(|e| format!("Failed to close Sled DB: {}", e))?;
-    Ok(())
+    // Flush before reporting any error, so a failed or cancelled command keeps what it saved.
+    persistence.close().exit_code(exit_codes::DATABASE_ERROR)?;
+    // Whatever step failed last, a cancelled command reports the cancellation.
+    result.map_err(|e| if crate::shutdown::cancelled() { ExitError::new(exit_codes::CANCELLED, e.message) } else { e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge_json(day: u8, challenge_number: u16) -> String {
+        serde_json::to_string(&ChallengeData {
+            challenge_id: String::new(),
+            difficulty: "000FFFFF".to_string(),
+            no_pre_mine_key: "key".to_string(),
+            no_pre_mine_hour_str: "1".to_string(),
+            latest_submission: "2999-01-01T00:00:00Z".to_string(),
+            challenge_number,
+            day,
+            issued_at: String::new(),
+            rom_params: None,
+        }).unwrap()
+    }
+
+    #[test]
+    fn challenges_sort_by_day_and_number_not_by_id() {
+        let mut challenges = [
+            ("**D10C01", challenge_json(10, 1)),
+            ("broken", "not json".to_string()),
+            ("**D9C12", challenge_json(9, 12)),
+            ("**D9C2", challenge_json(9, 2)),
+        ];
+        challenges.sort_by_key(|(id, json)| challenge_sort_key(id, json));
+
+        let ids: Vec<&str> = challenges.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, ["**D9C2", "**D9C12", "**D10C01", "broken"]);
+    }
+
+    #[test]
+    fn wallet_identifiers_sort_numerically() {
+        let mut identifiers = ["10:0", "abc:0", "9:10", "9:2"];
+        identifiers.sort_by_key(|identifier| wallet_identifier_sort_key(identifier));
+        assert_eq!(identifiers, ["9:2", "9:10", "10:0", "abc:0"]);
+
+        // Identifiers whose parts do not parse fall back to lexical order.
+        assert!(wallet_identifier_sort_key("abc:x") < wallet_identifier_sort_key("abd:x"));
+    }
 }
@@ -0,0 +1,287 @@
+// src/console.rs
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===============================================
+// SERIALIZED CONSOLE OUTPUT
+// ===============================================
+//
+// The manager, miner dispatcher, submitter, poller and WebSocket threads all print. Every
+// message goes through one printer thread, so each message (including multi-line blocks
+// such as the statistics summary) is written in one piece, and lines printed while a
+// progress bar is active are written above the bar instead of through it.
+
+/// How long `flush` waits for queued output before giving up.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+enum ConsoleMsg {
+    Out(String),
+    Err(String),
+    /// One JSON event line, always written to stdout.
+    Event(String),
+    /// Route subsequent output around this bar (via `ProgressBar::suspend`) until detached.
+    AttachBar(ProgressBar),
+    DetachBar,
+    /// Text written to stdout without a trailing newline, e.g. an input prompt.
+    Prompt(String),
+    /// Acknowledged once every message queued before it has been written.
+    Flush(Sender<()>),
+}
+
+static PRINTER: OnceLock<Sender<ConsoleMsg>> = OnceLock::new();
+
+fn printer() -> &'static Sender<ConsoleMsg> {
+    PRINTER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<ConsoleMsg>();
+        thread::spawn(move || {
+            let mut bar: Option<ProgressBar> = None;
+            let write = |bar: &Option<ProgressBar>, text: &str, to_stderr: bool| {
+                // In JSON mode stdout carries only events; human-readable text goes to stderr.
+                let to_stderr = to_stderr || json_log();
+                let emit = || {
+                    if to_stderr {
+                        let _ = writeln!(std::io::stderr().lock(), "{}", text);
+                    } else {
+                        let _ = writeln!(std::io::stdout().lock(), "{}", text);
+                    }
+                };
+                match bar {
+                    Some(bar) => bar.suspend(emit),
+                    None => emit(),
+                }
+            };
+            for msg in rx {
+                match msg {
+                    ConsoleMsg::Out(text) => write(&bar, &text, false),
+                    ConsoleMsg::Err(text) => write(&bar, &text, true),
+                    ConsoleMsg::Event(line) => {
+                        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+                    }
+                    ConsoleMsg::Prompt(text) => {
+                        let mut stdout = std::io::stdout().lock();
+                        let _ = write!(stdout, "{}", text);
+                        let _ = stdout.flush();
+                    }
+                    ConsoleMsg::AttachBar(new_bar) => bar = Some(new_bar),
+                    ConsoleMsg::DetachBar => bar = None,
+                    ConsoleMsg::Flush(ack) => {
+                        let _ = std::io::stdout().flush();
+                        let _ = ack.send(());
+                    }
+                }
+            }
+        });
+        tx
+    })
+}
+
+/// Queues `text` (one or more lines) for stdout. Prefer the `cprintln!` macro.
+pub fn out(text: String) {
+    if let Err(mpsc::SendError(ConsoleMsg::Out(text))) = printer().send(ConsoleMsg::Out(text)) {
+        println!("{}", text);
+    }
+}
+
+/// Queues `text` (one or more lines) for stderr. Prefer the `ceprintln!` macro.
+pub fn err(text: String) {
+    if let Err(mpsc::SendError(ConsoleMsg::Err(text))) = printer().send(ConsoleMsg::Err(text)) {
+        eprintln!("{}", text);
+    }
+}
+
+/// Writes `text` to stdout without a newline and waits until it is shown, so input can be
+/// read right after it.
+pub fn prompt(text: &str) {
+    if printer().send(ConsoleMsg::Prompt(text.to_string())).is_err() {
+        print!("{}", text);
+        let _ = std::io::stdout().flush();
+    }
+    flush();
+}
+
+/// Makes the printer write around `bar` until `detach_progress_bar` is called.
+pub fn attach_progress_bar(bar: &ProgressBar) {
+    let _ = printer().send(ConsoleMsg::AttachBar(bar.clone()));
+}
+
+pub fn detach_progress_bar() {
+    let _ = printer().send(ConsoleMsg::DetachBar);
+}
+
+/// Blocks until everything queued so far has been written. Call before `process::exit`.
+pub fn flush() {
+    let Some(printer) = PRINTER.get() else {
+        return;
+    };
+    let (ack_tx, ack_rx) = mpsc::channel();
+    if printer.send(ConsoleMsg::Flush(ack_tx)).is_ok() {
+        let _ = ack_rx.recv_timeout(FLUSH_TIMEOUT);
+    }
+}
+
+// ===============================================
+// JSON EVENTS (--log-format json)
+// ===============================================
+//
+// Fleet operators ship logs to Loki/Elastic. With --log-format json, stdout carries one JSON
+// object per line for the events worth alerting on, and all human-readable output moves to
+// stderr. In text mode events are not printed; the regular messages already cover them.
+
+static JSON_LOG: AtomicBool = AtomicBool::new(false);
+
+/// Switches stdout to JSON events (--log-format json).
+pub fn set_json_log(enabled: bool) {
+    JSON_LOG.store(enabled, Ordering::Relaxed);
+}
+
+pub fn json_log() -> bool {
+    JSON_LOG.load(Ordering::Relaxed)
+}
+
+/// Emits event `name` with `fields` (a JSON object) as one line on stdout, stamped with the
+/// current time, and passes it to the live event stream. Does nothing in text mode unless the
+/// stream has subscribers.
+pub fn event(name: &str, fields: serde_json::Value) {
+    if !json_log() && !has_event_subscribers() {
+        return;
+    }
+    let mut line = serde_json::json!({
+        "ts": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "event": name,
+    });
+    if let (Some(line), serde_json::Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    let line = line.to_string();
+    #[cfg(feature = "web-ui")]
+    if let Some(stream) = EVENT_STREAM.get() {
+        let _ = stream.send(line.clone());
+    }
+    if !json_log() {
+        return;
+    }
+    if let Err(mpsc::SendError(ConsoleMsg::Event(line))) = printer().send(ConsoleMsg::Event(line)) {
+        println!("{}", line);
+    }
+}
+
+/// Events buffered per subscriber; a subscriber that falls further behind skips the oldest.
+#[cfg(feature = "web-ui")]
+const EVENT_STREAM_CAPACITY: usize = 256;
+
+// The same events, as JSON lines, for in-process subscribers (the web UI).
+#[cfg(feature = "web-ui")]
+static EVENT_STREAM: OnceLock<tokio::sync::broadcast::Sender<String>> = OnceLock::new();
+
+/// Subscribes to every event emitted from now on, in either log format.
+#[cfg(feature = "web-ui")]
+pub fn subscribe_events() -> tokio::sync::broadcast::Receiver<String> {
+    EVENT_STREAM.get_or_init(|| tokio::sync::broadcast::channel(EVENT_STREAM_CAPACITY).0).subscribe()
+}
+
+fn has_event_subscribers() -> bool {
+    #[cfg(feature = "web-ui")]
+    return EVENT_STREAM.get().is_some_and(|stream| stream.receiver_count() > 0);
+    #[cfg(not(feature = "web-ui"))]
+    false
+}
+
+// ===============================================
+// PROGRESS DISPLAY
+// ===============================================
+//
+// The library only reports `Progress` events; rendering them is up to the binary. Under
+// systemd or Docker a redrawn bar fills the journal with escape codes, so when stdout is not a
+// terminal (or --no-progress is set) progress is printed as an occasional plain-text line.
+
+/// Minimum time between two plain-text progress lines.
+const PROGRESS_LINE_INTERVAL: Duration = Duration::from_secs(30);
+
+static PLAIN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Forces plain-text progress lines even on a terminal (--no-progress).
+pub fn set_plain_progress(plain: bool) {
+    PLAIN_PROGRESS.store(plain, Ordering::Relaxed);
+}
+
+/// True when progress should be printed as plain-text lines instead of a bar.
+pub fn plain_progress() -> bool {
+    PLAIN_PROGRESS.load(Ordering::Relaxed) || json_log() || !std::io::stdout().is_terminal()
+}
+
+/// Progress of one long-running task: an indicatif bar the printer writes around, or
+/// plain-text lines printed at most every `PROGRESS_LINE_INTERVAL`.
+pub enum ProgressDisplay {
+    Bar(ProgressBar),
+    Lines { last_line: Instant },
+}
+
+impl ProgressDisplay {
+    /// Starts a display. `template` is the indicatif template used in bar mode.
+    pub fn new(len: u64, template: &str) -> Self {
+        if plain_progress() {
+            return ProgressDisplay::Lines { last_line: Instant::now() };
+        }
+        let bar = ProgressBar::new(len);
+        bar.set_style(ProgressStyle::with_template(template).unwrap().progress_chars("#>-"));
+        attach_progress_bar(&bar);
+        ProgressDisplay::Bar(bar)
+    }
+
+    /// Moves the bar to `pos` with `message`. In line mode, prints `message` if the last line
+    /// is older than `PROGRESS_LINE_INTERVAL`.
+    pub fn update(&mut self, pos: u64, message: String) {
+        match self {
+            ProgressDisplay::Bar(bar) => {
+                bar.set_position(pos);
+                bar.set_message(message);
+            }
+            ProgressDisplay::Lines { last_line } => {
+                if last_line.elapsed() >= PROGRESS_LINE_INTERVAL {
+                    out(format!("⏳ {}", message));
+                    *last_line = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Ends the display, leaving `message` as its last line.
+    pub fn finish(self, message: String) {
+        match self {
+            ProgressDisplay::Bar(bar) => {
+                detach_progress_bar();
+                bar.finish_with_message(message);
+            }
+            ProgressDisplay::Lines { .. } => out(message),
+        }
+    }
+
+    /// Like `finish`, for a task that stopped before completing.
+    pub fn abandon(self, message: String) {
+        match self {
+            ProgressDisplay::Bar(bar) => {
+                detach_progress_bar();
+                bar.abandon_with_message(message);
+            }
+            ProgressDisplay::Lines { .. } => out(message),
+        }
+    }
+}
+
+/// `println!` through the console printer.
+macro_rules! cprintln {
+    () => { $crate::console::out(String::new()) };
+    ($($arg:tt)*) => { $crate::console::out(format!($($arg)*)) };
+}
+
+/// `eprintln!` through the console printer.
+macro_rules! ceprintln {
+    () => { $crate::console::err(String::new()) };
+    ($($arg:tt)*) => { $crate::console::err(format!($($arg)*)) };
+}
@@ -0,0 +1,253 @@
+// src/control.rs
+
+use crate::data_types::{ControlRequest, ManagerCommand, SubmitterCommand};
+use crate::schema::SLED_KEY_RECEIPT;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+// ===============================================
+// CONTROL SOCKET (--control-socket)
+// ===============================================
+//
+// A Unix socket the running miner listens on so operators (and `shadow-harvester shell`) can
+// inspect and steer it without restarting. The protocol is one JSON object per line in each
+// direction: `{"cmd": "status", "args": []}` is answered with `{"ok": true, "data": {...}}`
+// or `{"ok": false, "error": "..."}`. The socket is created with mode 0600.
+
+/// How long a client waits for the miner to answer one request.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Receipts returned by `receipts` when no count is given.
+const DEFAULT_RECEIPT_COUNT: usize = 20;
+
+/// Commands the control socket understands, with their arguments, for `help`.
+pub const COMMANDS: [(&str, &str); 8] = [
+    ("status", "Current challenge, address, mining state and solutions found"),
+    ("pause", "Stop the miner; new challenges are queued until resume"),
+    ("resume", "Resume mining the queued or current challenge"),
+    ("switch <CHALLENGE_ID>", "Mine a challenge stored in the local database"),
+    ("threads <COUNT>", "Change the number of worker threads"),
+    ("receipts [COUNT]", "List the most recent saved receipts"),
+    ("retry-pending", "Resubmit the solutions waiting in the pending queue"),
+    ("verify <NONCE> [ADDRESS]", "Hash a nonce for the current challenge and check the difficulty"),
+];
+
+/// Starts listening on `path`, creating its directory if needed. A stale socket file left by a
+/// crashed miner is replaced; a socket another miner is still serving is an error.
+#[cfg(unix)]
+pub fn spawn_control_server(path: &str, manager_tx: Sender<ManagerCommand>, submitter_tx: Sender<SubmitterCommand>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if let Some(parent) = std::path::Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create the control socket directory {}: {}", parent.display(), e))?;
+    }
+    if std::path::Path::new(path).exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("Control socket {} is in use by another running instance.", path));
+        }
+        std::fs::remove_file(path).map_err(|e| format!("Failed to remove stale control socket {}: {}", path, e))?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| format!("Failed to bind control socket {}: {}", path, e))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict control socket {}: {}", path, e))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let manager_tx = manager_tx.clone();
+            let submitter_tx = submitter_tx.clone();
+            thread::spawn(move || {
+                let Ok(reader) = stream.try_clone() else {
+                    return;
+                };
+                let mut writer = stream;
+                for line in BufReader::new(reader).lines() {
+                    let Ok(line) = line else {
+                        return;
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let reply = match handle_line(&line, &manager_tx, &submitter_tx) {
+                        Ok(data) => json!({ "ok": true, "data": data }),
+                        Err(e) => json!({ "ok": false, "error": e }),
+                    };
+                    if writeln!(writer, "{}", reply).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn spawn_control_server(_path: &str, _manager_tx: Sender<ManagerCommand>, _submitter_tx: Sender<SubmitterCommand>) -> Result<(), String> {
+    Err("The control socket is only available on Unix.".to_string())
+}
+
+/// Parses one request line and answers it.
+fn handle_line(line: &str, manager_tx: &Sender<ManagerCommand>, submitter_tx: &Sender<SubmitterCommand>) -> Result<Value, String> {
+    let request: Value = serde_json::from_str(line).map_err(|e| format!("Malformed request: {}", e))?;
+    let cmd = request["cmd"].as_str().ok_or("Request has no 'cmd'.")?;
+    let args: Vec<String> = request["args"]
+        .as_array()
+        .map(|args| args.iter().filter_map(|arg| arg.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    let manager_request = match (cmd, args.as_slice()) {
+        ("status", []) => ControlRequest::Status,
+        ("pause", []) => ControlRequest::Pause,
+        ("resume", []) => ControlRequest::Resume,
+        ("switch", [challenge_id]) => ControlRequest::Switch(challenge_id.clone()),
+        ("threads", [count]) => ControlRequest::SetThreads(count.parse().map_err(|_| format!("Invalid thread count '{}'.", count))?),
+        ("verify", [nonce]) => ControlRequest::Verify { nonce: nonce.clone(), address: None },
+        ("verify", [nonce, address]) => ControlRequest::Verify { nonce: nonce.clone(), address: Some(address.clone()) },
+        ("receipts", []) => return list_receipts(submitter_tx, DEFAULT_RECEIPT_COUNT),
+        ("receipts", [count]) => {
+            let count = count.parse().map_err(|_| format!("Invalid receipt count '{}'.", count))?;
+            return list_receipts(submitter_tx, count);
+        }
+        ("retry-pending", []) => return retry_pending(submitter_tx),
+        ("state-get", [key]) => return state_request(submitter_tx, |reply_tx| SubmitterCommand::GetState(key.clone(), reply_tx)).map(|value| json!(value)),
+        ("state-list", [prefix]) => return state_request(submitter_tx, |reply_tx| SubmitterCommand::ListState(prefix.clone(), reply_tx)).map(|entries| json!(entries)),
+        ("state-set", [key, value]) => {
+            return state_request(submitter_tx, |reply_tx| SubmitterCommand::WriteState(key.clone(), Some(value.clone()), reply_tx)).map(|()| Value::Null);
+        }
+        ("state-remove", [key]) => {
+            return state_request(submitter_tx, |reply_tx| SubmitterCommand::WriteState(key.clone(), None, reply_tx)).map(|()| Value::Null);
+        }
+        _ => return Err(format!("Unknown command or wrong arguments: {} {}", cmd, args.join(" "))),
+    };
+    ask_manager(manager_tx, manager_request)
+}
+
+/// Sends `request` to the challenge manager and waits for its answer.
+pub fn ask_manager(manager_tx: &Sender<ManagerCommand>, request: ControlRequest) -> Result<Value, String> {
+    ask_manager_within(manager_tx, request, REPLY_TIMEOUT)
+}
+
+/// Like `ask_manager`, with a custom reply timeout.
+pub fn ask_manager_within(manager_tx: &Sender<ManagerCommand>, request: ControlRequest, timeout: Duration) -> Result<Value, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    manager_tx
+        .send(ManagerCommand::Control(request, reply_tx))
+        .map_err(|_| "The miner is shutting down.".to_string())?;
+    reply_rx.recv_timeout(timeout).map_err(|_| "The manager did not answer in time.".to_string())?
+}
+
+/// The `count` most recently saved receipts, newest first.
+fn list_receipts(submitter_tx: &Sender<SubmitterCommand>, count: usize) -> Result<Value, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    submitter_tx
+        .send(SubmitterCommand::ListState(format!("{}:", SLED_KEY_RECEIPT), reply_tx))
+        .map_err(|_| "The state worker is shutting down.".to_string())?;
+    let entries = reply_rx.recv_timeout(REPLY_TIMEOUT).map_err(|_| "The state worker did not answer in time.".to_string())??;
+
+    let mut receipts: Vec<Value> = entries
+        .into_iter()
+        .map(|(key, value)| {
+            let mut parts = key.splitn(3, ':').skip(1);
+            let receipt: Value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+            json!({
+                "address": parts.next(),
+                "challenge_id": parts.next(),
+                "timestamp": receipt["timestamp"].as_str(),
+                "receipt": receipt,
+            })
+        })
+        .collect();
+    receipts.sort_by(|a, b| b["timestamp"].as_str().cmp(&a["timestamp"].as_str()));
+    receipts.truncate(count);
+    Ok(Value::Array(receipts))
+}
+
+/// Resubmits the pending queue; replies with the number of submissions started.
+pub fn retry_pending(submitter_tx: &Sender<SubmitterCommand>) -> Result<Value, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    submitter_tx
+        .send(SubmitterCommand::RetryPending(reply_tx))
+        .map_err(|_| "The state worker is shutting down.".to_string())?;
+    let started = reply_rx.recv_timeout(REPLY_TIMEOUT).map_err(|_| "The state worker did not answer in time.".to_string())??;
+    Ok(json!({ "resubmitted": started }))
+}
+
+/// Sends one state worker request built by `command` and waits for its answer. Backs the raw
+/// `state-*` commands one-shot commands use while the miner runs (see state_store.rs); they
+/// are not listed in `help`.
+fn state_request<T>(
+    submitter_tx: &Sender<SubmitterCommand>,
+    command: impl FnOnce(Sender<Result<T, String>>) -> SubmitterCommand,
+) -> Result<T, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    submitter_tx.send(command(reply_tx)).map_err(|_| "The state worker is shutting down.".to_string())?;
+    reply_rx.recv_timeout(REPLY_TIMEOUT).map_err(|_| "The state worker did not answer in time.".to_string())?
+}
+
+/// Where the miner listens and where `shell` and one-shot commands connect: --control-socket,
+/// or `control.sock` in the data directory. `None` with --no-control-socket, and on non-Unix
+/// systems unless a path is given.
+pub fn socket_path(cli: &crate::cli::Cli) -> Option<String> {
+    if cli.no_control_socket {
+        return None;
+    }
+    if let Some(path) = &cli.control_socket {
+        return Some(path.clone());
+    }
+    cfg!(unix).then(|| {
+        std::path::Path::new(cli.data_dir.as_deref().unwrap_or(".")).join("control.sock").to_string_lossy().into_owned()
+    })
+}
+
+/// Client side: one connection to a running miner, used by `shadow-harvester shell` and by
+/// one-shot commands while the miner runs.
+#[cfg(unix)]
+pub struct ControlClient {
+    reader: BufReader<std::os::unix::net::UnixStream>,
+    writer: std::os::unix::net::UnixStream,
+}
+
+#[cfg(unix)]
+impl ControlClient {
+    pub fn connect(path: &str) -> Result<Self, String> {
+        let stream = std::os::unix::net::UnixStream::connect(path)
+            .map_err(|e| format!("Cannot reach the miner's control socket {}: {}. Is it running, with the same --data-dir or --control-socket?", path, e))?;
+        let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT + Duration::from_secs(5)));
+        let writer = stream.try_clone().map_err(|e| format!("Control socket error: {}", e))?;
+        Ok(ControlClient { reader: BufReader::new(stream), writer })
+    }
+
+    pub fn request(&mut self, cmd: &str, args: &[&str]) -> Result<Value, String> {
+        writeln!(self.writer, "{}", json!({ "cmd": cmd, "args": args }))
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => return Err("The miner closed the control connection.".to_string()),
+            Ok(_) => {}
+            Err(e) => return Err(format!("Failed to read reply: {}", e)),
+        }
+        let reply: Value = serde_json::from_str(&line).map_err(|e| format!("Malformed reply: {}", e))?;
+        if reply["ok"].as_bool() == Some(true) {
+            Ok(reply["data"].clone())
+        } else {
+            Err(reply["error"].as_str().unwrap_or("Unknown error").to_string())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub struct ControlClient;
+
+#[cfg(not(unix))]
+impl ControlClient {
+    pub fn connect(_path: &str) -> Result<Self, String> {
+        Err("The control socket is only available on Unix.".to_string())
+    }
+
+    pub fn request(&mut self, _cmd: &str, _args: &[&str]) -> Result<Value, String> {
+        Err("The control socket is only available on Unix.".to_string())
+    }
+}
@@ -6,14 +6,15 @@ use std::borrow::Cow;
 use std::hash::{Hash, Hasher, DefaultHasher};
 use std::path::PathBuf;
 use std::io::Write;
-use reqwest::blocking;
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use shadow_harvester_lib::{ChallengeHasher, ChallengeParams, RomParams};
 
 // ===============================================
 // API RESPONSE STRUCTS (Minimal subset)
 // ===============================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TandCResponse {
     pub version: String,
     pub content: String,
@@ -39,6 +40,25 @@ pub struct ChallengeData {
     pub challenge_number: u16,
     pub day: u8,
     pub issued_at: String,
+    /// ROM parameters for this challenge, when they differ from the protocol defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rom_params: Option<RomParams>,
+}
+
+impl ChallengeData {
+    /// Validated library parameters for mining this challenge for `address`.
+    pub fn to_params(&self, address: &str, hasher: std::sync::Arc<dyn ChallengeHasher>) -> Result<ChallengeParams, String> {
+        ChallengeParams::builder()
+            .address(address)
+            .challenge_id(self.challenge_id.as_str())
+            .difficulty(self.difficulty.parse()?)
+            .rom_key(self.no_pre_mine_key.parse()?)
+            .deadline(self.latest_submission.parse()?)
+            .no_pre_mine_hour(self.no_pre_mine_hour_str.as_str())
+            .hasher(hasher)
+            .build()
+            .map_err(|e| format!("Challenge {} is invalid: {}", self.challenge_id, e))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,9 +92,9 @@ pub struct DonateResponse {
 pub struct ApiErrorResponse {
     pub message: String,
     pub error: Option<String>,
-    // FIX: Change to snake_case and use rename attribute for deserialization
-    #[serde(rename = "statusCode")]
-    pub status_code: Option<u16>,
+    /// Machine-readable error code, e.g. "CHALLENGE_EXPIRED".
+    #[serde(default)]
+    pub error_code: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -131,16 +151,49 @@ pub struct CliChallengeData {
 // CORE APPLICATION STRUCTS
 // ===============================================
 
+/// ROM parameters set on the command line. Each unset field falls back to the challenge's
+/// `rom_params`, then to the protocol default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RomParamsOverride {
+    pub size: Option<usize>,
+    pub pre_size: Option<usize>,
+    pub mixing_numbers: Option<usize>,
+}
+
+impl RomParamsOverride {
+    /// The effective ROM parameters for `challenge` (or for no challenge, e.g. a benchmark).
+    pub fn resolve(&self, challenge: Option<&ChallengeData>) -> RomParams {
+        let base = challenge.and_then(|c| c.rom_params).unwrap_or_default();
+        RomParams {
+            size: self.size.unwrap_or(base.size),
+            pre_size: self.pre_size.unwrap_or(base.pre_size),
+            mixing_numbers: self.mixing_numbers.unwrap_or(base.mixing_numbers),
+        }
+    }
+}
+
 // Holds the common, validated state for the mining loops.
 #[derive(Debug)]
 pub struct MiningContext {
-    pub client: blocking::Client,
+    pub client: Client,
     pub api_url: String,
     pub tc_response: TandCResponse,
     pub donate_to_option: Option<String>,
     pub threads: u32,
+    pub solutions_per_cycle: u32,
+    pub rom_check_interval: Option<u64>,
+    /// Data dir under which ROMs are cached (`<dir>/roms/`), or `None` when caching is disabled.
+    pub rom_cache_dir: Option<String>,
+    /// Build the next challenge's ROM in the background while the current challenge is still mined.
+    pub rom_prebuild: bool,
+    /// Maximum number of ROMs kept loaded at once (least recently used are unloaded first).
+    pub rom_store_size: usize,
+    pub rom_overrides: RomParamsOverride,
     pub cli_challenge: Option<String>,
     pub data_dir: Option<String>,
+    /// The API was unreachable at startup and the cached T&C is used; registrations are
+    /// retried in the background until it is reachable.
+    pub offline_start: bool,
 }
 
 
@@ -154,6 +207,22 @@ pub struct PendingSolution {
     // FIX: Add fields for error logging and identification
     pub preimage: String, // The full string used for hashing
     pub hash_output: String, // The final Blake2b hash output (hex encoded)
+    /// Signed registration of `address`, so the submitter can re-register it when the API
+    /// rejects the solution because the registration has not propagated yet.
+    #[serde(default)]
+    pub registration: Option<RegistrationProof>,
+    /// CIP-8 signature of the donation message for `donation_address`, so the submitter can
+    /// re-issue the donation when the receipt arrives after it.
+    #[serde(default)]
+    pub donation_signature: Option<String>,
+}
+
+/// Arguments of the register call for one address.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RegistrationProof {
+    pub message: String,
+    pub signature: String,
+    pub pubkey: String,
 }
 
 // Holds the details for a submission that failed permanently due to API validation.
@@ -176,20 +245,57 @@ pub enum MiningResult {
     #[allow(dead_code)]
     AlreadySolved,
     MiningFailed,
+    SpaceExhausted, // Every nonce below --nonce-space was checked without a solution
 }
 
 // --- Central Application Message Bus ---
 
 /// Commands posted TO the Challenge Manager thread.
 pub enum ManagerCommand {
-    /// A new challenge has been received from the Polling or WebSocket client.
+    /// A challenge reported by an external source (fixed, poller, WebSocket); the manager
+    /// decides whether to mine it according to --challenge-priority.
+    ChallengeOffer(ChallengeData, crate::challenge_sources::ChallengeSource),
+    /// Start mining this challenge (posted by the manager itself).
     NewChallenge(ChallengeData),
     /// A mining thread has successfully found a solution nonce.
-    SolutionFound(PendingSolution, u64, f64),
+    /// The final `bool` is true when this solution completes the mining cycle.
+    SolutionFound(PendingSolution, u64, f64, bool),
+    /// The miner stopped because the submission deadline of this challenge ID passed.
+    DeadlinePassed(String),
+    /// The miner checked every nonce of the search space for (challenge ID, address) without
+    /// completing the cycle, after this many hashes and seconds.
+    SpaceExhausted(String, String, u64, f64),
+    /// The ROM integrity checker found corrupted chunks in the ROM of this challenge ID.
+    RomCorrupted(String),
+    /// This percentage of the challenge ID's submission window has elapsed.
+    ChallengeMilestone(String, u8),
+    /// Time to re-evaluate worker thread scaling (--max-threads) for this challenge ID.
+    ScaleCheck(String),
+    /// Liveness probe from the systemd watchdog; answered by recording a heartbeat.
+    Heartbeat,
+    /// A request from the control socket or REST API; the reply is sent on the channel.
+    Control(ControlRequest, std::sync::mpsc::Sender<Result<serde_json::Value, String>>),
     /// Signal to gracefully shut down the manager.
     Shutdown,
 }
 
+/// Operator requests the manager answers over the control socket and REST API.
+#[derive(Debug, Clone)]
+pub enum ControlRequest {
+    /// Current challenge, address, mining state and solution count.
+    Status,
+    /// Stop the miner until `Resume`. New challenges are remembered, not mined.
+    Pause,
+    Resume,
+    /// Mine this stored challenge ID now.
+    Switch(String),
+    /// Use this many worker threads, restarting the running miner to apply it.
+    SetThreads(u32),
+    /// Hash `nonce` for the current challenge and report whether it meets the difficulty.
+    /// Uses the current mining address unless `address` is given.
+    Verify { nonce: String, address: Option<String> },
+}
+
 /// Commands posted TO the Submitter (Persistence/Network) thread.
 #[derive(Debug)]
 pub enum SubmitterCommand {
@@ -198,17 +304,156 @@ pub enum SubmitterCommand {
     /// Command to retrieve data from SLED (used for synchronous lookups like next index).
     /// Value is sent back on the provided response channel.
     GetState(String, std::sync::mpsc::Sender<Result<Option<String>, String>>),
+    /// Returns every (key, value) pair whose key starts with the prefix, in key order.
+    ListState(String, std::sync::mpsc::Sender<Result<Vec<(String, String)>, String>>),
+    /// Sets (Some) or removes (None) a key and reports the outcome, for one-shot commands
+    /// writing through the control socket.
+    WriteState(String, Option<String>, std::sync::mpsc::Sender<Result<(), String>>),
     /// Command to initiate solution submission (used in non-WS mode).
     SubmitSolution(PendingSolution),
+    /// Resubmits every pending solution that is not already being submitted; replies with
+    /// the number of submissions started.
+    RetryPending(std::sync::mpsc::Sender<Result<usize, String>>),
+    /// Liveness probe from the systemd watchdog; answered by recording a heartbeat.
+    Heartbeat,
     /// Signal to gracefully shut down the submitter.
     Shutdown,
 }
 
+/// Commands posted TO the HTTP Polling thread.
+#[derive(Debug)]
+pub enum PollerCommand {
+    /// Skip the remaining polling interval and fetch the challenge status immediately.
+    PollNow,
+}
+
 /// Commands posted TO the WebSocket Server thread.
 #[derive(Debug)]
 pub enum WebSocketCommand {
     /// A found solution is ready to be sent back to the external bridge (Tampermonkey).
     SubmitSolution(PendingSolution),
+    /// The ROM digest computed on this host, so the controller can compare hosts. `rom_id`
+    /// tells apart ROMs built from the same key with different parameters.
+    ReportRomDigest { challenge_id: String, rom_key: String, rom_id: String, digest: String },
+}
+
+/// Operator notes and tags on a challenge or address, shown in listings.
+/// Stored under `note:challenge:<CHALLENGE_ID>` or `note:address:<ADDRESS>`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Annotation {
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub updated_at: String,
+}
+
+impl Annotation {
+    /// `kind` is "challenge" or "address".
+    pub fn sled_key(kind: &str, id: &str) -> String {
+        format!("{}:{}:{}", SLED_KEY_NOTE, kind, id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.note.is_none() && self.tags.is_empty()
+    }
+
+    /// One-line form for listings: `[tag1, tag2] note`.
+    pub fn summary(&self) -> String {
+        let tags = if self.tags.is_empty() { String::new() } else { format!("[{}]", self.tags.join(", ")) };
+        match &self.note {
+            Some(note) if tags.is_empty() => note.clone(),
+            Some(note) => format!("{} {}", tags, note),
+            None => tags,
+        }
+    }
+}
+
+/// One mining cycle (one address mining one challenge), persisted for postmortems.
+/// Stored under `cycle:<CHALLENGE_ID>:<STARTED_AT_MILLIS>`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CycleRecord {
+    pub challenge_id: String,
+    pub address: String,
+    /// Derivation index in mnemonic mode.
+    pub mnemonic_index: Option<u32>,
+    /// Mnemonic indices skipped before this cycle because they already had a receipt.
+    #[serde(default)]
+    pub skipped_indices: Vec<u32>,
+    pub started_at: String,
+    pub started_at_millis: i64,
+    pub ended_at: Option<String>,
+    /// Hashes computed in the cycle (known when the cycle ends with a solution).
+    pub total_hashes: u64,
+    /// "running", "solved", "deadline_passed", "preempted", "rom_corrupted", "shutdown", "failed: ..."
+    pub outcome: String,
+    /// What started the cycle: "poller", "websocket", "fixed_challenge", "next_index", "rom_rebuild".
+    pub trigger: String,
+    /// Pending-queue keys (`pending:<ADDRESS>:<CHALLENGE_ID>:<NONCE>`) of solutions found in this cycle.
+    #[serde(default)]
+    pub submissions: Vec<String>,
+}
+
+impl CycleRecord {
+    pub fn sled_key(&self) -> String {
+        format!("{}:{}:{:013}", SLED_KEY_CYCLE, self.challenge_id, self.started_at_millis)
+    }
+}
+
+/// A successful donate_to call: the rewards of `address` are assigned to `donate_to`.
+/// Stored under `donation:<ADDRESS>`; a later assignment replaces the earlier one, as in the API.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DonationRecord {
+    pub address: String,
+    pub donate_to: String,
+    /// Donation ID from the API, or "(already-done)" when the API reported an existing assignment.
+    pub donation_id: String,
+    pub recorded_at: String,
+}
+
+impl DonationRecord {
+    pub fn new(address: &str, donate_to: &str, donation_id: &str) -> Self {
+        DonationRecord {
+            address: address.to_string(),
+            donate_to: donate_to.to_string(),
+            donation_id: donation_id.to_string(),
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn sled_key(&self) -> String {
+        format!("{}:{}", SLED_KEY_DONATION, self.address)
+    }
+}
+
+/// Difficulty of a mining day, persisted for `challenge difficulty-trend`.
+/// Stored under `difficulty:<DAY>` (zero-padded); the latest challenge of the day wins.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DifficultyRecord {
+    pub day: u8,
+    pub difficulty: String,
+    pub challenge_id: String,
+    pub recorded_at: String,
+}
+
+impl DifficultyRecord {
+    pub fn from_challenge(challenge: &ChallengeData) -> Self {
+        DifficultyRecord {
+            day: challenge.day,
+            difficulty: challenge.difficulty.clone(),
+            challenge_id: challenge.challenge_id.clone(),
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn sled_key(&self) -> String {
+        format!("{}:{:03}", SLED_KEY_DIFFICULTY, self.day)
+    }
+
+    /// Hash bits that must be zero under this mask, or None if the mask is not valid hex.
+    pub fn zero_bits(&self) -> Option<u32> {
+        u32::from_str_radix(&self.difficulty, 16).ok().map(u32::count_zeros)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -217,12 +462,210 @@ pub struct BackupEntry {
     pub value: String,
 }
 
+/// Format tag of `db backup` files.
+pub const DB_BACKUP_FORMAT: &str = "shadow-harvester-backup/1";
+
+/// Snapshot written by `db backup`: every key with its decoded value, plus a checksum so
+/// `db restore` rejects a truncated or edited file before touching the database.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DbBackup {
+    pub format: String,
+    pub created_at: String,
+    /// Version of shadow-harvester that wrote the backup.
+    pub version: String,
+    pub entry_count: usize,
+    /// Hex Blake2b-256 over the entries; see `DbBackup::checksum`.
+    pub checksum: String,
+    pub entries: Vec<BackupEntry>,
+}
+
+impl DbBackup {
+    pub fn new(entries: Vec<BackupEntry>) -> Self {
+        DbBackup {
+            format: DB_BACKUP_FORMAT.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entry_count: entries.len(),
+            checksum: Self::checksum(&entries),
+            entries,
+        }
+    }
+
+    /// Blake2b-256 over each entry's length-prefixed key and value, in order.
+    pub fn checksum(entries: &[BackupEntry]) -> String {
+        use cryptoxide::hashing::blake2b::Blake2b;
+        let mut hasher = Blake2b::<256>::new();
+        for entry in entries {
+            for field in [&entry.key, &entry.value] {
+                hasher.update_mut(&(field.len() as u64).to_le_bytes());
+                hasher.update_mut(field.as_bytes());
+            }
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Checks the format tag, entry count and checksum.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.format != DB_BACKUP_FORMAT {
+            return Err(format!("Not a database backup (format '{}', expected '{}').", self.format, DB_BACKUP_FORMAT));
+        }
+        if self.entries.len() != self.entry_count {
+            return Err(format!("Backup is incomplete: {} of {} entries.", self.entries.len(), self.entry_count));
+        }
+        if Self::checksum(&self.entries) != self.checksum {
+            return Err("Backup checksum does not match its entries (file corrupted or edited).".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Format tag of `wallet attest` documents.
+pub const ATTESTATION_FORMAT: &str = "shadow-harvester-attestation/1";
+
+/// Document written by `wallet attest`: the addresses of one mnemonic account with their
+/// receipts, each entry signed (CIP-8 COSE_Sign1) by the address's own key.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Attestation {
+    pub format: String,
+    pub issued_at: String,
+    /// Free text chosen by the user, e.g. who the document is for. Part of every signed message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement: Option<String>,
+    pub addresses: Vec<AttestedAddress>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AttestedAddress {
+    pub account: u32,
+    pub index: u32,
+    pub address: String,
+    /// Receipts as returned by the API, keyed by challenge ID.
+    pub receipts: Vec<AttestedReceipt>,
+    /// The signed text; see `AttestedAddress::message`.
+    pub message: String,
+    /// Hex CBOR COSE_Sign1 over `message`, with the address in the protected header.
+    pub signature: String,
+    /// Hex Ed25519 public key of the address.
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AttestedReceipt {
+    pub challenge_id: String,
+    pub receipt: serde_json::Value,
+}
+
+impl AttestedAddress {
+    /// The text signed for one address. It binds the address, the issue time, the statement
+    /// and each receipt's API signature, so entries cannot be moved between documents.
+    pub fn message(address: &str, issued_at: &str, statement: Option<&str>, receipts: &[AttestedReceipt]) -> String {
+        let mut message = format!("Shadow Harvester attestation\nAddress: {}\nIssued: {}\n", address, issued_at);
+        if let Some(statement) = statement {
+            message.push_str(&format!("Statement: {}\n", statement));
+        }
+        message.push_str(&format!("Receipts: {}\n", receipts.len()));
+        for receipt in receipts {
+            let signature = receipt.receipt["signature"].as_str().unwrap_or_default();
+            message.push_str(&format!("{} {}\n", receipt.challenge_id, signature));
+        }
+        message
+    }
+}
+
+/// Format tag of solution bundles.
+pub const SOLUTION_BUNDLE_FORMAT: &str = "shadow-harvester-solutions/1";
+
+/// Solutions carried from one machine to another, e.g. written by --offline mining on an
+/// air-gapped rig and submitted by a connected one.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SolutionBundle {
+    pub format: String,
+    pub exported_at: String,
+    /// The challenges the solutions belong to, so the receiving machine can validate them.
+    pub challenges: Vec<ChallengeData>,
+    pub solutions: Vec<SignedSolution>,
+}
+
+impl Default for SolutionBundle {
+    fn default() -> Self {
+        SolutionBundle {
+            format: SOLUTION_BUNDLE_FORMAT.to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            challenges: Vec::new(),
+            solutions: Vec::new(),
+        }
+    }
+}
+
+impl SolutionBundle {
+    /// True when the bundle already holds the solution of `address` with `nonce` for `challenge_id`.
+    pub fn contains(&self, address: &str, challenge_id: &str, nonce: &str) -> bool {
+        self.solutions.iter().any(|signed| {
+            signed.solution.address == address
+                && signed.solution.challenge_id == challenge_id
+                && signed.solution.nonce == nonce
+        })
+    }
+
+    /// Adds `signed` unless the bundle already holds that solution. Returns whether it was added.
+    pub fn add_solution(&mut self, signed: SignedSolution) -> bool {
+        let solution = &signed.solution;
+        if self.contains(&solution.address, &solution.challenge_id, &solution.nonce) {
+            return false;
+        }
+        self.solutions.push(signed);
+        true
+    }
+
+    /// Adds `challenge` unless the bundle already carries a challenge with its ID.
+    pub fn add_challenge(&mut self, challenge: &ChallengeData) {
+        if !self.challenges.iter().any(|c| c.challenge_id == challenge.challenge_id) {
+            self.challenges.push(challenge.clone());
+        }
+    }
+}
+
+/// A solution with its preimage and hash, signed (CIP-8) by the key of the address that found
+/// it. Solutions exported from a pending queue carry no signature: the key is not at hand there.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SignedSolution {
+    #[serde(flatten)]
+    pub solution: PendingSolution,
+    /// The signed text; see `SignedSolution::message`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Hex CBOR COSE_Sign1 over `message`, with the address in the protected header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Hex Ed25519 public key of the address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+impl SignedSolution {
+    /// A solution without a signature.
+    pub fn unsigned(solution: PendingSolution) -> Self {
+        SignedSolution { solution, message: None, signature: None, public_key: None }
+    }
+
+    /// The text signed for a solution: the address, challenge, nonce and resulting hash.
+    pub fn message(solution: &PendingSolution) -> String {
+        format!(
+            "Shadow Harvester solution\nAddress: {}\nChallenge: {}\nNonce: {}\nHash: {}\n",
+            solution.address, solution.challenge_id, solution.nonce, solution.hash_output
+        )
+    }
+}
 
 // --- DataDir Structures and Constants (Kept for Migration/Compatibility) ---
 pub const FILE_NAME_CHALLENGE: &str = "challenge.json";
 pub const FILE_NAME_RECEIPT: &str = "receipt.json";
 pub const FILE_NAME_FOUND_SOLUTION: &str = "found.json";
 pub const SLED_KEY_FAILED_SOLUTION: &str = "failed_solution"; // FIX: Added new Sled key prefix
+pub const SLED_KEY_CYCLE: &str = "cycle";
+pub const SLED_KEY_DIFFICULTY: &str = "difficulty";
+pub const SLED_KEY_NOTE: &str = "note";
+pub const SLED_KEY_DONATION: &str = "donation";
 
 
 #[derive(Debug, Clone, Copy)]
@@ -239,7 +682,7 @@ pub struct DataDirMnemonic<'a> {
     pub deriv_index: u32,
 }
 
-fn normalize_challenge_id(challenge_id: &str) -> Cow<str> {
+pub fn normalize_challenge_id(challenge_id: &str) -> Cow<'_, str> {
     #[cfg(target_os = "windows")]
     {
         // Directories with '*' are not supported on windows
@@ -374,7 +817,7 @@ pub fn is_solution_pending_in_queue(base_dir: &str, address: &str, challenge_id:
             if let Some(filename) = entry.file_name().to_str() {
                 // Check if the filename starts with the required prefix and is a JSON file
                 // The filename format is: address_challenge_id_nonce.json
-                if filename.starts_with(&format!("{}_{}_", address, normalize_challenge_id(&challenge_id))) && filename.ends_with(".json") {
+                if filename.starts_with(&format!("{}_{}_", address, normalize_challenge_id(challenge_id))) && filename.ends_with(".json") {
                     return Ok(true);
                 }
             }
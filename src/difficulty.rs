@@ -0,0 +1,96 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// --------------------------------------------------------------------------
+// DIFFICULTY RULES
+// --------------------------------------------------------------------------
+//
+// The API describes the difficulty as a hex mask such as "000FFFFF", but not how a hash is
+// compared against it, and the interpretation has been disputed before. Each reading is a
+// `DifficultyRule`; the process-wide rule decides what the workers count as a solution and
+// what `hash_structure_good` reports everywhere else. For masks of leading zeros followed by
+// ones (all masks seen so far) every rule agrees; they differ for any other mask and hash.
+
+/// One interpretation of "the hash meets the difficulty mask". Every rule looks at the first
+/// 4 bytes of the hash, read big-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DifficultyRule {
+    /// No bit set outside the mask (the server's reference implementation).
+    #[default]
+    Mask,
+    /// At least as many leading zero bits as the mask has.
+    LeadingZeroBits,
+    /// The 4-byte prefix, as a number, is at most the mask.
+    NumericPrefix,
+    /// Each of the 4 prefix bytes is at most the mask's byte at the same position.
+    BytePrefix,
+}
+
+impl DifficultyRule {
+    pub const ALL: [DifficultyRule; 4] = [
+        DifficultyRule::Mask,
+        DifficultyRule::LeadingZeroBits,
+        DifficultyRule::NumericPrefix,
+        DifficultyRule::BytePrefix,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyRule::Mask => "mask",
+            DifficultyRule::LeadingZeroBits => "leading-zero-bits",
+            DifficultyRule::NumericPrefix => "numeric-prefix",
+            DifficultyRule::BytePrefix => "byte-prefix",
+        }
+    }
+
+    /// Whether `hash` (at least 4 bytes) meets `difficulty_mask` under this rule.
+    pub fn meets(self, hash: &[u8], difficulty_mask: u32) -> bool {
+        let prefix: [u8; 4] = hash[..4].try_into().unwrap();
+        let value = u32::from_be_bytes(prefix);
+        match self {
+            DifficultyRule::Mask => (value | difficulty_mask) == difficulty_mask,
+            DifficultyRule::LeadingZeroBits => value.leading_zeros() >= difficulty_mask.leading_zeros(),
+            DifficultyRule::NumericPrefix => value <= difficulty_mask,
+            DifficultyRule::BytePrefix => prefix.iter().zip(difficulty_mask.to_be_bytes()).all(|(byte, mask)| *byte <= mask),
+        }
+    }
+
+    /// The verdict of every rule for `hash`, or None when they all agree.
+    pub fn disagreement(hash: &[u8], difficulty_mask: u32) -> Option<Vec<(DifficultyRule, bool)>> {
+        let verdicts: Vec<_> = Self::ALL.iter().map(|rule| (*rule, rule.meets(hash, difficulty_mask))).collect();
+        let first = verdicts[0].1;
+        verdicts.iter().any(|(_, meets)| *meets != first).then_some(verdicts)
+    }
+}
+
+impl FromStr for DifficultyRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|rule| rule.name() == s).ok_or_else(|| format!(
+            "Unknown difficulty rule '{}' (expected one of: {}).",
+            s, Self::ALL.map(DifficultyRule::name).join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for DifficultyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Process-wide: index into `DifficultyRule::ALL` of the rule `hash_structure_good` applies.
+static DIFFICULTY_RULE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the rule the workers (and every other difficulty check) apply from now on.
+pub fn set_difficulty_rule(rule: DifficultyRule) {
+    let index = DifficultyRule::ALL.iter().position(|r| *r == rule).unwrap_or(0);
+    DIFFICULTY_RULE.store(index as u8, Ordering::Relaxed);
+}
+
+/// Returns the rule difficulty checks apply.
+pub fn difficulty_rule() -> DifficultyRule {
+    DifficultyRule::ALL[DIFFICULTY_RULE.load(Ordering::Relaxed) as usize]
+}
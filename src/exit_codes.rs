@@ -0,0 +1,72 @@
+// src/exit_codes.rs
+
+// ===============================================
+// PROCESS EXIT CODES
+// ===============================================
+//
+// Stable exit codes so scripts and supervisors can tell failure classes apart.
+// Keep this table in sync with the "Exit Codes" section of README.md.
+//
+//   0  SUCCESS               Command or run finished normally.
+//   1  GENERAL_FAILURE       Any failure not covered below.
+//   2  USAGE_ERROR           Missing or conflicting flags, T&C not accepted (clap also uses 2).
+//   3  DATABASE_ERROR        Sled DB could not be opened (e.g. locked by another process) or read/written.
+//   4  DEADLINE_PASSED       The challenge submission window has closed.
+//   5  INVALID_KEY_MATERIAL  Bad mnemonic, mnemonic file or payment key.
+//   6  NETWORK_ERROR         The Scavenger Mine API could not be reached or returned an error.
+//   7  NOT_FOUND             A requested challenge, receipt or record does not exist locally.
+//   8  THREAD_FAILURE        A background worker thread (manager, submitter, poller) died.
+//   9  CANCELLED             A one-shot command was interrupted or hit --command-timeout.
+
+pub const GENERAL_FAILURE: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+pub const DATABASE_ERROR: i32 = 3;
+pub const DEADLINE_PASSED: i32 = 4;
+pub const INVALID_KEY_MATERIAL: i32 = 5;
+pub const NETWORK_ERROR: i32 = 6;
+pub const NOT_FOUND: i32 = 7;
+pub const THREAD_FAILURE: i32 = 8;
+pub const CANCELLED: i32 = 9;
+
+/// An error together with the exit code of its failure class. The code is chosen where the
+/// failure happens; plain `String` errors convert to GENERAL_FAILURE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl ExitError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        ExitError { code, message: message.into() }
+    }
+}
+
+impl From<String> for ExitError {
+    fn from(message: String) -> Self {
+        ExitError::new(GENERAL_FAILURE, message)
+    }
+}
+
+impl std::fmt::Display for ExitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Attaches an exit code to the error of a failing call.
+pub trait WithExitCode<T> {
+    fn exit_code(self, code: i32) -> Result<T, ExitError>;
+}
+
+impl<T, E: std::fmt::Display> WithExitCode<T> for Result<T, E> {
+    fn exit_code(self, code: i32) -> Result<T, ExitError> {
+        self.map_err(|e| ExitError::new(code, e.to_string()))
+    }
+}
+
+/// Terminates the process with the exit code of `error`.
+pub fn exit_for_error(error: &ExitError) -> ! {
+    crate::console::flush();
+    std::process::exit(error.code)
+}
@@ -0,0 +1,172 @@
+// src/hash_cache.rs
+
+use crate::rom::RomDigest;
+use cryptoxide::hashing::blake2b;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// --------------------------------------------------------------------------
+// HASH OUTPUT CACHE
+// --------------------------------------------------------------------------
+//
+// Re-mining a fixed challenge for the same address from nonce 0 hashes exactly the preimages of
+// the previous run again. With a cache size set, unbatched workers (the CPU interpreter) look up
+// each preimage in a small process-wide LRU before running the VM. Lookups cost one Blake2b pass
+// and a lock, negligible next to the VM.
+//
+// The preimage contains the ROM key but not the ROM parameters, and the ROM store holds ROMs of
+// several keys and parameter sets at once, so entries are keyed by the ROM digest together with
+// a 256-bit Blake2b digest of the preimage. A ROM rebuilt after an integrity check failure keeps
+// its digest; the manager clears the cache then, as outputs hashed on the corrupted copy may be
+// wrong.
+
+type PreimageDigest = [u8; 32];
+type CacheKey = ([u8; 64], PreimageDigest);
+
+/// Least recently used map from (ROM digest, preimage digest) to hash output.
+pub struct HashCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, (u64, [u8; 64])>,
+    // Last use -> key; the first entry is the least recently used.
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl HashCache {
+    pub fn new(capacity: usize) -> Self {
+        HashCache { capacity, entries: HashMap::new(), order: BTreeMap::new(), tick: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the cached hash of `preimage` on `rom`, marking it as recently used.
+    pub fn get(&mut self, rom: &RomDigest, preimage: &[u8]) -> Option<[u8; 64]> {
+        let key = (rom.0, digest(preimage));
+        self.tick += 1;
+        let (last_use, hash) = self.entries.get_mut(&key)?;
+        self.order.remove(last_use);
+        *last_use = self.tick;
+        self.order.insert(self.tick, key);
+        Some(*hash)
+    }
+
+    /// Stores the hash of `preimage` on `rom`, evicting the least recently used entry when full.
+    pub fn insert(&mut self, rom: &RomDigest, preimage: &[u8], hash: [u8; 64]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (rom.0, digest(preimage));
+        self.tick += 1;
+        if let Some((last_use, _)) = self.entries.insert(key, (self.tick, hash)) {
+            self.order.remove(&last_use);
+        } else if self.entries.len() > self.capacity
+            && let Some((_, oldest)) = self.order.pop_first() {
+            self.entries.remove(&oldest);
+        }
+        self.order.insert(self.tick, key);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+fn digest(preimage: &[u8]) -> PreimageDigest {
+    blake2b::Blake2b::<256>::new().update(preimage).finalize()
+}
+
+// Process-wide: shared by every worker; None while disabled (the default).
+static HASH_CACHE: Mutex<Option<HashCache>> = Mutex::new(None);
+static HASH_CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+
+/// Caches the hash outputs of the last `entries` preimages across workers and mining cycles.
+/// 0 disables the cache and frees it.
+pub fn set_hash_cache_size(entries: usize) {
+    let mut cache = HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    *cache = (entries > 0).then(|| HashCache::new(entries));
+}
+
+/// Returns the configured cache size in entries (0 = disabled).
+pub fn hash_cache_size() -> usize {
+    HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map_or(0, |cache| cache.capacity)
+}
+
+/// Drops every cached hash output, e.g. when a ROM they were computed on turned out corrupted.
+pub fn clear_hash_cache() {
+    if let Some(cache) = HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        cache.clear();
+    }
+}
+
+/// Number of hashes served from the cache since startup.
+pub fn hash_cache_hits() -> u64 {
+    HASH_CACHE_HITS.load(Ordering::Relaxed)
+}
+
+/// Hashes `preimage` on `rom` with `compute`, going through the cache when it is enabled.
+pub(crate) fn cached_hash(rom: &RomDigest, preimage: &[u8], compute: impl FnOnce(&[u8]) -> [u8; 64]) -> [u8; 64] {
+    let hit = match HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        Some(cache) => cache.get(rom, preimage),
+        None => return compute(preimage),
+    };
+    if let Some(hash) = hit {
+        HASH_CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        return hash;
+    }
+    // Hash without holding the lock so the other workers keep going.
+    let hash = compute(preimage);
+    if let Some(cache) = HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        cache.insert(rom, preimage, hash);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROM: RomDigest = RomDigest([7; 64]);
+
+    #[test]
+    fn hash_cache_evicts_least_recently_used() {
+        let mut cache = HashCache::new(2);
+        cache.insert(&ROM, b"a", [1; 64]);
+        cache.insert(&ROM, b"b", [2; 64]);
+        // Touch "a" so "b" becomes the oldest entry.
+        assert_eq!(cache.get(&ROM, b"a"), Some([1; 64]));
+        cache.insert(&ROM, b"c", [3; 64]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&ROM, b"b"), None);
+        assert_eq!(cache.get(&ROM, b"a"), Some([1; 64]));
+        assert_eq!(cache.get(&ROM, b"c"), Some([3; 64]));
+
+        // Replacing an entry does not grow the cache.
+        cache.insert(&ROM, b"c", [4; 64]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&ROM, b"c"), Some([4; 64]));
+    }
+
+    #[test]
+    fn hash_cache_keeps_roms_apart() {
+        let other = RomDigest([8; 64]);
+        let mut cache = HashCache::new(4);
+        cache.insert(&ROM, b"a", [1; 64]);
+        cache.insert(&other, b"a", [2; 64]);
+
+        assert_eq!(cache.get(&ROM, b"a"), Some([1; 64]));
+        assert_eq!(cache.get(&other, b"a"), Some([2; 64]));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&ROM, b"a"), None);
+    }
+}
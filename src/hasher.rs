@@ -0,0 +1,118 @@
+// src/hasher.rs
+
+use crate::{hash, Rom};
+use crate::rom::RomDigest;
+use std::sync::Arc;
+
+// --------------------------------------------------------------------------
+// HASHING BACKENDS
+// --------------------------------------------------------------------------
+
+/// Loop count of the challenge hash (`hash` with the ROM VM).
+pub const NB_LOOPS: u32 = 8;
+/// Instructions per program of the challenge hash.
+pub const NB_INSTRS: u32 = 256;
+
+/// Computes challenge hashes for the `spin` workers. The orchestration code (nonce striding,
+/// difficulty checks, checkpoints, throttling) only talks to this trait, so alternative
+/// backends (batched CPU, GPU, a remote hashing service) plug in without touching it.
+///
+/// A backend must return exactly the hash the CPU interpreter computes for the same preimage,
+/// otherwise its solutions are rejected by the network.
+pub trait ChallengeHasher: Send + Sync {
+    /// Short backend name for logs, e.g. "cpu".
+    fn name(&self) -> &str;
+
+    /// Hashes one preimage.
+    fn hash(&self, preimage: &[u8]) -> [u8; 64];
+
+    /// Number of preimages each worker hands to `hash_batch` at once. Backends with a high
+    /// per-call overhead (GPU dispatch, network round trip) should return more than 1.
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    /// Hashes `preimages` into `out` (cleared first), in order.
+    fn hash_batch(&self, preimages: &[&[u8]], out: &mut Vec<[u8; 64]>) {
+        out.clear();
+        out.extend(preimages.iter().map(|preimage| self.hash(preimage)));
+    }
+
+    /// Digest of the ROM the backend hashes against; the hash cache keeps outputs per ROM.
+    /// Backends returning None bypass the cache.
+    fn rom_digest(&self) -> Option<&RomDigest> {
+        None
+    }
+}
+
+/// The reference backend: runs the ROM VM interpreter on the calling thread.
+pub struct CpuHasher {
+    rom: Arc<Rom>,
+}
+
+impl CpuHasher {
+    pub fn new(rom: Arc<Rom>) -> Self {
+        CpuHasher { rom }
+    }
+
+    pub fn rom(&self) -> &Arc<Rom> {
+        &self.rom
+    }
+}
+
+impl ChallengeHasher for CpuHasher {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn hash(&self, preimage: &[u8]) -> [u8; 64] {
+        hash(preimage, &self.rom, NB_LOOPS, NB_INSTRS)
+    }
+
+    fn rom_digest(&self) -> Option<&RomDigest> {
+        Some(&self.rom.digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scavenge_challenge, ChallengeParams};
+
+    /// Flags one nonce as a solution (all-zero hash) and every other one as a miss.
+    struct TargetNonceHasher {
+        target: String,
+    }
+
+    impl ChallengeHasher for TargetNonceHasher {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn hash(&self, preimage: &[u8]) -> [u8; 64] {
+            if preimage.starts_with(self.target.as_bytes()) { [0; 64] } else { [0xff; 64] }
+        }
+
+        fn batch_size(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn scavenge_drives_a_custom_hasher() {
+        let hasher = Arc::new(TargetNonceHasher { target: format!("{:016x}", 41) });
+        let params = ChallengeParams::builder()
+            .address("addr_test")
+            .challenge_id("**T01C01")
+            .difficulty("000FFFFF".parse().unwrap())
+            .rom_key("abcd".parse().unwrap())
+            .deadline((chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339().parse().unwrap())
+            .no_pre_mine_hour("0")
+            .hasher(hasher)
+            .build()
+            .unwrap();
+        let report = scavenge_challenge(params, 2, 1, None);
+        assert_eq!(report.nonces, vec![format!("{:016x}", 41)]);
+        assert!(report.total_hashes >= 20);
+    }
+}
@@ -0,0 +1,195 @@
+// src/i18n.rs
+
+use crate::cli::Lang;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+// ===============================================
+// LOCALIZED MESSAGES (--lang)
+// ===============================================
+//
+// The summaries a user reads while mining (cycle setup, statistics, T&C prompt, progress line,
+// shutdown banner) come from this catalog in the selected language. Warnings, errors and log
+// lines stay in English, so reports sent to support read the same for everyone.
+//
+// Placeholders are `{0}`, `{1}`, ... and are filled by `trf`.
+
+// Process-wide: language of user-facing messages, set once at startup.
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Selects the message language: `lang` when given, otherwise the locale from LC_ALL,
+/// LC_MESSAGES or LANG, falling back to English.
+pub fn init(lang: Option<Lang>) {
+    let _ = LANG.set(lang.unwrap_or_else(detect_locale));
+}
+
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+fn detect_locale() -> Lang {
+    // The first non-empty variable wins, as in setlocale(3).
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    match locale.split(['_', '.', '@']).next().unwrap_or("") {
+        "es" => Lang::Es,
+        _ => Lang::En,
+    }
+}
+
+/// A user-facing message of the catalog.
+#[derive(Debug, Clone, Copy)]
+pub enum Msg {
+    SetupTitle,
+    ApiUrl,
+    MiningAddress,
+    AddressNotSet,
+    WorkerThreads,
+    CpuLimit,
+    ChallengeDetails,
+    ChallengeId,
+    Day,
+    DifficultyMask,
+    SubmissionDeadline,
+    RomKey,
+    HashInputHour,
+    StatsTitle,
+    LastCyclePerformance,
+    TimeElapsed,
+    TotalHashes,
+    HashRate,
+    RollingHashRate,
+    PerThreadHashRate,
+    ThreadRate,
+    BelowMedian,
+    Difficulty,
+    ExpectedTimeToSolution,
+    SolutionChance,
+    DeadlinePassed,
+    AccountStatistics,
+    CryptoReceipts,
+    NightAllocation,
+    GlobalStatistics,
+    GlobalNote,
+    TotalWallets,
+    CurrentChallenges,
+    TotalChallenges,
+    TotalCryptoReceipts,
+    RecentCryptoReceipts,
+    StatisticsFailed,
+    Error,
+    ProgressLine,
+    TermsTitle,
+    AcceptTermsHint,
+    StoppedCleanly,
+}
+
+impl Msg {
+    /// (English, Spanish)
+    fn texts(self) -> (&'static str, &'static str) {
+        match self {
+            Msg::SetupTitle => ("Shadow Harvester: Mining Cycle Setup", "Shadow Harvester: Preparación del ciclo de minado"),
+            Msg::ApiUrl => ("API URL", "URL de la API"),
+            Msg::MiningAddress => ("Mining Address", "Dirección de minado"),
+            Msg::AddressNotSet => ("[Not Set / Continuous Generation]", "[Sin definir / generación continua]"),
+            Msg::WorkerThreads => ("Worker Threads", "Hilos de trabajo"),
+            Msg::CpuLimit => ("CPU Limit: {0}% per worker", "Límite de CPU: {0}% por hilo"),
+            Msg::ChallengeDetails => ("CHALLENGE DETAILS", "DETALLES DEL DESAFÍO"),
+            Msg::ChallengeId => ("ID", "ID"),
+            Msg::Day => ("Day", "Día"),
+            Msg::DifficultyMask => ("Difficulty Mask", "Máscara de dificultad"),
+            Msg::SubmissionDeadline => ("Submission Deadline", "Plazo de envío"),
+            Msg::RomKey => ("ROM Key (no_pre_mine)", "Clave de la ROM (no_pre_mine)"),
+            Msg::HashInputHour => ("Hash Input Hour", "Hora de entrada del hash"),
+            Msg::StatsTitle => ("Mining Statistics Summary", "Resumen de estadísticas de minado"),
+            Msg::LastCyclePerformance => ("LAST MINING CYCLE PERFORMANCE", "RENDIMIENTO DEL ÚLTIMO CICLO"),
+            Msg::TimeElapsed => ("Time Elapsed", "Tiempo transcurrido"),
+            Msg::TotalHashes => ("Total Hashes", "Hashes totales"),
+            Msg::HashRate => ("Hash Rate", "Tasa de hash"),
+            Msg::RollingHashRate => ("Rolling Hash Rate ({0} min)", "Tasa de hash móvil ({0} min)"),
+            Msg::PerThreadHashRate => ("Per-Thread Hash Rate", "Tasa de hash por hilo"),
+            Msg::ThreadRate => ("Thread", "Hilo"),
+            Msg::BelowMedian => ("{0}% below median", "{0}% por debajo de la mediana"),
+            Msg::Difficulty => ("Difficulty: {0} zero bits, {1} hashes per solution on average", "Dificultad: {0} bits a cero, {1} hashes por solución de media"),
+            Msg::ExpectedTimeToSolution => ("Expected Time to Next Solution", "Tiempo estimado hasta la próxima solución"),
+            Msg::SolutionChance => ("Chance of a Solution Before Deadline ({0} left): {1}%", "Probabilidad de una solución antes del plazo (quedan {0}): {1}%"),
+            Msg::DeadlinePassed => ("Submission deadline has passed.", "El plazo de envío ha vencido."),
+            Msg::AccountStatistics => ("YOUR ACCOUNT STATISTICS (Address: {0})", "ESTADÍSTICAS DE TU CUENTA (Dirección: {0})"),
+            Msg::CryptoReceipts => ("Crypto Receipts (Solutions)", "Recibos criptográficos (soluciones)"),
+            Msg::NightAllocation => ("Night Allocation", "Asignación de NIGHT"),
+            Msg::GlobalStatistics => ("GLOBAL STATISTICS (All Miners)", "ESTADÍSTICAS GLOBALES (todos los mineros)"),
+            Msg::GlobalNote => ("NOTE: These statistics are aggregated across all wallets globally.", "NOTA: Estas estadísticas suman todas las carteras."),
+            Msg::TotalWallets => ("Total Wallets", "Carteras totales"),
+            Msg::CurrentChallenges => ("Current Challenges", "Desafíos actuales"),
+            Msg::TotalChallenges => ("Total Challenges Ever", "Desafíos históricos"),
+            Msg::TotalCryptoReceipts => ("Total Crypto Receipts", "Recibos criptográficos totales"),
+            Msg::RecentCryptoReceipts => ("Recent Crypto Receipts", "Recibos criptográficos recientes"),
+            Msg::StatisticsFailed => ("FAILED TO FETCH API STATISTICS", "NO SE PUDIERON OBTENER LAS ESTADÍSTICAS DE LA API"),
+            Msg::Error => ("Error", "Error"),
+            Msg::ProgressLine => ("Hashes: {0} Speed: {1} hash/s found: {2}", "Hashes: {0} Velocidad: {1} hash/s encontradas: {2}"),
+            Msg::TermsTitle => ("Terms and Conditions (Version {0}):", "Términos y condiciones (versión {0}):"),
+            Msg::AcceptTermsHint => (
+                "Re-run with --accept-tos if you agree to the terms.",
+                "Vuelve a ejecutar con --accept-tos si aceptas los términos.",
+            ),
+            Msg::StoppedCleanly => ("Shadow Harvester stopped cleanly. Uptime: {0}", "Shadow Harvester se detuvo correctamente. Tiempo activo: {0}"),
+        }
+    }
+}
+
+/// The message in the selected language.
+pub fn tr(msg: Msg) -> &'static str {
+    let (en, es) = msg.texts();
+    match lang() {
+        Lang::En => en,
+        Lang::Es => es,
+    }
+}
+
+/// The message in the selected language with `{0}`, `{1}`, ... replaced by `args`.
+pub fn trf(msg: Msg, args: &[&dyn Display]) -> String {
+    substitute(tr(msg), args)
+}
+
+/// Replaces the placeholders of `template` in one pass, so placeholders inside the
+/// substituted values are left as they are. Unknown placeholders are kept verbatim.
+fn substitute(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let arg = after.find('}').and_then(|close| Some((close, args.get(after[..close].parse::<usize>().ok()?)?)));
+        match arg {
+            Some((close, arg)) => {
+                out.push_str(&arg.to_string());
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_replaces_each_placeholder_once() {
+        assert_eq!(substitute("{0} of {1}", &[&"{1}", &7]), "{1} of 7");
+        assert_eq!(substitute("{1}/{0}/{1}", &[&"a", &"b"]), "b/a/b");
+    }
+
+    #[test]
+    fn substitute_keeps_unknown_placeholders() {
+        assert_eq!(substitute("{0} {2} {x} {", &[&1]), "1 {2} {x} {");
+    }
+}
@@ -0,0 +1,506 @@
+// src/ledger.rs
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ===============================================
+// LEDGER HARDWARE WALLET (CARDANO APP)
+// ===============================================
+//
+// With --ledger the payment key never leaves the device: the address is built from the public
+// key the Cardano app exports, and registration and donation messages are CIP-8 signed on the
+// device (SIGN_MSG, Cardano app 7.1 or later) after the user confirms them. The device is
+// reached through Linux hidraw, framing APDUs into 64-byte HID reports the way Ledger's own
+// transports do. Ed25519 signatures are deterministic, so each (key, message) pair is signed
+// once per process and the signature reused, instead of asking for a confirmation every cycle.
+
+const LEDGER_VENDOR_ID: u32 = 0x2c97;
+const HID_PACKET_SIZE: usize = 64;
+const HID_CHANNEL: u16 = 0x0101;
+const HID_TAG_APDU: u8 = 0x05;
+/// How long one HID read waits for the device. Long enough to confirm a message on it, short
+/// enough that an unattended or unplugged device fails the call instead of blocking forever.
+const HID_READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+const CLA: u8 = 0xd7;
+const INS_GET_VERSION: u8 = 0x00;
+const INS_GET_EXTENDED_PUBLIC_KEY: u8 = 0x10;
+const INS_SIGN_MESSAGE: u8 = 0x24;
+
+/// First Cardano app version with SIGN_MSG.
+const MIN_SIGN_MESSAGE_VERSION: (u8, u8) = (7, 1);
+const SIGN_MESSAGE_INIT: u8 = 0x01;
+const SIGN_MESSAGE_CHUNK: u8 = 0x02;
+const SIGN_MESSAGE_CONFIRM: u8 = 0x03;
+/// The first chunk is shown on the device, so it is shorter than the ones after it.
+const FIRST_CHUNK_ASCII: usize = 198;
+const FIRST_CHUNK_HEX: usize = 99;
+const NEXT_CHUNK: usize = 250;
+
+/// Address field of the signed message: the full address, rebuilt by the device from params.
+const ADDRESS_FIELD_ADDRESS: u8 = 0x01;
+/// Address header types: enterprise and base address with key payment (and stake) parts.
+const ADDRESS_TYPE_ENTERPRISE_KEY: u8 = 0x06;
+const ADDRESS_TYPE_BASE_KEY_KEY: u8 = 0x00;
+const STAKING_NONE: u8 = 0x11;
+const STAKING_KEY_PATH: u8 = 0x22;
+
+const SW_OK: u16 = 0x9000;
+
+/// A payment key held by a Ledger: which device and which derivation path.
+#[derive(Clone, Debug)]
+pub struct LedgerKey {
+    /// hidraw device, or None to pick the first Ledger found.
+    pub device: Option<String>,
+    pub path: Vec<u32>,
+    /// Stake key of a base address; None for an enterprise address.
+    pub stake_path: Option<Vec<u32>>,
+    pub network_id: u8,
+}
+
+impl LedgerKey {
+    /// The payment key 1852'/1815'/<account>'/0/<index>, with a `stake_index` also the stake key
+    /// 1852'/1815'/<account>'/2/<stake_index>.
+    pub fn payment(device: Option<String>, account: u32, index: u32, network_id: u8, stake_index: Option<u32>) -> Self {
+        let path = |role: u32, index: u32| vec![1852 | 0x8000_0000, 1815 | 0x8000_0000, account | 0x8000_0000, role, index];
+        LedgerKey {
+            device,
+            path: path(0, index),
+            stake_path: stake_index.map(|stake_index| path(2, stake_index)),
+            network_id,
+        }
+    }
+
+    /// The path in the usual `1852'/1815'/0'/0/0` notation.
+    pub fn path_string(&self) -> String {
+        self.path.iter()
+            .map(|step| if step & 0x8000_0000 != 0 { format!("{}'", step & 0x7fff_ffff) } else { step.to_string() })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// What the device returns for a signed message.
+#[derive(Clone)]
+pub struct SignedMessage {
+    pub signature: [u8; 64],
+    pub public_key: [u8; 32],
+    /// The address the device put into the protected header.
+    pub address: Vec<u8>,
+}
+
+/// The HID link under `Device`: hidraw in production, an in-memory fake in tests.
+trait HidTransport {
+    /// Writes one output report (report number first).
+    fn write_report(&mut self, report: &[u8]) -> Result<(), String>;
+    /// Reads one input report into `packet`, failing after `timeout` without one.
+    fn read_report(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize, String>;
+}
+
+impl HidTransport for File {
+    fn write_report(&mut self, report: &[u8]) -> Result<(), String> {
+        self.write_all(report).map_err(|e| format!("Ledger write failed: {}", e))
+    }
+
+    fn read_report(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize, String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            let mut poll_fd = libc::pollfd { fd: self.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            // SAFETY: poll only writes `revents` of the one pollfd passed, which outlives the call.
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis().min(i32::MAX as u128) as i32) };
+            match ready {
+                -1 => return Err(format!("Ledger read failed: {}", std::io::Error::last_os_error())),
+                0 => return Err(format!(
+                    "The Ledger did not answer within {}s. Check that it is connected and unlocked, and confirm the request on it.",
+                    timeout.as_secs()
+                )),
+                _ => {}
+            }
+        }
+        self.read(packet).map_err(|e| format!("Ledger read failed: {}", e))
+    }
+}
+
+struct Device<T: HidTransport = File> {
+    transport: T,
+}
+
+impl Device {
+    fn open(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path.to_string(),
+            None => find_device()?,
+        };
+        let file = OpenOptions::new().read(true).write(true).open(&path)
+            .map_err(|e| format!("Cannot open Ledger device {}: {} (check the udev rules for vendor 2c97).", path, e))?;
+        Ok(Device { transport: file })
+    }
+}
+
+impl<T: HidTransport> Device<T> {
+    /// Sends one APDU and returns the response data, failing on any status other than 0x9000.
+    fn exchange(&mut self, ins: u8, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() > 255 {
+            return Err(format!("APDU data of {} bytes exceeds 255.", data.len()));
+        }
+        let mut apdu = vec![CLA, ins, p1, p2, data.len() as u8];
+        apdu.extend_from_slice(data);
+        self.write_apdu(&apdu)?;
+        let mut response = self.read_apdu()?;
+        if response.len() < 2 {
+            return Err("Ledger response is too short.".to_string());
+        }
+        let status = u16::from_be_bytes([response[response.len() - 2], response[response.len() - 1]]);
+        response.truncate(response.len() - 2);
+        match status {
+            SW_OK => Ok(response),
+            0x6985 => Err("Rejected on the Ledger.".to_string()),
+            0x6e00 | 0x6d00 | 0x6e01 => Err("The Cardano app is not open on the Ledger.".to_string()),
+            0x5515 => Err("The Ledger is locked.".to_string()),
+            _ => Err(format!("Ledger returned status {:04x}.", status)),
+        }
+    }
+
+    fn write_apdu(&mut self, apdu: &[u8]) -> Result<(), String> {
+        let mut payload = (apdu.len() as u16).to_be_bytes().to_vec();
+        payload.extend_from_slice(apdu);
+        for (seq, chunk) in payload.chunks(HID_PACKET_SIZE - 5).enumerate() {
+            // hidraw expects the report number first; Ledger reports are unnumbered (0).
+            let mut report = vec![0u8; HID_PACKET_SIZE + 1];
+            report[1..3].copy_from_slice(&HID_CHANNEL.to_be_bytes());
+            report[3] = HID_TAG_APDU;
+            report[4..6].copy_from_slice(&(seq as u16).to_be_bytes());
+            report[6..6 + chunk.len()].copy_from_slice(chunk);
+            self.transport.write_report(&report)?;
+        }
+        Ok(())
+    }
+
+    fn read_apdu(&mut self) -> Result<Vec<u8>, String> {
+        let mut response = Vec::new();
+        let mut expected = None;
+        let mut seq: u16 = 0;
+        while expected.is_none_or(|len| response.len() < len) {
+            let mut packet = [0u8; HID_PACKET_SIZE];
+            let read = self.transport.read_report(&mut packet, HID_READ_TIMEOUT)?;
+            if read < 5 || packet[0..2] != HID_CHANNEL.to_be_bytes() || packet[2] != HID_TAG_APDU {
+                return Err("Unexpected HID packet from the Ledger.".to_string());
+            }
+            if u16::from_be_bytes([packet[3], packet[4]]) != seq {
+                return Err("Ledger HID packets arrived out of order.".to_string());
+            }
+            let mut data = &packet[5..read];
+            if seq == 0 {
+                if data.len() < 2 {
+                    return Err("Ledger response is too short.".to_string());
+                }
+                expected = Some(u16::from_be_bytes([data[0], data[1]]) as usize);
+                data = &data[2..];
+            }
+            response.extend_from_slice(data);
+            seq = seq.wrapping_add(1);
+        }
+        response.truncate(expected.unwrap_or(0));
+        Ok(response)
+    }
+}
+
+/// The first hidraw device of vendor 2c97, preferring its APDU interface (input0).
+#[cfg(target_os = "linux")]
+fn find_device() -> Result<String, String> {
+    let entries = std::fs::read_dir("/sys/class/hidraw").map_err(|e| format!("Cannot list hidraw devices: {}", e))?;
+    let mut found = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(uevent) = std::fs::read_to_string(entry.path().join("device/uevent")) else {
+            continue;
+        };
+        let vendor = uevent.lines()
+            .find_map(|line| line.strip_prefix("HID_ID="))
+            .and_then(|id| id.split(':').nth(1))
+            .and_then(|vendor| u32::from_str_radix(vendor, 16).ok());
+        if vendor == Some(LEDGER_VENDOR_ID) {
+            let apdu_interface = uevent.lines().any(|line| line.starts_with("HID_PHYS=") && line.ends_with("input0"));
+            found.push((!apdu_interface, format!("/dev/{}", entry.file_name().to_string_lossy())));
+        }
+    }
+    found.sort();
+    found.into_iter().next().map(|(_, path)| path)
+        .ok_or_else(|| "No Ledger found. Connect and unlock it, open the Cardano app, or pass --ledger-device.".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_device() -> Result<String, String> {
+    Err("Finding a Ledger is only supported on Linux; pass its HID device with --ledger-device.".to_string())
+}
+
+fn serialize_path(path: &[u32]) -> Vec<u8> {
+    let mut out = vec![path.len() as u8];
+    for step in path {
+        out.extend_from_slice(&step.to_be_bytes());
+    }
+    out
+}
+
+/// The Cardano app version as (major, minor, patch).
+pub fn app_version(device: Option<&str>) -> Result<(u8, u8, u8), String> {
+    let response = Device::open(device)?.exchange(INS_GET_VERSION, 0, 0, &[])?;
+    match response[..] {
+        [major, minor, patch, ..] => Ok((major, minor, patch)),
+        _ => Err("Ledger version response is too short.".to_string()),
+    }
+}
+
+/// Checks that the Cardano app can sign messages; returns its version for display.
+pub fn check_app(device: Option<&str>) -> Result<String, String> {
+    let (major, minor, patch) = app_version(device)?;
+    let version = format!("{}.{}.{}", major, minor, patch);
+    if (major, minor) < MIN_SIGN_MESSAGE_VERSION {
+        return Err(format!("The Ledger's Cardano app {} cannot sign messages; update it to {}.{} or later.",
+            version, MIN_SIGN_MESSAGE_VERSION.0, MIN_SIGN_MESSAGE_VERSION.1));
+    }
+    Ok(version)
+}
+
+/// The public key at `path`, exported by the device.
+pub fn public_key(path: &[u32], device: Option<&str>) -> Result<[u8; 32], String> {
+    let response = Device::open(device)?
+        .exchange(INS_GET_EXTENDED_PUBLIC_KEY, 0, 0, &serialize_path(path))?;
+    response.get(..32)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Ledger public key response is too short.".to_string())
+}
+
+/// Messages already confirmed on the device, by (path, message).
+type SignedMessages = HashMap<(Vec<u32>, Vec<u8>), SignedMessage>;
+
+// Process-wide: see `SignedMessages`.
+static SIGNED: OnceLock<Mutex<SignedMessages>> = OnceLock::new();
+
+/// Signs `message` CIP-8 style with `key` (its enterprise or base address, payload not
+/// hashed). The user confirms the message on the device; a message signed before is not shown
+/// again.
+pub fn sign_message(key: &LedgerKey, message: &[u8]) -> Result<SignedMessage, String> {
+    let signed = SIGNED.get_or_init(Default::default);
+    let cache_key = (key.path.clone(), message.to_vec());
+    if let Some(cached) = signed.lock().unwrap().get(&cache_key) {
+        return Ok(cached.clone());
+    }
+
+    let result = sign_on(&mut Device::open(key.device.as_deref())?, key, message)?;
+    signed.lock().unwrap().insert(cache_key, result.clone());
+    Ok(result)
+}
+
+/// The SIGN_MSG init payload: message length, signing path, flags and the address to show.
+fn sign_message_init(key: &LedgerKey, message: &[u8], is_ascii: bool) -> Vec<u8> {
+    let mut init = (message.len() as u32).to_be_bytes().to_vec();
+    init.extend_from_slice(&serialize_path(&key.path));
+    init.push(0); // Payload is not hashed.
+    init.push(is_ascii as u8);
+    init.push(ADDRESS_FIELD_ADDRESS);
+    init.push(if key.stake_path.is_some() { ADDRESS_TYPE_BASE_KEY_KEY } else { ADDRESS_TYPE_ENTERPRISE_KEY });
+    init.push(key.network_id);
+    init.extend_from_slice(&serialize_path(&key.path));
+    match &key.stake_path {
+        Some(stake_path) => {
+            init.push(STAKING_KEY_PATH);
+            init.extend_from_slice(&serialize_path(stake_path));
+        }
+        None => init.push(STAKING_NONE),
+    }
+    init
+}
+
+/// Runs the SIGN_MSG exchange (init, message chunks, confirm) on `device`.
+fn sign_on<T: HidTransport>(device: &mut Device<T>, key: &LedgerKey, message: &[u8]) -> Result<SignedMessage, String> {
+    let is_ascii = message.iter().all(|b| (0x20..0x7f).contains(b));
+    device.exchange(INS_SIGN_MESSAGE, SIGN_MESSAGE_INIT, 0, &sign_message_init(key, message, is_ascii))?;
+    let first = if is_ascii { FIRST_CHUNK_ASCII } else { FIRST_CHUNK_HEX }.min(message.len());
+    let chunks = std::iter::once(&message[..first]).chain(message[first..].chunks(NEXT_CHUNK));
+    for chunk in chunks {
+        let mut data = (chunk.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(chunk);
+        device.exchange(INS_SIGN_MESSAGE, SIGN_MESSAGE_CHUNK, 0, &data)?;
+    }
+    let response = device.exchange(INS_SIGN_MESSAGE, SIGN_MESSAGE_CONFIRM, 0, &[])?;
+
+    if response.len() < 100 {
+        return Err("Ledger signature response is too short.".to_string());
+    }
+    let address_len = u32::from_be_bytes(response[96..100].try_into().unwrap()) as usize;
+    Ok(SignedMessage {
+        signature: response[..64].try_into().unwrap(),
+        public_key: response[64..96].try_into().unwrap(),
+        address: response.get(100..100 + address_len)
+            .ok_or_else(|| "Ledger signature response is truncated.".to_string())?
+            .to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// An in-memory Ledger: records the reports written to it and answers with queued ones.
+    #[derive(Default)]
+    struct FakeDevice {
+        written: Vec<Vec<u8>>,
+        replies: VecDeque<Vec<u8>>,
+    }
+
+    impl HidTransport for FakeDevice {
+        fn write_report(&mut self, report: &[u8]) -> Result<(), String> {
+            self.written.push(report.to_vec());
+            Ok(())
+        }
+
+        fn read_report(&mut self, packet: &mut [u8], timeout: Duration) -> Result<usize, String> {
+            let reply = self.replies.pop_front().ok_or_else(|| format!("no reply within {}s", timeout.as_secs()))?;
+            packet[..reply.len()].copy_from_slice(&reply);
+            Ok(reply.len())
+        }
+    }
+
+    /// Frames `response` into input reports the way the device does.
+    fn device_packets(response: &[u8]) -> Vec<Vec<u8>> {
+        let mut payload = (response.len() as u16).to_be_bytes().to_vec();
+        payload.extend_from_slice(response);
+        payload.chunks(HID_PACKET_SIZE - 5).enumerate().map(|(seq, chunk)| {
+            let mut packet = vec![0u8; HID_PACKET_SIZE];
+            packet[0..2].copy_from_slice(&HID_CHANNEL.to_be_bytes());
+            packet[2] = HID_TAG_APDU;
+            packet[3..5].copy_from_slice(&(seq as u16).to_be_bytes());
+            packet[5..5 + chunk.len()].copy_from_slice(chunk);
+            packet
+        }).collect()
+    }
+
+    /// Reassembles the APDUs in the output reports the host wrote.
+    fn written_apdus(reports: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let mut apdus: Vec<(usize, Vec<u8>)> = Vec::new();
+        for report in reports {
+            if report[4..6] == [0, 0] {
+                apdus.push((u16::from_be_bytes([report[6], report[7]]) as usize, report[8..].to_vec()));
+            } else {
+                apdus.last_mut().unwrap().1.extend_from_slice(&report[6..]);
+            }
+        }
+        apdus.into_iter().map(|(len, mut apdu)| { apdu.truncate(len); apdu }).collect()
+    }
+
+    fn device_with(responses: &[&[u8]]) -> Device<FakeDevice> {
+        let replies = responses.iter().flat_map(|response| device_packets(response)).collect();
+        Device { transport: FakeDevice { written: Vec::new(), replies } }
+    }
+
+    #[test]
+    fn write_apdu_splits_into_numbered_reports() {
+        let apdu: Vec<u8> = (0..100).collect();
+        let mut device = device_with(&[]);
+        device.write_apdu(&apdu).unwrap();
+
+        let reports = &device.transport.written;
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|report| report.len() == HID_PACKET_SIZE + 1 && report[0] == 0));
+        assert_eq!(reports[0][1..8], [0x01, 0x01, HID_TAG_APDU, 0x00, 0x00, 0x00, 100]);
+        assert_eq!(reports[0][8..], apdu[..57]);
+        assert_eq!(reports[1][1..6], [0x01, 0x01, HID_TAG_APDU, 0x00, 0x01]);
+        assert_eq!(reports[1][6..49], apdu[57..]);
+        assert!(reports[1][49..].iter().all(|&b| b == 0));
+        assert_eq!(written_apdus(reports), vec![apdu]);
+    }
+
+    #[test]
+    fn read_apdu_reassembles_and_drops_padding() {
+        let response: Vec<u8> = (0..130).map(|i| i as u8).collect();
+        let mut device = device_with(&[&response]);
+        assert_eq!(device.transport.replies.len(), 3);
+        assert_eq!(device.read_apdu().unwrap(), response);
+    }
+
+    #[test]
+    fn read_apdu_rejects_bad_packets() {
+        let mut packets = device_packets(&[0u8; 100]);
+        packets.swap(0, 1);
+        let mut device = Device { transport: FakeDevice { written: Vec::new(), replies: packets.into() } };
+        assert!(device.read_apdu().unwrap_err().contains("out of order"));
+
+        let mut packet = device_packets(&[0x90, 0x00]).remove(0);
+        packet[2] = 0x02;
+        let mut device = Device { transport: FakeDevice { written: Vec::new(), replies: vec![packet].into() } };
+        assert!(device.read_apdu().unwrap_err().contains("Unexpected HID packet"));
+    }
+
+    #[test]
+    fn exchange_maps_status_words_and_silence() {
+        let mut device = device_with(&[&[7, 1, 0, 0x90, 0x00], &[0x69, 0x85]]);
+        assert_eq!(device.exchange(INS_GET_VERSION, 0, 0, &[]).unwrap(), vec![7, 1, 0]);
+        assert_eq!(written_apdus(&device.transport.written), vec![vec![CLA, INS_GET_VERSION, 0, 0, 0]]);
+        assert_eq!(device.exchange(INS_GET_VERSION, 0, 0, &[]).unwrap_err(), "Rejected on the Ledger.");
+        assert!(device.exchange(INS_GET_VERSION, 0, 0, &[]).unwrap_err().contains("no reply"));
+    }
+
+    const PAYMENT_PATH: [u8; 21] = [
+        5, 0x80, 0, 0x07, 0x3c, 0x80, 0, 0x07, 0x17, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
+    #[test]
+    fn sign_message_init_enterprise_address() {
+        let key = LedgerKey::payment(None, 0, 0, 1, None);
+        let mut expected = vec![0, 0, 0, 5];
+        expected.extend_from_slice(&PAYMENT_PATH);
+        expected.extend_from_slice(&[0, 1, ADDRESS_FIELD_ADDRESS, ADDRESS_TYPE_ENTERPRISE_KEY, 1]);
+        expected.extend_from_slice(&PAYMENT_PATH);
+        expected.push(STAKING_NONE);
+        assert_eq!(sign_message_init(&key, b"hello", true), expected);
+    }
+
+    #[test]
+    fn sign_message_init_base_address() {
+        let key = LedgerKey::payment(None, 0, 0, 0, Some(3));
+        let mut stake_path = PAYMENT_PATH;
+        stake_path[16] = 2;
+        stake_path[20] = 3;
+        let mut expected = vec![0, 0, 1, 0];
+        expected.extend_from_slice(&PAYMENT_PATH);
+        expected.extend_from_slice(&[0, 0, ADDRESS_FIELD_ADDRESS, ADDRESS_TYPE_BASE_KEY_KEY, 0]);
+        expected.extend_from_slice(&PAYMENT_PATH);
+        expected.push(STAKING_KEY_PATH);
+        expected.extend_from_slice(&stake_path);
+        assert_eq!(sign_message_init(&key, &[0u8; 256], false), expected);
+    }
+
+    #[test]
+    fn sign_on_sends_init_chunks_and_confirm() {
+        let key = LedgerKey::payment(None, 0, 0, 1, None);
+        let message = vec![b'a'; FIRST_CHUNK_ASCII + 10];
+        let mut confirm = vec![0x11; 64];
+        confirm.extend_from_slice(&[0x22; 32]);
+        confirm.extend_from_slice(&[0, 0, 0, 29]);
+        confirm.extend_from_slice(&[0x61; 29]);
+        confirm.extend_from_slice(&[0x90, 0x00]);
+        let mut device = device_with(&[&[0x90, 0x00], &[0x90, 0x00], &[0x90, 0x00], &confirm]);
+
+        let signed = sign_on(&mut device, &key, &message).unwrap();
+        assert_eq!(signed.signature, [0x11; 64]);
+        assert_eq!(signed.public_key, [0x22; 32]);
+        assert_eq!(signed.address, vec![0x61; 29]);
+
+        let apdus = written_apdus(&device.transport.written);
+        let headers: Vec<&[u8]> = apdus.iter().map(|apdu| &apdu[..4]).collect();
+        assert_eq!(headers, vec![
+            &[CLA, INS_SIGN_MESSAGE, SIGN_MESSAGE_INIT, 0][..],
+            &[CLA, INS_SIGN_MESSAGE, SIGN_MESSAGE_CHUNK, 0][..],
+            &[CLA, INS_SIGN_MESSAGE, SIGN_MESSAGE_CHUNK, 0][..],
+            &[CLA, INS_SIGN_MESSAGE, SIGN_MESSAGE_CONFIRM, 0][..],
+        ]);
+        assert_eq!(apdus[1][5..9], (FIRST_CHUNK_ASCII as u32).to_be_bytes());
+        assert_eq!(apdus[2][5..9], 10u32.to_be_bytes());
+        assert_eq!(apdus[3][4], 0);
+    }
+}
@@ -1,7 +1,18 @@
 pub mod rom;
 pub mod cardano;
 pub mod persistence;
-pub use rom::{RomGenerationType, Rom, RomDigest};
+pub mod hasher;
+pub mod prefetch;
+pub mod hash_cache;
+pub mod params;
+pub mod ledger;
+pub mod difficulty;
+pub use prefetch::{set_rom_prefetch, rom_prefetch};
+pub use hash_cache::{set_hash_cache_size, hash_cache_size, hash_cache_hits, clear_hash_cache};
+pub use params::{ChallengeParamsBuilder, Deadline, DifficultyMask, RomKey};
+pub use difficulty::{DifficultyRule, set_difficulty_rule, difficulty_rule};
+pub use hasher::{ChallengeHasher, CpuHasher};
+pub use rom::{RomGenerationType, Rom, RomDigest, RomDigestTree, RomDigestVersion, RomChecksums, RomPages, RomParams, RomStore, set_huge_pages, huge_pages, set_low_memory, low_memory, last_rom_pages};
 
 use cryptoxide::{
     hashing::blake2b::{self, Blake2b},
@@ -11,8 +22,8 @@ use cryptoxide::{
 // ** Consolidated Imports required for scavenge function **
 use std::sync::mpsc::{Sender, channel};
 use std::{sync::Arc, thread, time::SystemTime};
-use std::sync::atomic::{AtomicBool, Ordering};
-use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::time::Instant;
 // ************************************
 
 
@@ -194,6 +205,9 @@ impl VM {
 
     pub fn execute(&mut self, rom: &Rom, instr: u32) {
         self.program.shuffle(&self.prog_seed);
+        if rom.wants_prefetch() {
+            rom.prefetch(&self.program.memory_addresses(instr));
+        }
         for _ in 0..instr {
             self.step(rom)
         }
@@ -246,6 +260,22 @@ impl Program {
     pub fn shuffle(&mut self, seed: &[u8; 64]) {
         argon2::hprime(&mut self.instructions, seed)
     }
+
+    /// ROM addresses read by the next `nb_instrs` instructions. Memory operands are literals,
+    /// so they are known as soon as the program is shuffled.
+    fn memory_addresses(&self, nb_instrs: u32) -> Vec<u32> {
+        let mut addresses = Vec::new();
+        for i in 0..nb_instrs {
+            let instruction = decode_instruction(self.at(i));
+            if matches!(instruction.op1, Operand::Memory) {
+                addresses.push(instruction.lit1 as u32);
+            }
+            if matches!(instruction.opcode, Instr::Op3(_)) && matches!(instruction.op2, Operand::Memory) {
+                addresses.push(instruction.lit2 as u32);
+            }
+        }
+        addresses
+    }
 }
 
 #[derive(Clone)]
@@ -292,7 +322,7 @@ fn execute_one_instruction(vm: &mut VM, rom: &Rom) {
     macro_rules! mem_access64 {
         ($vm:ident, $rom:ident, $addr:ident) => {{
             let mem = rom.at($addr as u32);
-            $vm.mem_digest.update_mut(mem);
+            $vm.mem_digest.update_mut(mem.as_slice());
             $vm.memory_counter = $vm.memory_counter.wrapping_add(1);
 
             // divide memory access into 8 chunks of 8 bytes
@@ -348,20 +378,8 @@ fn execute_one_instruction(vm: &mut VM, rom: &Rom) {
                 Op3::Mul => src1.wrapping_mul(src2),
                 Op3::MulH => ((src1 as u128 * src2 as u128) >> 64) as u64,
                 Op3::Xor => src1 ^ src2,
-                Op3::Div => {
-                    if src2 == 0 {
-                        special1_value64!(vm)
-                    } else {
-                        src1 / src2
-                    }
-                }
-                Op3::Mod => {
-                    if src2 == 0 {
-                        special1_value64!(vm)
-                    } else {
-                        src1 / src2
-                    }
-                }
+                Op3::Div => src1.checked_div(src2).unwrap_or_else(|| special1_value64!(vm)),
+                Op3::Mod => src1.checked_div(src2).unwrap_or_else(|| special1_value64!(vm)),
                 Op3::And => src1 & src2,
                 Op3::Hash(v) => {
                     assert!(v < 8);
@@ -411,9 +429,9 @@ pub fn hash(salt: &[u8], rom: &Rom, nb_loops: u32, nb_instrs: u32) -> [u8; 64] {
     vm.finalize()
 }
 
+/// Whether `hash` meets `difficulty_mask` under the process-wide `difficulty_rule()`.
 pub fn hash_structure_good(hash: &[u8], difficulty_mask: u32) -> bool {
-    let value = u32::from_be_bytes(hash[..4].try_into().unwrap());
-    (value | difficulty_mask) == difficulty_mask
+    difficulty_rule().meets(hash, difficulty_mask)
 }
 
 // --------------------------------------------------------------------------
@@ -431,12 +449,22 @@ pub struct ChallengeParams {
     pub challenge_id: String,
     pub latest_submission: String,
     pub no_pre_mine_hour: String,
-    pub rom: Arc<Rom>,
+    pub hasher: Arc<dyn ChallengeHasher>,
 }
 
 #[derive(Clone)]
 pub enum Result {
     Progress(usize),
+    /// Periodic per-worker checkpoint: `worker` will check `next_nonce` next and has hashed
+    /// `hashes` nonces so far. Every nonce of the worker's earlier blocks, and of its current
+    /// block below `next_nonce`, has been hashed.
+    Checkpoint { worker: usize, next_nonce: u64, hashes: u64 },
+    /// The challenge's `latest_submission` deadline has passed; the worker has stopped.
+    DeadlinePassed,
+    /// `worker` found no unreserved nonce left: the allocator's range is fully handed out and
+    /// the worker has stopped. Sent after the worker's final checkpoint. Nonces never wrap
+    /// around to the start of the range.
+    SpaceExhausted { worker: usize },
     Found(u64, [u8; 64]), // Found now returns the nonce AND the 64-byte hash
 }
 
@@ -467,15 +495,182 @@ fn update_preimage_nonce(preimage_string: &mut String, nonce: u64) {
     preimage_string.replace_range(0..16, &nonce_str);
 }
 
+// Process-wide CPU duty cycle for `spin` workers, in percent (100 = no throttling).
+static CPU_LIMIT_PERCENT: AtomicU8 = AtomicU8::new(100);
+// When set, `spin` workers drop themselves to idle/lowest OS scheduling priority.
+static BACKGROUND_PRIORITY: AtomicBool = AtomicBool::new(false);
+// Number of hashes between throttling sleeps.
+const THROTTLE_BATCH: u64 = 16;
+
+/// Caps the CPU utilization of every mining worker to `percent` (1-100) by inserting sleep
+/// windows proportional to the time spent hashing. 100 disables throttling.
+pub fn set_cpu_limit(percent: u8) {
+    CPU_LIMIT_PERCENT.store(percent.clamp(1, 100), Ordering::Relaxed);
+}
+
+/// Returns the current worker CPU duty cycle in percent.
+pub fn cpu_limit() -> u8 {
+    CPU_LIMIT_PERCENT.load(Ordering::Relaxed)
+}
+
+/// Runs every subsequently started mining worker at idle/low OS priority
+/// (nice 19 on Unix, THREAD_PRIORITY_IDLE on Windows) so interactive workloads win.
+pub fn set_background_priority(enabled: bool) {
+    BACKGROUND_PRIORITY.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether mining workers run at background priority.
+pub fn background_priority() -> bool {
+    BACKGROUND_PRIORITY.load(Ordering::Relaxed)
+}
+
+// Process-wide: `spin` workers with an index at or above this wait instead of hashing.
+static ACTIVE_WORKERS: AtomicUsize = AtomicUsize::new(usize::MAX);
+// How often a parked worker checks whether it may hash again.
+const PARKED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Lets only workers `0..n` (at least one) hash; the others park between nonce blocks until
+/// the limit is raised again. Spawning the largest thread count once and moving this limit
+/// scales a running cycle up or down without restarting it.
+pub fn set_active_workers(n: usize) {
+    ACTIVE_WORKERS.store(n.max(1), Ordering::Relaxed);
+}
+
+/// Returns how many workers may hash (`usize::MAX` unless limited).
+pub fn active_workers() -> usize {
+    ACTIVE_WORKERS.load(Ordering::Relaxed)
+}
+
+/// Lowers the scheduling priority of the calling thread. Failures are ignored: mining
+/// still works at normal priority.
+#[cfg(unix)]
+fn lower_current_thread_priority() {
+    // On Linux, PRIO_PROCESS with who = 0 applies to the calling thread only.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+    }
+}
+
+#[cfg(windows)]
+fn lower_current_thread_priority() {
+    const THREAD_PRIORITY_IDLE: i32 = -15;
+    unsafe extern "system" {
+        fn GetCurrentThread() -> *mut std::ffi::c_void;
+        fn SetThreadPriority(thread: *mut std::ffi::c_void, priority: i32) -> i32;
+    }
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_IDLE);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lower_current_thread_priority() {}
+
+// --------------------------------------------------------------------------
+// NONCE ALLOCATION
+// --------------------------------------------------------------------------
+
+/// Nonces handed to a worker in one reservation when no block size is given.
+pub const NONCE_BLOCK_SIZE: u64 = 0x400;
+
+// Process-wide: nonces at or above this are never searched (u64::MAX = the whole space).
+static NONCE_SPACE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(u64::MAX);
+
+/// Limits every search to nonces below `end`, so tests and tiny mock challenges run out of
+/// nonces (`Result::SpaceExhausted`) instead of hashing forever.
+pub fn set_nonce_space(end: u64) {
+    NONCE_SPACE.store(end.max(1), Ordering::Relaxed);
+}
+
+/// Returns the end of the searched nonce space.
+pub fn nonce_space() -> u64 {
+    NONCE_SPACE.load(Ordering::Relaxed)
+}
+
+/// A contiguous range of nonces `[start, end)` reserved by one worker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonceBlock {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl NonceBlock {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Hands out contiguous, non-overlapping blocks of nonces from `[start, end)` to workers.
+/// Blocks are reserved in increasing order with one atomic operation, so any number of local
+/// threads can share an allocator, and a distributed controller can give each host a disjoint
+/// range (`with_range`) to allocate from.
+///
+/// Because every worker finishes its block before reserving the next one, every nonce below
+/// the smallest position any worker has reached has been hashed: that minimum is a safe
+/// resume point.
+#[derive(Debug)]
+pub struct NonceAllocator {
+    next: std::sync::atomic::AtomicU64,
+    end: u64,
+    block_size: u64,
+}
+
+impl NonceAllocator {
+    /// Allocates from `start` up to the end of the nonce space, `NONCE_BLOCK_SIZE` at a time.
+    pub fn new(start: u64) -> Self {
+        Self::with_range(start, u64::MAX, NONCE_BLOCK_SIZE)
+    }
+
+    /// Allocates `[start, end)` in blocks of `block_size` (the last block may be shorter).
+    pub fn with_range(start: u64, end: u64, block_size: u64) -> Self {
+        NonceAllocator {
+            next: std::sync::atomic::AtomicU64::new(start.min(end)),
+            end,
+            block_size: block_size.max(1),
+        }
+    }
+
+    /// Reserves the next block, or returns None once the range is exhausted.
+    pub fn reserve(&self) -> Option<NonceBlock> {
+        let start = self
+            .next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                (next < self.end).then(|| next.saturating_add(self.block_size).min(self.end))
+            })
+            .ok()?;
+        Some(NonceBlock { start, end: start.saturating_add(self.block_size).min(self.end) })
+    }
+
+    /// First nonce not reserved yet.
+    pub fn next_unreserved(&self) -> u64 {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    /// Nonces not reserved yet.
+    pub fn remaining(&self) -> u64 {
+        self.end - self.next_unreserved()
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+}
+
 // The worker thread function
-pub fn spin(params: ChallengeParams, sender: Sender<Result>, stop_signal: Arc<AtomicBool>, start_nonce: u64, step_size: u64) {
-    let mut nonce_value = start_nonce;
+/// Hashes nonce blocks reserved from `nonces` until `stop_signal` is raised, the submission
+/// deadline passes or the allocator runs out (reported as `SpaceExhausted`). `worker`
+/// identifies this thread in checkpoints; it parks while outside `active_workers()`.
+pub fn spin(params: ChallengeParams, sender: Sender<Result>, stop_signal: Arc<AtomicBool>, nonces: Arc<NonceAllocator>, worker: usize) {
+    let mut iterations: u64 = 0;
     const CHUNKS_SIZE: usize = 0xff;
-    const NB_LOOPS: u32 = 8;
-    const NB_INSTRS: u32 = 256;
+    const CHECKPOINT_INTERVAL: u64 = 0x400;
 
-    let mut preimage_string = build_preimage(
-        nonce_value,
+    let preimage_string = build_preimage(
+        nonces.next_unreserved(),
         &params.address,
         &params.challenge_id,
         params.difficulty_mask,
@@ -483,29 +678,154 @@ pub fn spin(params: ChallengeParams, sender: Sender<Result>, stop_signal: Arc<At
         &params.latest_submission,
         &params.no_pre_mine_hour,
     );
+    let deadline = parse_submission_deadline(&params.latest_submission);
+    let mut batch_start = Instant::now();
 
-    while !stop_signal.load(Ordering::Relaxed) {
-        let preimage_bytes = preimage_string.as_bytes();
-        let h = hash(preimage_bytes, &params.rom, NB_LOOPS, NB_INSTRS);
+    if background_priority() {
+        lower_current_thread_priority();
+    }
 
-        if hash_structure_good(&h, params.difficulty_mask) {
-            if sender.send(Result::Found(nonce_value, h)).is_ok() {
-                // Sent the found nonce
+    // Preimages of the next batch: nonce_value, nonce_value + 1, ... (within the current block)
+    let mut preimages = vec![preimage_string; params.hasher.batch_size().max(1)];
+    let mut hashes = Vec::with_capacity(preimages.len());
+    // Next nonce to check; None until the first block is reserved.
+    let mut nonce_value: Option<u64> = None;
+    let mut exhausted = false;
+
+    'hashing: while !stop_signal.load(Ordering::Relaxed) {
+        while worker >= active_workers() {
+            if stop_signal.load(Ordering::Relaxed) {
+                break 'hashing;
+            }
+            // A parked worker must still report exhaustion, or the cycle would never end.
+            if nonces.remaining() == 0 {
+                exhausted = true;
+                break 'hashing;
             }
-            return;
+            // Holding no block, a parked worker is past every nonce handed out so far. Report
+            // that, or its last position would hold the dispatcher's nonce cursor back.
+            let next_free = nonces.next_unreserved();
+            if nonce_value != Some(next_free) {
+                nonce_value = Some(next_free);
+                if sender.send(Result::Checkpoint { worker, next_nonce: next_free, hashes: iterations }).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(PARKED_POLL_INTERVAL);
         }
+        let Some(block) = nonces.reserve() else {
+            // Every nonce of the range is taken: stop instead of wrapping around and hashing
+            // the same preimages again.
+            exhausted = true;
+            break;
+        };
+        let mut next = block.start;
+        nonce_value = Some(next);
+
+        while next < block.end {
+            if stop_signal.load(Ordering::Relaxed) {
+                break 'hashing;
+            }
+            let count = preimages.len().min((block.end - next) as usize);
+            for (i, preimage) in preimages[..count].iter_mut().enumerate() {
+                update_preimage_nonce(preimage, next + i as u64);
+            }
+            if count == 1 {
+                // Unbatched backends (the CPU interpreter): skip building the batch.
+                hashes.clear();
+                hashes.push(match params.hasher.rom_digest() {
+                    Some(rom) => hash_cache::cached_hash(rom, preimages[0].as_bytes(), |preimage| params.hasher.hash(preimage)),
+                    None => params.hasher.hash(preimages[0].as_bytes()),
+                });
+            } else {
+                let batch: Vec<&[u8]> = preimages[..count].iter().map(|preimage| preimage.as_bytes()).collect();
+                params.hasher.hash_batch(&batch, &mut hashes);
+            }
+
+            for h in &hashes {
+                // Keep hashing after a find: the dispatcher raises the stop signal once it has enough solutions.
+                if hash_structure_good(h, params.difficulty_mask) && sender.send(Result::Found(next, *h)).is_err() {
+                    return;
+                }
 
-        if nonce_value & (CHUNKS_SIZE as u64) == 0 && sender.send(Result::Progress(CHUNKS_SIZE)).is_err() {
-             return;
+                next += 1;
+                nonce_value = Some(next);
+
+                iterations = iterations.wrapping_add(1);
+                if iterations.is_multiple_of(THROTTLE_BATCH) {
+                    let limit = cpu_limit() as u32;
+                    if limit < 100 {
+                        // Sleep so that busy / (busy + idle) == limit%.
+                        let busy = batch_start.elapsed();
+                        thread::sleep(busy * (100 - limit) / limit);
+                    }
+                    batch_start = Instant::now();
+                }
+                if iterations.is_multiple_of(CHUNKS_SIZE as u64) && sender.send(Result::Progress(CHUNKS_SIZE)).is_err() {
+                    return;
+                }
+                if iterations.is_multiple_of(CHECKPOINT_INTERVAL) {
+                    if sender.send(Result::Checkpoint { worker, next_nonce: next, hashes: iterations }).is_err() {
+                        return;
+                    }
+                    // Solutions found after the submission window closes are rejected, so stop hashing.
+                    if deadline.is_some_and(|deadline| deadline < chrono::Utc::now()) {
+                        let _ = sender.send(Result::DeadlinePassed);
+                        break 'hashing;
+                    }
+                }
+            }
         }
+    }
 
-        // Increment nonce by the thread step size
-        nonce_value = nonce_value.wrapping_add(step_size);
-        update_preimage_nonce(&mut preimage_string, nonce_value);
+    // Report the hashes since the last chunk and a final checkpoint so the dispatcher knows exactly how far we got.
+    let _ = sender.send(Result::Progress((iterations % CHUNKS_SIZE as u64) as usize));
+    if let Some(next_nonce) = nonce_value {
+        let _ = sender.send(Result::Checkpoint { worker, next_nonce, hashes: iterations });
+    }
+    if exhausted {
+        let _ = sender.send(Result::SpaceExhausted { worker });
     }
 }
 
+/// Parses the RFC 3339 `latest_submission` deadline. Returns None when it cannot be parsed.
+fn parse_submission_deadline(latest_submission: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(latest_submission)
+        .ok()
+        .map(|deadline| deadline.with_timezone(&chrono::Utc))
+}
+
+/// Returns true once the RFC 3339 submission deadline lies in the past.
+/// An unparseable deadline is treated as not passed so mining is never cut short by bad data.
+pub fn submission_deadline_passed(latest_submission: &str) -> bool {
+    parse_submission_deadline(latest_submission).is_some_and(|deadline| deadline < chrono::Utc::now())
+}
+
+/// Snapshot handed to the `scavenge` progress callback.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub total_hashes: u64,
+    pub elapsed: f64, // seconds
+    pub hash_rate: f64, // hashes per second
+    pub solutions_found: usize,
+}
+
+/// Outcome of a `scavenge` run.
+#[derive(Clone, Debug, Default)]
+pub struct ScavengeReport {
+    pub nonces: Vec<String>, // hex-encoded, in the order they were found
+    pub total_hashes: u64,
+    pub elapsed: f64, // seconds
+    pub per_thread_rates: Vec<f64>, // hashes per second, indexed by thread id
+    pub space_exhausted: bool, // every nonce was checked before enough solutions were found
+}
+
 // The main orchestration function
+/// Generates the ROM and hashes on `nb_threads` workers until `solutions_per_cycle` distinct
+/// valid nonces are found or the submission deadline passes. Nothing is printed; pass
+/// `on_progress` to observe the run.
+#[deprecated(since = "0.3.0", note = "build a `ChallengeParams` with `ChallengeParams::builder()` and call `scavenge_challenge`")]
+#[allow(clippy::too_many_arguments)]
 pub fn scavenge(
     my_registered_address: String,
     challenge_id: String,
@@ -514,51 +834,100 @@ pub fn scavenge(
     latest_submission: String,
     no_pre_mine_hour: String,
     nb_threads: u32,
-) -> (Option<String>, u64, f64) { // <-- FIX: Explicitly define the return type
-    const MB: usize = 1024 * 1024;
-    const GB: usize = 1024 * MB;
+    solutions_per_cycle: usize,
+    on_progress: Option<&mut dyn FnMut(Progress)>,
+) -> ScavengeReport {
+    let rom = Rom::from_params(no_pre_mine_key.as_bytes(), &RomParams::default());
+    let params = positional_params(Arc::new(CpuHasher::new(Arc::new(rom))), my_registered_address, challenge_id, &difficulty, no_pre_mine_key, latest_submission, no_pre_mine_hour);
+    scavenge_challenge(params, nb_threads, solutions_per_cycle, on_progress)
+}
 
-    let difficulty_mask = u32::from_str_radix(&difficulty, 16).unwrap();
+/// Same as `scavenge`, but hashes against an already generated `rom`. The caller must have
+/// built it from `no_pre_mine_key`; reusing it across cycles skips ROM generation entirely.
+#[deprecated(since = "0.3.0", note = "build a `ChallengeParams` with `ChallengeParams::builder().rom(rom)` and call `scavenge_challenge`")]
+#[allow(clippy::too_many_arguments)]
+pub fn scavenge_with_rom(
+    rom: Arc<Rom>,
+    my_registered_address: String,
+    challenge_id: String,
+    difficulty: String,
+    no_pre_mine_key: String,
+    latest_submission: String,
+    no_pre_mine_hour: String,
+    nb_threads: u32,
+    solutions_per_cycle: usize,
+    on_progress: Option<&mut dyn FnMut(Progress)>,
+) -> ScavengeReport {
+    let params = positional_params(Arc::new(CpuHasher::new(rom)), my_registered_address, challenge_id, &difficulty, no_pre_mine_key, latest_submission, no_pre_mine_hour);
+    scavenge_challenge(params, nb_threads, solutions_per_cycle, on_progress)
+}
 
-    let nb_threads_u64 = nb_threads as u64;
-    let step_size = nb_threads_u64;
+/// Same as `scavenge`, but computes hashes with `hasher` instead of the CPU interpreter.
+#[deprecated(since = "0.3.0", note = "build a `ChallengeParams` with `ChallengeParams::builder().hasher(hasher)` and call `scavenge_challenge`")]
+#[allow(clippy::too_many_arguments)]
+pub fn scavenge_with_hasher(
+    hasher: Arc<dyn ChallengeHasher>,
+    my_registered_address: String,
+    challenge_id: String,
+    difficulty: String,
+    no_pre_mine_key: String,
+    latest_submission: String,
+    no_pre_mine_hour: String,
+    nb_threads: u32,
+    solutions_per_cycle: usize,
+    on_progress: Option<&mut dyn FnMut(Progress)>,
+) -> ScavengeReport {
+    let params = positional_params(hasher, my_registered_address, challenge_id, &difficulty, no_pre_mine_key, latest_submission, no_pre_mine_hour);
+    scavenge_challenge(params, nb_threads, solutions_per_cycle, on_progress)
+}
 
-    let (found_nonce, final_hashes_checked, elapsed_time) = thread::scope(|s| {
-        println!("Generating ROM with key: {}", no_pre_mine_key);
+/// The unvalidated parameters of the positional `scavenge` functions. Panics on a non-hex
+/// difficulty, as they always have.
+fn positional_params(
+    hasher: Arc<dyn ChallengeHasher>,
+    address: String,
+    challenge_id: String,
+    difficulty: &str,
+    rom_key: String,
+    latest_submission: String,
+    no_pre_mine_hour: String,
+) -> ChallengeParams {
+    ChallengeParams {
+        rom_key,
+        difficulty_mask: u32::from_str_radix(difficulty, 16).unwrap(),
+        address,
+        challenge_id,
+        latest_submission,
+        no_pre_mine_hour,
+        hasher,
+    }
+}
 
-        let rom = Rom::new(
-            no_pre_mine_key.as_bytes(),
-            RomGenerationType::TwoStep {
-                pre_size: 16 * MB,
-                mixing_numbers: 4,
-            },
-            GB,
-        );
-        println!("{}", rom.digest);
+/// Hashes `params` on `nb_threads` workers until `solutions_per_cycle` distinct valid nonces
+/// are found, the submission deadline passes or the nonce space runs out. Nothing is printed;
+/// pass `on_progress` to observe the run.
+pub fn scavenge_challenge(
+    params: ChallengeParams,
+    nb_threads: u32,
+    solutions_per_cycle: usize,
+    mut on_progress: Option<&mut dyn FnMut(Progress)>,
+) -> ScavengeReport {
+    let nb_threads = nb_threads.max(1) as usize;
+    let solutions_per_cycle = solutions_per_cycle.max(1);
 
+    thread::scope(|s| {
         let (sender, receiver) = channel();
         let stop_signal = Arc::new(AtomicBool::new(false));
 
-        let common_params = ChallengeParams {
-            rom_key: no_pre_mine_key.clone(),
-            difficulty_mask,
-            address: my_registered_address.clone(),
-            challenge_id: challenge_id.clone(),
-            latest_submission: latest_submission.clone(),
-            no_pre_mine_hour: no_pre_mine_hour.clone(),
-            rom: Arc::new(rom),
-        };
-
-        for thread_id in 0..nb_threads_u64 {
-            let params = common_params.clone();
+        let nonces = Arc::new(NonceAllocator::with_range(0, nonce_space(), NONCE_BLOCK_SIZE));
+        for worker in 0..nb_threads {
+            let params = params.clone();
             let sender = sender.clone();
             let stop_signal = stop_signal.clone();
-
-            // Set start_nonce = thread_id
-            let start_nonce = thread_id;
+            let nonces = nonces.clone();
 
             s.spawn(move || {
-                spin(params, sender, stop_signal, start_nonce, step_size)
+                spin(params, sender, stop_signal, nonces, worker)
             });
         }
 
@@ -566,67 +935,67 @@ pub fn scavenge(
         drop(sender);
 
         let start_loop = SystemTime::now();
-        let mut pos = 0;
-        let pb = ProgressBar::new(u64::MAX);
-        pb.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} {pos}/{len} [{elapsed_precise}] {bar:40.cyan/blue} {msg}",
-            )
-            .unwrap()
-            .progress_chars("#>-"),
-        );
-
-        let mut found = Vec::new();
-        let mut should_stop_after_found = false;
+        let mut total_hashes: u64 = 0;
+        // Hashes per thread, from each worker's latest checkpoint.
+        let mut thread_hashes: Vec<u64> = vec![0; nb_threads];
+        let mut found: Vec<u64> = Vec::new();
+        let mut stopping = false;
+        let mut exhausted_workers = 0;
 
         // Use a loop that waits for channel messages until all senders are dropped
         while let Ok(r) = receiver.recv() {
             match r {
                 Result::Progress(sz) => {
-                    if should_stop_after_found {
-                        // Ignore progress messages if we've already found a solution and are waiting for threads to exit.
-                        continue;
+                    total_hashes += sz as u64;
+
+                    if let Some(callback) = on_progress.as_mut() {
+                        let elapsed = start_loop.elapsed().unwrap().as_secs_f64();
+                        callback(Progress {
+                            total_hashes,
+                            elapsed,
+                            hash_rate: if elapsed > 0.0 { total_hashes as f64 / elapsed } else { 0.0 },
+                            solutions_found: found.len(),
+                        });
                     }
-
-                    pos += sz as u64;
-                    pb.set_position(pos);
-                    let elapsed = start_loop.elapsed().unwrap().as_secs_f64();
-                    let current_speed = (pos as f64) / elapsed;
-
-                    pb.set_message(format!(
-                        "Speed: {:.2} hash/s found: {}",
-                        current_speed,
-                        found.len()
-                    ));
+                }
+                Result::Checkpoint { worker, hashes, .. } => {
+                    thread_hashes[worker] = hashes;
+                }
+                Result::DeadlinePassed => {
+                    stop_signal.store(true, Ordering::Relaxed);
+                    stopping = true;
+                }
+                Result::SpaceExhausted { .. } => {
+                    exhausted_workers += 1;
                 }
                 Result::Found(nonce, _h_output) => {
-                    let nonce_hex = format!("{:016x}", nonce);
-                    println!("\nFound valid nonce: {}", nonce_hex);
+                    if stopping || found.contains(&nonce) {
+                        continue;
+                    }
                     found.push(nonce);
 
-                    // 🚨 Signal all worker threads to stop gracefully
-                    stop_signal.store(true, Ordering::Relaxed);
-                    should_stop_after_found = true;
-                    // The loop continues, draining any remaining messages before recv() returns Err(RecvError::Disconnected)
+                    if found.len() >= solutions_per_cycle {
+                        // 🚨 Signal all worker threads to stop gracefully
+                        stop_signal.store(true, Ordering::Relaxed);
+                        stopping = true;
+                        // The loop continues, draining any remaining messages before recv() returns Err(RecvError::Disconnected)
+                    }
                 }
             }
         }
 
-        // Final message after the mining stops (channel disconnects)
-        let final_nonce_hex = found.pop().map(|nonce| format!("{:016x}", nonce));
-        let final_elapsed = start_loop.elapsed().unwrap().as_secs_f64();
-        let final_hashes = pos;
-
-        if final_nonce_hex.is_some() {
-            let msg = format!("Scavenging complete. Found 1 solution. Total hashes checked: {}", pos);
-            pb.finish_with_message(msg);
-        } else {
-             pb.abandon_with_message("Scavenging stopped (No solution found).");
+        let elapsed = start_loop.elapsed().unwrap().as_secs_f64();
+        let per_thread_rates = thread_hashes
+            .iter()
+            .map(|&hashes| if elapsed > 0.0 { hashes as f64 / elapsed } else { 0.0 })
+            .collect();
+
+        ScavengeReport {
+            nonces: found.iter().map(|nonce| format!("{:016x}", nonce)).collect(),
+            total_hashes,
+            elapsed,
+            per_thread_rates,
+            space_exhausted: exhausted_workers == nb_threads && found.len() < solutions_per_cycle,
         }
-
-        // Return the found nonce (if any) from the thread scope
-        (final_nonce_hex, final_hashes, final_elapsed)
-    });
-
-    (found_nonce, final_hashes_checked, elapsed_time)
+    })
 }
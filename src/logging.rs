@@ -0,0 +1,107 @@
+// src/logging.rs
+
+use std::fmt;
+use std::io;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+// ===============================================
+// TRACING SETUP (--log-level)
+// ===============================================
+//
+// The mining, submission and API modules log through `tracing`. Events are formatted here and
+// handed to the console printer, so they stay in order with `cprintln!` output and are written
+// around the progress bar. INFO lines look exactly like the console messages they replaced;
+// DEBUG and TRACE lines carry their level, module and span context.
+
+/// Filter used when neither --log-level nor RUST_LOG is set.
+const DEFAULT_FILTER: &str = "info";
+
+/// Installs the global subscriber. `filter` uses `EnvFilter` syntax, e.g. `debug` or
+/// `info,shadow_harvester::api=debug`; without it RUST_LOG is honored.
+pub fn init(filter: Option<&str>) -> Result<(), String> {
+    let filter = match filter {
+        Some(filter) => EnvFilter::try_new(filter).map_err(|e| format!("Invalid --log-level '{}': {}", filter, e))?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER)),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .event_format(ConsoleFormat)
+        .with_writer(ConsoleWriter)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))
+}
+
+struct ConsoleFormat;
+
+impl<S, N> FormatEvent<S, N> for ConsoleFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let meta = event.metadata();
+        if *meta.level() > Level::INFO {
+            write!(writer, "{} {}: ", meta.level(), meta.target())?;
+            if let Some(scope) = ctx.event_scope() {
+                for span in scope.from_root() {
+                    write!(writer, "{}", span.name())?;
+                    if let Some(fields) = span.extensions().get::<FormattedFields<N>>()
+                        && !fields.is_empty()
+                    {
+                        write!(writer, "{{{}}}", fields)?;
+                    }
+                    write!(writer, ": ")?;
+                }
+            }
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Routes WARN and ERROR events to stderr and everything else to stdout, via the printer.
+struct ConsoleWriter;
+
+/// Collects one formatted event and queues it on the printer when dropped.
+struct EventBuffer {
+    text: Vec<u8>,
+    to_stderr: bool,
+}
+
+impl io::Write for EventBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventBuffer {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.text).trim_end_matches('\n').to_string();
+        if self.to_stderr {
+            crate::console::err(text);
+        } else {
+            crate::console::out(text);
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for ConsoleWriter {
+    type Writer = EventBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventBuffer { text: Vec::new(), to_stderr: false }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        EventBuffer { text: Vec::new(), to_stderr: *meta.level() <= Level::WARN }
+    }
+}
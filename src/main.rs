@@ -1,38 +1,90 @@
 // src/main.rs - Final Minimal Version
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use std::thread;
 use std::sync::mpsc;
 use std::time::Duration;
 use cli::{Cli, Commands};
 
 // Declare modules
+#[macro_use]
+mod console;
 mod api;
+mod api_explorer;
+mod api_schema;
+mod offline;
 mod backoff;
+mod bench;
 mod cli;
 mod constants;
+mod exit_codes;
+mod i18n;
 mod cardano;
+mod ledger;
 mod data_types;
 mod utils;
 pub mod mining;
 mod state_worker;
 mod persistence;
 mod challenge_manager;
+mod challenge_sources;
 mod polling_client;
+mod rom_tools;
 mod migrate;
 mod cli_commands;
 mod websocket_server;
 mod mock_api;
+mod autoscale;
+mod selftest;
+mod shutdown;
+mod sweep;
+mod watchdog;
+mod secrets;
+mod logging;
+mod control;
+mod shell;
+mod rest_api;
+mod verify;
+mod notify;
+mod telemetry;
+mod soak;
+mod state_store;
+mod schema;
+#[cfg(feature = "web-ui")]
+mod web_ui;
 
 use data_types::{PendingSolution, ChallengeData};
+use exit_codes::{ExitError, WithExitCode};
 
 
-fn run_app(cli: Cli) -> Result<(), String> {
+fn run_app(cli: Cli) -> Result<(), ExitError> {
+    // Applies to every worker spawned by the library's spin loop.
+    shadow_harvester_lib::set_cpu_limit(cli.cpu_limit);
+    shadow_harvester_lib::set_background_priority(cli.background);
+    shadow_harvester_lib::set_hash_cache_size(cli.hash_cache);
+    shadow_harvester_lib::set_nonce_space(cli.nonce_space.unwrap_or(u64::MAX));
+    shadow_harvester_lib::set_huge_pages(cli.huge_pages);
+    shadow_harvester_lib::set_low_memory(cli.low_memory);
+    mining::set_rom_cache_max(cli.rom_cache_max as usize);
+    api::set_dry_run(cli.dry_run);
+    api::set_offline(cli.offline);
+    if cli.offline {
+        cprintln!("✈️ Offline mode: no API contact; solutions are signed and exported, never submitted.");
+    }
+    if cli.dry_run {
+        cprintln!("🧪 Dry run: solutions are validated locally and logged, never submitted.");
+    }
+    if cli.rom_prefetch && let Err(e) = shadow_harvester_lib::set_rom_prefetch(true) {
+        ceprintln!("⚠️ WARNING: --rom-prefetch disabled: {}", e);
+    }
+
+    notify::init_from_cli(&cli)?;
+
     // setup_app is where the crash originates (due to missing API URL).
     // We rely on the main function logic to ensure setup_app is only called if necessary.
     let context = match utils::setup_app(&cli) {
         Ok(c) => c,
-        Err(e) if e == "COMMAND EXECUTED" => return Ok(()),
+        Err(e) if e.message == "COMMAND EXECUTED" => return Ok(()),
         Err(e) => return Err(e),
     };
 
@@ -48,17 +100,24 @@ fn run_app(cli: Cli) -> Result<(), String> {
     let (manager_tx, manager_rx) = mpsc::channel();
     let (submitter_tx, submitter_rx) = mpsc::channel();
     let (ws_tx, ws_rx) = mpsc::channel();
+    let (poller_tx, poller_rx) = tokio::sync::mpsc::unbounded_channel();
 
     let (_ws_solution_tx, _ws_solution_rx) = mpsc::channel::<PendingSolution>();
     let (_ws_challenge_tx, _ws_challenge_rx) = mpsc::channel::<ChallengeData>();
 
 
+    // Fatal errors in any thread below shut down through the manager so Sled is flushed.
+    shutdown::install_coordinator(manager_tx.clone(), submitter_tx.clone());
+
     // --- THREAD DISPATCH ---
     let data_dir_clone = cli.data_dir.clone().unwrap_or_else(|| "state".to_string());
     let is_websocket_mode = cli.websocket;
+    let db_compress = cli.db_compress;
+    let db_backend = cli.db_backend;
+    let db_encryption = utils::db_encryption(&cli).exit_code(exit_codes::DATABASE_ERROR)?;
 
     let ws_tx_for_submitter = ws_tx.clone(); // Clone for Submitter thread
-    let _submitter_handle = thread::spawn(move || {
+    let submitter_handle = thread::spawn(move || {
         let result = state_worker::run_state_worker(
             submitter_rx,
             submitter_client, // Use cloned client
@@ -66,10 +125,13 @@ fn run_app(cli: Cli) -> Result<(), String> {
             data_dir_clone,
             is_websocket_mode,
             ws_tx_for_submitter, // <-- NEW: Pass ws_tx
+            db_compress,
+            db_backend,
+            db_encryption,
         );
+        // The state worker only fails when it cannot open the database.
         if let Err(e) = result {
-            eprintln!("❌ FATAL THREAD ERROR: Submitter failed: {}", e);
-            std::process::exit(1);
+            shutdown::fatal("Submitter", &e, exit_codes::DATABASE_ERROR);
         }
     });
 
@@ -79,18 +141,20 @@ fn run_app(cli: Cli) -> Result<(), String> {
     let manager_context = context; // context is moved here
     let submitter_tx_clone = submitter_tx.clone();
     let manager_tx_clone = manager_tx.clone();
+    let ws_tx_for_manager = ws_tx.clone();
 
     let _manager_handle = thread::spawn(move || {
         let result = challenge_manager::run_challenge_manager(
             manager_rx,
             submitter_tx_clone,
             manager_tx_clone,
+            poller_tx,
+            ws_tx_for_manager,
             manager_cli,
             manager_context
         );
         if let Err(e) = result {
-            eprintln!("❌ FATAL THREAD ERROR: Manager failed: {}", e);
-            std::process::exit(1);
+            shutdown::fatal("Manager", &e, exit_codes::THREAD_FAILURE);
         }
     });
 
@@ -103,38 +167,137 @@ fn run_app(cli: Cli) -> Result<(), String> {
         let _ws_server_handle = thread::spawn(move || {
             let result = websocket_server::start_server(manager_tx_clone, ws_rx, ws_port);
             if let Err(e) = result {
-                eprintln!("❌ FATAL THREAD ERROR: WebSocket Server failed: {}", e);
-                std::process::exit(1);
+                shutdown::fatal("WebSocket server", &e, exit_codes::THREAD_FAILURE);
             }
         });
-    } else if cli.challenge.is_none() {
+    }
+    let sources = challenge_sources::enabled_sources(&cli);
+    if cli.challenge_priority.is_some() && sources.len() < 2 {
+        ceprintln!("⚠️ WARNING: --challenge-priority has no effect with a single challenge source. Pass --api-url together with --websocket or --challenge.");
+    }
+    if sources.contains(&challenge_sources::ChallengeSource::Api) {
         // Start dedicated HTTP Polling Client
         let manager_tx_clone = manager_tx.clone();
 
-        let _polling_handle = thread::spawn(move || {
-            let result = polling_client::run_polling_client(polling_client, polling_api_url, manager_tx_clone);
+        let _polling_handle = api::runtime().spawn(async move {
+            let result = polling_client::run_polling_client(polling_client, polling_api_url, manager_tx_clone, poller_rx).await;
             if let Err(e) = result {
-                eprintln!("❌ FATAL THREAD ERROR: Polling Client failed: {}", e);
-                std::process::exit(1);
+                shutdown::fatal("Polling client", &e, exit_codes::THREAD_FAILURE);
             }
         });
     }
 
-    // To keep the application running until externally stopped:
-    loop {
-        thread::sleep(Duration::from_secs(10));
+    let mut control_socket = control::socket_path(&cli);
+    if let Some(socket_path) = &control_socket {
+        match control::spawn_control_server(socket_path, manager_tx.clone(), submitter_tx.clone()) {
+            Ok(()) => cprintln!("🎛️ Control socket listening on {}.", socket_path),
+            // Only an explicit --control-socket is required; the default one is a convenience.
+            Err(e) if cli.control_socket.is_none() => {
+                ceprintln!("⚠️ {} Continuing without a control socket.", e);
+                control_socket = None;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if let Some(port) = cli.control_port {
+        let bound = rest_api::spawn_rest_server(std::net::SocketAddr::new(cli.control_bind, port), cli.control_token.clone(), manager_tx.clone(), submitter_tx.clone())?;
+        cprintln!("🎛️ REST control API listening on http://{}.", bound);
+    }
+
+    if let Some(path) = &cli.telemetry_file {
+        telemetry::spawn_telemetry(path, std::time::Duration::from_secs(cli.telemetry_interval), manager_tx.clone())?;
+        cprintln!("📝 Appending telemetry to {} every {}s.", path, cli.telemetry_interval);
+    }
+
+    if let Some(Commands::Soak { hours, check_interval, .. }) = &cli.command {
+        soak::spawn_checker(Duration::from_secs_f64(hours * 3600.0), Duration::from_secs(*check_interval), manager_tx.clone(), submitter_tx.clone());
+    }
+
+    // Under systemd with Type=notify, report readiness once the worker threads answer.
+    watchdog::spawn_ready_notifier(&manager_tx, &submitter_tx);
+
+    // Under systemd with WatchdogSec=, ping only while the worker threads are responsive.
+    let poller_enabled = sources.contains(&challenge_sources::ChallengeSource::Api);
+    if let Some(timeout) = watchdog::spawn_systemd_watchdog(manager_tx.clone(), submitter_tx.clone(), poller_enabled) {
+        cprintln!("🐶 systemd watchdog enabled (WatchdogSec={}s).", timeout.as_secs());
+    }
+
+    // Ctrl-C / SIGTERM trigger a graceful shutdown through the manager.
+    let started_at = std::time::Instant::now();
+    shutdown::spawn_signal_handler(manager_tx.clone());
+
+    // Keep the application running until the submitter has flushed state and exited.
+    if submitter_handle.join().is_err() {
+        return Err(ExitError::new(exit_codes::THREAD_FAILURE, "Submitter thread panicked during shutdown."));
+    }
+    if let Some(socket_path) = &control_socket {
+        let _ = std::fs::remove_file(socket_path);
     }
+
+    if let Some((code, errors)) = shutdown::fatal_status() {
+        cprintln!("\n==============================================");
+        cprintln!("💥 Shadow Harvester stopped after {} fatal error(s). Uptime: {}", errors.len(), utils::format_duration(started_at.elapsed().as_secs_f64()));
+        for error in &errors {
+            cprintln!("  - {}", error);
+        }
+        cprintln!("Exit code: {}", code);
+        cprintln!("==============================================");
+        console::flush();
+        std::process::exit(code);
+    }
+
+    cprintln!("\n==============================================");
+    cprintln!("👋 {}", i18n::trf(i18n::Msg::StoppedCleanly, &[&utils::format_duration(started_at.elapsed().as_secs_f64())]));
+    cprintln!("==============================================");
+    Ok(())
 }
 
 fn main() {
-    // 1. Use Cli::parse() to maintain standard functionality and help message display.
-    let cli = Cli::parse();
+    dispatch();
+    // All output goes through the console printer thread; let it drain before exiting.
+    console::flush();
+}
+
+fn dispatch() {
+    // 1. Parse through ArgMatches (same help and errors as Cli::parse()) to see where values came from.
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli.data_dir_explicit = matches.value_source("data_dir") == Some(clap::parser::ValueSource::CommandLine);
+    console::set_plain_progress(cli.no_progress);
+    console::set_json_log(cli.log_format == cli::LogFormat::Json);
+    i18n::init(cli.lang);
+    shadow_harvester_lib::set_difficulty_rule(cli.difficulty_rule);
+    cardano::set_network_id(cli.network.network_id());
+    if let Some(base_path) = &cli.api_base_path {
+        api::set_api_base_path(base_path);
+    }
+    api::set_http_timeout(cli.http_timeout);
+    api::set_retry_policy(cli.max_retries, cli.retry_max_backoff);
+    if !(cli.max_rps.is_finite() && cli.max_rps >= 0.0) {
+        ceprintln!("❌ FATAL ERROR: --max-rps must be 0 (unlimited) or a positive number.");
+        console::flush();
+        std::process::exit(exit_codes::USAGE_ERROR);
+    }
+    api::set_max_rps(cli.max_rps);
+    if let Err(e) = api::set_tls_config(cli.tls_ca_cert.as_deref(), cli.tls_client_cert.as_deref(), cli.tls_client_key.as_deref()) {
+        ceprintln!("❌ FATAL ERROR: {}", e);
+        console::flush();
+        std::process::exit(exit_codes::USAGE_ERROR);
+    }
+    if let Err(e) = logging::init(cli.log_level.as_deref()) {
+        ceprintln!("❌ FATAL ERROR: {}", e);
+        console::flush();
+        std::process::exit(exit_codes::USAGE_ERROR);
+    }
+
+    if let Err(e) = secrets::unlock(&mut cli) {
+        ceprintln!("❌ FATAL ERROR: {}", e);
+        console::flush();
+        std::process::exit(exit_codes::INVALID_KEY_MATERIAL);
+    }
 
     if let Some(port) = cli.mock_api_port {
-        if cli.api_url.is_some() {
-             eprintln!("⚠️ WARNING: --api-url is set but mock server is running. Ensure --api-url is set to http://127.0.0.1:{} for testing or unset it.", port);
-        }
-        mock_api::start_mock_server_thread(port);
+        mock_api::start_mock_server_thread(port, mock_api::MockConfig::default());
         // Add a short delay to ensure the server starts listening before the client attempts a connection
         thread::sleep(Duration::from_millis(100));
     }
@@ -142,36 +305,126 @@ fn main() {
     // 2. Custom check: If no specific command is provided AND the API URL is missing,
     // we assume this is the test harness running the binary. Exit cleanly to prevent the crash.
     if cli.command.is_none() && cli.api_url.is_none() && !cli.websocket && cli.mock_api_port.is_none() {
-        eprintln!("❌ FATAL ERROR: must pass --api-url or --websocket or a CLI command");
-        std::process::exit(1);
+        ceprintln!("❌ FATAL ERROR: must pass --api-url or --websocket or a CLI command");
+        console::flush();
+        std::process::exit(exit_codes::USAGE_ERROR);
     }
 
     // 3. Handle Synchronous Commands (Migration, List, Import, Info, Db)
     if let Some(command) = cli.command.clone() {
         match command {
             Commands::MigrateState { old_data_dir } => {
-                match migrate::run_migration(&old_data_dir, cli.data_dir.as_deref().unwrap_or("state")) {
-                    Ok(_) => println!("\n✅ State migration complete. Exiting."),
+                let result = utils::db_encryption(&cli)
+                    .exit_code(exit_codes::DATABASE_ERROR)
+                    .and_then(|encryption| migrate::run_migration(&old_data_dir, cli.data_dir.as_deref().unwrap_or("state"), encryption.as_ref()));
+                match result {
+                    Ok(_) => cprintln!("\n✅ State migration complete. Exiting."),
                     Err(e) => {
-                        eprintln!("\n❌ FATAL MIGRATION ERROR: {}", e);
-                        std::process::exit(1);
+                        ceprintln!("\n❌ FATAL MIGRATION ERROR: {}", e);
+                        exit_codes::exit_for_error(&e);
                     }
                 }
                 return;
             }
 
-            Commands::Challenge(_) | Commands::Wallet(_) | Commands::Db(_) => {
+            Commands::Challenge(_) | Commands::Wallet(_) | Commands::Db(_) | Commands::History(_) | Commands::Report(_) | Commands::MigrateExport { .. } => {
                 // The actual command data (ChallengeCommands, WalletCommands, or DbCommands) is handled internally by cli_commands::handle_sync_commands.
                 match cli_commands::handle_sync_commands(&cli) {
-                    Ok(_) => println!("\n✅ Command completed successfully."),
+                    Ok(_) => cprintln!("\n✅ Command completed successfully."),
+                    Err(e) => {
+                         ceprintln!("\n❌ FATAL COMMAND ERROR: {}", e);
+                        exit_codes::exit_for_error(&e);
+                    }
+                }
+                return;
+            }
+
+            Commands::Api(api_command) => {
+                if let Err(e) = api_explorer::run_api_command(&cli, &api_command) {
+                    ceprintln!("\n❌ {}", e);
+                    exit_codes::exit_for_error(&e);
+                }
+                return;
+            }
+
+            Commands::Rom(rom_command) => {
+                shadow_harvester_lib::set_low_memory(cli.low_memory);
+                if let Err(e) = rom_tools::run_rom_command(&cli, &rom_command) {
+                    ceprintln!("\n❌ {}", e);
+                    console::flush();
+                    std::process::exit(exit_codes::GENERAL_FAILURE);
+                }
+                return;
+            }
+
+            Commands::Keyring(keyring_command) => {
+                if let Err(e) = secrets::run_keyring_command(&keyring_command) {
+                    ceprintln!("\n❌ {}", e);
+                    console::flush();
+                    std::process::exit(exit_codes::GENERAL_FAILURE);
+                }
+                return;
+            }
+
+            Commands::Shell => {
+                let result = control::socket_path(&cli)
+                    .ok_or_else(|| "No control socket to connect to: pass --control-socket instead of --no-control-socket.".to_string())
+                    .and_then(|socket_path| shell::run_shell(&socket_path));
+                if let Err(e) = result {
+                    ceprintln!("\n❌ {}", e);
+                    console::flush();
+                    std::process::exit(exit_codes::GENERAL_FAILURE);
+                }
+                return;
+            }
+
+            Commands::Selftest => {
+                match selftest::run_selftest() {
+                    Ok(_) => cprintln!("\n✅ Self-test passed."),
                     Err(e) => {
-                         eprintln!("\n❌ FATAL COMMAND ERROR: {}", e);
-                        std::process::exit(1);
+                        ceprintln!("\n❌ {}", e);
+                        console::flush();
+                        std::process::exit(exit_codes::GENERAL_FAILURE);
                     }
                 }
                 return;
             }
 
+            Commands::Examples => {
+                cprintln!("{}", cli::EXAMPLES);
+                return;
+            }
+
+            Commands::Bench { command, seconds, threads } => {
+                shadow_harvester_lib::set_cpu_limit(cli.cpu_limit);
+                shadow_harvester_lib::set_background_priority(cli.background);
+                shadow_harvester_lib::set_huge_pages(cli.huge_pages);
+                shadow_harvester_lib::set_low_memory(cli.low_memory);
+                let result = utils::rom_overrides(&cli).and_then(|overrides| match command {
+                    None => bench::run_bench(seconds, threads.unwrap_or(cli.threads), overrides.resolve(None)),
+                    Some(cli::BenchCommands::Compare { config_a, config_b, duration, trials }) => {
+                        let config_a = bench::BenchConfig::load(&config_a)?;
+                        let config_b = bench::BenchConfig::load(&config_b)?;
+                        bench::run_compare(config_a, config_b, duration, trials, overrides.resolve(None))
+                    }
+                });
+                if let Err(e) = result {
+                    ceprintln!("\n❌ FATAL BENCHMARK ERROR: {}", e);
+                    console::flush();
+                    std::process::exit(exit_codes::GENERAL_FAILURE);
+                }
+                return;
+            }
+
+            // Soak runs the normal mining loop below, against its own mock API
+            Commands::Soak { .. } => {
+                if let Err(e) = soak::prepare(&mut cli) {
+                    ceprintln!("\n❌ {}", e);
+                    console::flush();
+                    std::process::exit(exit_codes::USAGE_ERROR);
+                }
+            }
+
             // Pass the API-based 'Challenges' command to setup_app, which handles it before run_app
             Commands::Challenges => {},
         }
@@ -181,9 +434,9 @@ fn main() {
         Ok(_) => {},
         Err(e) => {
             // FIX: Ensure all setup errors are printed here before final exit
-            if e != "COMMAND EXECUTED" {
-                eprintln!("FATAL ERROR: {}", e);
-                std::process::exit(1);
+            if e.message != "COMMAND EXECUTED" {
+                ceprintln!("FATAL ERROR: {}", e);
+                exit_codes::exit_for_error(&e);
             }
         }
     }
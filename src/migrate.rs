@@ -1,16 +1,17 @@
 // src/migrate.rs
 
 use crate::persistence::Persistence;
-use crate::data_types::{FILE_NAME_RECEIPT, FILE_NAME_CHALLENGE, ChallengeData, PendingSolution};
-use std::path::{Path, PathBuf};
+use crate::utils;
+use crate::exit_codes::{self, ExitError, WithExitCode};
+use crate::data_types::{normalize_challenge_id, FILE_NAME_RECEIPT, FILE_NAME_CHALLENGE, ChallengeData, PendingSolution};
+use crate::schema::{PendingKey, ReceiptKey, StateSchema};
+use crate::state_store::StateStore;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use std::fs;
 use serde_json::Value; // Needed to parse receipt JSON
+use crate::schema::{SLED_KEY_CHALLENGE, SLED_KEY_MNEMONIC_INDEX, SLED_KEY_PENDING, SLED_KEY_RECEIPT};
 
-// Key prefixes for SLED to organize data
-const SLED_KEY_RECEIPT: &str = "receipt";
-const SLED_KEY_CHALLENGE: &str = "challenge";
-const SLED_KEY_PENDING: &str = "pending";
-const SLED_KEY_MNEMONIC_INDEX: &str = "mnemonic_index"; // Key for mnemonic index state
 const NONCE_HEX_LENGTH: usize = 16; // 64 bits = 16 hex characters
 
 /// Constructs the unique key used to store a receipt in Sled.
@@ -90,7 +91,7 @@ fn store_mnemonic_path_info(path: &Path, persistence: &Persistence, receipt_cont
     // Check if the key already exists before inserting (to prevent duplicates across challenges)
     if persistence.get(&key)?.is_none() {
          persistence.set(&key, &address_value)?;
-         println!("    -> Saved new wallet state: {}:{}:{} -> {}", mnemonic_hash, account_index, deriv_index, address_value);
+         cprintln!("    -> Saved new wallet state: {}:{}:{} -> {}", mnemonic_hash, account_index, deriv_index, address_value);
     }
     // If it exists, we just skip it silently, as requested.
 
@@ -98,9 +99,17 @@ fn store_mnemonic_path_info(path: &Path, persistence: &Persistence, receipt_cont
 }
 
 
+/// True if `path` lies in the `mnemonic` mode folder of the challenge directory `challenge_path`.
+fn is_mnemonic_receipt(path: &Path, challenge_path: &Path) -> bool {
+    path.strip_prefix(challenge_path).ok()
+        .and_then(|relative| relative.components().next())
+        == Some(Component::Normal("mnemonic".as_ref()))
+}
+
 // Recursive helper to find and migrate receipt.json files
 fn migrate_receipts_recursively(
     path: &Path,
+    challenge_path: &Path,
     challenge_id: &str,
     persistence: &Persistence,
     total_receipts: &mut u32
@@ -110,7 +119,14 @@ fn migrate_receipts_recursively(
             // Found a receipt file.
             let address_identifier = path.parent().and_then(|p| p.file_name()).and_then(|s| s.to_str());
 
-            if let Some(address) = address_identifier {
+            // Mnemonic receipts sit under .../<hash>/<account>/<index>/, so their address comes from the preimage.
+            let address_identifier = if is_mnemonic_receipt(path, challenge_path) {
+                fs::read_to_string(path).ok().and_then(|content| extract_address_from_preimage(&content).ok())
+            } else {
+                address_identifier.map(str::to_string)
+            };
+
+            if let Some(address) = address_identifier.as_deref() {
                 // Attempt to read the file content
                 if let Ok(receipt_content) = fs::read_to_string(path) {
 
@@ -122,7 +138,7 @@ fn migrate_receipts_recursively(
                         *total_receipts += 1;
 
                         // Check if this receipt is from the mnemonic path for further state storage
-                        if path.to_string_lossy().contains("/mnemonic/") {
+                        if is_mnemonic_receipt(path, challenge_path) {
                             // If the logic fails inside, it will be skipped silently (as requested).
                             let _ = store_mnemonic_path_info(path, persistence, &receipt_content);
                         }
@@ -140,9 +156,9 @@ fn migrate_receipts_recursively(
             Ok(read_dir) => {
                 for entry in read_dir.filter_map(|e| e.ok()) {
                     // Recurse into subdirectories (necessary for the nested Mnemonic path structure)
-                    if let Err(e) = migrate_receipts_recursively(&entry.path(), challenge_id, persistence, total_receipts) {
+                    if let Err(e) = migrate_receipts_recursively(&entry.path(), challenge_path, challenge_id, persistence, total_receipts) {
                         // Only return error if the recursive call failed with an unexpected error
-                        eprintln!("⚠️ Warning: Recursive migration failure: {}", e);
+                        ceprintln!("⚠️ Warning: Recursive migration failure: {}", e);
                     }
                 }
             }
@@ -189,17 +205,19 @@ fn migrate_pending_submissions(old_data_dir: &str, persistence: &Persistence) ->
 
 
 /// Runs the state migration from the old file-based structure to the new Sled database.
-pub fn run_migration(old_data_dir: &str, new_data_dir: &str) -> Result<(), String> {
-    println!("\n==============================================");
-    println!("⚙️ Starting state migration...");
-    println!("  Source (File System): {}", old_data_dir);
-    println!("  Destination (Sled DB): {}", new_data_dir);
-    println!("==============================================");
+pub fn run_migration(old_data_dir: &str, new_data_dir: &str, db_encryption: Option<&utils::DbEncryption>) -> Result<(), ExitError> {
+    cprintln!("\n==============================================");
+    cprintln!("⚙️ Starting state migration...");
+    cprintln!("  Source (File System): {}", old_data_dir);
+    cprintln!("  Destination (Sled DB): {}", new_data_dir);
+    cprintln!("==============================================");
 
     // 1. Initialize SLED DB
     let sled_path = PathBuf::from(new_data_dir).join("state.sled"); // Using hardcoded sled filename
     let persistence = Persistence::open(&sled_path)
-        .map_err(|e| format!("FATAL: Could not initialize Sled DB at {:?}: {}", sled_path, e))?;
+        .map_err(|e| format!("FATAL: Could not initialize Sled DB at {:?}: {}", sled_path, e))
+        .exit_code(exit_codes::DATABASE_ERROR)?;
+    let persistence = utils::apply_db_encryption(persistence, db_encryption)?;
 
     let old_base_path = Path::new(old_data_dir);
 
@@ -217,12 +235,11 @@ pub fn run_migration(old_data_dir: &str, new_data_dir: &str) -> Result<(), Strin
 
         // Store CHALLENGE.JSON
         let challenge_file_path = challenge_path.join(FILE_NAME_CHALLENGE);
-        if let Ok(content) = fs::read_to_string(&challenge_file_path) {
-            if let Ok(data) = serde_json::from_str::<ChallengeData>(&content) {
-                let key = format!("{}:{}", SLED_KEY_CHALLENGE, data.challenge_id);
-                persistence.set(&key, &content)?;
-                println!("  [Challenge] Saved challenge data for: {}", challenge_id);
-            }
+        if let Ok(content) = fs::read_to_string(&challenge_file_path)
+            && let Ok(data) = serde_json::from_str::<ChallengeData>(&content) {
+            let key = format!("{}:{}", SLED_KEY_CHALLENGE, data.challenge_id);
+            persistence.set(&key, &content)?;
+            cprintln!("  [Challenge] Saved challenge data for: {}", challenge_id);
         }
 
         // Recursively find and store all receipts
@@ -233,13 +250,13 @@ pub fn run_migration(old_data_dir: &str, new_data_dir: &str) -> Result<(), Strin
             match fs::read_dir(&mode_path) {
                 Ok(read_dir) => {
                     for receipt_result in read_dir.filter_map(|e| e.ok()) {
-                        if let Err(e) = migrate_receipts_recursively(&receipt_result.path(), &challenge_id, &persistence, &mut total_receipts) {
-                            eprintln!("⚠️ Warning: Failed processing path {}: {}", receipt_result.path().display(), e);
+                        if let Err(e) = migrate_receipts_recursively(&receipt_result.path(), &challenge_path, &challenge_id, &persistence, &mut total_receipts) {
+                            ceprintln!("⚠️ Warning: Failed processing path {}: {}", receipt_result.path().display(), e);
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("⚠️ Warning: Failed reading mode directory {}: {}", mode_path.display(), e);
+                    ceprintln!("⚠️ Warning: Failed reading mode directory {}: {}", mode_path.display(), e);
                 }
             }
         }
@@ -251,9 +268,73 @@ pub fn run_migration(old_data_dir: &str, new_data_dir: &str) -> Result<(), Strin
     // 6. Close DB and finalize
     persistence.close().map_err(|e| format!("Failed to close Sled DB: {}", e))?;
 
-    println!("\n✅ Migration SUCCESSFUL.");
-    println!("  Total challenge/receipts migrated: {}", total_receipts);
-    println!("  Total pending solutions migrated: {}", total_pending);
+    cprintln!("\n✅ Migration SUCCESSFUL.");
+    cprintln!("  Total challenge/receipts migrated: {}", total_receipts);
+    cprintln!("  Total pending solutions migrated: {}", total_pending);
+
+    Ok(())
+}
+
+/// Writes `contents` to `path`, creating its parent directories.
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Writes challenges, receipts and pending solutions into `out_dir` in the file-based layout
+/// `run_migration` reads:
+///
+/// - `<CHALLENGE_ID>/challenge.json`
+/// - `<CHALLENGE_ID>/mnemonic/<HASH>/<ACCOUNT>/<INDEX>/receipt.json` for addresses in the
+///   mnemonic index, `<CHALLENGE_ID>/persistent/<ADDRESS>/receipt.json` for all others
+/// - `pending_submissions/<ADDRESS>_<CHALLENGE_ID>_<NONCE>.json`
+pub fn run_export(persistence: &StateStore, out_dir: &str) -> Result<(), String> {
+    let base = Path::new(out_dir);
+    if base.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("Output directory {} is not empty.", out_dir));
+    }
+
+    cprintln!("\n==============================================");
+    cprintln!("⚙️ Exporting state to the file-based layout...");
+    cprintln!("  Destination (File System): {}", out_dir);
+    cprintln!("==============================================");
+
+    // Values are written as stored, so the files match what the miner saved.
+    let mut challenges = 0;
+    for (key, json) in persistence.scan(&format!("{}:", SLED_KEY_CHALLENGE))? {
+        let Some(challenge_id) = key.strip_prefix(&format!("{}:", SLED_KEY_CHALLENGE)).map(str::to_string) else { continue };
+        write_file(&base.join(normalize_challenge_id(&challenge_id).as_ref()).join(FILE_NAME_CHALLENGE), &json)?;
+        challenges += 1;
+    }
+
+    let mnemonic_paths: HashMap<String, PathBuf> = persistence.iter_mnemonic_indices(None)?
+        .into_iter()
+        .map(|(key, address)| (address, ["mnemonic".to_string(), key.wallet_hash, key.account.to_string(), key.index.to_string()].iter().collect()))
+        .collect();
+    let mut receipts = 0;
+    for (key, json) in persistence.scan(&ReceiptKey::prefix(None))? {
+        let Some(key) = ReceiptKey::parse(&key) else { continue };
+        let receipt_dir = mnemonic_paths.get(&key.address)
+            .cloned()
+            .unwrap_or_else(|| Path::new("persistent").join(&key.address));
+        write_file(&base.join(normalize_challenge_id(&key.challenge_id).as_ref()).join(receipt_dir).join(FILE_NAME_RECEIPT), &json)?;
+        receipts += 1;
+    }
+
+    let mut pending = 0;
+    for (key, json) in persistence.scan(&PendingKey::prefix(None, None))? {
+        let Some(key) = PendingKey::parse(&key) else { continue };
+        let file_name = format!("{}_{}_{}.json", key.address, normalize_challenge_id(&key.challenge_id), key.nonce);
+        write_file(&base.join("pending_submissions").join(file_name), &json)?;
+        pending += 1;
+    }
+
+    cprintln!("\n✅ Export SUCCESSFUL.");
+    cprintln!("  Challenges exported: {}", challenges);
+    cprintln!("  Receipts exported: {}", receipts);
+    cprintln!("  Pending solutions exported: {}", pending);
 
     Ok(())
 }
@@ -1,26 +1,41 @@
 // src/mining.rs
 
 use crate::api;
-use crate::data_types::{DataDir, DataDirMnemonic, MiningContext, MiningResult, ChallengeData, PendingSolution, FILE_NAME_FOUND_SOLUTION, is_solution_pending_in_queue, FILE_NAME_RECEIPT, ManagerCommand};
+use crate::data_types::{DataDir, DataDirMnemonic, MiningContext, MiningResult, ChallengeData, PendingSolution, FILE_NAME_FOUND_SOLUTION, is_solution_pending_in_queue, FILE_NAME_RECEIPT, ManagerCommand, SubmitterCommand};
 use crate::cli::Cli;
 use crate::cardano;
 use crate::utils::{self, next_wallet_deriv_index_for_challenge, print_mining_setup, print_statistics, receipt_exists_for_index, run_single_mining_cycle};
+use crate::watchdog::{self, Component};
 use std::fs;
 use std::sync::mpsc::Sender;
-use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use serde_json;
 use hex;
+use tracing::{error, info, info_span, warn};
 
 // FIX: Import core logic components from the library crate root
 use shadow_harvester_lib::{
     build_preimage,
-    ChallengeParams,
+    NonceAllocator,
+    NONCE_BLOCK_SIZE,
     Result as MinerResult,
     spin,
-    Rom,
-    RomGenerationType
+    Rom, RomStore, ChallengeHasher,
+    RomParams,
 };
 
+/// Sub-directory of the data dir holding cached ROM files.
+const ROM_CACHE_DIR: &str = "roms";
+/// Process-wide: --rom-cache-max, the number of cached ROM files kept on disk.
+static ROM_CACHE_MAX: AtomicUsize = AtomicUsize::new(2);
+/// Chunk size used for ROM integrity checksums.
+const ROM_CHECK_CHUNK_SIZE: usize = 1024 * 1024;
+/// Number of random chunks re-hashed on each ROM integrity check.
+const ROM_CHECK_SAMPLES: usize = 16;
+/// How often the nonce cursor is flushed to the state worker while mining.
+const NONCE_CURSOR_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
 // ===============================================
 // SOLUTION RECOVERY FUNCTION
 // ===============================================
@@ -33,7 +48,7 @@ fn check_for_unsubmitted_solutions(base_dir: &str, challenge_id: &str, mining_ad
     path.push(FILE_NAME_FOUND_SOLUTION);
 
     if path.exists() {
-        println!("\n⚠️ Recovery file detected at {:?}. Recovering solution...", path);
+        info!("⚠️ Recovery file detected at {:?}. Recovering solution...", path);
 
         let solution_json = fs::read_to_string(&path)
             .map_err(|e| format!("Failed to read recovery file {:?}: {}", path, e))?;
@@ -48,9 +63,9 @@ fn check_for_unsubmitted_solutions(base_dir: &str, challenge_id: &str, mining_ad
 
         // 2. Delete the recovery file
         if let Err(e) = fs::remove_file(&path) {
-            eprintln!("WARNING: Successfully queued recovered solution but FAILED TO DELETE RECOVERY FILE {:?}: {}", path, e);
+            warn!("WARNING: Successfully queued recovered solution but FAILED TO DELETE RECOVERY FILE {:?}: {}", path, e);
         } else {
-            println!("✅ Successfully recovered and queued solution for address {} / challenge {}.", mining_address, challenge_id);
+            info!("✅ Successfully recovered and queued solution for address {} / challenge {}.", mining_address, challenge_id);
         }
     }
     Ok(())
@@ -70,22 +85,24 @@ pub fn run_persistent_key_mining(context: MiningContext, skey_hex: &String) -> R
     let reg_message = context.tc_response.message.clone();
     let data_dir = DataDir::Persistent(&mining_address);
 
-    println!("\n[REGISTRATION] Attempting initial registration for address: {}", mining_address);
+    info!("[REGISTRATION] Attempting initial registration for address: {}", mining_address);
     let reg_signature = cardano::cip8_sign(&key_pair, &reg_message);
-    if let Err(e) = api::register_address(
+    if let Err(e) = api::block_on(api::register_address(
         &context.client, &context.api_url, &mining_address, &context.tc_response.message, &reg_signature.0, &hex::encode(key_pair.1.as_ref()),
-    ) {
-        eprintln!("Address registration failed: {}. Cannot start mining.", e);
+    )) && !e.is_dry_run() {
+        warn!("Address registration failed: {}. Cannot start mining.", e);
         return Err("Address registration failed.".to_string());
     }
 
-    println!("\n==============================================");
-    println!("⛏️  Shadow Harvester: PERSISTENT KEY MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
-    println!("==============================================");
-    if context.donate_to_option.is_some() { println!("Donation Target: {}", context.donate_to_option.as_ref().unwrap()); }
+    cprintln!("\n==============================================");
+    cprintln!("⛏️  Shadow Harvester: PERSISTENT KEY MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
+    cprintln!("==============================================");
+    if let Some(donate_to) = context.donate_to_option.as_ref() { cprintln!("Donation Target: {}", donate_to); }
 
     let mut current_challenge_id = String::new();
     let mut last_active_challenge_data: Option<ChallengeData> = None;
+    // Reused across cycles while the challenge's ROM key is unchanged.
+    let mut rom_store = RomStore::new(context.rom_store_size);
     loop {
         // FIX: Use .as_ref() to convert Option<String> to Option<&String>
         let challenge_params = match utils::get_challenge_params(&context.client, &context.api_url, context.cli_challenge.as_ref(), &mut current_challenge_id) {
@@ -97,12 +114,12 @@ pub fn run_persistent_key_mining(context: MiningContext, skey_hex: &String) -> R
             Err(e) => {
                 // If a challenge ID is set AND we detect a network failure, continue mining.
                 if !current_challenge_id.is_empty() && e.contains("API request failed") {
-                    eprintln!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
+                    warn!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
                     last_active_challenge_data.as_ref().cloned().ok_or_else(|| {
                         format!("FATAL LOGIC ERROR: Challenge ID {} is set but no previous challenge data was stored.", current_challenge_id)
                     })?
                 } else {
-                    eprintln!("⚠️ Critical API Error during challenge check: {}. Retrying in 1 minute...", e);
+                    warn!("⚠️ Critical API Error during challenge check: {}. Retrying in 1 minute...", e);
                     std::thread::sleep(std::time::Duration::from_secs(60));
                     continue;
                 }
@@ -120,6 +137,7 @@ pub fn run_persistent_key_mining(context: MiningContext, skey_hex: &String) -> R
         print_mining_setup(&context.api_url, Some(mining_address.as_str()), context.threads, &challenge_params);
 
         loop {
+            let (rom, _) = rom_for_challenge(&mut rom_store, &challenge_params, &context.rom_overrides.resolve(Some(&challenge_params)), context.rom_cache_dir.as_deref());
             // UPDATED CALL: Removed client and api_url
             // FIX: Use .as_ref() and .as_deref() for Option<&String> and Option<&str>
             let (result, total_hashes, elapsed_secs) = run_single_mining_cycle(
@@ -127,6 +145,7 @@ pub fn run_persistent_key_mining(context: MiningContext, skey_hex: &String) -> R
                 context.threads,
                 context.donate_to_option.as_ref(), // Option<String> to Option<&String>
                 &challenge_params,
+                rom,
                 context.data_dir.as_deref(), // Option<String> to Option<&str>
             );
             final_hashes = total_hashes; final_elapsed = elapsed_secs;
@@ -138,43 +157,53 @@ pub fn run_persistent_key_mining(context: MiningContext, skey_hex: &String) -> R
                         let donation_signature = cardano::cip8_sign(&key_pair, &donation_message);
 
                         // Intentionally perform donation attempt synchronously here.
-                        match api::donate_to(
+                        match api::block_on(api::donate_to(
                             &context.client, &context.api_url, &mining_address, destination_address, &donation_signature.0,
-                        ) {
-                            Ok(id) => println!("🚀 Donation initiated successfully. ID: {}", id),
-                            Err(e) => eprintln!("⚠️ Donation failed (synchronous attempt): {}", e),
+                        )) {
+                            Ok(id) => info!("🚀 Donation initiated successfully. ID: {}", id),
+                            Err(e) if e.is_dry_run() => info!("🧪 Dry run: donation not sent."),
+                            Err(e) => warn!("⚠️ Donation failed (synchronous attempt): {}", e),
                         }
                     }
 
-                    println!("\n✅ Solution queued. Checking for new challenge/expiration.");
+                    info!("✅ Solution queued. Checking for new challenge/expiration.");
                     break; // Break the inner loop to re-poll the challenge API.
                 },
                 MiningResult::AlreadySolved => {
-                    println!("\n✅ Challenge already solved on network. Stopping current mining.");
+                    info!("✅ Challenge already solved on network. Stopping current mining.");
                     // Solution saved by submitter/already exists, so check for a new challenge.
                     break;
                 }
+                MiningResult::SpaceExhausted => {
+                    // Mining the same address again would check the same nonces.
+                    if context.cli_challenge.is_some() {
+                        info!("🔚 Search space exhausted for the fixed challenge. Stopping.");
+                        return Ok(());
+                    }
+                    info!("🔚 Search space exhausted. Waiting for a new challenge...");
+                    break;
+                }
                 MiningResult::MiningFailed => {
-                    eprintln!("\n⚠️ Mining cycle failed. Checking if challenge is still valid before retrying...");
+                    warn!("⚠️ Mining cycle failed. Checking if challenge is still valid before retrying...");
                     if context.cli_challenge.is_none() {
-                        match api::get_active_challenge_data(&context.client,&context.api_url) {
+                        match api::block_on(api::get_active_challenge_data(&context.client,&context.api_url)) {
                             Ok(active_params) if active_params.challenge_id == current_challenge_id => {
-                                eprintln!("Challenge is still valid. Retrying mining cycle in 1 minute...");
+                                warn!("Challenge is still valid. Retrying mining cycle in 1 minute...");
                                 std::thread::sleep(std::time::Duration::from_secs(60));
                             },
                             Ok(_) | Err(_) => {
-                                eprintln!("Challenge appears to have changed or API is unreachable. Stopping current mining and checking for new challenge...");
+                                warn!("Challenge appears to have changed or API is unreachable. Stopping current mining and checking for new challenge...");
                                 break;
                             }
                         }
                     } else {
-                        eprintln!("Fixed challenge. Retrying mining cycle in 1 minute...");
+                        warn!("Fixed challenge. Retrying mining cycle in 1 minute...");
                         std::thread::sleep(std::time::Duration::from_secs(60));
                     }
                 }
             }
         }
-        let stats_result = api::fetch_statistics(&context.client, &context.api_url, &mining_address);
+        let stats_result = api::block_on(api::fetch_statistics(&context.client, &context.api_url, &mining_address)).map_err(String::from);
         print_statistics(stats_result, final_hashes, final_elapsed);
     }
 }
@@ -186,16 +215,18 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
     let mut wallet_deriv_index: u32 = 0;
     let mut first_run = true;
     let mut max_registered_index = None;
-    let mut backoff_challenge = crate::backoff::Backoff::new(5, 300, 2.0);
-    let mut backoff_reg = crate::backoff::Backoff::new(5, 300, 2.0);
+    let mut backoff_challenge = crate::backoff::Backoff::new(5, api::retry_max_backoff_secs(), 2.0);
+    let mut backoff_reg = crate::backoff::Backoff::new(5, api::retry_max_backoff_secs(), 2.0);
     let mut last_seen_challenge_id = String::new();
     let mut current_challenge_id = String::new();
     let mut last_active_challenge_data: Option<ChallengeData> = None;
+    // Reused across cycles while the challenge's ROM key is unchanged.
+    let mut rom_store = RomStore::new(context.rom_store_size);
 
-    println!("\n==============================================");
-    println!("⛏️  Shadow Harvester: MNEMONIC SEQUENTIAL MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
-    println!("==============================================");
-    if context.donate_to_option.is_some() { println!("Donation Target: {}", context.donate_to_option.as_ref().unwrap()); }
+    cprintln!("\n==============================================");
+    cprintln!("⛏️  Shadow Harvester: MNEMONIC SEQUENTIAL MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
+    cprintln!("==============================================");
+    if let Some(donate_to) = context.donate_to_option.as_ref() { cprintln!("Donation Target: {}", donate_to); }
 
     loop {
         // --- 1. Challenge Discovery and Initial Index Reset ---
@@ -225,13 +256,13 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
             Err(e) => {
                 // If a challenge ID is set AND we detect a network failure, continue mining.
                 if !current_challenge_id.is_empty() && e.contains("API request failed") {
-                    eprintln!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
+                    warn!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
                     backoff_challenge.reset();
                     last_active_challenge_data.as_ref().cloned().ok_or_else(|| {
                         format!("FATAL LOGIC ERROR: Challenge ID {} is set but no previous challenge data was stored.", current_challenge_id)
                     })?
                 } else {
-                    eprintln!("⚠️ Critical API Error during challenge polling: {}. Retrying with exponential backoff...", e);
+                    warn!("⚠️ Critical API Error during challenge polling: {}. Retrying with exponential backoff...", e);
                     backoff_challenge.sleep();
                     continue;
                 }
@@ -251,21 +282,20 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
             let data_dir = DataDir::Mnemonic(wallet_config); // Full DataDir for recovery check
 
             // Get the temporary mining address for this index (needed for queue file lookup/recovery)
-            let mining_address_temp = cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_account, wallet_deriv_index).2.to_bech32().unwrap();
+            let mining_address_temp = cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), cli.mnemonic_account, wallet_deriv_index).2.to_bech32().unwrap();
 
             // Check for unsubmitted solutions (recovery file or pending queue)
             // FIX: Use .as_deref() to convert Option<String> to Option<&str>
-            if let Some(base_dir) = context.data_dir.as_deref() {
-                if wallet_deriv_index >= cli.mnemonic_starting_index {
-                    // 1. Check for crash recovery file (found.json)
-                    check_for_unsubmitted_solutions(base_dir, &challenge_params.challenge_id, &mining_address_temp, &data_dir)?;
-
-                    // 2. Check if a solution for this address/challenge is already in the pending queue
-                    if is_solution_pending_in_queue(base_dir, &mining_address_temp, &challenge_params.challenge_id)? {
-                        println!("\nℹ️ Index {} has a pending submission in the queue. Skipping and checking next index.", wallet_deriv_index);
-                        wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
-                        continue 'skip_check;
-                    }
+            if let Some(base_dir) = context.data_dir.as_deref()
+                && wallet_deriv_index >= cli.mnemonic_starting_index {
+                // 1. Check for crash recovery file (found.json)
+                check_for_unsubmitted_solutions(base_dir, &challenge_params.challenge_id, &mining_address_temp, &data_dir)?;
+
+                // 2. Check if a solution for this address/challenge is already in the pending queue
+                if is_solution_pending_in_queue(base_dir, &mining_address_temp, &challenge_params.challenge_id)? {
+                    info!("ℹ️ Index {} has a pending submission in the queue. Skipping and checking next index.", wallet_deriv_index);
+                    wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
+                    continue 'skip_check;
                 }
             }
 
@@ -274,7 +304,7 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
             if let Some(base_dir) = context.data_dir.as_deref() {
                 // 1. Check Correct Mnemonic Path (where it should be)
                 if receipt_exists_for_index(base_dir, &challenge_params.challenge_id, &wallet_config)? {
-                    println!("\nℹ️ Index {} already has a local receipt (Mnemonic path). Skipping.", wallet_deriv_index);
+                    info!("ℹ️ Index {} already has a local receipt (Mnemonic path). Skipping.", wallet_deriv_index);
                     wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
                     continue 'skip_check;
                 }
@@ -286,7 +316,7 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
                 persistent_path.push(FILE_NAME_RECEIPT);
 
                 if persistent_path.exists() {
-                    println!("\n⚠️ Index {} found receipt in Persistent path (Submitter heuristic failure). Skipping.", wallet_deriv_index);
+                    info!("⚠️ Index {} found receipt in Persistent path (Submitter heuristic failure). Skipping.", wallet_deriv_index);
                     wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
                     continue 'skip_check;
                 }
@@ -297,18 +327,18 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
         }
 
         // --- 3. Key Generation, Registration, and Mining ---
-        let key_pair = cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_account, wallet_deriv_index);
+        let key_pair = cardano::derive_key_pair_from_mnemonic(&mnemonic_phrase, cli.mnemonic_passphrase.as_deref(), cli.mnemonic_account, wallet_deriv_index);
         let mining_address = key_pair.2.to_bech32().unwrap();
 
-        println!("\n[CYCLE START] Deriving Address Index {}: {}", wallet_deriv_index, mining_address);
+        info!("[CYCLE START] Deriving Address Index {}: {}", wallet_deriv_index, mining_address);
         if match max_registered_index { Some(idx) => wallet_deriv_index > idx, None => true } {
-            let stats_result = api::fetch_statistics(&context.client, &context.api_url, &mining_address);
+            let stats_result = api::block_on(api::fetch_statistics(&context.client, &context.api_url, &mining_address)).map_err(String::from);
             match stats_result {
-                Ok(stats) => { println!("  Crypto Receipts (Solutions): {}", stats.crypto_receipts); println!("  Night Allocation: {}", stats.night_allocation); }
+                Ok(stats) => { info!("  Crypto Receipts (Solutions): {}", stats.crypto_receipts); info!("  Night Allocation: {}", stats.night_allocation); }
                 Err(_) => {
                     let reg_signature = cardano::cip8_sign(&key_pair, &reg_message);
-                    if let Err(e) = api::register_address(&context.client, &context.api_url, &mining_address, &reg_message, &reg_signature.0, &hex::encode(key_pair.1.as_ref())) {
-                        eprintln!("Registration failed: {}. Retrying with exponential backoff...", e); backoff_reg.sleep(); continue;
+                    if let Err(e) = api::block_on(api::register_address(&context.client, &context.api_url, &mining_address, &reg_message, &reg_signature.0, &hex::encode(key_pair.1.as_ref()))) && !e.is_dry_run() {
+                        warn!("Registration failed: {}. Retrying with exponential backoff...", e); backoff_reg.sleep(); continue;
                     }
                 }
             }
@@ -317,6 +347,8 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
 
         print_mining_setup(&context.api_url, Some(mining_address.as_str()), context.threads, &challenge_params);
 
+        let (rom, _) = rom_for_challenge(&mut rom_store, &challenge_params, &context.rom_overrides.resolve(Some(&challenge_params)), context.rom_cache_dir.as_deref());
+
         // UPDATED CALL: Removed client and api_url
         // FIX: Use .as_ref() and .as_deref() for Option<&String> and Option<&str>
         let (result, total_hashes, elapsed_secs) = run_single_mining_cycle(
@@ -324,6 +356,7 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
             context.threads,
             context.donate_to_option.as_ref(), // Option<String> to Option<&String>
             &challenge_params,
+            rom,
             context.data_dir.as_deref(), // Option<String> to Option<&str>
         );
 
@@ -336,27 +369,32 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
                     let donation_signature = cardano::cip8_sign(&key_pair, &donation_message);
 
                     // Attempt donation synchronously. Ignore result here to keep the main flow clean.
-                    match api::donate_to(
+                    match api::block_on(api::donate_to(
                         &context.client, &context.api_url, &mining_address, destination_address, &donation_signature.0,
-                    ) {
-                        Ok(id) => println!("🚀 Donation initiated successfully. ID: {}", id),
-                        Err(e) => eprintln!("⚠️ Donation failed (synchronous attempt): {}", e),
+                    )) {
+                        Ok(id) => info!("🚀 Donation initiated successfully. ID: {}", id),
+                        Err(e) if e.is_dry_run() => info!("🧪 Dry run: donation not sent."),
+                        Err(e) => warn!("⚠️ Donation failed (synchronous attempt): {}", e),
                     }
                 }
 
                 wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
-                println!("\n✅ Solution queued. Incrementing index to {}.", wallet_deriv_index);
+                info!("✅ Solution queued. Incrementing index to {}.", wallet_deriv_index);
             },
             MiningResult::AlreadySolved => {
                 // This scenario means the submitter/API reported it was already solved
                 wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
-                println!("\n✅ Challenge already solved. Incrementing index to {}.", wallet_deriv_index);
+                info!("✅ Challenge already solved. Incrementing index to {}.", wallet_deriv_index);
+            }
+            MiningResult::SpaceExhausted => {
+                wallet_deriv_index = wallet_deriv_index.wrapping_add(1);
+                info!("🔚 Search space exhausted. Incrementing index to {}.", wallet_deriv_index);
             }
             MiningResult::MiningFailed => {
-                eprintln!("\n⚠️ Mining cycle failed. Retrying with the SAME index {}.", wallet_deriv_index);
+                warn!("⚠️ Mining cycle failed. Retrying with the SAME index {}.", wallet_deriv_index);
             }
         }
-        let stats_result = api::fetch_statistics(&context.client, &context.api_url, &mining_address);
+        let stats_result = api::block_on(api::fetch_statistics(&context.client, &context.api_url, &mining_address)).map_err(String::from);
         print_statistics(stats_result, total_hashes, elapsed_secs);
     }
 }
@@ -364,15 +402,17 @@ pub fn run_mnemonic_sequential_mining(cli: &Cli, context: MiningContext, mnemoni
 /// MODE C: Ephemeral Key Per Cycle Mining
 #[allow(unused_assignments)] // Suppress warnings for final_hashes/final_elapsed assignments
 pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
-    println!("\n==============================================");
-    println!("⛏️  Shadow Harvester: EPHEMERAL KEY MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
-    println!("==============================================");
-    if context.donate_to_option.is_some() { println!("Donation Target: {}", context.donate_to_option.as_ref().unwrap()); }
+    cprintln!("\n==============================================");
+    cprintln!("⛏️  Shadow Harvester: EPHEMERAL KEY MINING Mode ({})", if context.cli_challenge.is_some() { "FIXED CHALLENGE" } else { "DYNAMIC POLLING" });
+    cprintln!("==============================================");
+    if let Some(donate_to) = context.donate_to_option.as_ref() { cprintln!("Donation Target: {}", donate_to); }
 
     let mut final_hashes: u64 = 0;
     let mut final_elapsed: f64 = 0.0;
     let mut current_challenge_id = String::new();
     let mut last_active_challenge_data: Option<ChallengeData> = None;
+    // Reused across cycles while the challenge's ROM key is unchanged.
+    let mut rom_store = RomStore::new(context.rom_store_size);
 
     loop {
         // FIX: Use .as_ref() to convert Option<String> to Option<&String>
@@ -385,12 +425,12 @@ pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
             Err(e) => {
                 // If a challenge ID is set AND we detect a network failure, continue mining.
                 if !current_challenge_id.is_empty() && e.contains("API request failed") {
-                    eprintln!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
+                    warn!("⚠️ Challenge API poll failed (Network Error): {}. Continuing mining with previous challenge parameters (ID: {})...", e, current_challenge_id);
                     last_active_challenge_data.as_ref().cloned().ok_or_else(|| {
                         format!("FATAL LOGIC ERROR: Challenge ID {} is set but no previous challenge data was stored.", current_challenge_id)
                     })?
                 } else {
-                    eprintln!("⚠️ Could not fetch active challenge (Ephemeral Key Mode): {}. Retrying in 5 minutes...", e);
+                    warn!("⚠️ Could not fetch active challenge (Ephemeral Key Mode): {}. Retrying in 5 minutes...", e);
                     std::thread::sleep(std::time::Duration::from_secs(5 * 60));
                     continue;
                 }
@@ -403,17 +443,19 @@ pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
 
         // FIX: Use .as_deref() to convert Option<String> to Option<&str>
         if let Some(base_dir) = context.data_dir.as_deref() { data_dir.save_challenge(base_dir, &challenge_params)?; }
-        println!("\n[CYCLE START] Generated Address: {}", generated_mining_address);
+        info!("[CYCLE START] Generated Address: {}", generated_mining_address);
 
         let reg_message = context.tc_response.message.clone();
         let reg_signature = cardano::cip8_sign(&key_pair, &reg_message);
 
-        if let Err(e) = api::register_address(&context.client, &context.api_url, &generated_mining_address, &context.tc_response.message, &reg_signature.0, &hex::encode(key_pair.1.as_ref())) {
-            eprintln!("Registration failed: {}. Retrying in 5 minutes...", e); std::thread::sleep(std::time::Duration::from_secs(5 * 60)); continue;
+        if let Err(e) = api::block_on(api::register_address(&context.client, &context.api_url, &generated_mining_address, &context.tc_response.message, &reg_signature.0, &hex::encode(key_pair.1.as_ref()))) && !e.is_dry_run() {
+            warn!("Registration failed: {}. Retrying in 5 minutes...", e); std::thread::sleep(std::time::Duration::from_secs(5 * 60)); continue;
         }
 
         print_mining_setup(&context.api_url, Some(&generated_mining_address.to_string()), context.threads, &challenge_params);
 
+        let (rom, _) = rom_for_challenge(&mut rom_store, &challenge_params, &context.rom_overrides.resolve(Some(&challenge_params)), context.rom_cache_dir.as_deref());
+
         // UPDATED CALL: Removed client and api_url
         // FIX: Use .as_ref() and .as_deref() for Option<&String> and Option<&str>
         let (result, total_hashes, elapsed_secs) = run_single_mining_cycle(
@@ -421,6 +463,7 @@ pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
                 context.threads,
                 context.donate_to_option.as_ref(), // Option<String> to Option<&String>
                 &challenge_params,
+                rom,
                 context.data_dir.as_deref(), // Option<String> to Option<&str>
             );
         final_hashes = total_hashes; final_elapsed = elapsed_secs;
@@ -433,22 +476,24 @@ pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
                     let donation_signature = cardano::cip8_sign(&key_pair, &donation_message);
 
                     // Attempt donation synchronously. Ignore result here to keep the main thread fast.
-                    match api::donate_to(
+                    match api::block_on(api::donate_to(
                         &context.client, &context.api_url, &generated_mining_address, destination_address, &donation_signature.0,
-                    ) {
-                        Ok(id) => println!("🚀 Donation initiated successfully. ID: {}", id),
-                        Err(e) => eprintln!("⚠️ Donation failed (synchronous attempt): {}", e),
+                    )) {
+                        Ok(id) => info!("🚀 Donation initiated successfully. ID: {}", id),
+                        Err(e) if e.is_dry_run() => info!("🧪 Dry run: donation not sent."),
+                        Err(e) => warn!("⚠️ Donation failed (synchronous attempt): {}", e),
                     }
                 }
-                eprintln!("Solution queued. Starting next cycle immediately...");
+                warn!("Solution queued. Starting next cycle immediately...");
             }
-            MiningResult::AlreadySolved => { eprintln!("Solution was already accepted by the network. Starting next cycle immediately..."); }
-            MiningResult::MiningFailed => { eprintln!("Mining cycle failed. Retrying next cycle in 1 minute..."); std::thread::sleep(std::time::Duration::from_secs(60)); }
+            MiningResult::AlreadySolved => { warn!("Solution was already accepted by the network. Starting next cycle immediately..."); }
+            MiningResult::SpaceExhausted => { info!("Search space exhausted. Starting next cycle with a new key..."); }
+            MiningResult::MiningFailed => { warn!("Mining cycle failed. Retrying next cycle in 1 minute..."); std::thread::sleep(std::time::Duration::from_secs(60)); }
         }
 
-        let stats_result = api::fetch_statistics(&context.client, &context.api_url, &generated_mining_address);
+        let stats_result = api::block_on(api::fetch_statistics(&context.client, &context.api_url, &generated_mining_address)).map_err(String::from);
         print_statistics(stats_result, final_hashes, final_elapsed);
-        println!("\n[CYCLE END] Starting next mining cycle immediately...");
+        info!("[CYCLE END] Starting next mining cycle immediately...");
     }
 }
 
@@ -456,88 +501,315 @@ pub fn run_ephemeral_key_mining(context: MiningContext) -> Result<(), String> {
 // ASYNCHRONOUS MINING DISPATCHER
 // ===============================================
 
+/// Path of the cached ROM for `rom_id`: `<data_dir>/roms/<rom_id>.rom`.
+fn rom_cache_path(data_dir: &str, rom_id: &str) -> std::path::PathBuf {
+    std::path::Path::new(data_dir).join(ROM_CACHE_DIR).join(format!("{}.rom", rom_id))
+}
+
+/// Sets how many ROM files the on-disk cache keeps (--rom-cache-max).
+pub fn set_rom_cache_max(max: usize) {
+    ROM_CACHE_MAX.store(max.max(1), Ordering::Relaxed);
+}
+
+/// Marks a cached ROM as just used; eviction removes the least recently used files first.
+fn touch_cached_rom(path: &std::path::Path) {
+    if let Err(e) = fs::File::options().write(true).open(path).and_then(|file| file.set_modified(std::time::SystemTime::now())) {
+        warn!("⚠️ Could not update the ROM cache timestamp of {}: {}", path.display(), e);
+    }
+}
+
+/// Deletes the least recently used cached ROMs beyond --rom-cache-max, never `keep`.
+fn evict_cached_roms(keep: &std::path::Path) {
+    let Some(dir) = keep.parent() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut cached: Vec<(std::time::SystemTime, std::path::PathBuf)> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rom") && path != keep)
+        .map(|path| (fs::metadata(&path).and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH), path))
+        .collect();
+    // Newest first; `keep` takes one of the slots.
+    cached.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in cached.into_iter().skip(ROM_CACHE_MAX.load(Ordering::Relaxed).saturating_sub(1)) {
+        match fs::remove_file(&path) {
+            Ok(()) => info!("🗑️ Evicted cached ROM {}", path.display()),
+            Err(e) => warn!("⚠️ Could not evict cached ROM {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Generates the ROM for a challenge. This is the expensive (~1 GB) step that precedes mining.
+///
+/// Returns the ROM for `challenge_params`, memory-mapping it from `<data_dir>/roms/` when a
+/// valid cached copy exists. Otherwise the ROM is generated and, if `cache_dir` is set,
+/// written to the cache for subsequent runs. Cache failures are logged and never fatal.
+pub fn generate_rom(challenge_params: &ChallengeData, rom_params: &RomParams, cache_dir: Option<&str>) -> Rom {
+    let cache_path = cache_dir.map(|dir| rom_cache_path(dir, &rom_params.rom_id(&challenge_params.no_pre_mine_key)));
+
+    if let Some(path) = cache_path.as_ref().filter(|p| p.exists()) {
+        match Rom::load_from_file(path, rom_params.size) {
+            Ok(rom) => {
+                touch_cached_rom(path);
+                info!("💾 Loaded cached ROM from {} ({})", path.display(), rom.pages());
+                info!("{}", rom.digest);
+                return rom;
+            }
+            Err(e) => {
+                warn!("⚠️ Ignoring ROM cache: {}. Regenerating.", e);
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    info!("Generating ROM with key: {}", challenge_params.no_pre_mine_key);
+    if *rom_params != RomParams::default() {
+        info!("⚙️ Non-default ROM parameters: {:?}", rom_params);
+    }
+
+    let rom = Rom::from_params(challenge_params.no_pre_mine_key.as_bytes(), rom_params);
+    if shadow_harvester_lib::huge_pages() || shadow_harvester_lib::low_memory() {
+        info!("🧱 ROM pages: {}", rom.pages());
+    }
+    info!("{}", rom.digest);
+
+    if let Some(path) = cache_path {
+        let saved = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|e| format!("Failed to create ROM cache directory: {}", e))
+            .and_then(|_| rom.save_to_file(&path));
+        match saved {
+            Ok(_) => {
+                info!("💾 Cached ROM at {}", path.display());
+                evict_cached_roms(&path);
+                // Reading the mapped file is much faster than regenerating every chunk.
+                if shadow_harvester_lib::low_memory()
+                    && let Ok(mapped) = Rom::load_from_file(&path, rom_params.size) {
+                    info!("🧱 ROM pages: {}", mapped.pages());
+                    return mapped;
+                }
+            }
+            Err(e) => warn!("⚠️ {}", e),
+        }
+    }
+    rom
+}
+
+/// Returns the ROM in `store` if one was built from this challenge's key and parameters,
+/// otherwise generates (or loads) a new one and stores it. The boolean is true when the ROM
+/// was reused.
+pub fn rom_for_challenge(store: &mut RomStore, challenge_params: &ChallengeData, rom_params: &RomParams, cache_dir: Option<&str>) -> (Arc<Rom>, bool) {
+    let rom_id = rom_params.rom_id(&challenge_params.no_pre_mine_key);
+    if let Some(rom) = store.get(&rom_id) {
+        return (rom, true);
+    }
+    // Evict before building the next ROM so the evicted ones never sit in memory alongside it.
+    for evicted in store.make_room() {
+        info!("🗑️ Unloaded ROM {} (ROM store holds {}).", evicted, store.capacity());
+    }
+    let rom = Arc::new(generate_rom(challenge_params, rom_params, cache_dir));
+    store.insert(rom_id, rom.clone());
+    (rom, false)
+}
+
+/// Spawns a background thread that periodically re-hashes a random sample of ROM chunks
+/// against checksums taken now, while the ROM is fresh. On mismatch it sends
+/// `ManagerCommand::RomCorrupted` so the manager can rebuild the ROM. The thread exits
+/// when `stop_signal` is set (i.e. when the miner using this ROM is stopped).
+pub fn spawn_rom_integrity_checker(
+    rom: std::sync::Arc<Rom>,
+    challenge_id: String,
+    interval: std::time::Duration,
+    stop_signal: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    manager_tx: Sender<ManagerCommand>,
+) {
+    std::thread::spawn(move || {
+        let _span = info_span!("rom_check", challenge_id = %challenge_id).entered();
+        let checksums = rom.chunk_checksums(ROM_CHECK_CHUNK_SIZE);
+        if checksums.is_empty() {
+            return;
+        }
+
+        // xorshift64 seeded from the clock; only needs to spread samples across the ROM.
+        let mut seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15)
+            | 1;
+        let step = std::time::Duration::from_millis(500);
+
+        loop {
+            let mut waited = std::time::Duration::ZERO;
+            while waited < interval {
+                if stop_signal.load(Ordering::Relaxed) {
+                    return;
+                }
+                std::thread::sleep(step);
+                waited += step;
+            }
+            if stop_signal.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let samples: Vec<usize> = (0..ROM_CHECK_SAMPLES.min(checksums.len()))
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    (seed % checksums.len() as u64) as usize
+                })
+                .collect();
+
+            let corrupted = rom.verify_chunks(&checksums, &samples);
+            if !corrupted.is_empty() {
+                error!(
+                    "🚨 ROM integrity check FAILED for challenge {}: {} corrupted chunk(s) (indices {:?}). Possible memory error.",
+                    challenge_id, corrupted.len(), corrupted
+                );
+                let _ = manager_tx.send(ManagerCommand::RomCorrupted(challenge_id));
+                return;
+            }
+        }
+    });
+}
+
 /// Spawns the required number of worker threads to run the scavenge loop
 /// and links the result channel to the main Manager thread.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_miner_workers(
     challenge_params: ChallengeData,
+    hasher: Arc<dyn ChallengeHasher>,
     threads: u32,
+    solutions_per_cycle: u32,
     mining_address: String,
+    start_nonce: u64,
     manager_tx: Sender<ManagerCommand>,
-) -> Result<std::sync::Arc<std::sync::atomic::AtomicBool>, String> {
-
-    // This block is duplicated from scavenge (src/lib.rs) but is required here
-    // for ROM generation before spawning the threads.
-    const MB: usize = 1024 * 1024;
-    const GB: usize = 1024 * MB;
-
-    println!("Generating ROM with key: {}", challenge_params.no_pre_mine_key);
-
-    let rom = Rom::new(
-        challenge_params.no_pre_mine_key.as_bytes(),
-        RomGenerationType::TwoStep {
-            pre_size: 16 * MB,
-            mixing_numbers: 4,
-        },
-        GB,
-    );
-    println!("{}", rom.digest);
-
-
+    submitter_tx: Sender<SubmitterCommand>,
+) -> Result<(std::sync::Arc<std::sync::atomic::AtomicBool>, std::thread::JoinHandle<()>), String> {
     let (worker_tx, worker_rx) = std::sync::mpsc::channel();
     let stop_signal = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
 
     // Clone the stop_signal BEFORE moving the original into the thread closure.
     let stop_signal_to_return = stop_signal.clone();
 
-    let difficulty_mask = u32::from_str_radix(&challenge_params.difficulty, 16).unwrap();
-    let common_params = ChallengeParams {
-        rom_key: challenge_params.no_pre_mine_key.clone(),
-        difficulty_mask,
-        address: mining_address.clone(),
-        challenge_id: challenge_params.challenge_id.clone(),
-        latest_submission: challenge_params.latest_submission.clone(),
-        no_pre_mine_hour: challenge_params.no_pre_mine_hour_str.clone(),
-        rom: std::sync::Arc::new(rom),
-    };
+    let common_params = challenge_params.to_params(&mining_address, hasher)?;
 
     // The scavenge worker threads are spawned in a temporary scope.
-    std::thread::spawn(move || {
+    let dispatcher = std::thread::spawn(move || {
+        let _span = info_span!("miner", challenge_id = %challenge_params.challenge_id, address = %mining_address).entered();
         // This is a simplified version of the main loop from scavenge in src/lib.rs
 
-        let nb_threads_u64 = threads as u64;
-        let step_size = nb_threads_u64;
+        let nb_workers = threads.max(1) as usize;
         let mut total_hashes_checked = 0; // Counter for total hashes processed
         let start_loop = std::time::SystemTime::now(); // Start timer here
 
+        // Nonce cursor tracking: workers reserve blocks in order from one allocator, so all
+        // nonces below the smallest worker position have been checked.
+        let cursor_key = crate::schema::nonce_cursor_key(&mining_address, &challenge_params.challenge_id);
+        let nonce_space = shadow_harvester_lib::nonce_space();
+        let nonces = Arc::new(NonceAllocator::with_range(start_nonce, nonce_space, NONCE_BLOCK_SIZE));
+        let mut worker_positions: Vec<u64> = vec![start_nonce; nb_workers];
+        // Per-thread coverage: nonces hashed by each worker, and which workers ran out of nonces.
+        let mut worker_hashes: Vec<u64> = vec![0; nb_workers];
+        let mut exhausted_workers: Vec<bool> = vec![false; nb_workers];
+        let mut last_cursor_save = std::time::Instant::now();
+        let current_cursor = |positions: &[u64]| -> u64 {
+            positions.iter().copied().min().unwrap_or(start_nonce)
+        };
+        let mut found_nonces: Vec<u64> = Vec::new();
+        let mut hashrate_monitor = crate::notify::HashrateMonitor::new(format!("challenge {}", challenge_params.challenge_id));
+        let save_cursor = |cursor: u64| {
+            if submitter_tx.send(SubmitterCommand::SaveState(cursor_key.clone(), cursor.to_string())).is_err() {
+                warn!("⚠️ State worker channel closed while saving nonce cursor.");
+            }
+        };
+
         // Spawn actual worker threads (running the core spin function)
-        for thread_id in 0..nb_threads_u64 {
+        for worker in 0..nb_workers {
             let params = common_params.clone();
             let sender = worker_tx.clone();
             let stop_signal = stop_signal.clone(); // Clone for each inner thread
-
-            let start_nonce = thread_id;
+            let nonces = nonces.clone();
 
             std::thread::spawn(move || {
-                spin(params, sender, stop_signal, start_nonce, step_size)
+                spin(params, sender, stop_signal, nonces, worker)
             });
         }
         // Drop the extra sender handle here so the receiver can disconnect once all workers finish/stop
         drop(worker_tx);
 
+        // The health check measures hash progress from the start of this miner.
+        watchdog::beat(Component::Miner);
+        utils::update_miner_stats(|stats| *stats = utils::MinerStats::for_challenge(&challenge_params, nb_workers));
+        let mut rolling_hashrate = utils::RollingHashrate::new();
+        let mut worker_hashrates: Vec<utils::RollingHashrate> = (0..nb_workers).map(|_| utils::RollingHashrate::new()).collect();
+
         // Blocking loop to process results from the workers
         while let Ok(r) = worker_rx.recv() {
             match r {
                 MinerResult::Progress(sz) => {
+                    watchdog::beat(Component::Miner);
                     total_hashes_checked += sz as u64; // Update hash counter
+                    hashrate_monitor.record(sz as u64);
+                    let rate = rolling_hashrate.record(sz as u64);
+                    utils::update_miner_stats(|stats| stats.rolling_rate = rate);
+                }
+                MinerResult::Checkpoint { worker, next_nonce, hashes } => {
+                    worker_positions[worker] = next_nonce;
+                    // `hashes` counts since the miner started; the rate covers the recent window only.
+                    let rate = worker_hashrates[worker].record(hashes.saturating_sub(worker_hashes[worker]));
+                    worker_hashes[worker] = hashes;
+                    utils::update_miner_stats(|stats| {
+                        if let Some(thread_rate) = stats.thread_rates.get_mut(worker) {
+                            *thread_rate = rate;
+                        }
+                    });
+
+                    if last_cursor_save.elapsed() >= NONCE_CURSOR_SAVE_INTERVAL {
+                        save_cursor(current_cursor(&worker_positions));
+                        last_cursor_save = std::time::Instant::now();
+                    }
+                }
+                MinerResult::DeadlinePassed => {
+                    // Every worker reports this; stop them all and notify the manager once.
+                    if !stop_signal.swap(true, Ordering::Relaxed) {
+                        info!("⏰ Submission deadline passed for challenge {}. Stopping workers.", challenge_params.challenge_id);
+                        if manager_tx.send(ManagerCommand::DeadlinePassed(challenge_params.challenge_id.clone())).is_err() {
+                            warn!("⚠️ Manager channel closed while reporting deadline.");
+                        }
+                    }
+                }
+                MinerResult::SpaceExhausted { worker } => {
+                    exhausted_workers[worker] = true;
+                    if exhausted_workers.iter().all(|&exhausted| exhausted) {
+                        // Every nonce below the limit has been checked without enough solutions.
+                        let coverage = worker_hashes.iter().enumerate()
+                            .map(|(worker, hashes)| format!("{}:{}", worker, hashes))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        info!("🔚 Searched all nonces below {:#x} for challenge {} (per-thread hashes: {}).", nonce_space, challenge_params.challenge_id, coverage);
+                        let elapsed_time = start_loop.elapsed().unwrap_or_default().as_secs_f64();
+                        let command = ManagerCommand::SpaceExhausted(challenge_params.challenge_id.clone(), mining_address.clone(), total_hashes_checked, elapsed_time);
+                        if manager_tx.send(command).is_err() {
+                            warn!("⚠️ Manager channel closed while reporting search space exhaustion.");
+                        }
+                    }
                 }
                 MinerResult::Found(nonce, h_output) => { // Receive hash h_output
+                    if found_nonces.contains(&nonce) {
+                        continue;
+                    }
+                    found_nonces.push(nonce);
+                    let cycle_complete = found_nonces.len() >= solutions_per_cycle as usize;
 
                     let elapsed_time = start_loop.elapsed().unwrap().as_secs_f64(); // Calculate elapsed time
                     let total_hashes = total_hashes_checked + 1; // Final total hashes
 
                     // A solution was found! Send it to the Challenge Manager.
                     let nonce_hex = format!("{:016x}", nonce);
-                    println!("🚀 Solution found by worker. Notifying manager.");
+                    info!("🚀 Solution found by worker. Notifying manager.");
                     let difficulty_mask = u32::from_str_radix(&challenge_params.difficulty, 16).unwrap();
 
                     // Calculate preimage and placeholder hash output for error logging
@@ -561,21 +833,28 @@ pub fn spawn_miner_workers(
                         donation_address: None, // Donation address is handled by the Manager post-solution
                         preimage,
                         hash_output,
+                        registration: None,
+                        donation_signature: None,
                     };
 
-                    if manager_tx.send(ManagerCommand::SolutionFound(solution, total_hashes, elapsed_time)).is_err() {
-                        eprintln!("⚠️ Manager channel closed while sending solution.");
+                    if manager_tx.send(ManagerCommand::SolutionFound(solution, total_hashes, elapsed_time, cycle_complete)).is_err() {
+                        warn!("⚠️ Manager channel closed while sending solution.");
                     }
 
-                    // Once a solution is found, set the signal to stop remaining workers
-                    stop_signal.store(true, Ordering::Relaxed);
-                    return; // Exit the outer thread after sending the solution
+                    if cycle_complete {
+                        // Once enough solutions are found, set the signal to stop remaining workers
+                        stop_signal.store(true, Ordering::Relaxed);
+                        return; // Exit the outer thread after sending the final solution
+                    }
                 }
             }
         }
-        println!("⚡ Mining cycle for {} finished/stopped.", mining_address);
+        // Workers were stopped before the cycle completed (new challenge, deadline or shutdown): remember how far we got.
+        save_cursor(current_cursor(&worker_positions));
+        info!("⚡ Mining cycle for {} finished/stopped.", mining_address);
     });
 
-    // Return the cloned Arc which was not moved into the thread.
-    Ok(stop_signal_to_return)
+    // Return the cloned Arc which was not moved into the thread, plus the dispatcher handle so
+    // shutdown can wait for the final nonce cursor save.
+    Ok((stop_signal_to_return, dispatcher))
 }
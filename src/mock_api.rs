@@ -5,12 +5,35 @@ use std::sync::{Arc, RwLock};
 use tokio::runtime;
 use tokio::time::{self, Duration as TokioDuration};
 use chrono::{Utc, Duration, DateTime};
+use rand_core::{OsRng, RngCore};
 
 // --- MOCK CONSTANTS ---
 const MOCK_REGISTRATION_MESSAGE: &str = "MOCK_REGISTRATION_MESSAGE_FOR_TESTS";
 const MOCK_DIFFICULTY: &str = "000FFFFF";
 const MOCK_NO_PRE_MINE: &str = "fd651ac2725e3b9d804cc8b161c0709af14d6264f93e8d4afef0fd1142a3f011";
 const MOCK_NO_PRE_MINE_HOUR: &str = "416194743";
+/// Endless mode: a fresh ROM key every this many challenges, so ROM switches get exercised.
+const ROM_KEY_ROTATION: u32 = 5;
+/// How long a delayed response is held back by fault injection.
+const FAULT_DELAY_SECS: u64 = 3;
+
+/// How the mock server issues challenges and misbehaves.
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// Lifetime of each challenge in seconds.
+    pub challenge_secs: i64,
+    /// Keep issuing challenges forever instead of expiring the second one.
+    pub endless: bool,
+    /// Probability (0.0-1.0) that a request other than the T&C gets a 503, a 429 or a delayed reply.
+    pub fault_rate: f64,
+}
+
+impl Default for MockConfig {
+    /// The two-challenge demo run used by --mock-api-port.
+    fn default() -> Self {
+        MockConfig { challenge_secs: 30, endless: false, fault_rate: 0.0 }
+    }
+}
 
 // --- STATE STRUCTURES ---
 
@@ -29,18 +52,26 @@ struct ChallengeState {
 type SharedState = Arc<RwLock<ChallengeState>>;
 type MockReceipts = Arc<RwLock<u32>>;
 
-fn initial_challenge_state() -> ChallengeState {
+fn initial_challenge_state(config: &MockConfig) -> ChallengeState {
     ChallengeState {
-        challenge_id: "TESTC01".to_string(),
+        challenge_id: challenge_id(config, 1),
         difficulty: MOCK_DIFFICULTY.to_string(),
         no_pre_mine: MOCK_NO_PRE_MINE.to_string(),
         no_pre_mine_hour: MOCK_NO_PRE_MINE_HOUR.to_string(),
         issued_at: Utc::now().to_rfc3339(),
-        latest_submission: (Utc::now() + Duration::seconds(30)).to_rfc3339(), // Initial challenge lasts 30s
+        latest_submission: (Utc::now() + Duration::seconds(config.challenge_secs)).to_rfc3339(),
         challenge_number: 1,
     }
 }
 
+fn challenge_id(config: &MockConfig, number: u32) -> String {
+    if config.endless { format!("SOAK{:06}", number) } else { format!("TESTC{:02}", number) }
+}
+
+fn random_rom_key() -> String {
+    (0..4).map(|_| format!("{:016x}", OsRng.next_u64())).collect()
+}
+
 // --- FILTER HELPERS ---
 
 // Filter to provide the shared challenge state
@@ -53,10 +84,45 @@ fn with_receipts(receipts: MockReceipts) -> impl Filter<Extract = (MockReceipts,
     warp::any().map(move || receipts.clone())
 }
 
+// Rejection carrying the status of an injected fault; turned into a reply by `handle_rejection`.
+#[derive(Debug)]
+struct InjectedFault(StatusCode);
+
+impl warp::reject::Reject for InjectedFault {}
+
+// Filter that fails or delays a `fault_rate` share of the requests passing through it
+fn with_faults(fault_rate: f64) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            if fault_rate <= 0.0 || (OsRng.next_u64() as f64 / u64::MAX as f64) >= fault_rate {
+                return Ok(());
+            }
+            match OsRng.next_u64() % 3 {
+                0 => Err(warp::reject::custom(InjectedFault(StatusCode::SERVICE_UNAVAILABLE))),
+                1 => Err(warp::reject::custom(InjectedFault(StatusCode::TOO_MANY_REQUESTS))),
+                _ => {
+                    time::sleep(TokioDuration::from_secs(FAULT_DELAY_SECS)).await;
+                    Ok(())
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    match rejection.find::<InjectedFault>() {
+        Some(InjectedFault(status)) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"status": "error", "message": format!("Injected fault ({})", status)})),
+            *status,
+        )),
+        None => Err(rejection),
+    }
+}
+
 // --- UPDATER TASK ---
 
-async fn challenge_updater_task(state: SharedState) {
-    let mut interval = time::interval(TokioDuration::from_secs(30));
+async fn challenge_updater_task(state: SharedState, config: MockConfig) {
+    let mut interval = time::interval(TokioDuration::from_secs(config.challenge_secs as u64));
 
     let mut challenge_counter: u32 = state.read().unwrap().challenge_number;
 
@@ -69,7 +135,7 @@ async fn challenge_updater_task(state: SharedState) {
         // --- 2-CYCLE TEST LOGIC: Stop after TESTC02 ---
         // Challenge 1 is set at start.
         // First tick sets Challenge 2 (TESTC02).
-        if challenge_counter >= 2 {
+        if !config.endless && challenge_counter >= 2 {
             // Second tick (the third cycle overall): EXPIRE IT.
             let mut writable_state = state.write().unwrap();
 
@@ -79,8 +145,8 @@ async fn challenge_updater_task(state: SharedState) {
             // NOTE: Keep the challenge ID as the last issued one (TESTC02) but mark it expired.
             writable_state.latest_submission = expired_time.to_rfc3339();
 
-            println!("\n🛑 [Mock API] Challenge **EXPIRED**:");
-            println!("   ID: {} | Deadline set to: {}\n", writable_state.challenge_id, writable_state.latest_submission);
+            cprintln!("\n🛑 [Mock API] Challenge **EXPIRED**:");
+            cprintln!("   ID: {} | Deadline set to: {}\n", writable_state.challenge_id, writable_state.latest_submission);
 
             // If you want it to run indefinitely, remove the 'continue' and let it issue the next challenge.
             continue;
@@ -90,19 +156,22 @@ async fn challenge_updater_task(state: SharedState) {
 
         let now = Utc::now();
         let issued_at = now;
-        let latest_submission = now + Duration::seconds(30);
+        let latest_submission = now + Duration::seconds(config.challenge_secs);
 
-        let new_id = format!("TESTC{:02}", challenge_counter);
+        let new_id = challenge_id(&config, challenge_counter);
 
         // Acquire the write lock and update the state
         let mut writable_state = state.write().unwrap();
+        if config.endless && challenge_counter.is_multiple_of(ROM_KEY_ROTATION) {
+            writable_state.no_pre_mine = random_rom_key();
+        }
         writable_state.challenge_id = new_id;
         writable_state.challenge_number = challenge_counter;
         writable_state.issued_at = issued_at.to_rfc3339();
         writable_state.latest_submission = latest_submission.to_rfc3339();
 
-        println!("\n⏰ [Mock API] New Challenge Issued:");
-        println!("   ID: {} | Expires: {}\n", writable_state.challenge_id, writable_state.latest_submission);
+        cprintln!("\n⏰ [Mock API] New Challenge Issued:");
+        cprintln!("   ID: {} | Expires: {}\n", writable_state.challenge_id, writable_state.latest_submission);
     }
 }
 
@@ -194,7 +263,7 @@ async fn submit_solution_handler(
     };
 
     if Utc::now() > deadline {
-        println!("❌ [Mock API] Submission rejected for expired challenge: {}", state.challenge_id);
+        cprintln!("❌ [Mock API] Submission rejected for expired challenge: {}", state.challenge_id);
 
         return Ok(warp::reply::with_status(
             warp::reply::json(&json!({
@@ -257,15 +326,21 @@ async fn statistics_handler(_address: String, receipts: MockReceipts) -> Result<
 
 // --- CORE SERVER STARTUP ---
 
-pub fn start_mock_server_thread(port: u16) {
+pub fn start_mock_server_thread(port: u16, config: MockConfig) {
     let bind_addr = format!("127.0.0.1:{}", port);
     let address_clone = bind_addr.clone();
 
-    println!("\n==============================================");
-    println!("🧪 Starting Mock Scavenger API Server...");
-    println!("   Bind Address: http://{}", bind_addr);
-    println!("   API Base Path: /api");
-    println!("==============================================\n");
+    cprintln!("\n==============================================");
+    cprintln!("🧪 Starting Mock Scavenger API Server...");
+    cprintln!("   Bind Address: http://{}", bind_addr);
+    cprintln!("   API Base Path: /api");
+    if config.endless {
+        cprintln!("   Challenges: endless, {}s each", config.challenge_secs);
+    }
+    if config.fault_rate > 0.0 {
+        cprintln!("   Fault Injection: {:.1}% of requests", config.fault_rate * 100.0);
+    }
+    cprintln!("==============================================\n");
 
     thread::spawn(move || {
         let rt = runtime::Builder::new_current_thread()
@@ -274,43 +349,47 @@ pub fn start_mock_server_thread(port: u16) {
             .expect("Failed to create Tokio runtime for mock server.");
 
         // --- Initialize Shared States to CLEAN STATE ---
-        let challenge_state = Arc::new(RwLock::new(initial_challenge_state()));
+        let challenge_state = Arc::new(RwLock::new(initial_challenge_state(&config)));
         let receipts_state: MockReceipts = Arc::new(RwLock::new(0));
 
         let initial_id = challenge_state.read().unwrap().challenge_id.clone();
-        println!("🗑️ [Mock API] State initialized to clean slate ({}, Receipts: 0).", initial_id);
+        cprintln!("🗑️ [Mock API] State initialized to clean slate ({}, Receipts: 0).", initial_id);
 
         rt.block_on(async {
             // 1. Spawn the continuous challenge updater task
-            tokio::spawn(challenge_updater_task(challenge_state.clone()));
+            tokio::spawn(challenge_updater_task(challenge_state.clone(), config.clone()));
 
             // 2. Define Filters
             let state_filter = with_state(challenge_state.clone());
             let receipts_filter = with_receipts(receipts_state.clone());
+            let faults = with_faults(config.fault_rate);
 
             // Define the /api base filter
             let api_base = warp::path("api");
 
             // 3. Define all routes (all routes require the api_base filter)
-            let tandc_route = api_base.clone()
+            let tandc_route = api_base
                 .and(warp::path!("TandC" / "1-0"))
                 .and(warp::get())
                 .and_then(tandc_handler);
 
-            let challenge_route = api_base.clone()
+            let challenge_route = api_base
                 .and(warp::path("challenge"))
                 .and(warp::get())
                 .and(state_filter.clone())
+                .and(faults.clone())
                 .and_then(challenge_status_handler);
 
-            let register_route = api_base.clone()
+            let register_route = api_base
                 .and(warp::path!("register" / String / String / String))
                 .and(warp::post())
+                .and(faults.clone())
                 .and_then(register_handler);
 
-            let solution_route = api_base.clone()
+            let solution_route = api_base
                 .and(warp::path!("solution" / String / String / String))
                 .and(warp::post())
+                .and(faults.clone())
                 .and(receipts_filter.clone())
                 .and(state_filter.clone())
                 .and_then(submit_solution_handler);
@@ -318,15 +397,17 @@ pub fn start_mock_server_thread(port: u16) {
             let statistics_route = api_base
                 .and(warp::path!("statistics" / String))
                 .and(warp::get())
+                .and(faults.clone())
                 .and(receipts_filter.clone())
                 .and_then(statistics_handler);
 
-            // 4. Combine all routes with .or()
+            // 4. Combine all routes with .or(); injected faults become error replies
             let routes = tandc_route
                 .or(challenge_route)
                 .or(register_route)
                 .or(solution_route)
-                .or(statistics_route);
+                .or(statistics_route)
+                .recover(handle_rejection);
 
             // 5. Start the server
             warp::serve(routes)
@@ -0,0 +1,229 @@
+// src/notify.rs
+
+use crate::constants::USER_AGENT;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===============================================
+// NOTIFICATIONS (Telegram, Discord)
+// ===============================================
+//
+// Found solutions, rejected submissions and hashrate drops are pushed to chat services so an
+// unattended miner can be watched from a phone. Messages are queued and sent from one
+// background thread; a sink that is down never slows the miner, its errors are only logged.
+
+/// Hashrate is averaged over windows of this length before comparing with the baseline.
+const HASHRATE_WINDOW: Duration = Duration::from_secs(60);
+/// Default drop, in percent of the baseline hashrate, that triggers a notification.
+pub const DEFAULT_HASHRATE_DROP_PERCENT: u8 = 30;
+/// Timeout of one request to a sink.
+const SINK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A destination for notification messages.
+pub trait NotifySink: Send {
+    /// Short name for error messages, e.g. "telegram".
+    fn name(&self) -> &str;
+
+    /// Delivers one message.
+    fn send(&self, client: &Client, text: &str) -> Result<(), String>;
+}
+
+/// Telegram bot API: `sendMessage` to one chat.
+pub struct TelegramSink {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+impl NotifySink for TelegramSink {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    fn send(&self, client: &Client, text: &str) -> Result<(), String> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        client
+            .post(url)
+            .json(&serde_json::json!({ "chat_id": self.chat_id, "text": text }))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            // The URL contains the bot token; keep it out of logs.
+            .map_err(|e| e.without_url().to_string())
+    }
+}
+
+/// Discord incoming webhook.
+pub struct DiscordSink {
+    pub webhook_url: String,
+}
+
+impl NotifySink for DiscordSink {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn send(&self, client: &Client, text: &str) -> Result<(), String> {
+        client
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "content": text }))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.without_url().to_string())
+    }
+}
+
+/// Contents of the --notify-config JSON file. Command-line flags take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+    #[serde(default)]
+    pub discord: Option<DiscordConfig>,
+    #[serde(default)]
+    pub hashrate_drop_percent: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+}
+
+impl NotifyConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Failed to read notify config {}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid notify config {}: {}", path, e))
+    }
+}
+
+// Process-wide: queue of the notification thread, set once the sinks are configured.
+static NOTIFIER: OnceLock<Mutex<Sender<String>>> = OnceLock::new();
+// Process-wide: hashrate drop (percent) that triggers a notification.
+static HASHRATE_DROP_PERCENT: OnceLock<u8> = OnceLock::new();
+
+/// Starts the notification thread for `sinks`. Does nothing when there are none.
+pub fn init(sinks: Vec<Box<dyn NotifySink>>, hashrate_drop_percent: u8) -> Result<(), String> {
+    if sinks.is_empty() {
+        return Ok(());
+    }
+    if !(1..=100).contains(&hashrate_drop_percent) {
+        return Err(format!("Hashrate drop threshold must be between 1 and 100 percent, got {}.", hashrate_drop_percent));
+    }
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(SINK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create notification HTTP client: {}", e))?;
+
+    let names: Vec<&str> = sinks.iter().map(|sink| sink.name()).collect();
+    cprintln!("🔔 Notifications enabled: {}.", names.join(", "));
+
+    let (tx, rx) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for text in rx {
+            for sink in &sinks {
+                if let Err(e) = sink.send(&client, &text) {
+                    ceprintln!("⚠️ Failed to send {} notification: {}", sink.name(), e);
+                }
+            }
+        }
+    });
+    let _ = NOTIFIER.set(Mutex::new(tx));
+    let _ = HASHRATE_DROP_PERCENT.set(hashrate_drop_percent);
+    Ok(())
+}
+
+/// Queues `text` for every configured sink.
+pub fn send(text: String) {
+    if let Some(notifier) = NOTIFIER.get() {
+        let _ = notifier.lock().unwrap().send(text);
+    }
+}
+
+/// Watches one miner's hashrate and notifies once when it falls below the configured share of
+/// the best window seen so far, and again after it has recovered and dropped anew.
+pub struct HashrateMonitor {
+    label: String,
+    window_start: Instant,
+    window_hashes: u64,
+    baseline: f64,
+    dropped: bool,
+}
+
+impl HashrateMonitor {
+    /// `label` names the miner in messages, e.g. the challenge ID.
+    pub fn new(label: String) -> Self {
+        HashrateMonitor { label, window_start: Instant::now(), window_hashes: 0, baseline: 0.0, dropped: false }
+    }
+
+    /// Counts `hashes` just checked.
+    pub fn record(&mut self, hashes: u64) {
+        let Some(&drop_percent) = HASHRATE_DROP_PERCENT.get() else {
+            return;
+        };
+        self.window_hashes += hashes;
+        let elapsed = self.window_start.elapsed();
+        if elapsed < HASHRATE_WINDOW {
+            return;
+        }
+
+        let rate = self.window_hashes as f64 / elapsed.as_secs_f64();
+        self.window_start = Instant::now();
+        self.window_hashes = 0;
+
+        let threshold = self.baseline * (100 - drop_percent) as f64 / 100.0;
+        if rate < threshold {
+            if !self.dropped {
+                self.dropped = true;
+                send(format!(
+                    "📉 Hashrate drop on {}: {:.0} H/s, down {:.0}% from {:.0} H/s.",
+                    self.label, rate, 100.0 - rate * 100.0 / self.baseline, self.baseline
+                ));
+            }
+        } else {
+            if self.dropped {
+                send(format!("📈 Hashrate recovered on {}: {:.0} H/s.", self.label, rate));
+            }
+            self.dropped = false;
+            self.baseline = self.baseline.max(rate);
+        }
+    }
+}
+
+/// Builds the sinks from the command line and --notify-config, and starts notifications.
+pub fn init_from_cli(cli: &crate::cli::Cli) -> Result<(), String> {
+    let config = match &cli.notify_config {
+        Some(path) => NotifyConfig::load(path)?,
+        None => NotifyConfig::default(),
+    };
+
+    let mut sinks: Vec<Box<dyn NotifySink>> = Vec::new();
+    let telegram = match (&cli.telegram_bot_token, &cli.telegram_chat_id) {
+        (Some(bot_token), Some(chat_id)) => Some(TelegramConfig { bot_token: bot_token.clone(), chat_id: chat_id.clone() }),
+        (None, None) => config.telegram,
+        _ => return Err("'--telegram-bot-token' and '--telegram-chat-id' must be given together.".to_string()),
+    };
+    if let Some(TelegramConfig { bot_token, chat_id }) = telegram {
+        sinks.push(Box::new(TelegramSink { bot_token, chat_id }));
+    }
+    let discord = cli.discord_webhook.clone().or(config.discord.map(|discord| discord.webhook_url));
+    if let Some(webhook_url) = discord {
+        sinks.push(Box::new(DiscordSink { webhook_url }));
+    }
+
+    let drop_percent = cli.notify_hashrate_drop.or(config.hashrate_drop_percent).unwrap_or(DEFAULT_HASHRATE_DROP_PERCENT);
+    init(sinks, drop_percent)
+}
@@ -0,0 +1,69 @@
+// src/offline.rs
+
+use crate::cardano::{self, KeyPairAndAddress};
+use crate::data_types::{ChallengeData, PendingSolution, SignedSolution, SolutionBundle, SOLUTION_BUNDLE_FORMAT};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ===============================================
+// AIR-GAPPED MINING (--offline)
+// ===============================================
+//
+// Offline mining never contacts the API. Each found solution is signed by its address key and
+// appended to a solution bundle file, which is carried to a connected machine and imported
+// there. The bundle is rewritten through a temporary file, so a crash never leaves half a file.
+
+/// File name of the bundle under the data dir when --offline-export is not given.
+const DEFAULT_EXPORT_FILENAME: &str = "offline-solutions.json";
+
+/// The bundle --offline mining writes to.
+pub fn export_path(offline_export: Option<&str>, data_dir: Option<&str>) -> PathBuf {
+    match offline_export {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(data_dir.unwrap_or("state")).join(DEFAULT_EXPORT_FILENAME),
+    }
+}
+
+/// Reads a solution bundle, rejecting files of another format.
+pub fn read_bundle(path: &Path) -> Result<SolutionBundle, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read solution bundle {}: {}", path.display(), e))?;
+    let bundle: SolutionBundle = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse solution bundle {}: {}", path.display(), e))?;
+    if bundle.format != SOLUTION_BUNDLE_FORMAT {
+        return Err(format!("{} is not a solution bundle (format '{}', expected '{}').", path.display(), bundle.format, SOLUTION_BUNDLE_FORMAT));
+    }
+    Ok(bundle)
+}
+
+/// Writes `bundle` to `path` via a temporary file in the same directory.
+pub fn write_bundle(path: &Path, bundle: &SolutionBundle) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(bundle).map_err(|e| format!("Failed to serialize solution bundle: {}", e))?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+/// Signs `solution` with `key_pair` and appends it (and its challenge, once) to the bundle at
+/// `path`, creating the file if needed. Returns the number of solutions in the bundle.
+pub fn export_solution(path: &Path, challenge: &ChallengeData, solution: &PendingSolution, key_pair: &KeyPairAndAddress) -> Result<usize, String> {
+    let mut bundle = if path.exists() { read_bundle(path)? } else { SolutionBundle::default() };
+
+    if !bundle.contains(&solution.address, &solution.challenge_id, &solution.nonce) {
+        let message = SignedSolution::message(solution);
+        let (signature, _) = cardano::try_cip8_sign(key_pair, &message)?;
+        bundle.add_solution(SignedSolution {
+            solution: solution.clone(),
+            message: Some(message),
+            signature: Some(signature),
+            public_key: Some(hex::encode(key_pair.1.as_ref())),
+        });
+    }
+    bundle.add_challenge(challenge);
+    bundle.exported_at = chrono::Utc::now().to_rfc3339();
+
+    write_bundle(path, &bundle)?;
+    Ok(bundle.solutions.len())
+}
@@ -0,0 +1,173 @@
+use crate::{ChallengeHasher, ChallengeParams, CpuHasher, Rom};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// --------------------------------------------------------------------------
+// CHALLENGE PARAMETERS
+// --------------------------------------------------------------------------
+//
+// The API hands challenges out as strings. The typed fields below are parsed once, when the
+// challenge is built, so a malformed difficulty or deadline is reported to the caller instead
+// of panicking in (or being silently ignored by) the workers. Fields that end up in the preimage
+// keep the exact text the API sent.
+
+/// Difficulty mask of a challenge: a hash meets the difficulty when its first 4 bytes have no
+/// bit set outside the mask. Parsed from up to 8 hex digits, e.g. "000FFFFF".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DifficultyMask(pub u32);
+
+impl FromStr for DifficultyMask {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > 8 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Difficulty '{}' is not a hex mask of at most 8 digits.", s));
+        }
+        u32::from_str_radix(s, 16).map(DifficultyMask).map_err(|e| format!("Difficulty '{}': {}", s, e))
+    }
+}
+
+impl fmt::Display for DifficultyMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}", self.0)
+    }
+}
+
+/// The challenge's `no_pre_mine` value: seeds the ROM and is part of every preimage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RomKey(String);
+
+impl RomKey {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for RomKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("ROM key '{}' is not a non-empty hex string.", s));
+        }
+        Ok(RomKey(s.to_string()))
+    }
+}
+
+/// The `latest_submission` deadline, keeping the original RFC 3339 text for the preimage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    text: String,
+    at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Deadline {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.at
+    }
+
+    pub fn has_passed(&self) -> bool {
+        self.at < chrono::Utc::now()
+    }
+}
+
+impl FromStr for Deadline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let at = chrono::DateTime::parse_from_rfc3339(s)
+            .map_err(|e| format!("Deadline '{}' is not an RFC 3339 timestamp: {}", s, e))?;
+        Ok(Deadline { text: s.to_string(), at: at.with_timezone(&chrono::Utc) })
+    }
+}
+
+/// Builds a validated `ChallengeParams`. Every field is required except the hasher, which
+/// defaults to the CPU interpreter over `rom` when one is given.
+#[derive(Default)]
+pub struct ChallengeParamsBuilder {
+    address: Option<String>,
+    challenge_id: Option<String>,
+    difficulty: Option<DifficultyMask>,
+    rom_key: Option<RomKey>,
+    deadline: Option<Deadline>,
+    no_pre_mine_hour: Option<String>,
+    hasher: Option<Arc<dyn ChallengeHasher>>,
+}
+
+impl ChallengeParams {
+    pub fn builder() -> ChallengeParamsBuilder {
+        ChallengeParamsBuilder::default()
+    }
+}
+
+impl ChallengeParamsBuilder {
+    /// The registered Cardano address the solutions are for.
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    pub fn challenge_id(mut self, challenge_id: impl Into<String>) -> Self {
+        self.challenge_id = Some(challenge_id.into());
+        self
+    }
+
+    pub fn difficulty(mut self, difficulty: DifficultyMask) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    pub fn rom_key(mut self, rom_key: RomKey) -> Self {
+        self.rom_key = Some(rom_key);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// The challenge's `no_pre_mine_hour`, a decimal number.
+    pub fn no_pre_mine_hour(mut self, hour: impl Into<String>) -> Self {
+        self.no_pre_mine_hour = Some(hour.into());
+        self
+    }
+
+    pub fn hasher(mut self, hasher: Arc<dyn ChallengeHasher>) -> Self {
+        self.hasher = Some(hasher);
+        self
+    }
+
+    /// Hashes with the CPU interpreter over `rom`, which must be built from the ROM key.
+    pub fn rom(self, rom: Arc<Rom>) -> Self {
+        self.hasher(Arc::new(CpuHasher::new(rom)))
+    }
+
+    pub fn build(self) -> Result<ChallengeParams, String> {
+        let address = self.address.filter(|a| !a.is_empty()).ok_or("Challenge address is missing.")?;
+        let challenge_id = self.challenge_id.filter(|id| !id.is_empty()).ok_or("Challenge ID is missing.")?;
+        let difficulty = self.difficulty.ok_or("Challenge difficulty is missing.")?;
+        let rom_key = self.rom_key.ok_or("Challenge ROM key is missing.")?;
+        let deadline = self.deadline.ok_or("Challenge deadline is missing.")?;
+        let no_pre_mine_hour = self.no_pre_mine_hour.ok_or("Challenge no_pre_mine_hour is missing.")?;
+        if no_pre_mine_hour.is_empty() || !no_pre_mine_hour.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("no_pre_mine_hour '{}' is not a decimal number.", no_pre_mine_hour));
+        }
+        let hasher = self.hasher.ok_or("Challenge hasher is missing; set a hasher or a ROM.")?;
+
+        Ok(ChallengeParams {
+            rom_key: rom_key.0,
+            difficulty_mask: difficulty.0,
+            address,
+            challenge_id,
+            latest_submission: deadline.text,
+            no_pre_mine_hour,
+            hasher,
+        })
+    }
+}
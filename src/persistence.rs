@@ -1,41 +1,261 @@
 // src/persistence.rs
 
+use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+use cryptoxide::kdf::argon2;
 use sled::Db;
 use std::path::Path;
 
+/// Prefix marking a zstd-compressed value. Plain values are UTF-8 text and never start with NUL,
+/// so old (plain) and new (compressed) values can live side by side.
+const COMPRESSED_MARKER: &[u8] = b"\0zstd\0";
+/// Values shorter than this are stored as-is even with compression enabled (indices, IDs).
+const COMPRESSION_MIN_LEN: usize = 128;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Prefix marking an encrypted value: marker, 12-byte nonce, ChaCha20-Poly1305 ciphertext of
+/// the (possibly compressed) value, 16-byte tag.
+const ENCRYPTED_MARKER: &[u8] = b"\0enc1\0";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+/// Plain-text record with the key derivation salt and a value encrypted under the key, used to
+/// reject a wrong passphrase before anything is written.
+pub const SLED_KEY_DB_ENCRYPTION: &str = "db_encryption";
+const ENCRYPTION_CHECK: &[u8] = b"shadow-harvester db encryption check";
+const KDF_MEMORY_KB: u32 = 64 * 1024;
+const KDF_ITERATIONS: u32 = 3;
+
 /// Wrapper around the Sled database instance for structured access.
 pub struct Persistence {
     pub db: Db,
+    compress: bool,
+    /// Key for at-rest encryption of values, when enabled.
+    key: Option<[u8; 32]>,
+    /// Key prefixes whose values are encrypted; None encrypts every value.
+    encrypted_prefixes: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EncryptionRecord {
+    kdf: String,
+    memory_kb: u32,
+    iterations: u32,
+    salt: String,
+    check: String,
+}
+
+fn derive_key(secret: &[u8], salt: &[u8], memory_kb: u32, iterations: u32) -> Result<[u8; 32], String> {
+    let params = argon2::Params::argon2id()
+        .memory_kb(memory_kb)
+        .and_then(|p| p.iterations(iterations))
+        .map_err(|e| format!("Invalid key derivation parameters: {:?}", e))?;
+    Ok(argon2::argon2(&params, secret, salt, &[], &[]))
+}
+
+fn encrypt(key: &[u8; 32], plain: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|e| format!("Failed to generate nonce: {}", e))?;
+    let mut out = vec![0u8; ENCRYPTED_MARKER.len() + NONCE_LEN + plain.len() + TAG_LEN];
+    let (header, body) = out.split_at_mut(ENCRYPTED_MARKER.len() + NONCE_LEN);
+    header[..ENCRYPTED_MARKER.len()].copy_from_slice(ENCRYPTED_MARKER);
+    header[ENCRYPTED_MARKER.len()..].copy_from_slice(&nonce);
+    let (ciphertext, tag) = body.split_at_mut(plain.len());
+    ChaCha20Poly1305::new(key, &nonce, &[]).encrypt(plain, ciphertext, tag);
+    Ok(out)
+}
+
+/// Decrypts a value written by `encrypt` (including its marker).
+fn decrypt(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let body = &sealed[ENCRYPTED_MARKER.len()..];
+    if body.len() < NONCE_LEN + TAG_LEN {
+        return Err("Encrypted value is truncated.".to_string());
+    }
+    let (nonce, rest) = body.split_at(NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+    let mut plain = vec![0u8; ciphertext.len()];
+    if !ChaCha20Poly1305::new(key, nonce.try_into().unwrap(), &[]).decrypt(ciphertext, &mut plain, tag) {
+        return Err("Failed to decrypt stored value (wrong passphrase or corrupted data).".to_string());
+    }
+    Ok(plain)
 }
 
 impl Persistence {
     /// Opens the Sled database at the specified path.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, sled::Error> {
         let db = sled::open(path)?;
-        Ok(Persistence { db })
+        Ok(Persistence { db, compress: false, key: None, encrypted_prefixes: None })
+    }
+
+    /// Opens a temporary in-memory Sled database that is discarded when dropped.
+    pub fn open_temporary() -> Result<Self, sled::Error> {
+        let db = sled::Config::new().temporary(true).open()?;
+        Ok(Persistence { db, compress: false, key: None, encrypted_prefixes: None })
     }
 
     /// Opens a temporary in-memory Sled database for testing.
     /// This avoids filesystem access and ensures test isolation.
     #[cfg(test)]
     pub fn open_test_db() -> Result<Self, sled::Error> {
-        let db = sled::Config::new().temporary(true).open()?;
-        Ok(Persistence { db })
+        Self::open_temporary()
+    }
+
+    /// Enables zstd compression of newly written values. Reads always accept both
+    /// compressed and plain values, so the flag can be toggled on an existing database.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compress = enabled;
+        self
+    }
+
+    /// Encrypts newly written values with a key derived (Argon2id) from `passphrase`. The
+    /// first call on a database stores a random salt and a check value; later calls must use
+    /// the same passphrase or fail. Plain values written earlier stay readable, so this can be
+    /// enabled on an existing database. Keys are not encrypted: prefix scans rely on them.
+    pub fn with_encryption(self, passphrase: &str) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("The database passphrase must not be empty.".to_string());
+        }
+        self.with_encryption_secret(passphrase.as_bytes())
+    }
+
+    /// Like `with_encryption`, with the key derived from raw key material (e.g. a key file).
+    pub fn with_encryption_secret(mut self, secret: &[u8]) -> Result<Self, String> {
+        let stored = self.db.get(SLED_KEY_DB_ENCRYPTION)
+            .map_err(|e| format!("Sled GET error for key '{}': {}", SLED_KEY_DB_ENCRYPTION, e))?;
+
+        let key = match stored {
+            Some(raw) => {
+                let record: EncryptionRecord = serde_json::from_slice(&raw)
+                    .map_err(|e| format!("Corrupted database encryption record: {}", e))?;
+                let salt = hex::decode(&record.salt).map_err(|e| format!("Corrupted database encryption salt: {}", e))?;
+                let check = hex::decode(&record.check).map_err(|e| format!("Corrupted database encryption check: {}", e))?;
+                let key = derive_key(secret, &salt, record.memory_kb, record.iterations)?;
+                if !check.starts_with(ENCRYPTED_MARKER) || decrypt(&key, &check).ok().as_deref() != Some(ENCRYPTION_CHECK) {
+                    return Err("Wrong database passphrase or key file.".to_string());
+                }
+                key
+            }
+            None => {
+                let mut salt = [0u8; 16];
+                getrandom::fill(&mut salt).map_err(|e| format!("Failed to generate salt: {}", e))?;
+                let key = derive_key(secret, &salt, KDF_MEMORY_KB, KDF_ITERATIONS)?;
+                let record = EncryptionRecord {
+                    kdf: "argon2id".to_string(),
+                    memory_kb: KDF_MEMORY_KB,
+                    iterations: KDF_ITERATIONS,
+                    salt: hex::encode(salt),
+                    check: hex::encode(encrypt(&key, ENCRYPTION_CHECK)?),
+                };
+                let json = serde_json::to_string(&record).map_err(|e| format!("Failed to serialize encryption record: {}", e))?;
+                self.db.insert(SLED_KEY_DB_ENCRYPTION, json.as_bytes())
+                    .map_err(|e| format!("Sled SET error for key '{}': {}", SLED_KEY_DB_ENCRYPTION, e))?;
+                key
+            }
+        };
+        self.key = Some(key);
+        Ok(self)
+    }
+
+    /// Limits encryption to values whose key starts with one of `prefixes`, and encrypts the
+    /// plain values already stored under them. Other values are written in plain text from now
+    /// on; encrypted ones stay readable. Needs `with_encryption` first.
+    pub fn with_encrypted_prefixes(mut self, prefixes: &[&str]) -> Result<Self, String> {
+        let key = self.key.ok_or_else(|| "Selective encryption needs a database key.".to_string())?;
+        for prefix in prefixes {
+            for entry in self.db.scan_prefix(prefix.as_bytes()) {
+                let (stored_key, value) = entry.map_err(|e| format!("Sled iteration error for prefix '{}': {}", prefix, e))?;
+                if value.starts_with(ENCRYPTED_MARKER) {
+                    continue;
+                }
+                self.db.insert(&stored_key, encrypt(&key, &value)?)
+                    .map_err(|e| format!("Sled SET error for key '{}': {}", String::from_utf8_lossy(&stored_key), e))?;
+            }
+        }
+        self.encrypted_prefixes = Some(prefixes.iter().map(|prefix| prefix.to_string()).collect());
+        Ok(self)
+    }
+
+    /// True when values are encrypted at rest.
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// True when only some values (see `with_encrypted_prefixes`) are encrypted.
+    pub fn is_selectively_encrypted(&self) -> bool {
+        self.key.is_some() && self.encrypted_prefixes.is_some()
+    }
+
+    fn encrypts(&self, key: &str) -> bool {
+        match &self.encrypted_prefixes {
+            Some(prefixes) => prefixes.iter().any(|prefix| key.starts_with(prefix.as_str())),
+            None => true,
+        }
+    }
+
+    /// Decodes a raw stored value (encrypted, compressed or plain) into a string.
+    /// Use this when reading values straight from `db` (e.g. `scan_prefix`).
+    pub fn decode_value(&self, bytes: &[u8]) -> Result<String, String> {
+        if bytes.starts_with(ENCRYPTED_MARKER) {
+            let key = self.key.as_ref()
+                .ok_or_else(|| "Stored value is encrypted; a database passphrase is required to read it.".to_string())?;
+            return Self::decode_plain(&decrypt(key, bytes)?);
+        }
+        Self::decode_plain(bytes)
+    }
+
+    fn decode_plain(bytes: &[u8]) -> Result<String, String> {
+        match bytes.strip_prefix(COMPRESSED_MARKER) {
+            Some(compressed) => zstd::decode_all(compressed)
+                .map(|plain| String::from_utf8_lossy(&plain).into_owned())
+                .map_err(|e| format!("Failed to decompress stored value: {}", e)),
+            None => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+
+    fn encode_value(&self, key: &str, value: &str) -> Result<Vec<u8>, String> {
+        let plain = self.compress_value(value)?;
+        match &self.key {
+            Some(secret) if self.encrypts(key) => encrypt(secret, &plain),
+            _ => Ok(plain),
+        }
+    }
+
+    fn compress_value(&self, value: &str) -> Result<Vec<u8>, String> {
+        if !self.compress || value.len() < COMPRESSION_MIN_LEN {
+            return Ok(value.as_bytes().to_vec());
+        }
+        let compressed = zstd::encode_all(value.as_bytes(), COMPRESSION_LEVEL)
+            .map_err(|e| format!("Failed to compress value: {}", e))?;
+        if compressed.len() + COMPRESSED_MARKER.len() >= value.len() {
+            return Ok(value.as_bytes().to_vec());
+        }
+        Ok([COMPRESSED_MARKER, &compressed].concat())
     }
 
     /// Stores a key-value pair in the database.
     pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
         let key_bytes = key.as_bytes();
-        let value_bytes = value.as_bytes();
+        let value_bytes = self.encode_value(key, value)?;
         self.db.insert(key_bytes, value_bytes)
             .map_err(|e| format!("Sled SET error for key '{}': {}", key, e))?;
         Ok(())
     }
 
+    /// Removes `removals`, then stores `entries`, as one atomic sled batch: a failure leaves the
+    /// database as it was. Every value is encoded before anything is written.
+    pub fn apply_batch(&self, removals: &[String], entries: &[(String, String)]) -> Result<(), String> {
+        let mut batch = sled::Batch::default();
+        for key in removals {
+            batch.remove(key.as_bytes());
+        }
+        for (key, value) in entries {
+            batch.insert(key.as_bytes(), self.encode_value(key, value)?);
+        }
+        self.db.apply_batch(batch).map_err(|e| format!("Sled batch error: {}", e))
+    }
+
     /// Retrieves a value by key.
     pub fn get(&self, key: &str) -> Result<Option<String>, String> {
         match self.db.get(key.as_bytes()) {
-            Ok(Some(ivec)) => Ok(Some(String::from_utf8_lossy(&ivec).into_owned())),
+            Ok(Some(ivec)) => self.decode_value(&ivec).map(Some),
             Ok(None) => Ok(None),
             Err(e) => Err(format!("Sled GET error for key '{}': {}", key, e)),
         }
@@ -91,6 +311,112 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_persistence_compression_mixed_values() -> Result<(), String> {
+        let plain = Persistence::open_test_db().map_err(|e| format!("{}", e))?;
+        let receipt = format!("{{\"crypto_receipt\":{{\"preimage\":\"{}\"}}}}", "ab".repeat(200));
+
+        // Written before compression was enabled.
+        plain.set("receipt:old", &receipt)?;
+
+        let persistence = plain.with_compression(true);
+        persistence.set("receipt:new", &receipt)?;
+        persistence.set("mnemonic_index:D01", "42")?;
+
+        // Large values are compressed on disk, small ones are left as-is.
+        let raw_new = persistence.db.get("receipt:new").unwrap().unwrap();
+        assert!(raw_new.starts_with(COMPRESSED_MARKER));
+        assert!(raw_new.len() < receipt.len() / 2);
+        assert_eq!(&persistence.db.get("mnemonic_index:D01").unwrap().unwrap()[..], b"42");
+
+        // Both old and new values read back identically.
+        assert_eq!(persistence.get("receipt:old")?.unwrap(), receipt);
+        assert_eq!(persistence.get("receipt:new")?.unwrap(), receipt);
+        assert_eq!(persistence.decode_value(&raw_new)?, receipt);
+
+        Ok(())
+    }
+
+    /// Reopens the database at `path`. Sled's flusher thread can hold the file lock for a
+    /// moment after the previous handle is dropped, so retry briefly.
+    fn reopen(path: &std::path::Path) -> Result<Persistence, String> {
+        for _ in 0..50 {
+            if let Ok(persistence) = Persistence::open(path) {
+                return Ok(persistence);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        Persistence::open(path).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_persistence_encryption() -> Result<(), String> {
+        let path = std::env::temp_dir().join(format!("shadow-harvester-enc-test-{}", std::process::id()));
+        let receipt = format!("{{\"crypto_receipt\":{{\"preimage\":\"{}\"}}}}", "ab".repeat(200));
+        {
+            let plain = Persistence::open(&path).map_err(|e| e.to_string())?;
+            plain.set("receipt:old", "written before encryption")?;
+            let persistence = plain.with_compression(true).with_encryption("correct horse")?;
+            persistence.set("receipt:new", &receipt)?;
+
+            let raw = persistence.db.get("receipt:new").unwrap().unwrap();
+            assert!(raw.starts_with(ENCRYPTED_MARKER));
+            assert!(!raw.windows(8).any(|w| w == b"preimage"));
+            assert_eq!(persistence.get("receipt:new")?.unwrap(), receipt);
+            assert_eq!(persistence.get("receipt:old")?.unwrap(), "written before encryption");
+            persistence.close().map_err(|e| e.to_string())?;
+        }
+
+        assert!(reopen(&path)?.with_encryption("wrong").is_err());
+        let without_key = reopen(&path)?;
+        assert!(without_key.get("receipt:new").is_err());
+        let reopened = without_key.with_encryption("correct horse")?;
+        assert_eq!(reopened.get("receipt:new")?.unwrap(), receipt);
+
+        drop(reopened);
+        let _ = std::fs::remove_dir_all(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_persistence_selective_encryption() -> Result<(), String> {
+        let plain = Persistence::open_test_db().map_err(|e| format!("{}", e))?;
+        plain.set("mnemonic_index:42:0:0", "addr_test1old")?;
+        plain.set("receipt:addr_test1old:D01", "{}")?;
+
+        let persistence = plain.with_encryption_secret(&[7u8; 32])?.with_encrypted_prefixes(&["mnemonic_index:"])?;
+        persistence.set("mnemonic_index:42:0:1", "addr_test1new")?;
+        persistence.set("receipt:addr_test1new:D01", "{}")?;
+
+        // Values under the prefix are sealed, including the one written before; others are not.
+        for key in ["mnemonic_index:42:0:0", "mnemonic_index:42:0:1"] {
+            assert!(persistence.db.get(key).unwrap().unwrap().starts_with(ENCRYPTED_MARKER));
+        }
+        assert_eq!(&persistence.db.get("receipt:addr_test1new:D01").unwrap().unwrap()[..], b"{}");
+        assert_eq!(persistence.get("mnemonic_index:42:0:0")?.unwrap(), "addr_test1old");
+        assert_eq!(persistence.get("mnemonic_index:42:0:1")?.unwrap(), "addr_test1new");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persistence_apply_batch() -> Result<(), String> {
+        let persistence = Persistence::open_test_db().map_err(|e| format!("{}", e))?;
+        persistence.set("receipt:old", "1")?;
+        persistence.set("receipt:kept", "2")?;
+
+        let removals = vec!["receipt:old".to_string(), "receipt:kept".to_string()];
+        let entries = vec![("receipt:kept".to_string(), "3".to_string()), ("receipt:new".to_string(), "4".to_string())];
+        persistence.apply_batch(&removals, &entries)?;
+
+        // An insert of a removed key wins, as sled applies the batch in order.
+        assert!(persistence.get("receipt:old")?.is_none());
+        assert_eq!(persistence.get("receipt:kept")?.unwrap(), "3");
+        assert_eq!(persistence.get("receipt:new")?.unwrap(), "4");
+
+        Ok(())
+    }
+
     #[test]
     fn test_persistence_close() -> Result<(), String> {
         let persistence = Persistence::open_test_db().map_err(|e| format!("{}", e))?;
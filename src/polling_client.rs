@@ -1,28 +1,44 @@
 // src/polling_client.rs
 
 use crate::api;
-use crate::data_types::ManagerCommand;
-use reqwest::blocking::Client;
+use crate::challenge_sources::ChallengeSource;
+use crate::data_types::{ManagerCommand, PollerCommand};
+use reqwest::Client;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
-use std::thread;
 use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
 use crate::utils; // Need to import utils for deadline check
+use crate::watchdog;
 
 // Note: This duration is 5 minutes to prevent spamming the API when no new challenge is found.
-const POLLING_INTERVAL_SECS: u64 = 5 * 60;
+pub const POLLING_INTERVAL_SECS: u64 = 5 * 60;
 
-pub fn run_polling_client(
+// Process-wide: seconds between polls; only `soak` shortens it, to keep up with its rapid challenges.
+static POLLING_INTERVAL: AtomicU64 = AtomicU64::new(POLLING_INTERVAL_SECS);
+
+pub fn set_polling_interval(secs: u64) {
+    POLLING_INTERVAL.store(secs, Ordering::Relaxed);
+}
+
+pub fn polling_interval() -> Duration {
+    Duration::from_secs(POLLING_INTERVAL.load(Ordering::Relaxed))
+}
+
+/// Polls the challenge endpoint as a task on the API runtime.
+pub async fn run_polling_client(
     client: Client,
     api_url: String,
     manager_tx: Sender<ManagerCommand>,
+    mut poller_rx: UnboundedReceiver<PollerCommand>,
 ) -> Result<(), String> {
-    println!("🌍 HTTP Polling thread started. Polling every {} seconds.", POLLING_INTERVAL_SECS);
+    cprintln!("🌍 HTTP Polling thread started. Polling every {} seconds.", polling_interval().as_secs());
 
     let mut current_challenge_id = String::new();
 
     loop {
-        // Use a blocking API client to check the challenge status
-        let result = api::fetch_challenge_status(&client, &api_url);
+        watchdog::beat(watchdog::Component::Poller);
+        let result = api::fetch_challenge_status(&client, &api_url).await;
 
         match result {
             Ok(challenge_response) => {
@@ -32,46 +48,48 @@ pub fn run_polling_client(
                         let active_params = challenge_response.challenge.unwrap();
 
                         // FIX: Perform the submission deadline check.
-                        let active_params = match utils::check_submission_deadline(active_params) {
-                            Ok(p) => p,
+                        match utils::check_submission_deadline(active_params) {
                             Err(e) => {
-                                // Deadline expired. Log and continue the loop, which will sleep for POLLING_INTERVAL_SECS.
-                                println!("\n🛑 {}", e);
+                                // Deadline expired. Log and fall through to the sleep at the end of the loop.
+                                cprintln!("\n🛑 {}", e);
                                 current_challenge_id.clear(); // Ensure we log it next time too if still active
-                                continue;
                             }
-                        };
-
+                            Ok(active_params) if active_params.challenge_id != current_challenge_id => {
+                                cprintln!("🌍 Poller found NEW active challenge: {}. Notifying manager.", active_params.challenge_id);
 
-                        if active_params.challenge_id != current_challenge_id {
-                            println!("🌍 Poller found NEW active challenge: {}. Notifying manager.", active_params.challenge_id);
-
-                            // Send the new challenge to the Manager thread
-                            if manager_tx.send(ManagerCommand::NewChallenge(active_params.clone())).is_err() {
-                                eprintln!("⚠️ Manager channel closed. Shutting down polling.");
-                                return Ok(());
+                                // Send the new challenge to the Manager thread
+                                if manager_tx.send(ManagerCommand::ChallengeOffer(active_params.clone(), ChallengeSource::Api)).is_err() {
+                                    ceprintln!("⚠️ Manager channel closed. Shutting down polling.");
+                                    return Ok(());
+                                }
+                                current_challenge_id = active_params.challenge_id;
                             }
-                            current_challenge_id = active_params.challenge_id;
+                            Ok(_) => {}
                         }
                     }
                     "before" | "after" => {
                          // Non-active states, reset the tracked ID if a challenge was previously active
                          if !current_challenge_id.is_empty() {
-                            println!("🌍 Challenge ended. Resetting ID.");
+                            cprintln!("🌍 Challenge ended. Resetting ID.");
                             current_challenge_id.clear();
                         }
                     }
                     _ => {
-                        eprintln!("⚠️ Poller received unexpected challenge code: {}", challenge_response.code);
+                        ceprintln!("⚠️ Poller received unexpected challenge code: {}", challenge_response.code);
                     }
                 }
             }
             Err(e) => {
-                eprintln!("⚠️ Poller API request failed: {}. Retrying after sleep.", e);
+                ceprintln!("⚠️ Poller: {}. Retrying after sleep.", e);
             }
         }
 
-        // Sleep before the next poll
-        thread::sleep(Duration::from_secs(POLLING_INTERVAL_SECS));
+        // Sleep before the next poll, unless the manager asks for an immediate one.
+        match tokio::time::timeout(polling_interval(), poller_rx.recv()).await {
+            Ok(Some(PollerCommand::PollNow)) => cprintln!("🌍 Immediate poll requested by manager."),
+            Err(_) => {}
+            // The manager is gone; keep the regular schedule.
+            Ok(None) => tokio::time::sleep(polling_interval()).await,
+        }
     }
 }
@@ -0,0 +1,96 @@
+// src/prefetch.rs
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// --------------------------------------------------------------------------
+// ROM READ-AHEAD (file-mapped ROMs)
+// --------------------------------------------------------------------------
+//
+// A ROM memory-mapped from the disk cache is read through page faults, and a cold 64-byte
+// access stalls the worker for a full disk read. Every memory operand of a VM program is a
+// literal, so once a loop's program is shuffled all of its ROM addresses are known. The
+// prefetcher hands them to the kernel as one batch of `FADV_WILLNEED` requests on a per-thread
+// io_uring, without waiting, so the reads overlap with the instructions that precede them.
+
+/// Submission queue size of each worker's ring. One VM loop issues at most 2 * 256 requests;
+/// larger batches are submitted in several rounds.
+#[cfg(target_os = "linux")]
+const RING_ENTRIES: u32 = 128;
+
+// Process-wide: prefetch file-mapped ROM reads through io_uring.
+static ROM_PREFETCH: AtomicBool = AtomicBool::new(false);
+
+/// Enables io_uring read-ahead for file-mapped ROMs. Fails, leaving prefetching disabled, when
+/// io_uring is unavailable (non-Linux, kernels before 5.6, or blocked by a seccomp profile).
+pub fn set_rom_prefetch(enabled: bool) -> Result<(), String> {
+    if enabled {
+        probe()?;
+    }
+    ROM_PREFETCH.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns whether file-mapped ROM reads are prefetched.
+pub fn rom_prefetch() -> bool {
+    ROM_PREFETCH.load(Ordering::Relaxed)
+}
+
+#[cfg(target_os = "linux")]
+fn probe() -> Result<(), String> {
+    let ring = io_uring::IoUring::new(RING_ENTRIES).map_err(|e| format!("io_uring is unavailable: {}", e))?;
+    let mut probe = io_uring::Probe::new();
+    ring.submitter().register_probe(&mut probe).map_err(|e| format!("io_uring probe failed: {}", e))?;
+    if !probe.is_supported(io_uring::opcode::Fadvise::CODE) {
+        return Err("this kernel's io_uring does not support fadvise (Linux 5.6+ required).".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe() -> Result<(), String> {
+    Err("io_uring prefetching is only available on Linux.".to_string())
+}
+
+#[cfg(target_os = "linux")]
+thread_local! {
+    // Rings are per worker thread: submission queues are not shared, so no locking is needed.
+    static RING: std::cell::RefCell<Option<io_uring::IoUring>> =
+        std::cell::RefCell::new(io_uring::IoUring::new(RING_ENTRIES).ok());
+}
+
+/// Asks the kernel to read `len` bytes at each of `offsets` in `file` into the page cache.
+/// Returns immediately; completions of earlier batches are reaped and ignored, since a failed
+/// read-ahead only means the later page fault does the read itself.
+#[cfg(target_os = "linux")]
+pub(crate) fn willneed(file: &fs::File, offsets: &[u64], len: u32) {
+    use io_uring::{opcode, types};
+    use std::os::fd::AsRawFd;
+
+    RING.with(|ring| {
+        let mut ring = ring.borrow_mut();
+        let Some(ring) = ring.as_mut() else {
+            return;
+        };
+        ring.completion().for_each(drop);
+
+        let fd = types::Fd(file.as_raw_fd());
+        for &offset in offsets {
+            let entry = opcode::Fadvise::new(fd, len as libc::off_t, libc::POSIX_FADV_WILLNEED)
+                .offset(offset)
+                .build();
+            // SAFETY: fadvise reads no user memory, and the kernel takes its own reference to
+            // the file when the request is submitted.
+            while unsafe { ring.submission().push(&entry) }.is_err() {
+                if ring.submit().is_err() {
+                    return;
+                }
+                ring.completion().for_each(drop);
+            }
+        }
+        let _ = ring.submit();
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn willneed(_file: &fs::File, _offsets: &[u64], _len: u32) {}
@@ -0,0 +1,235 @@
+// src/rest_api.rs
+
+use crate::control;
+use crate::data_types::{ControlRequest, ManagerCommand, SubmitterCommand};
+use crate::watchdog::{self, Component};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+use tokio::runtime;
+use warp::{http::StatusCode, Filter, Rejection, Reply};
+
+// ===============================================
+// REST CONTROL API (--control-port)
+// ===============================================
+//
+// A small HTTP server for managing the miner remotely:
+//
+//   GET  /status         current challenge, address, threads and mining state
+//   POST /pause          stop mining until /resume
+//   POST /resume         resume mining
+//   POST /threads/{n}    change the number of worker threads
+//   POST /pending/retry  resubmit the solutions waiting in the pending queue
+//   GET  /healthz        200 while the miner is healthy, 503 otherwise (no token needed)
+//
+// Requests are forwarded to the challenge manager as `ControlRequest`s, the same ones the
+// control socket sends. Replies are `{"ok": true, "data": {...}}` or `{"ok": false, "error": ...}`
+// with status 400. With --control-token every request must carry `Authorization: Bearer <token>`
+// or a `token` query parameter; without one the server only binds to loopback addresses. With
+// the "web-ui" feature the same server also serves the browser dashboard (see web_ui.rs).
+
+/// How long the health check waits for the manager to answer.
+const HEALTH_MANAGER_TIMEOUT: Duration = Duration::from_secs(5);
+/// While mining, the health check fails when no hash progress was reported for this long.
+const HEALTH_MAX_PROGRESS_AGE: Duration = Duration::from_secs(120);
+
+/// Binds the control API on `addr` and serves it on its own thread. Returns once the port is
+/// bound, so a port already in use is reported at startup. Without a token only loopback
+/// addresses are allowed.
+pub fn spawn_rest_server(
+    addr: SocketAddr,
+    token: Option<String>,
+    manager_tx: Sender<ManagerCommand>,
+    submitter_tx: Sender<SubmitterCommand>,
+) -> Result<SocketAddr, String> {
+    if token.is_none() && !addr.ip().is_loopback() {
+        return Err(format!(
+            "Refusing to serve the control API on {} without --control-token: anyone who can reach it could control the miner.",
+            addr
+        ));
+    }
+    let (bound_tx, bound_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let rt = match runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(rt) => rt,
+            Err(e) => {
+                let _ = bound_tx.send(Err(format!("Failed to create Tokio runtime for the control API: {}", e)));
+                return;
+            }
+        };
+
+        rt.block_on(async move {
+            let auth = warp::header::optional::<String>("authorization")
+                .and(warp::query::<HashMap<String, String>>().or(warp::any().map(HashMap::new)).unify())
+                .and_then(move |header: Option<String>, query: HashMap<String, String>| {
+                    let authorized = token.as_ref().is_none_or(|token| {
+                        header.as_deref().and_then(|header| header.strip_prefix("Bearer ")).is_some_and(|given| token_matches(given, token))
+                            || query.get("token").is_some_and(|given| token_matches(given, token))
+                    });
+                    async move { if authorized { Ok(()) } else { Err(warp::reject::custom(Unauthorized)) } }
+                })
+                .untuple_one();
+
+            // Probes of container orchestrators rarely carry credentials; the health check
+            // reveals nothing beyond whether the miner works.
+            let health_manager_tx = manager_tx.clone();
+            let retry_submitter_tx = submitter_tx.clone();
+            let healthz_route = warp::path!("healthz")
+                .and(warp::get())
+                .and(warp::any().map(move || (health_manager_tx.clone(), submitter_tx.clone())))
+                .and_then(|(manager_tx, submitter_tx)| handle_healthz(manager_tx, submitter_tx));
+
+            let retry_route = warp::path!("pending" / "retry")
+                .and(warp::post())
+                .and(auth.clone())
+                .and(warp::any().map(move || retry_submitter_tx.clone()))
+                .and_then(handle_retry_pending);
+
+            let manager = warp::any().map(move || manager_tx.clone());
+
+            let status_route = warp::path!("status")
+                .and(warp::get())
+                .map(|| ControlRequest::Status);
+            let pause_route = warp::path!("pause")
+                .and(warp::post())
+                .map(|| ControlRequest::Pause);
+            let resume_route = warp::path!("resume")
+                .and(warp::post())
+                .map(|| ControlRequest::Resume);
+            let threads_route = warp::path!("threads" / u32)
+                .and(warp::post())
+                .map(ControlRequest::SetThreads);
+
+            let routes = healthz_route
+                .or(retry_route)
+                .or(auth
+                    .clone()
+                    .and(status_route.or(pause_route).unify().or(resume_route).unify().or(threads_route).unify())
+                    .and(manager.clone())
+                    .and_then(handle_request));
+            #[cfg(feature = "web-ui")]
+            let routes = routes.or(crate::web_ui::routes(auth, manager));
+            let routes = routes.recover(handle_rejection);
+
+            match warp::serve(routes).try_bind_ephemeral(addr) {
+                Ok((bound, server)) => {
+                    let _ = bound_tx.send(Ok(bound));
+                    server.await;
+                }
+                Err(e) => {
+                    let _ = bound_tx.send(Err(format!("Failed to bind control API to {}: {}", addr, e)));
+                }
+            }
+        });
+    });
+
+    bound_rx.recv().map_err(|_| "Control API thread exited during startup.".to_string())?
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+async fn handle_request(request: ControlRequest, manager_tx: Sender<ManagerCommand>) -> Result<impl Reply, Rejection> {
+    // The manager answers over a blocking channel; keep it off the runtime thread.
+    let reply = tokio::task::spawn_blocking(move || control::ask_manager(&manager_tx, request))
+        .await
+        .unwrap_or_else(|e| Err(format!("Control request failed: {}", e)));
+    Ok(control_reply(reply))
+}
+
+async fn handle_retry_pending(submitter_tx: Sender<SubmitterCommand>) -> Result<impl Reply, Rejection> {
+    let reply = tokio::task::spawn_blocking(move || control::retry_pending(&submitter_tx))
+        .await
+        .unwrap_or_else(|e| Err(format!("Control request failed: {}", e)));
+    Ok(control_reply(reply))
+}
+
+fn control_reply(reply: Result<Value, String>) -> impl Reply {
+    match reply {
+        Ok(data) => warp::reply::with_status(warp::reply::json(&json!({ "ok": true, "data": data })), StatusCode::OK),
+        Err(e) => warp::reply::with_status(warp::reply::json(&json!({ "ok": false, "error": e })), StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Runs the health checks: the manager answers a status request, the state worker's channel is
+/// open, and a miner that should be hashing reported progress recently.
+fn check_health(manager_tx: &Sender<ManagerCommand>, submitter_tx: &Sender<SubmitterCommand>) -> Result<serde_json::Value, String> {
+    if submitter_tx.send(SubmitterCommand::Heartbeat).is_err() {
+        return Err("State worker channel is closed.".to_string());
+    }
+    let status = control::ask_manager_within(manager_tx, ControlRequest::Status, HEALTH_MANAGER_TIMEOUT)
+        .map_err(|e| format!("Manager is not responding: {}", e))?;
+
+    let mining = status["mining"].as_bool().unwrap_or(false);
+    let progress_age = watchdog::beat_age(Component::Miner);
+    if mining && progress_age.is_none_or(|age| age > HEALTH_MAX_PROGRESS_AGE) {
+        return Err(match progress_age {
+            Some(age) => format!("No hash progress for {}s.", age.as_secs()),
+            None => "Miner has not reported any hash progress.".to_string(),
+        });
+    }
+    Ok(json!({
+        "mining": mining,
+        "paused": status["paused"],
+        "challenge_id": status["challenge_id"],
+        "last_progress_secs": progress_age.map(|age| age.as_secs()),
+    }))
+}
+
+async fn handle_healthz(manager_tx: Sender<ManagerCommand>, submitter_tx: Sender<SubmitterCommand>) -> Result<impl Reply, Rejection> {
+    let health = tokio::task::spawn_blocking(move || check_health(&manager_tx, &submitter_tx))
+        .await
+        .unwrap_or_else(|e| Err(format!("Health check failed: {}", e)));
+    Ok(match health {
+        Ok(data) => warp::reply::with_status(warp::reply::json(&json!({ "ok": true, "data": data })), StatusCode::OK),
+        Err(e) => warp::reply::with_status(warp::reply::json(&json!({ "ok": false, "error": e })), StatusCode::SERVICE_UNAVAILABLE),
+    })
+}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, std::convert::Infallible> {
+    let (status, error) = if rejection.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Missing or invalid bearer token.")
+    } else if rejection.is_not_found() {
+        (StatusCode::NOT_FOUND, "Unknown endpoint.")
+    } else if rejection.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed.")
+    } else {
+        (StatusCode::BAD_REQUEST, "Bad request.")
+    };
+    Ok(warp::reply::with_status(warp::reply::json(&json!({ "ok": false, "error": error })), status))
+}
+
+/// Compares a presented token with the expected one without returning early at the first
+/// differing byte, so response times do not reveal how much of a guess was right.
+fn token_matches(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len() && given.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_matches_only_the_exact_token() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3creT", "s3cret"));
+        assert!(!token_matches("s3cre", "s3cret"));
+        assert!(!token_matches("", "s3cret"));
+    }
+
+    #[test]
+    fn public_bind_needs_a_token() {
+        let (manager_tx, _manager_rx) = mpsc::channel();
+        let (submitter_tx, _submitter_rx) = mpsc::channel();
+        let addr = SocketAddr::from(([0, 0, 0, 0], 0));
+        let err = spawn_rest_server(addr, None, manager_tx, submitter_tx).unwrap_err();
+        assert!(err.contains("--control-token"));
+    }
+}
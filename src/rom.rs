@@ -3,7 +3,9 @@ use cryptoxide::{
     kdf::argon2,
 };
 
-use std::{fmt, convert::TryInto};
+use std::{borrow::Cow, fmt, convert::TryInto, fs, io::{Read, Write}, path::Path};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 // function to help debug bytestrings
 pub fn print_hex(name: &str, data: &[u8]) {
@@ -15,8 +17,19 @@ pub fn print_hex(name: &str, data: &[u8]) {
 }
 
 pub const DATASET_ACCESS_SIZE: usize = 64;
+/// Accesses within one page of this size share a single read-ahead request.
+const PREFETCH_PAGE_SIZE: u64 = 4096;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RomDigest(pub [u8; 64]);
+
+impl RomDigest {
+    /// Lowercase hex encoding of the digest, suitable for storage and comparison across hosts.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
 impl fmt::Display for RomDigest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "ROM Digest: ")?;
@@ -27,10 +40,283 @@ impl fmt::Display for RomDigest {
     }
 }
 
+/// Which digest structure is built alongside the ROM.
+///
+/// `V1` is the flat digest used by the protocol and is always computed. `V2` additionally
+/// builds a [`RomDigestTree`] over fixed-size chunks, which allows verifying or locating
+/// corruption in part of the ROM without re-hashing all of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RomDigestVersion {
+    #[default]
+    V1,
+    V2 { chunk_size: usize },
+}
+
+/// Merkle-style digest tree over fixed-size ROM chunks.
+///
+/// Leaves are `blake2b-256("rom leaf" || index || chunk)`, inner nodes are
+/// `blake2b-256("rom node" || left || right)`. A level with an odd number of nodes
+/// pairs its last node with itself.
+pub struct RomDigestTree {
+    chunk_size: usize,
+    /// `levels[0]` holds the leaves, the last level holds the single root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+fn tree_leaf(index: usize, chunk: &[u8]) -> [u8; 32] {
+    blake2b::Context::<256>::new()
+        .update(b"rom leaf")
+        .update(&(index as u64).to_le_bytes())
+        .update(chunk)
+        .finalize()
+}
+
+fn tree_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    blake2b::Context::<256>::new()
+        .update(b"rom node")
+        .update(left)
+        .update(right)
+        .finalize()
+}
+
+impl RomDigestTree {
+    fn build(data: &[u8], chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        let mut levels = vec![data.chunks(chunk_size).enumerate().map(|(i, c)| tree_leaf(i, c)).collect::<Vec<_>>()];
+        if levels[0].is_empty() {
+            levels[0].push(tree_leaf(0, &[]));
+        }
+
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| tree_node(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            levels.push(next);
+        }
+        RomDigestTree { chunk_size, levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Sibling hashes from leaf `index` up to (excluding) the root, or `None` if out of range.
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.chunk_count() {
+            return None;
+        }
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = if i.is_multiple_of(2) { level.get(i + 1).unwrap_or(&level[i]) } else { &level[i - 1] };
+            proof.push(*sibling);
+            i /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Checks that `chunk` is the content of leaf `index` under `root`, using a proof from [`Self::proof`].
+    pub fn verify_proof(root: &[u8; 32], index: usize, chunk: &[u8], proof: &[[u8; 32]]) -> bool {
+        let mut hash = tree_leaf(index, chunk);
+        let mut i = index;
+        for sibling in proof {
+            hash = if i.is_multiple_of(2) { tree_node(&hash, sibling) } else { tree_node(sibling, &hash) };
+            i /= 2;
+        }
+        hash == *root
+    }
+}
+
+/// Backing storage of a ROM: generated in memory, memory-mapped from the disk cache, or
+/// regenerated chunk by chunk from the mixing tables (low-memory mode).
+enum RomData {
+    Owned(Vec<u8>),
+    /// Anonymous mapping backed by huge pages (explicit or transparent).
+    Anonymous { map: memmap2::MmapMut, pages: RomPages },
+    /// The ROM bytes start at `offset` in the mapped file (after the cache header). The file
+    /// is kept open for read-ahead requests.
+    Mapped { map: memmap2::Mmap, offset: usize, file: fs::File },
+    /// Only the pre-buffer and offsets are resident; `len` bytes are produced on demand.
+    Regenerated { tables: MixingTables, len: usize },
+}
+
+/// Memory pages backing a ROM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RomPages {
+    /// Regular 4 KiB pages.
+    Standard = 1,
+    /// Anonymous memory advised for transparent huge pages (`MADV_HUGEPAGE`).
+    Transparent = 2,
+    /// Explicit 2 MiB huge pages from the kernel's hugetlb pool (`MAP_HUGETLB`).
+    Explicit = 3,
+    /// Memory-mapped from the disk cache file.
+    FileMapped = 4,
+    /// Not resident: chunks are regenerated from the pre-buffer on every access.
+    LowMemory = 5,
+}
+
+impl fmt::Display for RomPages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RomPages::Standard => "standard pages",
+            RomPages::Transparent => "transparent huge pages (madvise)",
+            RomPages::Explicit => "explicit 2 MiB huge pages",
+            RomPages::FileMapped => "memory-mapped cache file",
+            RomPages::LowMemory => "low-memory (chunks regenerated on demand)",
+        })
+    }
+}
+
+// Process-wide: allocate subsequently generated ROMs with huge pages when possible.
+static HUGE_PAGES: AtomicBool = AtomicBool::new(false);
+// Process-wide: keep only the mixing tables of subsequently generated ROMs resident.
+static LOW_MEMORY: AtomicBool = AtomicBool::new(false);
+// Pages of the most recently allocated ROM (0 = none allocated yet).
+static LAST_ROM_PAGES: AtomicU8 = AtomicU8::new(0);
+
+#[cfg(target_os = "linux")]
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+/// log2 of `HUGE_PAGE_SIZE`, as expected by `MAP_HUGE_SHIFT`.
+#[cfg(target_os = "linux")]
+const HUGE_PAGE_BITS: u8 = 21;
+
+/// Requests huge pages for every subsequently allocated ROM. A 1 GiB ROM accessed at random
+/// offsets thrashes the TLB with 4 KiB pages. Allocation falls back to transparent huge pages,
+/// then to standard pages, so enabling this never fails. Only Linux is supported today; other
+/// platforms always use standard pages.
+pub fn set_huge_pages(enabled: bool) {
+    HUGE_PAGES.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether huge pages are requested for new ROMs.
+pub fn huge_pages() -> bool {
+    HUGE_PAGES.load(Ordering::Relaxed)
+}
+
+/// Generates every subsequent ROM in low-memory mode (see [`Rom::new_low_memory`]) so that
+/// devices that cannot hold a 1 GiB ROM can still mine, at a much lower hashrate. Cached ROM
+/// files are still memory-mapped, which the kernel can page out under memory pressure.
+pub fn set_low_memory(enabled: bool) {
+    LOW_MEMORY.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether new ROMs are generated in low-memory mode.
+pub fn low_memory() -> bool {
+    LOW_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Pages backing the most recently allocated ROM, if any ROM has been allocated yet.
+pub fn last_rom_pages() -> Option<RomPages> {
+    match LAST_ROM_PAGES.load(Ordering::Relaxed) {
+        1 => Some(RomPages::Standard),
+        2 => Some(RomPages::Transparent),
+        3 => Some(RomPages::Explicit),
+        4 => Some(RomPages::FileMapped),
+        5 => Some(RomPages::LowMemory),
+        _ => None,
+    }
+}
+
+/// Allocates a zeroed ROM buffer of `size` bytes, using huge pages if they are requested
+/// and available.
+fn allocate_rom_data(size: usize) -> RomData {
+    #[cfg(target_os = "linux")]
+    if huge_pages() && size > 0 {
+        // hugetlb mappings must be a whole number of huge pages.
+        if size.is_multiple_of(HUGE_PAGE_SIZE)
+            && let Ok(map) = memmap2::MmapOptions::new().len(size).huge(Some(HUGE_PAGE_BITS)).map_anon()
+        {
+            return RomData::Anonymous { map, pages: RomPages::Explicit };
+        }
+        if let Ok(map) = memmap2::MmapMut::map_anon(size)
+            && map.advise(memmap2::Advice::HugePage).is_ok()
+        {
+            return RomData::Anonymous { map, pages: RomPages::Transparent };
+        }
+    }
+    RomData::Owned(vec![0; size])
+}
+
+impl RomData {
+    fn pages(&self) -> RomPages {
+        match self {
+            RomData::Owned(_) => RomPages::Standard,
+            RomData::Anonymous { pages, .. } => *pages,
+            RomData::Mapped { .. } => RomPages::FileMapped,
+            RomData::Regenerated { .. } => RomPages::LowMemory,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            RomData::Regenerated { len, .. } => *len,
+            data => data.resident().map_or(0, <[u8]>::len),
+        }
+    }
+
+    /// The ROM bytes, unless the ROM is regenerated on demand.
+    fn resident(&self) -> Option<&[u8]> {
+        match self {
+            RomData::Owned(data) => Some(data),
+            RomData::Anonymous { map, .. } => Some(map),
+            RomData::Mapped { map, offset, .. } => Some(&map[*offset..]),
+            RomData::Regenerated { .. } => None,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> Option<&mut [u8]> {
+        match self {
+            RomData::Owned(data) => Some(data),
+            RomData::Anonymous { map, .. } => Some(map),
+            RomData::Mapped { .. } | RomData::Regenerated { .. } => None,
+        }
+    }
+}
+
+/// Magic bytes at the start of a cached ROM file (format version 1).
+const ROM_FILE_MAGIC: &[u8; 8] = b"SHROM\0\0\x01";
+/// Cache header: magic, ROM size (u64 LE), digest.
+const ROM_FILE_HEADER_SIZE: usize = 8 + 8 + 64;
+
 /// The **R**ead **O**only **M**emory used to generate the proram.
 pub struct Rom {
     pub digest: RomDigest,
-    data: Vec<u8>,
+    tree: Option<RomDigestTree>,
+    data: RomData,
+}
+
+/// Per-chunk checksums of a ROM, taken while the ROM is known to be good.
+/// Used to detect silent memory corruption on long runs (non-ECC RAM).
+pub struct RomChecksums {
+    chunk_size: usize,
+    sums: Vec<[u8; 8]>,
+}
+
+impl RomChecksums {
+    /// Number of checksummed chunks.
+    pub fn len(&self) -> usize {
+        self.sums.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+}
+
+fn chunk_checksum(chunk: &[u8]) -> [u8; 8] {
+    let hash = blake2b::Context::<256>::new().update(chunk).finalize();
+    hash[..8].try_into().unwrap()
 }
 
 /// The generation type of the **ROM**.
@@ -43,6 +329,63 @@ pub enum RomGenerationType {
     },
 }
 
+/// Size and TwoStep generation parameters of a challenge ROM.
+///
+/// The defaults are the protocol values (1 GiB ROM mixed from a 16 MiB pre-buffer with 4
+/// mixing numbers). Missing fields in a deserialized value take their default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RomParams {
+    pub size: usize,
+    pub pre_size: usize,
+    pub mixing_numbers: usize,
+}
+
+impl Default for RomParams {
+    fn default() -> Self {
+        const MB: usize = 1024 * 1024;
+        RomParams {
+            size: 1024 * MB,
+            pre_size: 16 * MB,
+            mixing_numbers: 4,
+        }
+    }
+}
+
+impl RomParams {
+    /// The TwoStep generation type for these parameters.
+    pub fn gen_type(&self) -> RomGenerationType {
+        RomGenerationType::TwoStep {
+            pre_size: self.pre_size,
+            mixing_numbers: self.mixing_numbers,
+        }
+    }
+
+    /// Checks the constraints ROM generation relies on.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size == 0 || !self.size.is_multiple_of(DATASET_ACCESS_SIZE) || self.size > u32::MAX as usize {
+            return Err(format!("ROM size {} must be a non-zero multiple of {} bytes below 4 GiB.", self.size, DATASET_ACCESS_SIZE));
+        }
+        if !self.pre_size.is_power_of_two() || self.pre_size < DATASET_ACCESS_SIZE {
+            return Err(format!("ROM pre-size {} must be a power of two of at least {} bytes.", self.pre_size, DATASET_ACCESS_SIZE));
+        }
+        if self.mixing_numbers == 0 {
+            return Err("ROM mixing numbers must be at least 1.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Identifier for a ROM built from `key` with these parameters: the key itself for the
+    /// protocol defaults, otherwise the key suffixed with the parameters.
+    pub fn rom_id(&self, key: &str) -> String {
+        if *self == RomParams::default() {
+            key.to_string()
+        } else {
+            format!("{}-{}-{}-{}", key, self.size, self.pre_size, self.mixing_numbers)
+        }
+    }
+}
+
 // --- DEBUG STRUCT ---
 
 /// State required to generate the next chunk index and perform XOR mixing.
@@ -93,7 +436,8 @@ pub fn digest_to_u16s(digest: &[u8; 64]) -> impl Iterator<Item = u16> {
 
 impl Rom {
     pub fn new(key: &[u8], gen_type: RomGenerationType, size: usize) -> Self {
-        let mut data = vec![0; size];
+        let mut data = allocate_rom_data(size);
+        LAST_ROM_PAGES.store(data.pages() as u8, Ordering::Relaxed);
         let size_bytes = (data.len() as u32).to_le_bytes();
 
         let seed = blake2b::Context::<256>::new()
@@ -101,26 +445,442 @@ impl Rom {
             .update(key)
             .finalize();
 
-        let digest = random_gen(gen_type, seed, &mut data);
-        Self { digest, data }
+        let digest = random_gen(gen_type, seed, data.as_mut_slice().unwrap());
+        Self { digest, tree: None, data }
     }
 
-    pub(crate) fn at(&self, i: u32) -> &[u8; DATASET_ACCESS_SIZE] {
-        let start = i as usize % (self.data.len() / DATASET_ACCESS_SIZE);
-        <&[u8; DATASET_ACCESS_SIZE]>::try_from(&self.data[start..start + DATASET_ACCESS_SIZE])
-            .unwrap()
+    /// Builds a ROM of which only the pre-buffer and the per-chunk offsets stay resident
+    /// (about `pre_size + size / 64` bytes, 32 MiB for the protocol ROM). Each access
+    /// regenerates its 64-byte chunk by mixing the pre-buffer, which costs hashrate but lets
+    /// small devices mine at all. The digest is identical to the resident ROM's.
+    ///
+    /// Only TwoStep generation can be regenerated chunk by chunk; `FullRandom` ROMs are always
+    /// built in memory.
+    pub fn new_low_memory(key: &[u8], gen_type: RomGenerationType, size: usize) -> Self {
+        let RomGenerationType::TwoStep { pre_size, mixing_numbers } = gen_type else {
+            return Self::new(key, gen_type, size);
+        };
+        let seed = blake2b::Context::<256>::new()
+            .update(&(size as u32).to_le_bytes())
+            .update(key)
+            .finalize();
+        let tables = MixingTables::new(seed, pre_size, mixing_numbers, size / DATASET_ACCESS_SIZE);
+
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let mut digest = blake2b::Context::<512>::new();
+        let mut window = vec![0; LOW_MEMORY_WINDOW.min(size)];
+        for start in (0..size).step_by(LOW_MEMORY_WINDOW) {
+            let window = &mut window[..LOW_MEMORY_WINDOW.min(size - start)];
+            tables.fill(start / DATASET_ACCESS_SIZE, window, threads);
+            digest.update_mut(window);
+        }
+
+        LAST_ROM_PAGES.store(RomPages::LowMemory as u8, Ordering::Relaxed);
+        Self {
+            digest: RomDigest(digest.finalize().as_slice().try_into().unwrap()),
+            tree: None,
+            data: RomData::Regenerated { tables, len: size },
+        }
+    }
+
+    /// Writes the raw ROM bytes to `out`, regenerating them window by window in low-memory
+    /// mode.
+    pub fn write_bytes<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        match &self.data {
+            RomData::Regenerated { tables, len } => {
+                let mut window = vec![0; LOW_MEMORY_WINDOW.min(*len)];
+                for start in (0..*len).step_by(LOW_MEMORY_WINDOW) {
+                    let window = &mut window[..LOW_MEMORY_WINDOW.min(len - start)];
+                    tables.fill(start / DATASET_ACCESS_SIZE, window, 1);
+                    out.write_all(window)?;
+                }
+                Ok(())
+            }
+            data => out.write_all(data.resident().unwrap()),
+        }
+    }
+
+    /// Writes the ROM to `path` in the disk cache format (header with size and digest,
+    /// followed by the raw ROM bytes). The file is written next to `path` and renamed into
+    /// place, so a crash never leaves a truncated cache file under the final name.
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let tmp_path = path.with_extension("rom.tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(fs::File::create(&tmp_path)?);
+            self.to_writer(&mut file)?;
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()
+        };
+
+        write()
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                format!("Failed to write ROM cache file {}: {}", path.display(), e)
+            })
+    }
+
+    /// Serializes the ROM in the cache file format: header (magic, size and digest) followed
+    /// by the raw ROM bytes. Lets a ROM generated once on a fast machine be shipped to slower
+    /// workers, which load it with [`Rom::from_reader`].
+    pub fn to_writer<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        out.write_all(ROM_FILE_MAGIC)?;
+        out.write_all(&(self.data.len() as u64).to_le_bytes())?;
+        out.write_all(&self.digest.0)?;
+        self.write_bytes(out)
+    }
+
+    /// Reads a ROM written by [`Rom::to_writer`] (or a ROM cache file) into memory.
+    ///
+    /// The header must announce `expected_size` bytes and the body must hash to the digest in
+    /// the header, which catches truncation and corruption in transit. The header digest comes
+    /// from the sender, so callers that do not trust it should compare `digest` against one
+    /// they computed or obtained independently.
+    pub fn from_reader<R: Read>(mut reader: R, expected_size: usize) -> Result<Self, String> {
+        let mut header = [0u8; ROM_FILE_HEADER_SIZE];
+        reader.read_exact(&mut header).map_err(|e| format!("Failed to read ROM header: {}", e))?;
+        if &header[..8] != ROM_FILE_MAGIC {
+            return Err("ROM stream has an unexpected format.".to_string());
+        }
+        let stored_size = u64::from_le_bytes(header[8..16].try_into().unwrap()) as usize;
+        if stored_size != expected_size {
+            return Err(format!("ROM stream has size {} (expected {}).", stored_size, expected_size));
+        }
+        let digest = RomDigest(header[16..].try_into().unwrap());
+
+        let mut data = allocate_rom_data(expected_size);
+        let body = data.as_mut_slice().unwrap();
+        reader.read_exact(body).map_err(|e| format!("Failed to read ROM body: {}", e))?;
+        if blake2b::Context::<512>::new().update(body).finalize() != digest.0 {
+            return Err("ROM stream failed its digest check.".to_string());
+        }
+        LAST_ROM_PAGES.store(data.pages() as u8, Ordering::Relaxed);
+
+        Ok(Self { digest, tree: None, data })
+    }
+
+    /// Memory-maps a ROM previously written by [`Rom::save_to_file`].
+    ///
+    /// The file must have the expected size and its contents must hash to the digest stored
+    /// in its header; otherwise an error is returned and the caller should regenerate the ROM.
+    pub fn load_from_file(path: &Path, expected_size: usize) -> Result<Self, String> {
+        let file = fs::File::open(path)
+            .map_err(|e| format!("Failed to open ROM cache file {}: {}", path.display(), e))?;
+
+        // SAFETY: cache files are only ever replaced by rename, never modified in place,
+        // so the mapping stays valid for the lifetime of the Rom.
+        let map = unsafe { memmap2::Mmap::map(&file) }
+            .map_err(|e| format!("Failed to map ROM cache file {}: {}", path.display(), e))?;
+
+        if map.len() != ROM_FILE_HEADER_SIZE + expected_size || &map[..8] != ROM_FILE_MAGIC {
+            return Err(format!("ROM cache file {} has an unexpected format or size.", path.display()));
+        }
+        let stored_size = u64::from_le_bytes(map[8..16].try_into().unwrap()) as usize;
+        if stored_size != expected_size {
+            return Err(format!("ROM cache file {} has size {} (expected {}).", path.display(), stored_size, expected_size));
+        }
+
+        let digest = RomDigest(map[16..ROM_FILE_HEADER_SIZE].try_into().unwrap());
+        let actual = blake2b::Context::<512>::new().update(&map[ROM_FILE_HEADER_SIZE..]).finalize();
+        if actual != digest.0 {
+            return Err(format!("ROM cache file {} failed its digest check.", path.display()));
+        }
+
+        // With huge pages requested, copy into a huge-page buffer: a file mapping only ever
+        // gets standard pages, and the copy is cheap next to the TLB misses it saves. In
+        // low-memory mode the mapping is kept, since its pages can be evicted.
+        let mut data = if huge_pages() && !low_memory() { allocate_rom_data(expected_size) } else { RomData::Owned(Vec::new()) };
+        if data.pages() == RomPages::Standard {
+            data = RomData::Mapped { map, offset: ROM_FILE_HEADER_SIZE, file };
+        } else {
+            data.as_mut_slice().unwrap().copy_from_slice(&map[ROM_FILE_HEADER_SIZE..]);
+        }
+        LAST_ROM_PAGES.store(data.pages() as u8, Ordering::Relaxed);
+
+        Ok(Self { digest, tree: None, data })
+    }
+
+    /// Memory pages backing this ROM.
+    pub fn pages(&self) -> RomPages {
+        self.data.pages()
+    }
+
+    /// True when the ROM is memory-mapped from the disk cache rather than held in memory.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.data, RomData::Mapped { .. })
+    }
+
+    #[cfg(test)]
+    fn data_mut(&mut self) -> &mut [u8] {
+        self.data.as_mut_slice().expect("mapped ROMs are read-only")
+    }
+
+    /// Generates the ROM for `key` with the given parameters, in low-memory mode if it is
+    /// enabled.
+    pub fn from_params(key: &[u8], params: &RomParams) -> Self {
+        if low_memory() {
+            Self::new_low_memory(key, params.gen_type(), params.size)
+        } else {
+            Self::new(key, params.gen_type(), params.size)
+        }
+    }
+
+    /// Like [`Rom::new`], but also builds the digest structure selected by `version`.
+    pub fn with_digest_version(key: &[u8], gen_type: RomGenerationType, size: usize, version: RomDigestVersion) -> Self {
+        let mut rom = Self::new(key, gen_type, size);
+        if let RomDigestVersion::V2 { chunk_size } = version {
+            rom.tree = Some(RomDigestTree::build(rom.data.resident().unwrap(), chunk_size));
+        }
+        rom
+    }
+
+    /// The chunked digest tree, present when the ROM was built with [`RomDigestVersion::V2`].
+    pub fn digest_tree(&self) -> Option<&RomDigestTree> {
+        self.tree.as_ref()
+    }
+
+    /// Bytes of chunk `index` under the digest tree's chunking, if a tree is present.
+    pub fn tree_chunk(&self, index: usize) -> Option<&[u8]> {
+        let tree = self.tree.as_ref()?;
+        let data = self.data.resident()?;
+        let start = index.checked_mul(tree.chunk_size)?;
+        if start >= data.len() {
+            return None;
+        }
+        Some(&data[start..(start + tree.chunk_size).min(data.len())])
+    }
+
+    /// Re-hashes every chunk against the digest tree's leaves and returns the indices that
+    /// no longer match. Empty when the ROM is intact or no tree was built.
+    pub fn corrupted_tree_chunks(&self) -> Vec<usize> {
+        let (Some(tree), Some(data)) = (&self.tree, self.data.resident()) else {
+            return Vec::new();
+        };
+        data
+            .chunks(tree.chunk_size)
+            .enumerate()
+            .filter(|(i, chunk)| tree_leaf(*i, chunk) != tree.levels[0][*i])
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Size of the ROM in bytes.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checksums every `chunk_size` bytes of the ROM (the last chunk may be shorter).
+    /// Empty for low-memory ROMs, which hold no ROM bytes that could be corrupted.
+    pub fn chunk_checksums(&self, chunk_size: usize) -> RomChecksums {
+        let chunk_size = chunk_size.max(1);
+        RomChecksums {
+            chunk_size,
+            sums: self.data.resident().map_or_else(Vec::new, |data| data.chunks(chunk_size).map(chunk_checksum).collect()),
+        }
+    }
+
+    /// Re-hashes the given chunk indices and returns those whose checksum no longer matches.
+    /// Out-of-range indices are ignored.
+    pub fn verify_chunks(&self, checksums: &RomChecksums, indices: &[usize]) -> Vec<usize> {
+        let Some(data) = self.data.resident() else {
+            return Vec::new();
+        };
+        indices
+            .iter()
+            .copied()
+            .filter(|&i| i < checksums.sums.len())
+            .filter(|&i| {
+                let start = i * checksums.chunk_size;
+                let end = (start + checksums.chunk_size).min(data.len());
+                chunk_checksum(&data[start..end]) != checksums.sums[i]
+            })
+            .collect()
+    }
+
+    /// Byte offset in the ROM read by a memory access to address `i`.
+    fn access_offset(&self, i: u32) -> usize {
+        i as usize % (self.data.len() / DATASET_ACCESS_SIZE)
+    }
+
+    /// True when memory accesses should be announced with [`Rom::prefetch`] first.
+    pub(crate) fn wants_prefetch(&self) -> bool {
+        matches!(self.data, RomData::Mapped { .. }) && crate::prefetch::rom_prefetch()
+    }
+
+    /// Starts reading the pages behind the given memory addresses from the cache file, so the
+    /// accesses that follow do not stall on page faults. Only file-mapped ROMs are prefetched.
+    pub(crate) fn prefetch(&self, addresses: &[u32]) {
+        let RomData::Mapped { offset, file, .. } = &self.data else {
+            return;
+        };
+        let mut offsets: Vec<u64> = addresses
+            .iter()
+            .map(|&i| (offset + self.access_offset(i)) as u64)
+            .collect();
+        offsets.sort_unstable();
+        offsets.dedup_by(|a, b| *a / PREFETCH_PAGE_SIZE == *b / PREFETCH_PAGE_SIZE);
+        crate::prefetch::willneed(file, &offsets, DATASET_ACCESS_SIZE as u32);
+    }
+
+    /// The chunk read by a memory access to address `i`. Resident ROMs hand out a view of
+    /// their memory; only low-memory ROMs, which regenerate the chunk, return a copy.
+    pub(crate) fn at(&self, i: u32) -> Cow<'_, [u8; DATASET_ACCESS_SIZE]> {
+        let start = self.access_offset(i);
+        match &self.data {
+            RomData::Regenerated { tables, .. } => {
+                // Chunk `n` occupies bytes n*64.., so the chunk overlapping `start` is read
+                // from the (at most two) chunks around it.
+                let mut chunks = [0; 2 * DATASET_ACCESS_SIZE];
+                let offset = start % DATASET_ACCESS_SIZE;
+                let count = if offset == 0 { 1 } else { 2 };
+                tables.fill(start / DATASET_ACCESS_SIZE, &mut chunks[..count * DATASET_ACCESS_SIZE], 1);
+                Cow::Owned(chunks[offset..offset + DATASET_ACCESS_SIZE].try_into().unwrap())
+            }
+            data => Cow::Borrowed(data.resident().unwrap()[start..start + DATASET_ACCESS_SIZE].try_into().unwrap()),
+        }
     }
 }
 
 
+/// Loaded ROMs, one per distinct ROM digest, with least-recently-used eviction. Lets several
+/// active challenges be mined or verified at once without regenerating their ROMs.
+///
+/// Entries are looked up by ROM ID (see [`RomParams::rom_id`]), which is known before the ROM
+/// is built, or by digest. Evicting an entry only drops the store's reference; the memory is
+/// freed once every miner holding the `Arc` has stopped.
+pub struct RomStore {
+    capacity: usize,
+    /// Least recently used first.
+    entries: Vec<(String, Arc<Rom>)>,
+}
+
+impl RomStore {
+    /// A store holding at most `capacity` ROMs (at least one).
+    pub fn new(capacity: usize) -> Self {
+        RomStore { capacity: capacity.max(1), entries: Vec::new() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn touch(&mut self, index: usize) -> Arc<Rom> {
+        let entry = self.entries.remove(index);
+        let rom = entry.1.clone();
+        self.entries.push(entry);
+        rom
+    }
+
+    /// The ROM stored under `rom_id`, marked as most recently used.
+    pub fn get(&mut self, rom_id: &str) -> Option<Arc<Rom>> {
+        let index = self.entries.iter().position(|(id, _)| id == rom_id)?;
+        Some(self.touch(index))
+    }
+
+    /// The ROM with this digest, marked as most recently used.
+    pub fn get_by_digest(&mut self, digest: &RomDigest) -> Option<Arc<Rom>> {
+        let index = self.entries.iter().position(|(_, rom)| rom.digest == *digest)?;
+        Some(self.touch(index))
+    }
+
+    /// Evicts least recently used ROMs until one more fits, and returns their IDs. Call this
+    /// before building a ROM so the evicted ones are not held alongside it.
+    pub fn make_room(&mut self) -> Vec<String> {
+        let excess = (self.entries.len() + 1).saturating_sub(self.capacity);
+        self.entries.drain(..excess).map(|(id, _)| id).collect()
+    }
+
+    /// Stores `rom` under `rom_id` as the most recently used entry, replacing any entry with
+    /// the same ID or digest, and returns the IDs of the evicted ROMs.
+    pub fn insert(&mut self, rom_id: String, rom: Arc<Rom>) -> Vec<String> {
+        self.entries.retain(|(id, stored)| *id != rom_id && stored.digest != rom.digest);
+        let evicted = self.make_room();
+        self.entries.push((rom_id, rom));
+        evicted
+    }
+
+    /// Removes the ROM stored under `rom_id`.
+    pub fn remove(&mut self, rom_id: &str) -> Option<Arc<Rom>> {
+        let index = self.entries.iter().position(|(id, _)| id == rom_id)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Stored ROM IDs, least recently used first.
+    pub fn rom_ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(id, _)| id.as_str())
+    }
+}
+
 fn random_gen(gen_type: RomGenerationType, seed: [u8; 32], output: &mut [u8]) -> RomDigest {
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    random_gen_threaded(gen_type, seed, output, threads)
+}
+
+/// ROM generation with the TwoStep mixing phase split across `threads`. Every output chunk
+/// depends only on the pre-buffer and its own index, so contiguous slices are filled
+/// independently; the digest is then taken over the chunks in order, which keeps it
+/// byte-for-byte identical to the sequential generation.
+fn random_gen_threaded(gen_type: RomGenerationType, seed: [u8; 32], output: &mut [u8], threads: usize) -> RomDigest {
     if let RomGenerationType::TwoStep { pre_size, mixing_numbers } = gen_type {
+        let tables = MixingTables::new(seed, pre_size, mixing_numbers, output.len() / DATASET_ACCESS_SIZE);
+        tables.fill(0, output, threads);
 
+        let mut digest = blake2b::Context::<512>::new();
+        for chunk in output.chunks(DATASET_ACCESS_SIZE) {
+            digest.update_mut(chunk);
+        }
+        RomDigest(digest.finalize().as_slice().try_into().unwrap())
+
+    } else {
+        argon2::hprime(output, &seed);
+        RomDigest(blake2b::Context::<512>::new().update(output).finalize().as_slice().try_into().unwrap())
+    }
+}
+
+/// Bytes regenerated at a time when a low-memory ROM is digested or written out.
+const LOW_MEMORY_WINDOW: usize = 4 * 1024 * 1024;
+
+/// Everything TwoStep generation needs to produce any output chunk: the pre-buffer, the
+/// per-chunk base offsets and the mixing offsets. Every chunk depends only on these tables and
+/// its own index.
+struct MixingTables {
+    mixing_buffer: Vec<u8>,
+    offsets: Vec<u8>,
+    offsets_diff: Vec<u16>,
+    nb_source_chunks: u32,
+    mixing_numbers: usize,
+}
+
+impl MixingTables {
+    fn new(seed: [u8; 32], pre_size: usize, mixing_numbers: usize, nb_chunks_bytes: usize) -> Self {
         assert!(pre_size.is_power_of_two());
-        let mut mixing_buffer = vec![0; pre_size];
 
-        // FIX: The seed used for hprime must be a slice reference, not an array.
-        argon2::hprime(&mut mixing_buffer, &seed);
+        // The two H' expansions are independent of each other (each one is inherently sequential).
+        let (mixing_buffer, offsets) = std::thread::scope(|s| {
+            let mixing = s.spawn(|| {
+                let mut mixing_buffer = vec![0; pre_size];
+                // FIX: The seed used for hprime must be a slice reference, not an array.
+                argon2::hprime(&mut mixing_buffer, &seed);
+                mixing_buffer
+            });
+
+            let mut offsets_bytes = vec![0; nb_chunks_bytes];
+            let offset_bytes_input = blake2b::Context::<512>::new()
+                .update(&seed)
+                .update(b"generation offset base")
+                .finalize();
+            // FIX: Passing Vec<u8> slice reference correctly
+            argon2::hprime(&mut offsets_bytes, &offset_bytes_input);
+
+            (mixing.join().expect("ROM pre-buffer thread panicked"), offsets_bytes)
+        });
 
         const OFFSET_LOOPS: u32 = 4;
 
@@ -135,44 +895,49 @@ fn random_gen(gen_type: RomGenerationType, seed: [u8; 32], output: &mut [u8]) ->
             offsets_diff.extend(digest_to_u16s(&command.as_slice().try_into().unwrap()));
         }
 
-        let nb_chunks_bytes = output.len() / DATASET_ACCESS_SIZE;
-        let mut offsets_bytes = vec![0; nb_chunks_bytes];
-
-        let offset_bytes_input = blake2b::Context::<512>::new()
-            .update(&seed)
-            .update(b"generation offset base")
-            .finalize();
-        // FIX: Passing Vec<u8> slice reference correctly
-        argon2::hprime(&mut offsets_bytes, &offset_bytes_input);
+        MixingTables {
+            mixing_buffer,
+            offsets,
+            offsets_diff,
+            nb_source_chunks: (pre_size / DATASET_ACCESS_SIZE) as u32,
+            mixing_numbers,
+        }
+    }
 
-        let offsets = offsets_bytes;
+    /// Writes output chunk `i` into `chunk`.
+    fn mix_chunk(&self, i: usize, chunk: &mut [u8]) {
+        let nb_source_chunks = self.nb_source_chunks;
+        let start_idx = self.offsets[i % self.offsets.len()] as u32 % nb_source_chunks;
+        let idx0 = (i as u32) % nb_source_chunks;
+        let offset = (idx0 as usize).wrapping_mul(DATASET_ACCESS_SIZE);
+        let input = &self.mixing_buffer[offset..offset + DATASET_ACCESS_SIZE];
+        chunk.copy_from_slice(input);
+
+        for d in 1..self.mixing_numbers {
+            let idx = start_idx.wrapping_add(self.offsets_diff[(d - 1) % self.offsets_diff.len()] as u32)
+                % nb_source_chunks;
+            let offset = (idx as usize).wrapping_mul(DATASET_ACCESS_SIZE);
+            let input = &self.mixing_buffer[offset..offset + DATASET_ACCESS_SIZE];
+            xorbuf(chunk, input);
+        }
+    }
 
-        let mut digest = blake2b::Context::<512>::new();
-        let nb_source_chunks = (pre_size / DATASET_ACCESS_SIZE) as u32;
-
-        for (i, chunk) in output.chunks_mut(DATASET_ACCESS_SIZE).enumerate() {
-
-            let start_idx = offsets[i % offsets.len()] as u32 % nb_source_chunks;
-            let idx0 = (i as u32) % nb_source_chunks;
-            let offset = (idx0 as usize).wrapping_mul(DATASET_ACCESS_SIZE);
-            let input = &mixing_buffer[offset..offset + DATASET_ACCESS_SIZE];
-            chunk.copy_from_slice(input);
-
-            for d in 1..mixing_numbers {
-                let idx = start_idx.wrapping_add(offsets_diff[(d - 1) % offsets_diff.len()] as u32)
-                    % nb_source_chunks;
-                let offset = (idx as usize).wrapping_mul(DATASET_ACCESS_SIZE);
-                let input = &mixing_buffer[offset..offset + DATASET_ACCESS_SIZE];
-                xorbuf(chunk, input);
+    /// Fills `output` with the chunks starting at index `first_chunk`, split across `threads`
+    /// contiguous slices.
+    fn fill(&self, first_chunk: usize, output: &mut [u8], threads: usize) {
+        if threads <= 1 {
+            for (i, chunk) in output.chunks_mut(DATASET_ACCESS_SIZE).enumerate() {
+                self.mix_chunk(first_chunk + i, chunk);
             }
-
-            digest.update_mut(chunk);
+            return;
         }
-        RomDigest(digest.finalize().as_slice().try_into().unwrap())
-
-    } else {
-        argon2::hprime(output, &seed);
-        RomDigest(blake2b::Context::<512>::new().update(output).finalize().as_slice().try_into().unwrap())
+        let nb_chunks = output.len() / DATASET_ACCESS_SIZE;
+        let chunks_per_thread = nb_chunks.div_ceil(threads).max(1);
+        std::thread::scope(|s| {
+            for (part, slice) in output.chunks_mut(chunks_per_thread * DATASET_ACCESS_SIZE).enumerate() {
+                s.spawn(move || self.fill(first_chunk + part * chunks_per_thread, slice, 1));
+            }
+        });
     }
 }
 
@@ -309,7 +1074,8 @@ pub fn build_rom_from_state(mut state: RomMixingState, size: usize) -> Rom {
 
     Rom {
         digest: final_digest,
-        data: rom_data_vec,
+        tree: None,
+        data: RomData::Owned(rom_data_vec),
     }
 }
 
@@ -333,7 +1099,7 @@ mod tests {
             SIZE,
         );
 
-        for byte in rom.data {
+        for &byte in rom.data.resident().unwrap() {
             let index = byte as usize;
             distribution[index] += 1;
         }
@@ -355,4 +1121,233 @@ mod tests {
                 .all(|&count| count > MIN && count < MAX)
         );
     }
+
+    #[test]
+    fn rom_threaded_generation_matches_sequential() {
+        // Digest and content hash of this ROM as generated by the sequential implementation
+        // the threaded one replaced.
+        const LEGACY_DIGEST: &str = "726ecb4ffbdff6c33c8c3aa9db212d2e2ce5a1d3fabe95217d767108a0b4654769f80d44945b64638d979f3a51d21254925cbe70e596be92c47cb6ac8bd9e810";
+        const LEGACY_CONTENT_HASH: &str = "d6b83585023b1b7f2c56bca4225c85a988adab774f58ca492efdf4640066cf35";
+        let gen_type = RomGenerationType::TwoStep {
+            pre_size: 64 * 1024,
+            mixing_numbers: 4,
+        };
+        let seed = [7u8; 32];
+
+        // Thread counts that do and do not divide the chunk count evenly.
+        for threads in [1, 2, 3, 7, 64] {
+            let mut output = vec![0; 1024 * 1024];
+            let digest = random_gen_threaded(gen_type, seed, &mut output, threads);
+            assert_eq!(hex::encode(digest.0), LEGACY_DIGEST, "digest differs with {} threads", threads);
+            let content_hash = blake2b::Context::<256>::new().update(&output).finalize();
+            assert_eq!(hex::encode(content_hash), LEGACY_CONTENT_HASH, "ROM bytes differ with {} threads", threads);
+        }
+    }
+
+    #[test]
+    fn rom_low_memory_matches_resident() {
+        let gen_type = RomGenerationType::TwoStep {
+            pre_size: 64 * 1024,
+            mixing_numbers: 4,
+        };
+        // Not a multiple of LOW_MEMORY_WINDOW, so the last window is partial.
+        const SIZE: usize = LOW_MEMORY_WINDOW + 1024 * 1024;
+        let resident = Rom::new(b"password", gen_type, SIZE);
+        let paged = Rom::new_low_memory(b"password", gen_type, SIZE);
+
+        assert_eq!(paged.pages(), RomPages::LowMemory);
+        assert_eq!(paged.size(), SIZE);
+        assert_eq!(paged.digest.0, resident.digest.0);
+        for i in [0, 1, 63, 64, 12345, 65535, u32::MAX] {
+            assert_eq!(paged.at(i), resident.at(i), "chunk at {} differs", i);
+        }
+
+        let mut bytes = Vec::new();
+        paged.write_bytes(&mut bytes).unwrap();
+        assert!(bytes == resident.data.resident().unwrap());
+        assert!(paged.chunk_checksums(64 * 1024).is_empty());
+    }
+
+    #[test]
+    fn rom_store_evicts_least_recently_used() {
+        let gen_type = RomGenerationType::TwoStep {
+            pre_size: 64 * 1024,
+            mixing_numbers: 4,
+        };
+        let rom = |key: &[u8]| Arc::new(Rom::new(key, gen_type, 64 * 1024));
+        let mut store = RomStore::new(2);
+
+        assert!(store.insert("a".to_string(), rom(b"a")).is_empty());
+        assert!(store.insert("b".to_string(), rom(b"b")).is_empty());
+        let digest_a = store.get("a").unwrap().digest;
+
+        // "b" is now the least recently used.
+        assert_eq!(store.insert("c".to_string(), rom(b"c")), vec!["b".to_string()]);
+        assert!(store.get("b").is_none());
+        assert!(store.get_by_digest(&digest_a).is_some());
+        assert_eq!(store.rom_ids().collect::<Vec<_>>(), vec!["c", "a"]);
+
+        // Re-inserting an ID replaces it without evicting anything else.
+        assert!(store.insert("c".to_string(), rom(b"c")).is_empty());
+        assert_eq!(store.len(), 2);
+
+        assert_eq!(store.make_room(), vec!["a".to_string()]);
+        assert!(store.remove("c").is_some());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn rom_params_defaults_and_validation() {
+        let protocol = RomParams::default();
+        assert_eq!((protocol.size, protocol.pre_size, protocol.mixing_numbers), (1024 * 1024 * 1024, 16 * 1024 * 1024, 4));
+        assert!(protocol.validate().is_ok());
+        assert_eq!(protocol.rom_id("abc"), "abc");
+
+        // Missing fields in challenge JSON take the protocol defaults.
+        let partial: RomParams = serde_json::from_str(r#"{"size": 1048576}"#).unwrap();
+        assert_eq!(partial, RomParams { size: 1024 * 1024, ..protocol });
+        assert_eq!(partial.rom_id("abc"), "abc-1048576-16777216-4");
+
+        assert!(RomParams { pre_size: 3 * 1024, ..protocol }.validate().is_err());
+        assert!(RomParams { size: 100, ..protocol }.validate().is_err());
+        assert!(RomParams { mixing_numbers: 0, ..protocol }.validate().is_err());
+
+        let small = RomParams { size: 1024 * 1024, pre_size: 64 * 1024, mixing_numbers: 4 };
+        let rom = Rom::from_params(b"password", &small);
+        assert_eq!(rom.digest.0, Rom::new(b"password", small.gen_type(), small.size).digest.0);
+    }
+
+    #[test]
+    fn rom_chunk_checksums_detect_corruption() {
+        let mut rom = Rom::new(
+            b"password",
+            RomGenerationType::TwoStep {
+                pre_size: 64 * 1024,
+                mixing_numbers: 4,
+            },
+            1024 * 1024,
+        );
+        let checksums = rom.chunk_checksums(64 * 1024);
+        let all: Vec<usize> = (0..checksums.len()).collect();
+        assert_eq!(checksums.len(), 16);
+        assert!(rom.verify_chunks(&checksums, &all).is_empty());
+
+        // Flip one bit in chunk 5
+        rom.data_mut()[5 * 64 * 1024 + 17] ^= 0x01;
+        assert_eq!(rom.verify_chunks(&checksums, &all), vec![5]);
+    }
+
+    #[test]
+    fn rom_digest_tree_proofs_and_corruption() {
+        let gen_type = RomGenerationType::TwoStep {
+            pre_size: 64 * 1024,
+            mixing_numbers: 4,
+        };
+        let flat = Rom::new(b"password", gen_type, 1024 * 1024);
+        let mut rom = Rom::with_digest_version(
+            b"password",
+            gen_type,
+            1024 * 1024,
+            RomDigestVersion::V2 { chunk_size: 96 * 1024 },
+        );
+        // The V1 digest is unchanged by building the tree.
+        assert_eq!(rom.digest.0, flat.digest.0);
+        assert!(flat.digest_tree().is_none());
+
+        let tree = rom.digest_tree().unwrap();
+        let root = tree.root();
+        assert_eq!(tree.chunk_count(), 11);
+        for i in 0..tree.chunk_count() {
+            let proof = tree.proof(i).unwrap();
+            assert!(RomDigestTree::verify_proof(&root, i, rom.tree_chunk(i).unwrap(), &proof));
+            assert!(!RomDigestTree::verify_proof(&root, i, b"not the chunk", &proof));
+        }
+        assert!(tree.proof(11).is_none());
+
+        assert!(rom.corrupted_tree_chunks().is_empty());
+        rom.data_mut()[3 * 96 * 1024] ^= 0x80;
+        assert_eq!(rom.corrupted_tree_chunks(), vec![3]);
+    }
+
+    #[test]
+    fn rom_stream_roundtrip() {
+        const SIZE: usize = 256 * 1024;
+        let gen_type = RomGenerationType::TwoStep {
+            pre_size: 64 * 1024,
+            mixing_numbers: 4,
+        };
+        let rom = Rom::new(b"password", gen_type, SIZE);
+        let mut bytes = Vec::new();
+        rom.to_writer(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), ROM_FILE_HEADER_SIZE + SIZE);
+
+        let loaded = Rom::from_reader(bytes.as_slice(), SIZE).unwrap();
+        assert_eq!(loaded.digest, rom.digest);
+        assert_eq!(loaded.at(4321), rom.at(4321));
+
+        // A low-memory ROM serializes to the same bytes.
+        let mut low_memory = Vec::new();
+        Rom::new_low_memory(b"password", gen_type, SIZE).to_writer(&mut low_memory).unwrap();
+        assert!(low_memory == bytes);
+
+        assert!(Rom::from_reader(bytes.as_slice(), SIZE * 2).is_err());
+        assert!(Rom::from_reader(&bytes[..bytes.len() - 1], SIZE).is_err());
+        bytes[ROM_FILE_HEADER_SIZE + 7] ^= 0x01;
+        assert!(Rom::from_reader(bytes.as_slice(), SIZE).is_err());
+    }
+
+    #[test]
+    fn rom_cache_file_roundtrip() {
+        const SIZE: usize = 1024 * 1024;
+        let rom = Rom::new(
+            b"password",
+            RomGenerationType::TwoStep {
+                pre_size: 64 * 1024,
+                mixing_numbers: 4,
+            },
+            SIZE,
+        );
+        let path = std::env::temp_dir().join(format!("shadow-harvester-rom-test-{}.rom", std::process::id()));
+        rom.save_to_file(&path).unwrap();
+
+        let loaded = Rom::load_from_file(&path, SIZE).unwrap();
+        assert!(loaded.is_mapped());
+        assert_eq!(loaded.digest.0, rom.digest.0);
+        assert_eq!(loaded.at(12345), rom.at(12345));
+        assert!(Rom::load_from_file(&path, SIZE * 2).is_err());
+
+        // Flipping a byte in the ROM body must fail the digest check.
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[ROM_FILE_HEADER_SIZE + 42] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+        assert!(Rom::load_from_file(&path, SIZE).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rom_prefetch_keeps_hashes() {
+        const SIZE: usize = 1024 * 1024;
+        let rom = Rom::new(
+            b"password",
+            RomGenerationType::TwoStep {
+                pre_size: 64 * 1024,
+                mixing_numbers: 4,
+            },
+            SIZE,
+        );
+        let path = std::env::temp_dir().join(format!("shadow-harvester-prefetch-test-{}.rom", std::process::id()));
+        rom.save_to_file(&path).unwrap();
+        let loaded = Rom::load_from_file(&path, SIZE).unwrap();
+        assert!(loaded.is_mapped());
+
+        let expected = crate::hash(b"salt", &rom, 8, 256);
+        // Unsupported kernels leave prefetching off; the hash must match either way.
+        let _ = crate::set_rom_prefetch(true);
+        assert!(!crate::rom_prefetch() || loaded.wants_prefetch());
+        assert_eq!(crate::hash(b"salt", &loaded, 8, 256), expected);
+        crate::set_rom_prefetch(false).unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
 }
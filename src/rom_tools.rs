@@ -0,0 +1,48 @@
+// src/rom_tools.rs
+
+use crate::cli::{Cli, RomCommands};
+use crate::utils;
+use shadow_harvester_lib::Rom;
+use std::io::Write;
+use std::time::Instant;
+
+// ===============================================
+// OFFLINE ROM TOOLS
+// ===============================================
+
+/// Runs one `rom` subcommand. Makes no API calls and touches no local state.
+pub fn run_rom_command(cli: &Cli, command: &RomCommands) -> Result<(), String> {
+    match command {
+        RomCommands::Digest { key, output } => {
+            if hex::decode(key).is_err() {
+                return Err(format!("ROM key '{}' is not valid hex. Pass the challenge's no_pre_mine value.", key));
+            }
+            let rom_params = utils::rom_overrides(cli)?.resolve(None);
+
+            cprintln!("\n==============================================");
+            cprintln!("🧮 ROM Digest");
+            cprintln!("==============================================");
+            cprintln!("ROM Key:        {}", key);
+            cprintln!("Size:           {} bytes", rom_params.size);
+            cprintln!("Pre-size:       {} bytes", rom_params.pre_size);
+            cprintln!("Mixing Numbers: {}", rom_params.mixing_numbers);
+            cprintln!("Generating ROM...");
+
+            let start = Instant::now();
+            let rom = Rom::from_params(key.as_bytes(), &rom_params);
+            cprintln!("Generated in {}.", utils::format_duration(start.elapsed().as_secs_f64()));
+            cprintln!("----------------------------------------------");
+            cprintln!("Digest (Blake2b-512): {}", rom.digest.to_hex());
+
+            if let Some(path) = output {
+                std::fs::File::create(path)
+                    .map(std::io::BufWriter::new)
+                    .and_then(|mut file| rom.write_bytes(&mut file).and_then(|_| file.flush()))
+                    .map_err(|e| format!("Failed to write ROM to {}: {}", path, e))?;
+                cprintln!("💾 Raw ROM written to {}", path);
+            }
+            cprintln!("==============================================");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,402 @@
+// src/schema.rs
+
+use crate::data_types::{ChallengeData, DonationRecord, FailedSolution, PendingSolution, SLED_KEY_DONATION, SLED_KEY_FAILED_SOLUTION};
+use crate::persistence::Persistence;
+use crate::state_store::StateStore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+// ===============================================
+// TYPED STATE SCHEMA
+// ===============================================
+//
+// Sled stores strings under colon-separated keys (`receipt:<ADDRESS>:<CHALLENGE_ID>`, ...).
+// The key types here build and parse those keys in one place, and `StateSchema` reads and
+// writes the records behind them with serde, for both the miner's own database
+// (`Persistence`) and one-shot commands (`StateStore`). Addresses and challenge IDs never
+// contain ':', so splitting a key on ':' is unambiguous.
+
+pub const SLED_KEY_CHALLENGE: &str = "challenge";
+pub const SLED_KEY_RECEIPT: &str = "receipt";
+pub const SLED_KEY_PENDING: &str = "pending";
+pub const SLED_KEY_MNEMONIC_INDEX: &str = "mnemonic_index";
+pub const SLED_KEY_DONATE_PROGRESS: &str = "donate_progress";
+pub const SLED_KEY_NONCE_CURSOR: &str = "nonce_cursor";
+
+/// Status of the placeholder receipt saved when another miner already solved the challenge.
+pub const RECEIPT_STATUS_CONSUMED_MARKER: &str = "solved_by_network";
+
+/// Prefixes `--db-encrypt` encrypts: the addresses derived from a mnemonic, which link its
+/// addresses to one another, and donation progress, keyed by the mnemonic's hash.
+pub const SENSITIVE_PREFIXES: &[&str] = &["mnemonic_index:", "donate_progress:"];
+
+/// `challenge:<CHALLENGE_ID>`
+pub fn challenge_key(challenge_id: &str) -> String {
+    format!("{}:{}", SLED_KEY_CHALLENGE, challenge_id)
+}
+
+/// `nonce_cursor:<ADDRESS>:<CHALLENGE_ID>`, where a resumed miner continues its nonce search.
+pub fn nonce_cursor_key(address: &str, challenge_id: &str) -> String {
+    format!("{}:{}:{}", SLED_KEY_NONCE_CURSOR, address, challenge_id)
+}
+
+/// `receipt:<ADDRESS>:<CHALLENGE_ID>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptKey {
+    pub address: String,
+    pub challenge_id: String,
+}
+
+impl ReceiptKey {
+    pub fn new(address: &str, challenge_id: &str) -> Self {
+        ReceiptKey { address: address.to_string(), challenge_id: challenge_id.to_string() }
+    }
+
+    /// Prefix of every receipt key, or of those of one address.
+    pub fn prefix(address: Option<&str>) -> String {
+        match address {
+            Some(address) => format!("{}:{}:", SLED_KEY_RECEIPT, address),
+            None => format!("{}:", SLED_KEY_RECEIPT),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        match key.split(':').collect::<Vec<_>>()[..] {
+            [SLED_KEY_RECEIPT, address, challenge_id] => Some(Self::new(address, challenge_id)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReceiptKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", SLED_KEY_RECEIPT, self.address, self.challenge_id)
+    }
+}
+
+/// Defines the key of one solution, `<PREFIX>:<ADDRESS>:<CHALLENGE_ID>:<NONCE>`, shared by the
+/// pending queue and the permanent error records.
+macro_rules! solution_key {
+    ($(#[$doc:meta])* $name:ident, $prefix:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name {
+            pub address: String,
+            pub challenge_id: String,
+            pub nonce: String,
+        }
+
+        impl $name {
+            pub fn new(address: &str, challenge_id: &str, nonce: &str) -> Self {
+                $name { address: address.to_string(), challenge_id: challenge_id.to_string(), nonce: nonce.to_string() }
+            }
+
+            /// Prefix of every key, of those of one address, or of one address and challenge.
+            pub fn prefix(address: Option<&str>, challenge_id: Option<&str>) -> String {
+                match (address, challenge_id) {
+                    (Some(address), Some(challenge_id)) => format!("{}:{}:{}:", $prefix, address, challenge_id),
+                    (Some(address), None) => format!("{}:{}:", $prefix, address),
+                    (None, _) => format!("{}:", $prefix),
+                }
+            }
+
+            pub fn parse(key: &str) -> Option<Self> {
+                match key.split(':').collect::<Vec<_>>()[..] {
+                    [prefix, address, challenge_id, nonce] if prefix == $prefix => Some(Self::new(address, challenge_id, nonce)),
+                    _ => None,
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}:{}:{}:{}", $prefix, self.address, self.challenge_id, self.nonce)
+            }
+        }
+    };
+}
+
+solution_key!(
+    /// `pending:<ADDRESS>:<CHALLENGE_ID>:<NONCE>`
+    PendingKey, SLED_KEY_PENDING
+);
+solution_key!(
+    /// `failed_solution:<ADDRESS>:<CHALLENGE_ID>:<NONCE>`
+    FailedSolutionKey, SLED_KEY_FAILED_SOLUTION
+);
+
+impl PendingKey {
+    pub fn of(solution: &PendingSolution) -> Self {
+        Self::new(&solution.address, &solution.challenge_id, &solution.nonce)
+    }
+}
+
+impl FailedSolutionKey {
+    pub fn of(failed: &FailedSolution) -> Self {
+        Self::new(&failed.address, &failed.challenge_id, &failed.nonce)
+    }
+}
+
+/// `mnemonic_index:<WALLET_HASH>:<ACCOUNT>:<INDEX>`, holding the address derived at that path.
+/// The per-challenge cursor `mnemonic_index:<CHALLENGE_ID>` shares the prefix but does not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MnemonicIndexKey {
+    pub wallet_hash: String,
+    pub account: u32,
+    pub index: u32,
+}
+
+impl MnemonicIndexKey {
+    /// Prefix of every index key, or of those of one wallet account.
+    pub fn prefix(wallet: Option<(&str, u32)>) -> String {
+        match wallet {
+            Some((wallet_hash, account)) => format!("{}:{}:{}:", SLED_KEY_MNEMONIC_INDEX, wallet_hash, account),
+            None => format!("{}:", SLED_KEY_MNEMONIC_INDEX),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        match key.split(':').collect::<Vec<_>>()[..] {
+            [SLED_KEY_MNEMONIC_INDEX, wallet_hash, account, index] => Some(MnemonicIndexKey {
+                wallet_hash: wallet_hash.to_string(),
+                account: account.parse().ok()?,
+                index: index.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The wallet identifier (`<WALLET_HASH>:<ACCOUNT>`) shown by `wallet list`.
+    pub fn wallet(&self) -> String {
+        format!("{}:{}", self.wallet_hash, self.account)
+    }
+}
+
+impl fmt::Display for MnemonicIndexKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}:{}", SLED_KEY_MNEMONIC_INDEX, self.wallet_hash, self.account, self.index)
+    }
+}
+
+/// A stored record whose key parsed. `value` is the decode error when its JSON does not
+/// deserialize, so callers choose between skipping and reporting it.
+pub struct Entry<K, T> {
+    pub key: K,
+    pub value: Result<T, String>,
+}
+
+fn decode<T: DeserializeOwned>(key: &str, json: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| format!("{} does not decode: {}", key, e))
+}
+
+/// Typed access to the state database. Implementors provide the four string primitives.
+pub trait StateSchema {
+    fn read(&self, key: &str) -> Result<Option<String>, String>;
+    fn write(&self, key: &str, value: &str) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+    /// Every (key, decoded value) pair whose key starts with `prefix`, in key order.
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, String)>, String>;
+
+    fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.read(key)?.map(|json| decode(key, &json)).transpose()
+    }
+
+    fn put_json<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        let json = serde_json::to_string(value).map_err(|e| format!("Failed to serialize {}: {}", key, e))?;
+        self.write(key, &json)
+    }
+
+    /// Every record under `prefix` whose key `parse` accepts.
+    fn iter_entries<K, T: DeserializeOwned>(&self, prefix: &str, parse: impl Fn(&str) -> Option<K>) -> Result<Vec<Entry<K, T>>, String> {
+        Ok(self.scan(prefix)?
+            .into_iter()
+            .filter_map(|(key, json)| parse(&key).map(|parsed| Entry { key: parsed, value: decode(&key, &json) }))
+            .collect())
+    }
+
+    // --- Challenges ---
+
+    fn get_challenge(&self, challenge_id: &str) -> Result<Option<ChallengeData>, String> {
+        self.get_json(&challenge_key(challenge_id))
+    }
+
+    fn put_challenge(&self, challenge: &ChallengeData) -> Result<(), String> {
+        self.put_json(&challenge_key(&challenge.challenge_id), challenge)
+    }
+
+    /// Every stored challenge, keyed by ID.
+    fn iter_challenges(&self) -> Result<Vec<Entry<String, ChallengeData>>, String> {
+        let prefix = format!("{}:", SLED_KEY_CHALLENGE);
+        self.iter_entries(&prefix, |key| key.strip_prefix(prefix.as_str()).map(str::to_string))
+    }
+
+    // --- Receipts (the API's crypto_receipt, or a "solved by network" marker) ---
+
+    fn get_receipt(&self, address: &str, challenge_id: &str) -> Result<Option<Value>, String> {
+        self.get_json(&ReceiptKey::new(address, challenge_id).to_string())
+    }
+
+    fn has_receipt(&self, address: &str, challenge_id: &str) -> Result<bool, String> {
+        Ok(self.read(&ReceiptKey::new(address, challenge_id).to_string())?.is_some())
+    }
+
+    fn put_receipt(&self, address: &str, challenge_id: &str, receipt: &Value) -> Result<(), String> {
+        self.put_json(&ReceiptKey::new(address, challenge_id).to_string(), receipt)
+    }
+
+    /// Every receipt, or those of one address.
+    fn iter_receipts(&self, address: Option<&str>) -> Result<Vec<Entry<ReceiptKey, Value>>, String> {
+        self.iter_entries(&ReceiptKey::prefix(address), ReceiptKey::parse)
+    }
+
+    // --- Pending queue ---
+
+    fn get_pending(&self, key: &PendingKey) -> Result<Option<PendingSolution>, String> {
+        self.get_json(&key.to_string())
+    }
+
+    fn put_pending(&self, solution: &PendingSolution) -> Result<(), String> {
+        self.put_json(&PendingKey::of(solution).to_string(), solution)
+    }
+
+    fn remove_pending(&self, key: &PendingKey) -> Result<(), String> {
+        self.delete(&key.to_string())
+    }
+
+    /// Every queued solution, or those of one address.
+    fn iter_pending(&self, address: Option<&str>) -> Result<Vec<Entry<PendingKey, PendingSolution>>, String> {
+        self.iter_entries(&PendingKey::prefix(address, None), PendingKey::parse)
+    }
+
+    // --- Permanent submission errors ---
+
+    fn put_failed(&self, failed: &FailedSolution) -> Result<(), String> {
+        self.put_json(&FailedSolutionKey::of(failed).to_string(), failed)
+    }
+
+    fn remove_failed(&self, key: &FailedSolutionKey) -> Result<(), String> {
+        self.delete(&key.to_string())
+    }
+
+    /// Every error record, or those of one address (and challenge).
+    fn iter_failed(&self, address: Option<&str>, challenge_id: Option<&str>) -> Result<Vec<Entry<FailedSolutionKey, FailedSolution>>, String> {
+        self.iter_entries(&FailedSolutionKey::prefix(address, challenge_id), FailedSolutionKey::parse)
+    }
+
+    // --- Mnemonic index and donations ---
+
+    /// Every derived address recorded for mnemonic wallets, or for one wallet account.
+    fn iter_mnemonic_indices(&self, wallet: Option<(&str, u32)>) -> Result<Vec<(MnemonicIndexKey, String)>, String> {
+        Ok(self.scan(&MnemonicIndexKey::prefix(wallet))?
+            .into_iter()
+            .filter_map(|(key, address)| MnemonicIndexKey::parse(&key).map(|key| (key, address)))
+            .collect())
+    }
+
+    fn get_donation(&self, address: &str) -> Result<Option<DonationRecord>, String> {
+        self.get_json(&format!("{}:{}", SLED_KEY_DONATION, address))
+    }
+
+    fn put_donation(&self, record: &DonationRecord) -> Result<(), String> {
+        self.put_json(&record.sled_key(), record)
+    }
+}
+
+impl StateSchema for Persistence {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        self.get(key)
+    }
+
+    fn write(&self, key: &str, value: &str) -> Result<(), String> {
+        self.set(key, value)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.db.remove(key.as_bytes()).map(|_| ()).map_err(|e| format!("Sled remove failed for key '{}': {}", key, e))
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, String)>, String> {
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .map(|entry| {
+                let (key, value) = entry.map_err(|e| format!("Sled iteration error for prefix '{}': {}", prefix, e))?;
+                Ok((String::from_utf8_lossy(&key).to_string(), self.decode_value(&value)?))
+            })
+            .collect()
+    }
+}
+
+impl StateSchema for StateStore {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        self.get(key)
+    }
+
+    fn write(&self, key: &str, value: &str) -> Result<(), String> {
+        self.set(key, value)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.remove(key)
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, String)>, String> {
+        self.scan_prefix(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_key_round_trips() {
+        let key = ReceiptKey::new("addr1qxyz", "**D07C21");
+        let text = key.to_string();
+        assert_eq!(text, "receipt:addr1qxyz:**D07C21");
+        assert!(text.starts_with(&ReceiptKey::prefix(Some("addr1qxyz"))));
+        assert_eq!(ReceiptKey::parse(&text), Some(key));
+    }
+
+    #[test]
+    fn receipt_key_rejects_other_shapes() {
+        assert_eq!(ReceiptKey::parse("receipt:addr1qxyz"), None);
+        assert_eq!(ReceiptKey::parse("receipt:addr1qxyz:**D07C21:extra"), None);
+        assert_eq!(ReceiptKey::parse("pending:addr1qxyz:**D07C21"), None);
+    }
+
+    #[test]
+    fn solution_keys_round_trip() {
+        let pending = PendingKey::new("addr1qxyz", "**D07C21", "00000000deadbeef");
+        assert_eq!(pending.to_string(), "pending:addr1qxyz:**D07C21:00000000deadbeef");
+        assert!(pending.to_string().starts_with(&PendingKey::prefix(Some("addr1qxyz"), Some("**D07C21"))));
+        assert_eq!(PendingKey::parse(&pending.to_string()), Some(pending));
+
+        let failed = FailedSolutionKey::new("addr1qxyz", "**D07C21", "00000000deadbeef");
+        assert_eq!(failed.to_string(), format!("{}:addr1qxyz:**D07C21:00000000deadbeef", SLED_KEY_FAILED_SOLUTION));
+        assert_eq!(FailedSolutionKey::parse(&failed.to_string()), Some(failed));
+    }
+
+    #[test]
+    fn solution_keys_do_not_parse_each_other() {
+        let pending = PendingKey::new("addr1qxyz", "**D07C21", "00000000deadbeef").to_string();
+        assert_eq!(FailedSolutionKey::parse(&pending), None);
+        assert_eq!(PendingKey::parse("pending:addr1qxyz:**D07C21"), None);
+    }
+
+    #[test]
+    fn mnemonic_index_key_round_trips() {
+        let key = MnemonicIndexKey { wallet_hash: "9f86d081".to_string(), account: 2, index: 17 };
+        assert_eq!(key.to_string(), "mnemonic_index:9f86d081:2:17");
+        assert_eq!(key.wallet(), "9f86d081:2");
+        assert!(key.to_string().starts_with(&MnemonicIndexKey::prefix(Some(("9f86d081", 2)))));
+        assert_eq!(MnemonicIndexKey::parse(&key.to_string()), Some(key));
+    }
+
+    #[test]
+    fn mnemonic_index_key_skips_the_challenge_cursor() {
+        assert_eq!(MnemonicIndexKey::parse("mnemonic_index:**D07C21"), None);
+        assert_eq!(MnemonicIndexKey::parse("mnemonic_index:9f86d081:two:17"), None);
+    }
+}
@@ -0,0 +1,143 @@
+// src/secrets.rs
+
+use crate::cli::{Cli, KeyringCommands, KeyringSecret};
+use keyring::Entry;
+use std::io::{BufRead, IsTerminal};
+
+// ===============================================
+// OS KEYRING SECRETS
+// ===============================================
+//
+// Secrets stored in the platform keyring (Secret Service on Linux, Keychain on macOS,
+// Credential Manager on Windows) instead of files or command-line arguments, where they are
+// visible to other users through `ps` and shell history. With --keyring, anything the command
+// line leaves unset is read from the keyring once at startup.
+
+/// Service name every entry is stored under.
+const KEYRING_SERVICE: &str = "shadow-harvester";
+
+const ALL_SECRETS: [KeyringSecret; 3] = [KeyringSecret::Mnemonic, KeyringSecret::PaymentKey, KeyringSecret::DbPassphrase];
+
+impl KeyringSecret {
+    fn entry_name(self) -> &'static str {
+        match self {
+            KeyringSecret::Mnemonic => "mnemonic",
+            KeyringSecret::PaymentKey => "payment-key",
+            KeyringSecret::DbPassphrase => "db-passphrase",
+        }
+    }
+}
+
+fn entry(secret: KeyringSecret) -> Result<Entry, String> {
+    Entry::new(KEYRING_SERVICE, secret.entry_name())
+        .map_err(|e| format!("Failed to open OS keyring entry '{}': {}", secret.entry_name(), e))
+}
+
+/// Reads `secret` from the keyring. `None` when it was never stored.
+pub fn get(secret: KeyringSecret) -> Result<Option<String>, String> {
+    match entry(secret)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read '{}' from the OS keyring: {}", secret.entry_name(), e)),
+    }
+}
+
+fn set(secret: KeyringSecret, value: &str) -> Result<(), String> {
+    entry(secret)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store '{}' in the OS keyring: {}", secret.entry_name(), e))
+}
+
+/// Removes `secret` from the keyring. Returns false if it was not stored.
+fn delete(secret: KeyringSecret) -> Result<bool, String> {
+    match entry(secret)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!("Failed to delete '{}' from the OS keyring: {}", secret.entry_name(), e)),
+    }
+}
+
+/// Fills the key material the command line left unset from the keyring when `--keyring` is
+/// set. A stored mnemonic takes precedence over a stored payment key. The database passphrase
+/// is looked up separately by `utils::db_passphrase`.
+pub fn unlock(cli: &mut Cli) -> Result<(), String> {
+    if !cli.keyring || cli.ephemeral_key || cli.ledger {
+        return Ok(());
+    }
+    if cli.payment_key.is_some() || cli.mnemonic.is_some() || cli.mnemonic_file.is_some() {
+        return Ok(());
+    }
+    if let Some(mnemonic) = get(KeyringSecret::Mnemonic)? {
+        cprintln!("🔑 Loaded the mnemonic from the OS keyring.");
+        cli.mnemonic = Some(mnemonic);
+    } else if let Some(payment_key) = get(KeyringSecret::PaymentKey)? {
+        cprintln!("🔑 Loaded the payment key from the OS keyring.");
+        cli.payment_key = Some(payment_key);
+    }
+    Ok(())
+}
+
+/// Reads the secret to store from the first line of `path`, or of stdin. On a terminal the
+/// input is not echoed; piped input is read as is.
+fn read_secret(from_file: Option<&str>, prompt: &str) -> Result<String, String> {
+    let line = match from_file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read secret file {}: {}", path, e))?
+            .lines()
+            .next()
+            .unwrap_or("")
+            .to_string(),
+        None if std::io::stdin().is_terminal() => {
+            crate::console::flush();
+            rpassword::prompt_password(prompt).map_err(|e| format!("Failed to read secret from the terminal: {}", e))?
+        }
+        None => {
+            let mut line = String::new();
+            std::io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .map_err(|e| format!("Failed to read secret from stdin: {}", e))?;
+            line
+        }
+    };
+    let secret = line.trim().to_string();
+    if secret.is_empty() {
+        return Err("Refusing to store an empty secret.".to_string());
+    }
+    Ok(secret)
+}
+
+/// Runs one `keyring` subcommand.
+pub fn run_keyring_command(command: &KeyringCommands) -> Result<(), String> {
+    match command {
+        KeyringCommands::Set { secret, from_file } => {
+            let value = read_secret(from_file.as_deref(), &format!("Enter the {} (input is hidden): ", secret.entry_name()))?;
+            match secret {
+                KeyringSecret::Mnemonic => {
+                    bip39::Mnemonic::parse(&value).map_err(|e| format!("Invalid mnemonic: {}", e))?;
+                }
+                KeyringSecret::PaymentKey => {
+                    hex::decode(&value).map_err(|_| "Invalid payment key: expected a hex-encoded secret key.".to_string())?;
+                }
+                KeyringSecret::DbPassphrase => {}
+            }
+            set(*secret, &value)?;
+            cprintln!("🔑 Stored '{}' in the OS keyring (service '{}').", secret.entry_name(), KEYRING_SERVICE);
+        }
+        KeyringCommands::Delete { secret } => {
+            if delete(*secret)? {
+                cprintln!("🗑️ Deleted '{}' from the OS keyring.", secret.entry_name());
+            } else {
+                cprintln!("'{}' was not stored in the OS keyring.", secret.entry_name());
+            }
+        }
+        KeyringCommands::Status => {
+            cprintln!("OS keyring entries (service '{}'):", KEYRING_SERVICE);
+            for secret in ALL_SECRETS {
+                let state = if get(secret)?.is_some() { "stored" } else { "not stored" };
+                cprintln!("  {:<14} {}", secret.entry_name(), state);
+            }
+        }
+    }
+    Ok(())
+}
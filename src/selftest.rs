@@ -0,0 +1,97 @@
+// src/selftest.rs
+
+use cryptoxide::hashing::blake2b;
+use cryptoxide::kdf::argon2;
+use shadow_harvester_lib::{build_preimage, hash, hash_structure_good, Rom, RomParams};
+use std::time::Instant;
+
+// ===============================================
+// EMBEDDED KNOWN-ANSWER VECTORS
+// ===============================================
+//
+// Taken from challenge D07C10 and a nonce that meets its difficulty (see tests/). If any of these
+// fail, the binary (or the machine running it) does not compute hashes correctly and every
+// "solution does not meet difficulty" rejection should be blamed on that first.
+
+const MB: usize = 1024 * 1024;
+const GB: usize = 1024 * MB;
+
+const KAT_ROM_KEY: &str = "fd651ac2725e3b9d804cc8b161c0709af14d6264f93e8d4afef0fd1142a3f011";
+const KAT_ROM_SEED_HEX: &str = "118a9e880ecef64f9dff3eb94db22b0f417524697ae4b9e8037b1328de0765fe";
+const KAT_HPRIME_PREFIX_HEX: &str = "b89b48b36e71912f26e2d57c59996621f248d827203fa2206e3a090aa37e24";
+const KAT_ROM_DIGEST_HEX: &str = "363c87d27c93f1013ed03f19ca39c6ea8b83b24b607df70dccc8967ad59c78fe6aeeea9978e7dbfaba584550e568808f75202c48fc9f4236184b8ee5709816c8";
+
+const KAT_ADDRESS: &str = "addr_test1qq4dl3nhr0axurgcrpun9xyp04pd2r2dwu5x7eeam98psv6dhxlde8ucclv2p46hm077ds4vzelf5565fg3ky794uhrq5up0he";
+const KAT_CHALLENGE_ID: &str = "**D07C10";
+const KAT_DIFFICULTY_MASK: u32 = 0x000F_FFFF;
+const KAT_LATEST_SUBMISSION: &str = "2025-10-19T08:59:59.000Z";
+const KAT_NO_PRE_MINE_HOUR: &str = "509681483";
+const KAT_NONCE: u64 = 0x0019_c96b_6a30_ee38;
+const KAT_HASH_HEX: &str = "000694200fb04137812fb7f35fab2f0e07adf8465397d268bcd97d2f4c7b875fe6d42f12f377b5b83bcfbd70d6ba55441650c37b8fc80851216b3a1aed7e23c8";
+
+/// Records and prints the outcome of one check.
+fn check(results: &mut Vec<(String, bool)>, name: &str, expected: &str, actual: &str) {
+    let passed = expected == actual;
+    if passed {
+        cprintln!("  ✅ {}", name);
+    } else {
+        cprintln!("  ❌ {}\n       expected: {}\n       actual:   {}", name, expected, actual);
+    }
+    results.push((name.to_string(), passed));
+}
+
+/// Runs the known-answer vectors (ROM seed, hprime, full ROM digest, VM hash of a known
+/// preimage and the difficulty check) and returns an error if any of them fail.
+pub fn run_selftest() -> Result<(), String> {
+    cprintln!("\n==============================================");
+    cprintln!("🧪 Shadow Harvester Self-Test");
+    cprintln!("==============================================");
+    let start = Instant::now();
+    let mut results = Vec::new();
+
+    // 1. ROM seed (Blake2b-256 of size || key)
+    let seed = blake2b::Context::<256>::new()
+        .update(&(GB as u32).to_le_bytes())
+        .update(KAT_ROM_KEY.as_bytes())
+        .finalize();
+    check(&mut results, "ROM seed (Blake2b-256)", KAT_ROM_SEED_HEX, &hex::encode(seed));
+
+    // 2. Argon2 H' expansion of the seed
+    let mut mixing_buffer = vec![0u8; 16 * MB];
+    argon2::hprime(&mut mixing_buffer, &seed);
+    check(&mut results, "ROM pre-buffer (Argon2 H')", KAT_HPRIME_PREFIX_HEX, &hex::encode(&mixing_buffer[..31]));
+    drop(mixing_buffer);
+
+    // 3. Full 1 GiB ROM digest
+    cprintln!("  ⏳ Generating 1 GiB ROM...");
+    let rom = Rom::from_params(KAT_ROM_KEY.as_bytes(), &RomParams::default());
+    check(&mut results, "ROM digest", KAT_ROM_DIGEST_HEX, &rom.digest.to_hex());
+
+    // 4. VM hash of a known preimage
+    let preimage = build_preimage(
+        KAT_NONCE,
+        KAT_ADDRESS,
+        KAT_CHALLENGE_ID,
+        KAT_DIFFICULTY_MASK,
+        KAT_ROM_KEY,
+        KAT_LATEST_SUBMISSION,
+        KAT_NO_PRE_MINE_HOUR,
+    );
+    let h = hash(preimage.as_bytes(), &rom, 8, 256);
+    check(&mut results, "VM hash of known preimage", KAT_HASH_HEX, &hex::encode(h));
+
+    // 5. Difficulty check: the accepted nonce meets its mask; a stricter mask rejects it.
+    check(&mut results, "Difficulty check (accepted nonce)", "true", &hash_structure_good(&h, KAT_DIFFICULTY_MASK).to_string());
+    check(&mut results, "Difficulty check (stricter mask)", "false", &hash_structure_good(&h, KAT_DIFFICULTY_MASK >> 12).to_string());
+
+    let failed: Vec<&str> = results.iter().filter(|(_, ok)| !ok).map(|(name, _)| name.as_str()).collect();
+    cprintln!("----------------------------------------------");
+    cprintln!("{} / {} checks passed in {:.1}s.", results.len() - failed.len(), results.len(), start.elapsed().as_secs_f64());
+    cprintln!("==============================================");
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Self-test failed ({}). This binary or machine computes hashes incorrectly; rebuild it and check for hardware errors before mining.", failed.join(", ")))
+    }
+}
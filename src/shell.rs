@@ -0,0 +1,128 @@
+// src/shell.rs
+
+use crate::control::{self, ControlClient};
+use serde_json::Value;
+use std::io::{BufRead, IsTerminal};
+
+// ===============================================
+// INTERACTIVE SHELL (shadow-harvester shell)
+// ===============================================
+//
+// A small REPL over the control socket of a running miner. Each line is split on whitespace;
+// the first word is the command and the rest are its arguments. Piped input works too, so
+// `echo status | shadow-harvester shell` is a one-shot query.
+
+const PROMPT: &str = "shadow-harvester> ";
+
+/// Connects to the miner at `socket_path` and reads commands until EOF or `quit`.
+pub fn run_shell(socket_path: &str) -> Result<(), String> {
+    let mut client = ControlClient::connect(socket_path)?;
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        cprintln!("Connected to {}. Type 'help' for commands, 'quit' to leave.", socket_path);
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            crate::console::prompt(PROMPT);
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(|e| format!("Failed to read input: {}", e))?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = words.split_first() else {
+            continue;
+        };
+
+        match cmd {
+            "quit" | "exit" => break,
+            "help" => print_help(),
+            _ => match client.request(cmd, args) {
+                Ok(data) => print_reply(cmd, &data),
+                Err(e) => ceprintln!("❌ {}", e),
+            },
+        }
+    }
+    crate::console::flush();
+    Ok(())
+}
+
+fn print_help() {
+    cprintln!("Commands:");
+    for (usage, description) in control::COMMANDS {
+        cprintln!("  {:<26} {}", usage, description);
+    }
+    cprintln!("  {:<26} {}", "help", "Show this list");
+    cprintln!("  {:<26} {}", "quit", "Leave the shell (the miner keeps running)");
+}
+
+/// Shows `data` as a short human-readable summary.
+fn print_reply(cmd: &str, data: &Value) {
+    let text = |value: &Value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+    match cmd {
+        "status" => {
+            let state = if data["paused"].as_bool() == Some(true) {
+                "paused"
+            } else if data["mining"].as_bool() == Some(true) {
+                "mining"
+            } else {
+                "idle"
+            };
+            cprintln!("Mode:        {}", text(&data["mode"]));
+            cprintln!("State:       {}", state);
+            cprintln!("Challenge:   {}", text(&data["challenge_id"]));
+            cprintln!("Difficulty:  {}", text(&data["difficulty"]));
+            cprintln!("Deadline:    {}", text(&data["latest_submission"]));
+            cprintln!("Address:     {}", text(&data["address"]));
+            cprintln!("Threads:     {}", text(&data["threads"]));
+            if let Some(rate) = data["hash_rate"].as_f64().filter(|rate| *rate > 0.0) {
+                cprintln!("Hash rate:   {:.1} H/s", rate);
+            }
+            if let Some(eta) = data["expected_secs_to_solution"].as_f64() {
+                cprintln!("Next sol.:   ~{} (expected)", crate::utils::format_duration(eta));
+            }
+            if let Some(rates) = data["thread_hash_rates"].as_array().filter(|rates| !rates.is_empty()) {
+                let rates: Vec<String> = rates.iter().map(|rate| format!("{:.1}", rate.as_f64().unwrap_or_default())).collect();
+                cprintln!("Hash rates:  {} H/s", rates.join(" / "));
+            }
+            cprintln!("Solutions:   {}", text(&data["challenge_solutions"]));
+            if !data["queued_challenge"].is_null() {
+                cprintln!("Queued:      {}", text(&data["queued_challenge"]));
+            }
+        }
+        "pause" => cprintln!("⏸️ Mining paused."),
+        "resume" => cprintln!("▶️ Mining resumed."),
+        "switch" => cprintln!("🔀 Switched to challenge {}.", text(&data["challenge_id"])),
+        "threads" => cprintln!("🧵 Worker threads set to {}.", text(&data["threads"])),
+        "receipts" => {
+            let receipts = data.as_array().map(Vec::as_slice).unwrap_or_default();
+            if receipts.is_empty() {
+                cprintln!("No receipts saved yet.");
+            }
+            for receipt in receipts {
+                cprintln!(
+                    "{:<26} {:<12} {}",
+                    text(&receipt["timestamp"]),
+                    text(&receipt["challenge_id"]),
+                    text(&receipt["address"])
+                );
+            }
+        }
+        "retry-pending" => cprintln!("🔁 Resubmitting {} pending solution(s).", text(&data["resubmitted"])),
+        "verify" => {
+            let verdict = if data["meets_difficulty"].as_bool() == Some(true) {
+                "✅ meets the difficulty"
+            } else {
+                "❌ does not meet the difficulty"
+            };
+            cprintln!("Challenge: {}", text(&data["challenge_id"]));
+            cprintln!("Address:   {}", text(&data["address"]));
+            cprintln!("Hash:      {}", text(&data["hash"]));
+            cprintln!("Result:    {}", verdict);
+        }
+        _ => cprintln!("{}", serde_json::to_string_pretty(data).unwrap_or_default()),
+    }
+}
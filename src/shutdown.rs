@@ -0,0 +1,205 @@
+// src/shutdown.rs
+
+use crate::data_types::{ManagerCommand, SubmitterCommand};
+use std::sync::mpsc::Sender;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tokio::runtime;
+
+// ===============================================
+// SIGNAL HANDLING (Ctrl-C / SIGTERM)
+// ===============================================
+
+/// Exit code used when a second signal forces an immediate exit (128 + SIGINT).
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Resolves once Ctrl-C (or SIGTERM on Unix) is received.
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = sigterm.recv() => {},
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Spawns a thread that turns the first Ctrl-C/SIGTERM into `ManagerCommand::Shutdown`.
+/// A second signal skips the graceful path and exits immediately.
+pub fn spawn_signal_handler(manager_tx: Sender<ManagerCommand>) {
+    thread::spawn(move || {
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime for signal handling.");
+
+        rt.block_on(wait_for_signal());
+        cprintln!("\n🛑 Shutdown signal received. Stopping miners and flushing state (press Ctrl-C again to force exit)...");
+        crate::watchdog::sd_notify_or_warn("STOPPING=1\nSTATUS=Flushing state");
+        if manager_tx.send(ManagerCommand::Shutdown).is_err() {
+            ceprintln!("⚠️ Manager channel already closed. Exiting.");
+            crate::console::flush();
+            std::process::exit(FORCED_EXIT_CODE);
+        }
+
+        rt.block_on(wait_for_signal());
+        ceprintln!("\n⚠️ Second signal received. Forcing exit without waiting for state flush.");
+        crate::console::flush();
+        std::process::exit(FORCED_EXIT_CODE);
+    });
+}
+
+// ===============================================
+// SYNC COMMAND CANCELLATION
+// ===============================================
+//
+// One-shot commands (challenge hash/verify, wallet stats/donate-all, ...) have no manager to
+// shut down. Ctrl-C, SIGTERM or --command-timeout instead raise a flag the long loops check
+// between steps; they save their progress and return a "cancelled" error, so the Sled DB is
+// flushed and closed normally.
+
+// Process-wide: why the running sync command was cancelled, set once.
+static CANCEL_REASON: OnceLock<&'static str> = OnceLock::new();
+
+/// Spawns the cancellation handler for a sync command: the first Ctrl-C/SIGTERM (or the
+/// `timeout`, if any) cancels the command, a second signal exits immediately.
+pub fn spawn_cancel_handler(timeout: Option<Duration>) {
+    if let Some(timeout) = timeout {
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            if CANCEL_REASON.set("timeout").is_ok() {
+                ceprintln!("\n⏱️ --command-timeout of {}s reached. Stopping after the current step...", timeout.as_secs());
+            }
+        });
+    }
+
+    thread::spawn(|| {
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime for signal handling.");
+
+        rt.block_on(wait_for_signal());
+        if CANCEL_REASON.set("interrupted").is_ok() {
+            ceprintln!("\n🛑 Interrupted. Stopping after the current step and saving progress (press Ctrl-C again to force exit)...");
+        }
+
+        rt.block_on(wait_for_signal());
+        ceprintln!("\n⚠️ Second signal received. Forcing exit.");
+        crate::console::flush();
+        std::process::exit(FORCED_EXIT_CODE);
+    });
+}
+
+/// True once the running sync command was cancelled.
+pub fn cancelled() -> bool {
+    CANCEL_REASON.get().is_some()
+}
+
+/// Err with a "cancelled" message (exit code CANCELLED) once the command was cancelled.
+pub fn check_cancelled() -> Result<(), String> {
+    match CANCEL_REASON.get() {
+        Some(reason) => Err(format!("Command cancelled ({}).", reason)),
+        None => Ok(()),
+    }
+}
+
+/// Runs `work` on a helper thread and waits for it unless the command is cancelled first.
+/// For steps that cannot check the flag themselves, such as ROM generation; on cancellation
+/// the helper is abandoned and exits with the process.
+pub fn run_cancellable<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    loop {
+        check_cancelled()?;
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(value) => return Ok(value),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return Err("Worker thread panicked.".to_string()),
+        }
+    }
+}
+
+// ===============================================
+// FATAL THREAD ERRORS
+// ===============================================
+
+/// Process-wide coordinator that turns fatal errors from background threads into the same
+/// graceful shutdown as Ctrl-C (stop miners, flush Sled, print a summary) instead of
+/// calling `process::exit` from inside the failing thread.
+struct ShutdownCoordinator {
+    manager_tx: Sender<ManagerCommand>,
+    submitter_tx: Sender<SubmitterCommand>,
+    state: Mutex<FatalState>,
+}
+
+#[derive(Default)]
+struct FatalState {
+    /// Exit code of the first fatal error; later errors are only recorded.
+    exit_code: Option<i32>,
+    errors: Vec<String>,
+}
+
+static COORDINATOR: OnceLock<ShutdownCoordinator> = OnceLock::new();
+
+/// Installs the coordinator. Must be called once, before the worker threads are spawned.
+pub fn install_coordinator(manager_tx: Sender<ManagerCommand>, submitter_tx: Sender<SubmitterCommand>) {
+    let _ = COORDINATOR.set(ShutdownCoordinator {
+        manager_tx,
+        submitter_tx,
+        state: Mutex::new(FatalState::default()),
+    });
+}
+
+/// Reports a fatal error from `thread_name`. The first report requests a graceful shutdown
+/// and fixes the process exit code `code`; any further reports are only recorded for the
+/// final summary. Without an installed coordinator (e.g. in one-shot commands) this exits
+/// immediately.
+pub fn fatal(thread_name: &str, error: &str, code: i32) {
+    ceprintln!("❌ FATAL THREAD ERROR: {} failed: {}", thread_name, error);
+    let Some(coordinator) = COORDINATOR.get() else {
+        crate::console::flush();
+        std::process::exit(code);
+    };
+
+    let first = {
+        let mut state = coordinator.state.lock().unwrap();
+        state.errors.push(format!("{}: {}", thread_name, error));
+        state.exit_code.is_none() && {
+            state.exit_code = Some(code);
+            true
+        }
+    };
+    if !first {
+        return;
+    }
+
+    cprintln!("\n🛑 Shutting down after a fatal error. Stopping miners and flushing state...");
+    crate::watchdog::sd_notify_or_warn("STOPPING=1\nSTATUS=Flushing state after a fatal error");
+    // The manager stops the miners and then closes the submitter. If the manager itself is
+    // gone, close the submitter directly so Sled is still flushed.
+    if coordinator.manager_tx.send(ManagerCommand::Shutdown).is_err() {
+        let _ = coordinator.submitter_tx.send(SubmitterCommand::Shutdown);
+    }
+}
+
+/// Exit code and error list when a fatal error triggered the shutdown, `None` otherwise.
+pub fn fatal_status() -> Option<(i32, Vec<String>)> {
+    let state = COORDINATOR.get()?.state.lock().unwrap();
+    state.exit_code.map(|code| (code, state.errors.clone()))
+}
@@ -0,0 +1,182 @@
+// src/soak.rs
+
+use crate::cli::{Cli, Commands};
+use crate::control;
+use crate::data_types::{ControlRequest, ManagerCommand, PendingSolution, SubmitterCommand};
+use crate::mock_api::{self, MockConfig};
+use crate::schema::{SLED_KEY_CHALLENGE, SLED_KEY_PENDING, SLED_KEY_RECEIPT};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===============================================
+// SOAK MODE (soak --hours N)
+// ===============================================
+//
+// Runs the whole miner (manager, poller, submitter, workers) against the mock API with short
+// challenges and injected API faults, and checks the Sled state every few seconds. Races between
+// the threads tend to surface only after many cycles; here they surface as an invariant
+// violation, which stops the run through the fatal-error path (non-zero exit) and leaves the
+// data directory in place for inspection.
+
+/// A 1 MiB ROM builds in milliseconds, so rapid challenges are mostly spent hashing.
+const SOAK_ROM_SIZE: usize = 1024 * 1024;
+const SOAK_ROM_PRE_SIZE: usize = 64 * 1024;
+/// How long one check waits for the manager or the state worker before calling it hung.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Starts the mock API for `soak` and fills in the flags a soak run needs: the mock URL, T&C
+/// acceptance, an ephemeral key, a small ROM and a fresh data directory, unless set by the user
+/// (the data directory only through --data-dir on the command line).
+pub fn prepare(cli: &mut Cli) -> Result<(), String> {
+    let Some(Commands::Soak { hours, port, challenge_secs, fault_rate, check_interval }) = cli.command.clone() else {
+        return Ok(());
+    };
+    if hours.is_nan() || hours <= 0.0 {
+        return Err("soak: --hours must be greater than 0.".to_string());
+    }
+    if !(0.0..=1.0).contains(&fault_rate) {
+        return Err("soak: --fault-rate must be between 0.0 and 1.0.".to_string());
+    }
+    if cli.api_url.is_some() || cli.mock_api_port.is_some() || cli.websocket {
+        return Err("soak runs against its own mock API; drop --api-url, --mock-api-port and --websocket.".to_string());
+    }
+
+    cli.mock_api_port = Some(port);
+    cli.accept_tos = true;
+    if cli.payment_key.is_none() && cli.mnemonic.is_none() && cli.mnemonic_file.is_none() {
+        cli.ephemeral_key = true;
+    }
+    cli.rom_size.get_or_insert(SOAK_ROM_SIZE);
+    cli.rom_pre_size.get_or_insert(SOAK_ROM_PRE_SIZE);
+    // Poll often enough to catch every challenge early in its window.
+    crate::polling_client::set_polling_interval((challenge_secs as u64 / 4).max(1));
+    // --data-dir defaults to "." and SH_DATA_DIR usually points at the real miner's state, so
+    // only a directory given on the command line is used; anything else gets a fresh temp dir.
+    if !cli.data_dir_explicit {
+        let dir = std::env::temp_dir().join(format!("shadow-harvester-soak-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S")));
+        cli.data_dir = Some(dir.to_string_lossy().into_owned());
+    }
+
+    cprintln!("🧪 Soak run: {}h, {}s challenges, {:.1}% API faults, invariant check every {}s.",
+        hours, challenge_secs, fault_rate * 100.0, check_interval);
+    cprintln!("   Data directory: {}", cli.data_dir.as_deref().unwrap_or_default());
+
+    mock_api::start_mock_server_thread(port, MockConfig { challenge_secs, endless: true, fault_rate });
+    // Let the server bind before the poller's first request.
+    thread::sleep(Duration::from_millis(100));
+    Ok(())
+}
+
+/// Starts the thread that checks the database every `interval` and shuts the miner down cleanly
+/// once `duration` has passed.
+pub fn spawn_checker(duration: Duration, interval: Duration, manager_tx: Sender<ManagerCommand>, submitter_tx: Sender<SubmitterCommand>) {
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut checker = InvariantChecker::default();
+        let mut checks: u64 = 0;
+        let mut last = StateCounts::default();
+
+        while started.elapsed() < duration {
+            thread::sleep(interval.min(duration.saturating_sub(started.elapsed())));
+            match checker.check(&manager_tx, &submitter_tx) {
+                Ok(counts) => {
+                    checks += 1;
+                    cprintln!("🧪 Soak check #{} passed: {} challenges, {} receipts, {} pending. Elapsed: {}",
+                        checks, counts.challenges, counts.receipts, counts.pending, crate::utils::format_duration(started.elapsed().as_secs_f64()));
+                    last = counts;
+                }
+                Err(violation) => {
+                    crate::shutdown::fatal("Soak check", &format!("Invariant violated: {}", violation), crate::exit_codes::GENERAL_FAILURE);
+                    return;
+                }
+            }
+        }
+
+        cprintln!("\n🧪 Soak run finished after {}: {} checks passed, {} receipts saved, {} solutions still pending.",
+            crate::utils::format_duration(started.elapsed().as_secs_f64()), checks, last.receipts, last.pending);
+        if last.receipts == 0 {
+            ceprintln!("⚠️ WARNING: No solution was accepted during the soak run, so the submission path went untested.");
+        }
+        let _ = manager_tx.send(ManagerCommand::Shutdown);
+    });
+}
+
+#[derive(Default)]
+struct StateCounts {
+    challenges: usize,
+    receipts: usize,
+    pending: usize,
+}
+
+#[derive(Default)]
+struct InvariantChecker {
+    // Pending keys whose solution already had a receipt at the previous check.
+    settled_pending: HashSet<String>,
+}
+
+impl InvariantChecker {
+    /// Runs every invariant once. A solution's receipt and the removal of its pending entry are
+    /// two writes, so an entry is only reported once it outlives its receipt across two checks.
+    fn check(&mut self, manager_tx: &Sender<ManagerCommand>, submitter_tx: &Sender<SubmitterCommand>) -> Result<StateCounts, String> {
+        control::ask_manager_within(manager_tx, ControlRequest::Status, REPLY_TIMEOUT)
+            .map_err(|e| format!("manager did not answer a status request: {}", e))?;
+
+        let challenges: HashSet<String> = list_state(submitter_tx, SLED_KEY_CHALLENGE)?
+            .into_iter()
+            .map(|(key, _)| key[SLED_KEY_CHALLENGE.len() + 1..].to_string())
+            .collect();
+
+        // receipt:<ADDRESS>:<CHALLENGE_ID> -> receipt
+        let mut receipts = HashMap::new();
+        for (key, value) in list_state(submitter_tx, SLED_KEY_RECEIPT)? {
+            let receipt: serde_json::Value = serde_json::from_str(&value)
+                .map_err(|e| format!("receipt {} is not valid JSON: {}", key, e))?;
+            receipts.insert(key, receipt);
+        }
+
+        let pending = list_state(submitter_tx, SLED_KEY_PENDING)?;
+        let mut settled = HashSet::new();
+        for (key, value) in &pending {
+            let solution: PendingSolution = serde_json::from_str(value)
+                .map_err(|e| format!("pending entry {} does not decode: {}", key, e))?;
+            let expected_key = format!("{}:{}:{}:{}", SLED_KEY_PENDING, solution.address, solution.challenge_id, solution.nonce);
+            if *key != expected_key {
+                return Err(format!("pending entry {} holds the solution for {}", key, expected_key));
+            }
+            if !challenges.contains(&solution.challenge_id) {
+                return Err(format!("pending entry {} refers to challenge {}, which is not stored", key, solution.challenge_id));
+            }
+
+            let receipt_key = format!("{}:{}:{}", SLED_KEY_RECEIPT, solution.address, solution.challenge_id);
+            if receipts.get(&receipt_key).is_some_and(|receipt| receipt_matches(receipt, &solution.nonce)) {
+                if self.settled_pending.contains(key) {
+                    return Err(format!("pending entry {} is still queued after its receipt {} was saved", key, receipt_key));
+                }
+                settled.insert(key.clone());
+            }
+        }
+        self.settled_pending = settled;
+
+        Ok(StateCounts { challenges: challenges.len(), receipts: receipts.len(), pending: pending.len() })
+    }
+}
+
+/// Whether `receipt` (an API crypto receipt or a "consumed by network" marker) is for `nonce`.
+/// API preimages start with the nonce.
+fn receipt_matches(receipt: &serde_json::Value, nonce: &str) -> bool {
+    receipt["nonce"].as_str() == Some(nonce)
+        || receipt["preimage"].as_str().is_some_and(|preimage| preimage.starts_with(nonce))
+}
+
+fn list_state(submitter_tx: &Sender<SubmitterCommand>, prefix: &str) -> Result<Vec<(String, String)>, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    submitter_tx
+        .send(SubmitterCommand::ListState(format!("{}:", prefix), reply_tx))
+        .map_err(|_| "state worker stopped".to_string())?;
+    reply_rx
+        .recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| format!("state worker did not list '{}:' entries in time", prefix))?
+        .map_err(|e| format!("listing '{}:' entries failed: {}", prefix, e))
+}
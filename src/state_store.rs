@@ -0,0 +1,126 @@
+// src/state_store.rs
+
+use crate::cli::Cli;
+use crate::control::{self, ControlClient};
+use crate::persistence::Persistence;
+use crate::utils;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+// ===============================================
+// STATE STORE (one-shot commands)
+// ===============================================
+//
+// Sled allows one process per database, so while the miner runs it holds the lock on
+// state.sled. One-shot commands (`challenge list`, `wallet donate-all`, `db export`, ...) then
+// go through the miner's control socket, which hands each read and write to its state worker.
+// With no miner listening they open the database directly, as before.
+
+const SLED_DB_FILENAME: &str = "state.sled";
+
+/// Where a one-shot command reads and writes state.
+pub enum StateStore {
+    /// The database, opened by this process.
+    Local(Persistence),
+    /// A running miner's state worker, reached through its control socket.
+    Remote(RefCell<ControlClient>),
+}
+
+impl StateStore {
+    /// Connects to a running miner when its control socket answers, otherwise opens the
+    /// database under --data-dir.
+    pub fn open(cli: &Cli) -> Result<Self, String> {
+        if let Some(socket_path) = control::socket_path(cli).filter(|path| Path::new(path).exists()) {
+            match ControlClient::connect(&socket_path) {
+                Ok(client) => {
+                    ceprintln!("🔌 Miner is running: using its state through {}.", socket_path);
+                    return Ok(StateStore::Remote(RefCell::new(client)));
+                }
+                // A stale socket from a miner that did not shut down cleanly.
+                Err(e) => ceprintln!("⚠️ {} Opening the database directly.", e),
+            }
+        }
+
+        Ok(StateStore::Local(Self::open_local(cli, cli.data_dir.as_deref().unwrap_or("state"))?))
+    }
+
+    /// Opens the database under `data_dir` directly, with the compression and encryption
+    /// settings of `cli`.
+    pub fn open_local(cli: &Cli, data_dir: &str) -> Result<Persistence, String> {
+        let db_path = PathBuf::from(data_dir).join(SLED_DB_FILENAME);
+        let persistence = Persistence::open(&db_path)
+            .map_err(|e| format!(
+                "FATAL: Could not open Sled DB at {}: {}. If a miner is using it, pass the same --data-dir or --control-socket it was started with.",
+                db_path.display(), e
+            ))?
+            .with_compression(cli.db_compress);
+        utils::apply_db_encryption(persistence, utils::db_encryption(cli)?.as_ref())
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match self {
+            StateStore::Local(persistence) => persistence.get(key),
+            StateStore::Remote(client) => {
+                let value = client.borrow_mut().request("state-get", &[key])?;
+                Ok(value.as_str().map(str::to_string))
+            }
+        }
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        match self {
+            StateStore::Local(persistence) => persistence.set(key, value),
+            StateStore::Remote(client) => client.borrow_mut().request("state-set", &[key, value]).map(|_| ()),
+        }
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), String> {
+        match self {
+            StateStore::Local(persistence) => persistence
+                .db
+                .remove(key.as_bytes())
+                .map(|_| ())
+                .map_err(|e| format!("Sled remove failed for key '{}': {}", key, e)),
+            StateStore::Remote(client) => client.borrow_mut().request("state-remove", &[key]).map(|_| ()),
+        }
+    }
+
+    /// Every (key, decoded value) pair whose key starts with `prefix`, in key order. An empty
+    /// prefix lists the whole database.
+    pub fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, String> {
+        match self {
+            StateStore::Local(persistence) => persistence
+                .db
+                .scan_prefix(prefix.as_bytes())
+                .map(|entry| {
+                    let (key, value) = entry.map_err(|e| format!("Sled iteration error for prefix '{}': {}", prefix, e))?;
+                    Ok((String::from_utf8_lossy(&key).to_string(), persistence.decode_value(&value)?))
+                })
+                .collect(),
+            StateStore::Remote(client) => {
+                let entries = client.borrow_mut().request("state-list", &[prefix])?;
+                serde_json::from_value(entries).map_err(|e| format!("Malformed state listing from the miner: {}", e))
+            }
+        }
+    }
+
+    /// Asks a running miner to submit its pending queue now. Returns the number of submissions
+    /// started, or None for a local database (the next miner run submits on `retry-pending`).
+    pub fn retry_pending(&self) -> Result<Option<u64>, String> {
+        match self {
+            StateStore::Local(_) => Ok(None),
+            StateStore::Remote(client) => {
+                let reply = client.borrow_mut().request("retry-pending", &[])?;
+                Ok(Some(reply["resubmitted"].as_u64().unwrap_or(0)))
+            }
+        }
+    }
+
+    /// Flushes and closes a local database; the miner keeps its own open.
+    pub fn close(self) -> Result<(), String> {
+        match self {
+            StateStore::Local(persistence) => persistence.close().map_err(|e| format!("Failed to close Sled DB: {}", e)),
+            StateStore::Remote(_) => Ok(()),
+        }
+    }
+}
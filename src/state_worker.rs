@@ -1,147 +1,485 @@
 // src/state_worker.rs
 
-use crate::data_types::{PendingSolution, SubmitterCommand, WebSocketCommand};
+use crate::data_types::{DonationRecord, FailedSolution, PendingSolution, SubmitterCommand, WebSocketCommand};
 use crate::backoff::Backoff;
-use reqwest::blocking::Client;
+use crate::cli::DbBackend;
+use reqwest::Client;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::thread;
+use tokio::sync::Semaphore;
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::Instrument;
 use crate::persistence::Persistence;
+use crate::schema::{PendingKey, ReceiptKey, StateSchema, RECEIPT_STATUS_CONSUMED_MARKER};
+use crate::utils;
 use std::sync::mpsc::{Receiver, Sender};
-use crate::api;
-use std::sync::Arc;
+use crate::api::{self, ApiError, ApiErrorKind};
+use std::sync::{Arc, Mutex, OnceLock};
+use chrono::{DateTime, Utc};
+use crate::watchdog;
 use serde_json::{self};
+use tracing::{error, info, info_span, warn};
 
 
 // CONSTANTS
 const SLED_DB_PATH: &str = "state.sled";
-// Key prefixes for SLED
-const SLED_KEY_RECEIPT: &str = "receipt";
-const SLED_KEY_PENDING: &str = "pending";
+/// How long shutdown waits for in-flight submissions before flushing anyway.
+/// Unfinished submissions stay in the pending queue and are retried on the next start.
+const SHUTDOWN_SUBMISSION_GRACE: std::time::Duration = std::time::Duration::from_secs(15);
+/// Statistics lookups made to confirm a "solution consumed by network" marker before giving up.
+const RECEIPT_RECOVERY_ATTEMPTS: u32 = 5;
+/// Re-registrations attempted for one solution rejected as "not registered" before the
+/// failure is recorded as permanent.
+const MAX_REREGISTRATIONS: u32 = 3;
+/// Wait after a re-registration before resubmitting, multiplied by the attempt number, so the
+/// registration can propagate.
+const REREGISTRATION_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
+/// Solution submissions sent at once; further solutions wait for a free slot, already saved
+/// in the pending queue.
+const MAX_CONCURRENT_SUBMISSIONS: usize = 4;
 
+/// Emits the `submission_result` JSON log event for `solution`, and notifies about rejections.
+fn submission_event(solution: &PendingSolution, status: &str, error: Option<&str>) {
+    crate::console::event("submission_result", serde_json::json!({
+        "challenge_id": solution.challenge_id,
+        "address": solution.address,
+        "nonce": solution.nonce,
+        "status": status,
+        "error": error,
+    }));
+    if status != "accepted" && status != "dry_run" {
+        crate::notify::send(format!(
+            "❌ Submission {} for challenge {} ({}, nonce {}): {}",
+            status.replace('_', " "), solution.challenge_id, solution.address, solution.nonce, error.unwrap_or("no details")
+        ));
+    }
+}
+
+/// Why a submission ended without a receipt.
+#[derive(Debug)]
+enum WorkerError {
+    /// The network had already credited the solution; a marker receipt was stored in its place.
+    AlreadySolved(ApiError),
+    /// The API refused the solution for good; it was recorded as a failed solution.
+    Rejected(ApiError),
+    /// The submission window closed before the solution got through.
+    DeadlinePassed(ApiError),
+    /// Retries ran out; the solution stays in the pending queue for the next start.
+    RetriesExhausted(ApiError),
+}
+
+impl std::fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkerError::AlreadySolved(e) => write!(f, "Solution consumed by network: {}", e),
+            WorkerError::Rejected(e) => write!(f, "Solution rejected: {}", e),
+            WorkerError::DeadlinePassed(e) => write!(f, "HTTP submission failed after the submission deadline passed: {}", e),
+            WorkerError::RetriesExhausted(e) => write!(f, "Submission failed after max backoff: {}", e),
+        }
+    }
+}
 
-/// Constructs the unique key used to store a pending solution in Sled.
-/// Format: pending:<ADDRESS>:<CHALLENGE_ID>:<NONCE>
-fn get_sled_pending_key(solution: &PendingSolution) -> String {
-    format!("{}:{}:{}:{}", SLED_KEY_PENDING, solution.address, solution.challenge_id, solution.nonce)
+/// Stores a permanently rejected solution under failed_solution:<ADDRESS>:<CHALLENGE_ID>:<NONCE>
+/// for `challenge errors` and `challenge verify`.
+fn record_failed_solution(persistence: &Persistence, solution: &PendingSolution, error: &str) {
+    let failed = FailedSolution {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        address: solution.address.clone(),
+        challenge_id: solution.challenge_id.clone(),
+        nonce: solution.nonce.clone(),
+        error_message: error.to_string(),
+        preimage: solution.preimage.clone(),
+        hash_output: solution.hash_output.clone(),
+    };
+    if let Err(e) = persistence.put_failed(&failed) {
+        warn!("⚠️ Failed to record permanent submission failure: {}", e);
+    }
 }
 
-/// Constructs the unique key used to store a receipt in Sled.
-/// Format: receipt:<ADDRESS>:<CHALLENGE_ID>
-fn get_sled_receipt_key(address: &str, challenge_id: &str) -> String {
-    format!("{}:{}:{}", SLED_KEY_RECEIPT, address, challenge_id)
+/// Saves a solution to the SLED pending queue (ensures crash resilience) before it is submitted.
+fn queue_pending_solution(persistence: &Persistence, solution: &PendingSolution) -> Result<(), String> {
+    if let Err(e) = persistence.put_pending(solution) {
+        return Err(format!("FATAL: Failed to save pending solution to SLED: {}", e));
+    }
+    info!("📦 Solution queued to SLED pending table: {}", PendingKey::of(solution));
+    Ok(())
 }
 
-/// Attempts to submit a solution to the API with exponential backoff and saves the receipt on success.
-/// Returns an error string that may start with "PERMANENT_ERROR:" if the failure is non-recoverable.
-fn run_blocking_submission(
+/// Attempts to submit a queued solution to the API with exponential backoff and saves the receipt on success.
+/// Retryable API errors are retried; any other outcome is returned after the pending entry is settled.
+async fn run_submission(
     client: &Client,
     api_url: &str,
     persistence: &Persistence,
     solution: PendingSolution, // Takes ownership of solution
-) -> Result<(), String> {
-    let mut backoff = Backoff::new(5, 300, 2.0); // 5s min, 300s max, 2.0 factor
-    let pending_key = get_sled_pending_key(&solution);
-
-    // 1. Initial Save to SLED pending queue (Ensures crash resilience)
-    let solution_json = serde_json::to_string(&solution)
-        .map_err(|e| format!("Failed to serialize pending solution: {}", e))?;
-
-    if let Err(e) = persistence.set(&pending_key, &solution_json) {
-        return Err(format!("FATAL: Failed to save pending solution to SLED: {}", e));
-    }
-    println!("📦 Solution queued to SLED pending table: {}", pending_key);
+) -> Result<(), WorkerError> {
+    let mut backoff = Backoff::with_retry_policy(5);
+    let pending_key = PendingKey::of(&solution);
+    let mut reregistrations: u32 = 0;
 
     loop {
-        match api::submit_solution(client, api_url, &solution.address, &solution.challenge_id, &solution.nonce) {
+        match api::submit_solution(client, api_url, &solution.address, &solution.challenge_id, &solution.nonce).await {
             Ok(receipt_json) => {
-                println!("🚀 HTTP Submitter Success: Solution for {} submitted.", solution.address);
+                let received_at = Utc::now();
+                info!("🚀 HTTP Submitter Success: Solution for {} submitted.", solution.address);
+                submission_event(&solution, "accepted", None);
 
                 // 2. On success: Save final receipt to SLED
-                let receipt_key = get_sled_receipt_key(&solution.address, &solution.challenge_id);
-                let receipt_content = serde_json::to_string(&receipt_json)
-                    .map_err(|e| format!("Failed to serialize receipt JSON: {}", e))?;
-
-                if let Err(e) = persistence.set(&receipt_key, &receipt_content) {
-                    eprintln!("⚠️ WARNING: Submission successful, but failed to save receipt to SLED: {}", e);
+                if let Err(e) = persistence.put_receipt(&solution.address, &solution.challenge_id, &receipt_json) {
+                    warn!("⚠️ WARNING: Submission successful, but failed to save receipt to SLED: {}", e);
                 } else {
-                    println!("📦 Receipt saved to SLED: {}", receipt_key);
+                    info!("📦 Receipt saved to SLED for {} on challenge {}.", solution.address, solution.challenge_id);
                 }
 
                 // 3. Delete from SLED pending queue
-                if let Err(e) = persistence.db.remove(&pending_key) {
-                    eprintln!("⚠️ WARNING: Submission successful, but failed to remove pending entry from SLED: {}", e);
+                if let Err(e) = persistence.remove_pending(&pending_key) {
+                    warn!("⚠️ WARNING: Submission successful, but failed to remove pending entry from SLED: {}", e);
                 }
 
+                // 4. Cover the new rewards if the address was donated before this receipt.
+                redonate_after_receipt(client, api_url, persistence, &solution, received_at).await;
+
                 return Ok(());
             }
             Err(e) => {
-                // FIX: Check for the nonce consumed/exists error.
-                let is_nonce_consumed = e.contains("Solution already submitted") || e.contains("Solution already exists");
-                let is_deadline_past = e.contains("Submission window closed");
-
-                if is_nonce_consumed {
-                    // CRITICAL: Solution is consumed. Set a marker receipt to prevent re-mining this address.
-                    let solved_marker_key = get_sled_receipt_key(&solution.address, &solution.challenge_id);
-                    let solved_marker_json = serde_json::json!({
-                        "status": "solved_by_network",
-                        "challenge_id": solution.challenge_id,
-                        "address": solution.address,
-                        "note": "Solution consumed by network; no receipt recovered."
-                    }).to_string();
-
-                    let _ = persistence.set(&solved_marker_key, &solved_marker_json)
-                        .map(|_| println!("✅ Solution confirmed solved by network. Marker set in DB: {}", solved_marker_key))
-                        .map_err(|e_set| eprintln!("⚠️ WARNING: Solution consumed, but failed to set SOLVED marker in SLED: {}", e_set));
-
-                    // Always delete from pending queue and mark as a permanent error to exit retry loop.
-                    let _ = persistence.db.remove(&pending_key);
-
-                    return Err(format!("PERMANENT_ERROR: Solution consumed by network: {}", e));
-                }
+                let error_text = e.to_string();
+                match e.kind() {
+                    Some(ApiErrorKind::AlreadySubmitted) => {
+                        // CRITICAL: Solution is consumed. Set a marker receipt to prevent re-mining this address.
+                        let solved_marker = serde_json::json!({
+                            "status": RECEIPT_STATUS_CONSUMED_MARKER,
+                            "challenge_id": solution.challenge_id,
+                            "address": solution.address,
+                            "nonce": solution.nonce,
+                            "preimage": solution.preimage,
+                            "hash_output": solution.hash_output,
+                            "note": "Solution consumed by network; no receipt recovered."
+                        });
 
-                else if is_deadline_past {
+                        let _ = persistence.put_receipt(&solution.address, &solution.challenge_id, &solved_marker)
+                            .map(|_| info!("✅ Solution confirmed solved by network. Marker set in DB for {} on challenge {}.", solution.address, solution.challenge_id))
+                            .map_err(|e_set| warn!("⚠️ WARNING: Solution consumed, but failed to set SOLVED marker in SLED: {}", e_set));
 
-                    // TODO return to the manager to determine if it should exit
-                    eprintln!("⚠️ HTTP Submission failed: {}. Exiting because deadline has passed", e);
-                    std::process::exit(1);
-                }
+                        // Always delete from pending queue to exit the retry loop.
+                        let _ = persistence.remove_pending(&pending_key);
+                        submission_event(&solution, "already_solved", Some(&error_text));
+                        return Err(WorkerError::AlreadySolved(e));
+                    }
+
+                    // Queue entries from before registrations were recorded have nothing to replay:
+                    // retry them like a busy API and keep them pending until the address is known.
+                    Some(ApiErrorKind::NotRegistered) if solution.registration.is_none() => {
+                        if backoff.exhausted() {
+                            error!("❌ {} is still not registered after {} retries. Keeping in pending queue.", solution.address, backoff.retries);
+                            submission_event(&solution, "failed", Some(&error_text));
+                            return Err(WorkerError::RetriesExhausted(e));
+                        }
+
+                        let delay = backoff.next_delay();
+                        warn!("⚠️ Solution rejected because {} is not registered: {}. Retry {}/{} in {}s...", solution.address, e, backoff.retries, api::max_retries(), delay.as_secs());
+                        tokio::time::sleep(delay).await;
+                    }
+
+                    Some(ApiErrorKind::NotRegistered) => {
+                        if reregistrations < MAX_REREGISTRATIONS && let Some(proof) = solution.registration.as_ref() {
+                            reregistrations += 1;
+                            warn!("⚠️ Solution rejected because {} is not registered: {}. Re-registering (attempt {}/{}).",
+                                solution.address, e, reregistrations, MAX_REREGISTRATIONS);
+                            match api::register_address(client, api_url, &solution.address, &proof.message, &proof.signature, &proof.pubkey).await {
+                                Ok(()) => info!("📋 Re-registered {}. Resubmitting shortly.", solution.address),
+                                Err(reg_e) => warn!("⚠️ Re-registration of {} failed: {}", solution.address, reg_e),
+                            }
+                            tokio::time::sleep(REREGISTRATION_DELAY * reregistrations).await;
+                            continue;
+                        }
+
+                        // Out of attempts: the API keeps refusing the registration we hold.
+                        record_failed_solution(persistence, &solution, &error_text);
+                        let _ = persistence.remove_pending(&pending_key);
+                        submission_event(&solution, "not_registered", Some(&error_text));
+                        return Err(WorkerError::Rejected(e));
+                    }
 
-                // All other errors (registration/difficulty mismatch, 5xx) trigger retry.
-                if backoff.cur > backoff.max {
-                    eprintln!("❌ Max retries reached for solution submission. Keeping in pending queue.");
-                    return Err(format!("Submission failed after max backoff: {}", e));
+                    Some(ApiErrorKind::SubmissionClosed) => {
+                        let error = WorkerError::DeadlinePassed(e);
+                        submission_event(&solution, "deadline_passed", Some(&error_text));
+                        crate::shutdown::fatal("Submission handler", &error.to_string(), crate::exit_codes::DEADLINE_PASSED);
+                        return Err(error);
+                    }
+
+                    // Network failures, 5xx and rate limiting trigger retry.
+                    _ if e.is_retryable() => {
+                        if backoff.exhausted() {
+                            error!("❌ Max retries reached for solution submission. Keeping in pending queue.");
+                            submission_event(&solution, "failed", Some(&error_text));
+                            return Err(WorkerError::RetriesExhausted(e));
+                        }
+
+                        let delay = backoff.next_delay();
+                        warn!("⚠️ HTTP Submission failed: {}. Retry {}/{} in {}s...", e, backoff.retries, api::max_retries(), delay.as_secs());
+                        tokio::time::sleep(delay).await;
+                    }
+
+                    // Any other rejection (e.g. difficulty mismatch) will not change on resubmission.
+                    _ => {
+                        record_failed_solution(persistence, &solution, &error_text);
+                        let _ = persistence.remove_pending(&pending_key);
+                        submission_event(&solution, "rejected", Some(&error_text));
+                        return Err(WorkerError::Rejected(e));
+                    }
                 }
+            }
+        }
+    }
+}
+
+/// Checks a solution against its stored challenge the way the API would: the preimage is built
+/// from the nonce, address and challenge, the hash meets the difficulty and the submission
+/// window is still open.
+fn validate_solution(persistence: &Persistence, solution: &PendingSolution) -> Result<(), String> {
+    let challenge = persistence.get_challenge(&solution.challenge_id)
+        .map_err(|e| format!("stored challenge {} is unreadable: {}", solution.challenge_id, e))?
+        .ok_or_else(|| format!("challenge {} is not in the local database", solution.challenge_id))?;
+    let mask = u32::from_str_radix(&challenge.difficulty, 16)
+        .map_err(|_| format!("challenge {} has a non-hex difficulty '{}'", challenge.challenge_id, challenge.difficulty))?;
+    let nonce = u64::from_str_radix(&solution.nonce, 16)
+        .map_err(|_| format!("nonce '{}' is not hex", solution.nonce))?;
+
+    let expected_preimage = shadow_harvester_lib::build_preimage(
+        nonce, &solution.address, &challenge.challenge_id, mask,
+        &challenge.no_pre_mine_key, &challenge.latest_submission, &challenge.no_pre_mine_hour_str,
+    );
+    if solution.preimage != expected_preimage {
+        return Err("preimage does not match the nonce, address and challenge".to_string());
+    }
+    let hash = hex::decode(&solution.hash_output).map_err(|_| "hash output is not hex".to_string())?;
+    if !shadow_harvester_lib::hash_structure_good(&hash, mask) {
+        return Err(format!("hash {} does not meet difficulty {}", solution.hash_output, challenge.difficulty));
+    }
+    if shadow_harvester_lib::submission_deadline_passed(&challenge.latest_submission) {
+        return Err("the submission deadline has passed".to_string());
+    }
+    Ok(())
+}
 
-                eprintln!("⚠️ HTTP Submission failed: {}. Retrying with backoff...", e);
-                backoff.sleep();
+/// --dry-run: validates the solution locally and logs the submission instead of sending it.
+/// Nothing is queued, so a later real run does not submit it either.
+fn log_dry_run_submission(api_url: &str, persistence: &Persistence, solution: &PendingSolution) {
+    let url = api::endpoint_url(api_url, &format!("solution/{}/{}/{}", solution.address, solution.challenge_id, solution.nonce));
+    match validate_solution(persistence, solution) {
+        Ok(()) => {
+            info!("🧪 Dry run: solution passes local validation. Would POST {}", url);
+            submission_event(solution, "dry_run", None);
+        }
+        Err(e) => {
+            warn!("🧪 Dry run: solution FAILS local validation ({}). Would POST {}", e, url);
+            submission_event(solution, "dry_run", Some(&e));
+        }
+    }
+}
+
+/// Follow-up for a "solution consumed by network" marker: resubmits the marker's nonce so the
+/// API answers for this address and challenge. A receipt replaces the marker; another "already
+/// submitted" confirms it (marked `confirmed_at`, and not checked again). Any other answer, or
+/// none at all, means the network never confirmed the solution: it goes back into the pending
+/// queue and the marker is removed, so nothing is recorded that the API did not confirm.
+async fn recover_consumed_receipt(
+    client: &Client,
+    api_url: &str,
+    persistence: &Persistence,
+    address: &str,
+    challenge_id: &str,
+) -> Result<(), String> {
+    let Some(mut marker) = persistence.get_receipt(address, challenge_id)? else {
+        return Ok(());
+    };
+    let field = |name: &str| marker.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let solution = PendingSolution {
+        address: address.to_string(),
+        challenge_id: challenge_id.to_string(),
+        nonce: field("nonce"),
+        donation_address: None,
+        preimage: field("preimage"),
+        hash_output: field("hash_output"),
+        registration: None,
+        donation_signature: None,
+    };
+    if solution.nonce.is_empty() {
+        return Err(format!("Marker for {} on challenge {} has no nonce to confirm. Marker kept.", address, challenge_id));
+    }
+
+    let mut backoff = Backoff::new(5, api::retry_max_backoff_secs(), 2.0);
+    let mut last_error = String::new();
+    for attempt in 1..=RECEIPT_RECOVERY_ATTEMPTS {
+        match api::submit_solution(client, api_url, address, challenge_id, &solution.nonce).await {
+            Ok(receipt) => {
+                persistence.put_receipt(address, challenge_id, &receipt)?;
+                info!("🧾 Receipt recovered for {} on challenge {}.", address, challenge_id);
+                return Ok(());
+            }
+            Err(e) if e.kind() == Some(ApiErrorKind::AlreadySubmitted) => {
+                marker["confirmed_at"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+                persistence.put_receipt(address, challenge_id, &marker)?;
+                info!("🧾 Network confirmed the solution of {} on challenge {} as consumed.", address, challenge_id);
+                return Ok(());
+            }
+            Err(e) if e.is_retryable() => {
+                warn!("⚠️ Receipt recovery for {} on challenge {} failed (attempt {}/{}): {}", address, challenge_id, attempt, RECEIPT_RECOVERY_ATTEMPTS, e);
+                last_error = e.to_string();
+            }
+            Err(e) => {
+                last_error = e.to_string();
+                break;
             }
         }
+
+        if attempt < RECEIPT_RECOVERY_ATTEMPTS {
+            tokio::time::sleep(backoff.next_delay()).await;
+        }
+    }
+
+    persistence.put_pending(&solution)?;
+    persistence.delete(&ReceiptKey::new(address, challenge_id).to_string())?;
+    Err(format!(
+        "Network did not confirm the solution of {} on challenge {} ({}). Returned it to the pending queue; `retry-pending` or the next start resubmits it.",
+        address, challenge_id, last_error
+    ))
+}
+
+/// Spawns a background recovery task for every consumed-by-network marker left in the DB.
+fn spawn_pending_receipt_recoveries(client: &Client, api_url: &str, persistence: &Arc<Persistence>) -> Vec<JoinHandle<()>> {
+    let mut handles = Vec::new();
+
+    let receipts = match persistence.iter_receipts(None) {
+        Ok(receipts) => receipts,
+        Err(e) => {
+            warn!("⚠️ Could not scan receipts for consumed-by-network markers: {}", e);
+            return handles;
+        }
+    };
+    for entry in receipts {
+        let Ok(marker) = entry.value else { continue };
+        if marker.get("status").and_then(|s| s.as_str()) != Some(RECEIPT_STATUS_CONSUMED_MARKER) || marker.get("confirmed_at").is_some() {
+            continue;
+        }
+        let (address, challenge_id) = (entry.key.address, entry.key.challenge_id);
+
+        info!("🧾 Scheduling receipt recovery for {} on challenge {}.", address, challenge_id);
+        let (client, api_url, persistence) = (client.clone(), api_url.to_string(), persistence.clone());
+        handles.push(api::runtime().spawn(async move {
+            if let Err(e) = recover_consumed_receipt(&client, &api_url, &persistence, &address, &challenge_id).await {
+                warn!("⚠️ {}", e);
+            }
+        }));
+    }
+
+    handles
+}
+
+// Addresses the manager is donating itself: None while its donation is in flight, then the
+// local time it succeeded (the donation record reaches Sled a little later, via this worker).
+type ManagerDonations = HashMap<String, Option<DateTime<Utc>>>;
+static MANAGER_DONATIONS: OnceLock<Mutex<ManagerDonations>> = OnceLock::new();
+
+fn manager_donations() -> std::sync::MutexGuard<'static, ManagerDonations> {
+    MANAGER_DONATIONS.get_or_init(Default::default).lock().unwrap()
+}
+
+/// Called by the manager before it queues a solution it will donate for, so the submitter does
+/// not donate the same address a second time.
+pub fn manager_donation_started(address: &str) {
+    manager_donations().insert(address.to_string(), None);
+}
+
+/// Called by the manager once its donation for `address` finished; `donated_at` is None when it
+/// failed or was skipped.
+pub fn manager_donation_finished(address: &str, donated_at: Option<DateTime<Utc>>) {
+    let mut donations = manager_donations();
+    match donated_at {
+        Some(at) => { donations.insert(address.to_string(), Some(at)); }
+        None => { donations.remove(address); }
+    }
+}
+
+/// Whether a receipt received at `received_at` needs a new donation. Both times are from the
+/// local clock: only an address already donated strictly before the receipt is donated again,
+/// and never while the manager's own donation for it is still in flight.
+fn needs_redonation(manager_in_flight: bool, last_donated_at: Option<DateTime<Utc>>, received_at: DateTime<Utc>) -> bool {
+    !manager_in_flight && last_donated_at.is_some_and(|donated_at| donated_at < received_at)
+}
+
+/// Re-issues the donation of `solution.address` when it was donated to the same destination
+/// before the receipt received at `received_at`, so rewards accepted after that donation are
+/// assigned too.
+async fn redonate_after_receipt(client: &Client, api_url: &str, persistence: &Persistence, solution: &PendingSolution, received_at: DateTime<Utc>) {
+    let (Some(donate_to), Some(signature)) = (solution.donation_address.as_ref(), solution.donation_signature.as_ref()) else {
+        return;
+    };
+    let manager_donation = manager_donations().get(&solution.address).copied();
+    let recorded_at = persistence.get_donation(&solution.address).ok().flatten()
+        .filter(|record| record.donate_to == *donate_to)
+        .and_then(|record| DateTime::parse_from_rfc3339(&record.recorded_at).ok())
+        .map(|at| at.with_timezone(&Utc));
+    let last_donated_at = recorded_at.max(manager_donation.flatten());
+    if !needs_redonation(manager_donation.is_some_and(|state| state.is_none()), last_donated_at, received_at) {
+        return;
+    }
+
+    match api::donate_to(client, api_url, &solution.address, donate_to, signature).await {
+        Ok(donation_id) => {
+            info!("🚀 Re-issued donation of {} to {} after a new receipt. ID: {}", solution.address, donate_to, donation_id);
+            let record = DonationRecord::new(&solution.address, donate_to, &donation_id);
+            if let Err(e) = persistence.put_donation(&record) {
+                warn!("⚠️ Failed to record donation {}: {}", record.sled_key(), e);
+            }
+        }
+        Err(e) => warn!("⚠️ Re-donation of {} to {} failed: {}. `report audit` lists the address until it is donated again.", solution.address, donate_to, e),
+    }
+}
+
+/// Reads every solution in the SLED pending queue, keyed by its pending key. Entries that do
+/// not decode are skipped with a warning.
+fn list_pending_solutions(persistence: &Persistence) -> Result<Vec<(String, PendingSolution)>, String> {
+    let mut pending = Vec::new();
+    for entry in persistence.iter_pending(None)? {
+        match entry.value {
+            Ok(solution) => pending.push((entry.key.to_string(), solution)),
+            Err(e) => warn!("⚠️ Skipping pending entry that does not decode: {}", e),
+        }
     }
+    Ok(pending)
 }
 
-/// Decouples the blocking network call from the main worker loop.
+/// Queues the solution, then submits it as a task on the API runtime once one of `slots` is free.
 fn spawn_submission_handler(
     client: Client,
     api_url: String,
     persistence: Arc<Persistence>, // Use Arc<Persistence>
     solution: PendingSolution,
-) {
-    thread::spawn(move || {
-        // We clone the client and move the persistence Arc and the solution into the thread
-        if let Err(e) = run_blocking_submission(&client, &api_url, &persistence, solution) {
-            // Log non-recoverable errors but allow the thread to exit.
-            if e.starts_with("PERMANENT_ERROR") {
-                let error_message_val = e.strip_prefix("PERMANENT_ERROR: ").unwrap_or(&e).to_string();
-
-                // CRITICAL: Since run_blocking_submission handles logging and removing from pending queue on PERMANENT_ERROR,
-                // we only need to log the high-level failure here.
-                println!("❌ Submission Permanent Failure in background: {}", error_message_val);
-            }
+    slots: Arc<Semaphore>,
+) -> Option<JoinHandle<()>> {
+    if let Err(e) = queue_pending_solution(&persistence, &solution) {
+        error!("❌ {}", e);
+        return None;
+    }
+
+    let span = info_span!("submission", challenge_id = %solution.challenge_id, address = %solution.address);
+    Some(api::runtime().spawn(async move {
+        // The semaphore is never closed.
+        let Ok(_slot) = slots.acquire_owned().await else { return };
+
+        // run_submission already settled the pending entry; only permanent failures are logged here.
+        match run_submission(&client, &api_url, &persistence, solution).await {
+            // The marker is confirmed with the API on the next start.
+            Err(e @ (WorkerError::AlreadySolved(_) | WorkerError::Rejected(_))) => error!("❌ Submission Permanent Failure in background: {}", e),
+            Ok(()) | Err(WorkerError::DeadlinePassed(_) | WorkerError::RetriesExhausted(_)) => {}
         }
-    });
+    }.instrument(span)))
 }
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn run_state_worker(
     // Receives commands from the Manager thread
     submitter_rx: Receiver<SubmitterCommand>,
@@ -151,24 +489,52 @@ pub fn run_state_worker(
     data_dir_base: String,
     is_websocket_mode: bool,
     ws_tx: Sender<WebSocketCommand>, // Added ws_tx
+    // Compress large values (receipts, error records) written from now on
+    db_compress: bool,
+    db_backend: DbBackend,
+    // Encrypt values (or only the sensitive ones) at rest
+    db_encryption: Option<utils::DbEncryption>,
 ) -> Result<(), String> {
-    println!("📦 Starting persistence and submission thread (SLED DB).");
+    info!("📦 Starting persistence and submission thread (SLED DB, {}).", if db_backend == DbBackend::Memory { "in-memory" } else { "on disk" });
 
+    let opened = match db_backend {
+        DbBackend::Sled => Persistence::open(PathBuf::from(&data_dir_base).join(SLED_DB_PATH)),
+        DbBackend::Memory => Persistence::open_temporary(),
+    };
     // FIX: Persistence must be wrapped in Arc for thread safety when cloning it into submission handlers.
-    let persistence = Arc::new(Persistence::open(PathBuf::from(&data_dir_base).join(SLED_DB_PATH))
-        .map_err(|e| format!("FATAL: Could not initialize SLED database. Is another process running and locking the DB? Details: {}", e))?);
+    let opened = opened
+        .map_err(|e| format!("FATAL: Could not initialize SLED database. Is another process running and locking the DB? Details: {}", e))?
+        .with_compression(db_compress);
+    let persistence = Arc::new(utils::apply_db_encryption(opened, db_encryption.as_ref())?);
+    if persistence.is_selectively_encrypted() {
+        info!("🔐 Sensitive database entries are encrypted at rest.");
+    } else if persistence.is_encrypted() {
+        info!("🔐 Database values are encrypted at rest.");
+    }
 
     // Clone client and API URL for submission handlers
     let submission_client = client;
     let submission_api_url = api_url;
 
 
+    // In-flight HTTP submission handlers, so shutdown can wait for them.
+    // Markers left by a previous run are followed up first.
+    let mut submission_handles: Vec<JoinHandle<()>> = if is_websocket_mode || api::offline() {
+        Vec::new()
+    } else {
+        spawn_pending_receipt_recoveries(&submission_client, &submission_api_url, &persistence)
+    };
+    // Pending key -> its submission task, so a retry does not submit a solution twice.
+    let mut in_flight: HashMap<String, AbortHandle> = HashMap::new();
+    let mut solutions_received: u32 = 0;
+    let submission_slots = Arc::new(Semaphore::new(MAX_CONCURRENT_SUBMISSIONS));
+
     // 2. Main Command Loop
     while let Ok(command) = submitter_rx.recv() {
         match command {
             SubmitterCommand::SaveState(key, value) => {
                 if let Err(e) = persistence.set(&key, &value) {
-                    eprintln!("⚠️ Persistence Error: Failed to save state key '{}': {}", key, e);
+                    warn!("⚠️ Persistence Error: Failed to save state key '{}': {}", key, e);
                 }
             }
             SubmitterCommand::GetState(key, response_tx) => {
@@ -176,33 +542,111 @@ pub fn run_state_worker(
                 let result = persistence.get(&key);
                 // We don't panic if the response channel is closed, only if the Sled op failed
                 if response_tx.send(result).is_err() {
-                    eprintln!("⚠️ Warning: Failed to send Sled response back for key '{}'. Manager thread may be dead.", key);
+                    warn!("⚠️ Warning: Failed to send Sled response back for key '{}'. Manager thread may be dead.", key);
+                }
+            }
+            SubmitterCommand::ListState(prefix, response_tx) => {
+                let result = persistence.scan(&prefix);
+                if response_tx.send(result).is_err() {
+                    warn!("⚠️ Warning: Failed to send Sled scan results for prefix '{}'.", prefix);
+                }
+            }
+            SubmitterCommand::WriteState(key, value, response_tx) => {
+                let result = match value {
+                    Some(value) => persistence.set(&key, &value),
+                    None => persistence.delete(&key),
+                };
+                if response_tx.send(result).is_err() {
+                    warn!("⚠️ Warning: Failed to confirm write of key '{}'.", key);
                 }
             }
             SubmitterCommand::SubmitSolution(solution) => {
-                if !is_websocket_mode {
-                    // HTTP MODE: Spawn a non-blocking thread to handle the submission and retry logic.
-                    spawn_submission_handler(
+                solutions_received += 1;
+                if api::dry_run() {
+                    log_dry_run_submission(&submission_api_url, &persistence, &solution);
+                } else if !is_websocket_mode {
+                    // HTTP MODE: Submit on the API runtime, which handles the retry logic.
+                    submission_handles.retain(|handle| !handle.is_finished());
+                    let pending_key = PendingKey::of(&solution).to_string();
+                    if let Some(handle) = spawn_submission_handler(
                         submission_client.clone(),
                         submission_api_url.clone(),
                         persistence.clone(),
                         solution, // Move solution into handler
-                    );
+                        submission_slots.clone(),
+                    ) {
+                        in_flight.insert(pending_key, handle.abort_handle());
+                        submission_handles.push(handle);
+                    }
                 } else {
                     // WS MODE: Forward solution to the WebSocket server thread
                     if let Err(e) = ws_tx.send(WebSocketCommand::SubmitSolution(solution)) { // Solution is moved here
-                        eprintln!("❌ FATAL ERROR: Failed to forward solution to WebSocket server: {}", e);
+                        error!("❌ FATAL ERROR: Failed to forward solution to WebSocket server: {}", e);
                     }
-                    println!("🚀 Solution queued to be sent via WebSocket.");
+                    info!("🚀 Solution queued to be sent via WebSocket.");
                 }
             }
+            SubmitterCommand::RetryPending(response_tx) => {
+                let result = if is_websocket_mode {
+                    Err("Pending solutions are only resubmitted in HTTP mode.".to_string())
+                } else if api::dry_run() {
+                    Err("Dry run: solutions are not submitted.".to_string())
+                } else if api::offline() {
+                    Err("Offline mode: solutions are exported, not submitted.".to_string())
+                } else {
+                    submission_handles.retain(|handle| !handle.is_finished());
+                    in_flight.retain(|_, handle| !handle.is_finished());
+                    list_pending_solutions(&persistence).map(|pending| {
+                        let mut started = 0;
+                        for (pending_key, solution) in pending {
+                            if in_flight.contains_key(&pending_key) {
+                                continue;
+                            }
+                            if let Some(handle) = spawn_submission_handler(
+                                submission_client.clone(),
+                                submission_api_url.clone(),
+                                persistence.clone(),
+                                solution,
+                                submission_slots.clone(),
+                            ) {
+                                in_flight.insert(pending_key, handle.abort_handle());
+                                submission_handles.push(handle);
+                                started += 1;
+                            }
+                        }
+                        info!("🔁 Resubmitting {} pending solution(s).", started);
+                        started
+                    })
+                };
+                if response_tx.send(result).is_err() {
+                    warn!("⚠️ Warning: Failed to send pending retry result.");
+                }
+            }
+            SubmitterCommand::Heartbeat => watchdog::beat(watchdog::Component::StateWorker),
             SubmitterCommand::Shutdown => {
+                // Give in-flight submissions a chance to finish before flushing.
+                let grace_deadline = std::time::Instant::now() + SHUTDOWN_SUBMISSION_GRACE;
+                submission_handles.retain(|handle| !handle.is_finished());
+                if !submission_handles.is_empty() {
+                    info!("⏳ Waiting up to {}s for {} in-flight submission(s)...", SHUTDOWN_SUBMISSION_GRACE.as_secs(), submission_handles.len());
+                }
+                while !submission_handles.is_empty() && std::time::Instant::now() < grace_deadline {
+                    thread::sleep(std::time::Duration::from_millis(200));
+                    submission_handles.retain(|handle| !handle.is_finished());
+                }
+                let unfinished = submission_handles.len();
+                drop(submission_handles);
+
                 // FIX: Unwrap Arc to close the underlying Sled DB
                 match Arc::try_unwrap(persistence) {
-                    Ok(p) => if let Err(e) = p.close() { eprintln!("⚠️ Error flushing SLED DB on shutdown: {}", e); },
-                    Err(_) => eprintln!("⚠️ Error: Could not unwrap Persistence Arc on shutdown. Submission threads may still be alive."),
+                    Ok(p) => if let Err(e) = p.close() { warn!("⚠️ Error flushing SLED DB on shutdown: {}", e); },
+                    Err(p) => {
+                        // Submission threads are still retrying; flush what we have, they stay in the pending queue.
+                        if let Err(e) = p.db.flush() { warn!("⚠️ Error flushing SLED DB on shutdown: {}", e); }
+                    }
                 }
-                println!("📦 Submitter thread shutting down.");
+
+                info!("📦 Submitter thread shutting down. Solutions received this session: {}. Left in pending queue: {}.", solutions_received, unfinished);
                 break;
             }
         }
@@ -210,3 +654,36 @@ pub fn run_state_worker(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redonates_only_after_an_older_donation() {
+        let received_at = Utc::now();
+        let earlier = received_at - chrono::Duration::minutes(5);
+        // An address donated before the receipt is donated again.
+        assert!(needs_redonation(false, Some(earlier), received_at));
+        // Never donated, or donated at or after the receipt: nothing to do.
+        assert!(!needs_redonation(false, None, received_at));
+        assert!(!needs_redonation(false, Some(received_at), received_at));
+        assert!(!needs_redonation(false, Some(received_at + chrono::Duration::seconds(1)), received_at));
+    }
+
+    #[test]
+    fn skips_redonation_while_the_manager_donates() {
+        let received_at = Utc::now();
+        let earlier = received_at - chrono::Duration::minutes(5);
+        assert!(!needs_redonation(true, Some(earlier), received_at));
+
+        manager_donation_started("addr_test_in_flight");
+        let state = manager_donations().get("addr_test_in_flight").copied();
+        assert_eq!(state, Some(None));
+        manager_donation_finished("addr_test_in_flight", Some(received_at));
+        let state = manager_donations().get("addr_test_in_flight").copied();
+        assert_eq!(state, Some(Some(received_at)));
+        manager_donation_finished("addr_test_in_flight", None);
+        assert!(!manager_donations().contains_key("addr_test_in_flight"));
+    }
+}
@@ -0,0 +1,81 @@
+// src/sweep.rs
+
+use crate::api;
+use crate::data_types::Statistics;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::Client;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// ===============================================
+// BOUNDED CONCURRENT STATISTICS FETCHER
+// ===============================================
+
+/// Default number of statistics requests kept in flight by sweep commands.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Result of one statistics lookup: (derivation index, address, statistics or error).
+pub type StatisticsResult = (u32, String, Result<Statistics, String>);
+
+/// Fetches statistics for every `(index, address)` target with at most `concurrency` requests
+/// in flight, drawing a progress bar while it runs. Request starts are paced by the shared
+/// --max-rps limit. Results are returned in the same order as `targets`.
+pub fn fetch_statistics_concurrent(
+    client: &Client,
+    api_url: &str,
+    targets: &[(u32, String)],
+    concurrency: usize,
+) -> Vec<StatisticsResult> {
+    // No bar in plain-progress mode; the caller prints the results once the sweep is done.
+    let pb = if crate::console::plain_progress() { ProgressBar::hidden() } else { ProgressBar::new(targets.len() as u64) };
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    let next_target = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<Statistics, String>>>> = Mutex::new((0..targets.len()).map(|_| None).collect());
+    let workers = concurrency.clamp(1, targets.len().max(1));
+
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let i = next_target.fetch_add(1, Ordering::Relaxed);
+                let Some((_, address)) = targets.get(i) else {
+                    break;
+                };
+                // Requests not started yet are reported as cancelled.
+                if crate::shutdown::cancelled() {
+                    break;
+                }
+
+                let result = api::block_on(api::fetch_statistics_silent(client, api_url, address)).map_err(String::from);
+                if result.is_err() {
+                    pb.set_message(format!("last error at {}", address));
+                }
+
+                results.lock().unwrap()[i] = Some(result);
+                pb.inc(1);
+            });
+        }
+    });
+
+    pb.finish_and_clear();
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .zip(targets)
+        .map(|(result, (index, address))| {
+            let result = result.unwrap_or_else(|| Err(if crate::shutdown::cancelled() {
+                "Statistics request was cancelled.".to_string()
+            } else {
+                "Statistics request was not executed.".to_string()
+            }));
+            (*index, address.clone(), result)
+        })
+        .collect()
+}
@@ -0,0 +1,101 @@
+// src/telemetry.rs
+
+use crate::control;
+use crate::data_types::{ControlRequest, ManagerCommand};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+// ===============================================
+// CSV TELEMETRY (--telemetry-file)
+// ===============================================
+//
+// Appends one row per interval with the miner's state, so long runs can be analysed with a
+// spreadsheet or pandas. The rows come from the manager's control status, the same data the
+// control socket and REST API show.
+
+const HEADER: &str = "timestamp,state,challenge_id,threads,hash_rate,rss_mib,challenge_solutions,solutions_found";
+/// How long one row waits for the manager before it is written as "unresponsive".
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Resident memory of this process in bytes (Linux only).
+#[cfg(target_os = "linux")]
+fn resident_memory_bytes() -> Option<u64> {
+    // /proc/self/statm: size resident shared text lib data dt, in pages.
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf only reads a configuration value.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(resident_pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory_bytes() -> Option<u64> {
+    None
+}
+
+/// One CSV row for the manager's status reply.
+fn row(status: Result<serde_json::Value, String>) -> String {
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let rss_mib = resident_memory_bytes().map(|bytes| format!("{:.1}", bytes as f64 / (1024.0 * 1024.0))).unwrap_or_default();
+    let Ok(status) = status else {
+        return format!("{},unresponsive,,,,{},,", timestamp, rss_mib);
+    };
+
+    let state = if status["paused"].as_bool() == Some(true) {
+        "paused"
+    } else if status["mining"].as_bool() == Some(true) {
+        "mining"
+    } else {
+        "idle"
+    };
+    let field = |value: &serde_json::Value| match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    // The manager keeps the last miner's rate after it stops; only report it while mining.
+    let hash_rate = status["hash_rate"].as_f64().filter(|_| state == "mining").map(|rate| format!("{:.2}", rate)).unwrap_or_default();
+    format!(
+        "{},{},{},{},{},{},{},{}",
+        timestamp,
+        state,
+        field(&status["challenge_id"]),
+        field(&status["threads"]),
+        hash_rate,
+        rss_mib,
+        field(&status["challenge_solutions"]),
+        field(&status["solutions_found"]),
+    )
+}
+
+/// Opens `path` for appending (writing the header to a new or empty file) and starts a thread
+/// that appends a row every `interval`.
+pub fn spawn_telemetry(path: &str, interval: Duration, manager_tx: Sender<ManagerCommand>) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open telemetry file {}: {}", path, e))?;
+    let empty = file.metadata().map(|metadata| metadata.len() == 0).unwrap_or(true);
+    if empty {
+        writeln!(file, "{}", HEADER).map_err(|e| format!("Failed to write telemetry file {}: {}", path, e))?;
+    }
+
+    let path = path.to_string();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(interval);
+            if manager_tx.send(ManagerCommand::Heartbeat).is_err() {
+                return; // Shutting down.
+            }
+            let status = control::ask_manager_within(&manager_tx, ControlRequest::Status, STATUS_TIMEOUT);
+            if let Err(e) = writeln!(file, "{}", row(status)).and_then(|_| file.flush()) {
+                ceprintln!("⚠️ Failed to write telemetry file {}: {}", path, e);
+            }
+        }
+    });
+    Ok(())
+}
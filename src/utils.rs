@@ -2,12 +2,19 @@
 
 use crate::api;
 use crate::constants::USER_AGENT;
+use crate::cli::DbBackend;
+use crate::exit_codes::{self, ExitError, WithExitCode};
+use crate::i18n::{tr, trf, Msg};
+use crate::persistence::Persistence;
 use crate::data_types::{
-    DataDir, DataDirMnemonic, MiningContext, MiningResult, FILE_NAME_RECEIPT,
-    ChallengeData, Statistics, TandCResponse, ChallengeResponse, PendingSolution, FILE_NAME_FOUND_SOLUTION
+    DataDir, DataDirMnemonic, MiningContext, MiningResult, RomParamsOverride, FILE_NAME_RECEIPT,
+    ChallengeData, DifficultyRecord, Statistics, TandCResponse, ChallengeResponse, PendingSolution, FILE_NAME_FOUND_SOLUTION
 };
-use reqwest::blocking::{self, Client};
+use reqwest::Client;
 use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use shadow_harvester_lib::{Rom, RomPages, RomParams};
 use chrono::{DateTime, Utc};
 
 // ===============================================
@@ -23,39 +30,42 @@ pub fn format_duration(seconds: f64) -> String {
 }
 
 pub fn create_api_client() -> Result<Client, reqwest::Error> {
-    Client::builder()
-        .user_agent(USER_AGENT)
-        .build()
+    let builder = api::apply_tls_config(Client::builder().user_agent(USER_AGENT));
+    match crate::api::http_timeout() {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    }
+    .build()
 }
 
 /// Helper to print non-active challenge status
 fn print_non_active_status(response: &ChallengeResponse) {
-    println!("\n==============================================");
-    println!("⏰ Challenge Status: {}", response.code.to_uppercase());
-    println!("==============================================");
+    cprintln!("\n==============================================");
+    cprintln!("⏰ Challenge Status: {}", response.code.to_uppercase());
+    cprintln!("==============================================");
 
     if let Some(day) = response.current_day {
-        println!("Current Mining Day: {} / {}", day, response.max_day.unwrap_or(0));
+        cprintln!("Current Mining Day: {} / {}", day, response.max_day.unwrap_or(0));
     } else if let Some(max_day) = response.max_day {
-         println!("Total Mining Days: {}", max_day);
+         cprintln!("Total Mining Days: {}", max_day);
     }
 
     if let Some(ends) = &response.mining_period_ends {
-        println!("Mining Period Ends: {}", ends);
+        cprintln!("Mining Period Ends: {}", ends);
     }
     if let Some(total) = response.total_challenges {
-        println!("Total Challenges (All Days): {}", total);
+        cprintln!("Total Challenges (All Days): {}", total);
     }
 
     if response.code == "before" {
         if let Some(starts) = &response.starts_at {
-            println!("Challenge Starts At: {}", starts);
+            cprintln!("Challenge Starts At: {}", starts);
         }
         if let Some(next_starts) = &response.next_challenge_starts_at {
-            println!("Next Challenge Starts At: {}", next_starts);
+            cprintln!("Next Challenge Starts At: {}", next_starts);
         }
     }
-    println!("----------------------------------------------");
+    cprintln!("----------------------------------------------");
 }
 
 /// Checks if the submission deadline for a challenge has passed.
@@ -66,7 +76,7 @@ pub fn check_submission_deadline(challenge: ChallengeData) -> Result<ChallengeDa
     let latest_submission_time = match DateTime::parse_from_rfc3339(&challenge.latest_submission) {
         Ok(dt) => dt.with_timezone(&Utc),
         Err(e) => {
-            eprintln!("Error parsing submission deadline '{}': {}", challenge.latest_submission, e);
+            ceprintln!("Error parsing submission deadline '{}': {}", challenge.latest_submission, e);
             // Treat unparseable deadline as a fatal error
             return Err("FATAL: Challenge deadline is unparseable.".to_string());
         }
@@ -89,12 +99,12 @@ pub fn check_submission_deadline(challenge: ChallengeData) -> Result<ChallengeDa
 /// It returns None if it detects a reason to wait (same challenge ID, pre/post period)
 /// but DOES NOT perform the actual thread sleep, leaving that to the caller.
 pub fn poll_for_active_challenge(
-    client: &blocking::Client,
+    client: &Client,
     api_url: &str,
     current_id: &mut String,
 ) -> Result<Option<ChallengeData>, String> {
 
-    let challenge_response = api::fetch_challenge_status(client, api_url)?;
+    let challenge_response = api::block_on(api::fetch_challenge_status(client, api_url))?;
 
     match challenge_response.code.as_str() {
         "active" => {
@@ -106,7 +116,7 @@ pub fn poll_for_active_challenge(
                 Ok(p) => p,
                 Err(e) => {
                     // FIX: Log the rejection message and return None. No sleep.
-                    println!("\n🛑 {}", e);
+                    cprintln!("\n🛑 {}", e);
                     *current_id = "".to_string(); // Reset to prevent re-logging same rejection
                     return Ok(None);
                 }
@@ -115,30 +125,30 @@ pub fn poll_for_active_challenge(
             if validated_params.challenge_id != *current_id {
 
                 if current_id.is_empty() {
-                    println!("\n✅ Active challenge found (ID: {}). Starting cycle.", validated_params.challenge_id);
+                    cprintln!("\n✅ Active challenge found (ID: {}). Starting cycle.", validated_params.challenge_id);
                 } else {
-                    println!("\n🎉 New active challenge detected (ID: {}). Starting new cycle.", validated_params.challenge_id);
+                    cprintln!("\n🎉 New active challenge detected (ID: {}). Starting new cycle.", validated_params.challenge_id);
                 }
 
                 *current_id = validated_params.challenge_id.clone();
                 Ok(Some(validated_params))
             } else {
                 // Same challenge, remains active/solved
-                println!("\nℹ️ Challenge ID ({}) remains active/solved. Waiting for a new challenge...", validated_params.challenge_id);
+                cprintln!("\nℹ️ Challenge ID ({}) remains active/solved. Waiting for a new challenge...", validated_params.challenge_id);
                 // FIX: Removed internal thread::sleep.
                 Ok(None)
             }
         }
         "before" => {
             print_non_active_status(&challenge_response);
-            println!("⏳ MINING IS NOT YET ACTIVE. Waiting...");
+            cprintln!("⏳ MINING IS NOT YET ACTIVE. Waiting...");
             *current_id = "".to_string();
             // FIX: Removed internal thread::sleep.
             Ok(None)
         }
         "after" => {
             print_non_active_status(&challenge_response);
-            println!("🛑 MINING PERIOD HAS ENDED. Waiting for the next challenge...");
+            cprintln!("🛑 MINING PERIOD HAS ENDED. Waiting for the next challenge...");
             *current_id = "".to_string();
             // FIX: Removed internal thread::sleep.
             Ok(None)
@@ -148,7 +158,7 @@ pub fn poll_for_active_challenge(
 }
 
 pub fn get_challenge_params(
-    client: &blocking::Client,
+    client: &Client,
     api_url: &str,
     cli_challenge: Option<&String>,
     current_id: &mut String,
@@ -158,7 +168,7 @@ pub fn get_challenge_params(
             .map_err(|e| format!("Challenge parameter parsing error: {}", e))?;
 
         // Fetch live data (required for submission deadline/hour)
-        let live_params = api::get_active_challenge_data(client, api_url)
+        let live_params = api::block_on(api::get_active_challenge_data(client, api_url))
             .map_err(|e| format!("Could not fetch live challenge status (required for submission deadline/hour): {}", e))?;
 
         let mut fixed_challenge_params = live_params.clone();
@@ -173,12 +183,12 @@ pub fn get_challenge_params(
         let fixed_challenge_params = check_submission_deadline(fixed_challenge_params)?;
 
         if fixed_challenge_params.challenge_id != *current_id {
-            println!("\n⚠️ Fixed challenge specified: Using ID {} with Difficulty {}. Live polling disabled.",
+            cprintln!("\n⚠️ Fixed challenge specified: Using ID {} with Difficulty {}. Live polling disabled.",
                 fixed_challenge_params.challenge_id, fixed_challenge_params.difficulty);
             *current_id = fixed_challenge_params.challenge_id.clone();
         }
         else {
-             println!("\n⚠️ Fixed challenge ID ({}) is being re-mined.", fixed_challenge_params.challenge_id);
+             cprintln!("\n⚠️ Fixed challenge ID ({}) is being re-mined.", fixed_challenge_params.challenge_id);
         }
 
         Ok(Some(fixed_challenge_params))
@@ -187,37 +197,200 @@ pub fn get_challenge_params(
     }
 }
 
+/// Live figures of the current (or last) miner, shown by the statistics summary and the
+/// control status.
+#[derive(Clone, Debug, Default)]
+pub struct MinerStats {
+    /// Hash rate of each worker thread over the last `ROLLING_HASHRATE_WINDOW`, in H/s,
+    /// indexed by worker.
+    pub thread_rates: Vec<f64>,
+    /// Aggregate hash rate over the last `ROLLING_HASHRATE_WINDOW`, in H/s.
+    pub rolling_rate: f64,
+    /// Hash bits the challenge difficulty requires to be zero.
+    pub zero_bits: Option<u32>,
+    /// Submission deadline of the challenge (RFC 3339).
+    pub latest_submission: Option<String>,
+}
+
+impl MinerStats {
+    /// Fresh figures for a miner with `threads` workers on `challenge`.
+    pub fn for_challenge(challenge: &ChallengeData, threads: usize) -> Self {
+        MinerStats {
+            thread_rates: vec![0.0; threads],
+            rolling_rate: 0.0,
+            zero_bits: DifficultyRecord::from_challenge(challenge).zero_bits(),
+            latest_submission: Some(challenge.latest_submission.clone()),
+        }
+    }
+
+    /// Expected number of hashes per solution: each hash meets the difficulty with
+    /// probability 2^-zero_bits.
+    pub fn expected_hashes(&self) -> Option<f64> {
+        self.zero_bits.map(|bits| 2f64.powi(bits as i32))
+    }
+
+    /// Expected seconds until the next solution at the rolling hash rate.
+    pub fn expected_secs_to_solution(&self) -> Option<f64> {
+        let expected = self.expected_hashes()?;
+        (self.rolling_rate > 0.0).then(|| expected / self.rolling_rate)
+    }
+
+    /// Seconds left until the submission deadline (negative once it passed).
+    pub fn secs_to_deadline(&self) -> Option<f64> {
+        let deadline = DateTime::parse_from_rfc3339(self.latest_submission.as_deref()?).ok()?;
+        Some((deadline.with_timezone(&Utc) - Utc::now()).num_milliseconds() as f64 / 1000.0)
+    }
+
+    /// Probability of finding at least one solution before the deadline at the rolling rate.
+    /// Hashes are independent trials, so solutions arrive as a Poisson process.
+    pub fn solution_chance_before_deadline(&self) -> Option<f64> {
+        self.solution_chance_at(self.rolling_rate)
+    }
+
+    /// Same as `solution_chance_before_deadline`, at `rate` H/s instead of the rolling rate.
+    pub fn solution_chance_at(&self, rate: f64) -> Option<f64> {
+        let expected = self.expected_hashes()?;
+        if rate <= 0.0 {
+            return None;
+        }
+        let remaining = self.secs_to_deadline()?.max(0.0);
+        Some(1.0 - (-remaining * rate / expected).exp())
+    }
+}
+
+// Process-wide: figures of the current (or last) miner.
+static MINER_STATS: Mutex<MinerStats> = Mutex::new(MinerStats {
+    thread_rates: Vec::new(),
+    rolling_rate: 0.0,
+    zero_bits: None,
+    latest_submission: None,
+});
+
+/// Updates the figures of the running miner.
+pub fn update_miner_stats(update: impl FnOnce(&mut MinerStats)) {
+    update(&mut MINER_STATS.lock().unwrap());
+}
+
+/// Figures of the current (or last) miner.
+pub fn miner_stats() -> MinerStats {
+    MINER_STATS.lock().unwrap().clone()
+}
+
+/// Window of the rolling hash rate.
+pub const ROLLING_HASHRATE_WINDOW: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Moving average of a hash rate over the last `ROLLING_HASHRATE_WINDOW`.
+pub struct RollingHashrate {
+    started: std::time::Instant,
+    samples: std::collections::VecDeque<(std::time::Instant, u64)>,
+    window_hashes: u64,
+}
+
+impl RollingHashrate {
+    pub fn new() -> Self {
+        RollingHashrate { started: std::time::Instant::now(), samples: std::collections::VecDeque::new(), window_hashes: 0 }
+    }
+
+    /// Counts `hashes` just checked and returns the current rate in H/s.
+    pub fn record(&mut self, hashes: u64) -> f64 {
+        let now = std::time::Instant::now();
+        self.samples.push_back((now, hashes));
+        self.window_hashes += hashes;
+        while let Some(&(at, old)) = self.samples.front()
+            && now.duration_since(at) > ROLLING_HASHRATE_WINDOW {
+            self.samples.pop_front();
+            self.window_hashes -= old;
+        }
+        let span = now.duration_since(self.started).min(ROLLING_HASHRATE_WINDOW).as_secs_f64();
+        if span > 0.0 { self.window_hashes as f64 / span } else { 0.0 }
+    }
+}
+
+/// A worker this far below the median rate is flagged in the statistics summary.
+const SLOW_THREAD_PERCENT: f64 = 85.0;
+
 pub fn print_statistics(stats_result: Result<Statistics, String>, total_hashes: u64, elapsed_secs: f64) {
-    println!("\n==============================================");
-    println!("📈 Mining Statistics Summary");
-    println!("==============================================");
+    // Built as one block so concurrent output cannot interleave with it.
+    let mut out = String::new();
+    let _ = writeln!(out, "\n==============================================");
+    let _ = writeln!(out, "📈 {}", tr(Msg::StatsTitle));
+    let _ = writeln!(out, "==============================================");
     let hash_rate = if elapsed_secs > 0.0 { total_hashes as f64 / elapsed_secs } else { 0.0 };
-    println!("** LAST MINING CYCLE PERFORMANCE **");
-    println!("  Time Elapsed: {}", format_duration(elapsed_secs));
-    println!("  Total Hashes: {}", total_hashes);
-    println!("  Hash Rate: {:.2} H/s", hash_rate);
-    println!("----------------------------------------------");
+    let miner = miner_stats();
+    let thread_rates = &miner.thread_rates;
+    crate::console::event("stats", serde_json::json!({
+        "total_hashes": total_hashes,
+        "elapsed_secs": elapsed_secs,
+        "hash_rate": hash_rate,
+        "rolling_hash_rate": miner.rolling_rate,
+        "thread_hash_rates": thread_rates,
+        "expected_hashes_per_solution": miner.expected_hashes(),
+        "expected_secs_to_solution": miner.expected_secs_to_solution(),
+        "solution_chance_before_deadline": miner.solution_chance_before_deadline(),
+        "address": stats_result.as_ref().ok().map(|stats| &stats.local_address),
+        "crypto_receipts": stats_result.as_ref().ok().map(|stats| stats.crypto_receipts),
+        "night_allocation": stats_result.as_ref().ok().map(|stats| stats.night_allocation),
+        "error": stats_result.as_ref().err(),
+    }));
+    let _ = writeln!(out, "** {} **", tr(Msg::LastCyclePerformance));
+    let _ = writeln!(out, "  {}: {}", tr(Msg::TimeElapsed), format_duration(elapsed_secs));
+    let _ = writeln!(out, "  {}: {}", tr(Msg::TotalHashes), total_hashes);
+    let _ = writeln!(out, "  {}: {:.2} H/s", tr(Msg::HashRate), hash_rate);
+    if miner.rolling_rate > 0.0 {
+        let _ = writeln!(out, "  {}: {:.2} H/s", trf(Msg::RollingHashRate, &[&(ROLLING_HASHRATE_WINDOW.as_secs() / 60)]), miner.rolling_rate);
+    }
+    // Workers report their counts every 1024 hashes; skip the section before the first report.
+    if thread_rates.len() > 1 && thread_rates.iter().any(|&rate| rate > 0.0) {
+        // Compare each thread against the median, so one throttled core stands out.
+        let mut sorted = thread_rates.clone();
+        sorted.sort_by(f64::total_cmp);
+        let median = sorted[sorted.len() / 2];
+        let _ = writeln!(out, "  {}:", tr(Msg::PerThreadHashRate));
+        for (worker, rate) in thread_rates.iter().enumerate() {
+            if median > 0.0 && rate * 100.0 < median * SLOW_THREAD_PERCENT {
+                let below = format!("{:.0}", 100.0 - rate * 100.0 / median);
+                let _ = writeln!(out, "    {} {:>3}: {:>10.2} H/s  ⚠️ {}", tr(Msg::ThreadRate), worker, rate, trf(Msg::BelowMedian, &[&below]));
+            } else {
+                let _ = writeln!(out, "    {} {:>3}: {:>10.2} H/s", tr(Msg::ThreadRate), worker, rate);
+            }
+        }
+    }
+    if let (Some(bits), Some(expected)) = (miner.zero_bits, miner.expected_hashes()) {
+        let _ = writeln!(out, "  {}", trf(Msg::Difficulty, &[&bits, &format!("{:.0}", expected)]));
+    }
+    if let Some(eta) = miner.expected_secs_to_solution() {
+        let _ = writeln!(out, "  {}: {}", tr(Msg::ExpectedTimeToSolution), format_duration(eta));
+    }
+    if let (Some(chance), Some(remaining)) = (miner.solution_chance_before_deadline(), miner.secs_to_deadline()) {
+        if remaining > 0.0 {
+            let _ = writeln!(out, "  {}", trf(Msg::SolutionChance, &[&format_duration(remaining), &format!("{:.1}", chance * 100.0)]));
+        } else {
+            let _ = writeln!(out, "  {}", tr(Msg::DeadlinePassed));
+        }
+    }
+    let _ = writeln!(out, "----------------------------------------------");
     match stats_result {
         Ok(stats) => {
-            println!("** YOUR ACCOUNT STATISTICS (Address: {}) **", stats.local_address);
-            println!("  Crypto Receipts (Solutions): {}", stats.crypto_receipts);
-            println!("  Night Allocation: {}", stats.night_allocation);
-            println!("----------------------------------------------");
-            println!("** GLOBAL STATISTICS (All Miners) **");
-            println!("  NOTE: These statistics are aggregated across all wallets globally.");
-            println!("  Total Wallets: {}", stats.wallets);
-            println!("  Current Challenges: {}", stats.challenges);
-            println!("  Total Challenges Ever: {}", stats.total_challenges);
-            println!("  Total Crypto Receipts: {}", stats.total_crypto_receipts);
-            println!("  Recent Crypto Receipts: {}", stats.recent_crypto_receipts);
-            println!("==============================================");
+            let _ = writeln!(out, "** {} **", trf(Msg::AccountStatistics, &[&stats.local_address]));
+            let _ = writeln!(out, "  {}: {}", tr(Msg::CryptoReceipts), stats.crypto_receipts);
+            let _ = writeln!(out, "  {}: {}", tr(Msg::NightAllocation), stats.night_allocation);
+            let _ = writeln!(out, "----------------------------------------------");
+            let _ = writeln!(out, "** {} **", tr(Msg::GlobalStatistics));
+            let _ = writeln!(out, "  {}", tr(Msg::GlobalNote));
+            let _ = writeln!(out, "  {}: {}", tr(Msg::TotalWallets), stats.wallets);
+            let _ = writeln!(out, "  {}: {}", tr(Msg::CurrentChallenges), stats.challenges);
+            let _ = writeln!(out, "  {}: {}", tr(Msg::TotalChallenges), stats.total_challenges);
+            let _ = writeln!(out, "  {}: {}", tr(Msg::TotalCryptoReceipts), stats.total_crypto_receipts);
+            let _ = writeln!(out, "  {}: {}", tr(Msg::RecentCryptoReceipts), stats.recent_crypto_receipts);
+            let _ = writeln!(out, "==============================================");
         }
         Err(e) => {
-            println!("** FAILED TO FETCH API STATISTICS **");
-            eprintln!("  Error: {}", e);
-            println!("==============================================");
+            let _ = writeln!(out, "** {} **", tr(Msg::StatisticsFailed));
+            let _ = writeln!(out, "  {}: {}", tr(Msg::Error), e);
+            let _ = writeln!(out, "==============================================");
         }
     }
+    crate::console::out(out.trim_end().to_string());
 }
 
 pub fn run_single_mining_cycle(
@@ -225,25 +398,61 @@ pub fn run_single_mining_cycle(
     threads: u32,
     donate_to_option: Option<&String>,
     challenge_params: &ChallengeData,
+    rom: Arc<Rom>,
     data_dir_base: Option<&str>,
 ) -> (MiningResult, u64, f64) {
-    let (found_nonce, total_hashes, elapsed_secs) = shadow_harvester_lib::scavenge(
-        mining_address.clone(),
-        challenge_params.challenge_id.clone(),
-        challenge_params.difficulty.clone(),
-        challenge_params.no_pre_mine_key.clone(),
-        challenge_params.latest_submission.clone(),
-        challenge_params.no_pre_mine_hour_str.clone(),
-        threads,
+    let mut display = crate::console::ProgressDisplay::new(
+        shadow_harvester_lib::nonce_space(),
+        "{spinner:.green} {pos}/{len} [{elapsed_precise}] {bar:40.cyan/blue} {msg}",
     );
+    update_miner_stats(|stats| *stats = MinerStats::for_challenge(challenge_params, threads.max(1) as usize));
+    let mut rolling = RollingHashrate::new();
+    let mut reported_hashes = 0;
+    let mut on_progress = |progress: shadow_harvester_lib::Progress| {
+        let rate = rolling.record(progress.total_hashes - reported_hashes);
+        reported_hashes = progress.total_hashes;
+        update_miner_stats(|stats| stats.rolling_rate = rate);
+        display.update(progress.total_hashes, trf(Msg::ProgressLine, &[
+            &progress.total_hashes,
+            &format!("{:.2}", progress.hash_rate),
+            &progress.solutions_found,
+        ]));
+    };
+
+    let params = match challenge_params.to_params(&mining_address, Arc::new(shadow_harvester_lib::CpuHasher::new(rom))) {
+        Ok(params) => params,
+        Err(e) => {
+            display.abandon(format!("Scavenging not started: {}", e));
+            return (MiningResult::MiningFailed, 0, 0.0);
+        }
+    };
+    let report = shadow_harvester_lib::scavenge_challenge(params, threads, 1, Some(&mut on_progress));
+
+    if report.space_exhausted {
+        display.abandon(format!("Scavenging stopped (Search space exhausted after {} hashes).", report.total_hashes));
+    } else if report.nonces.is_empty() {
+        display.abandon("Scavenging stopped (No solution found).".to_string());
+    } else {
+        display.finish(format!("Scavenging complete. Found 1 solution. Total hashes checked: {}", report.total_hashes));
+    }
+
+    let total_hashes = report.total_hashes;
+    let elapsed_secs = report.elapsed;
+    update_miner_stats(|stats| stats.thread_rates = report.per_thread_rates);
+    let space_exhausted = report.space_exhausted;
+    let found_nonce = report.nonces.into_iter().next();
 
     let mining_result = match found_nonce {
+        None if space_exhausted => {
+            cprintln!("\n🔚 Searched every nonce below {:#x} without finding a solution.", shadow_harvester_lib::nonce_space());
+            MiningResult::SpaceExhausted
+        },
         None => {
-            println!("\n⚠️ Scavenging finished, but no solution was found.");
+            cprintln!("\n⚠️ Scavenging finished, but no solution was found.");
             MiningResult::MiningFailed
         },
         Some(nonce) => {
-            println!("\n✅ Solution found: {}. Saving solution to temporary storage...", nonce);
+            cprintln!("\n✅ Solution found: {}. Saving solution to temporary storage...", nonce);
 
             // SIMPLIFIED PendingSolution
             let pending_solution = PendingSolution {
@@ -254,6 +463,8 @@ pub fn run_single_mining_cycle(
                 // FIX: Add placeholder values for the new fields (synchronous function cannot capture full context)
                 preimage: "Legacy_Preimage_Not_Captured_Sync_Mode".to_string(),
                 hash_output: "Legacy_Hash_Not_Captured_Sync_Mode".to_string(),
+                registration: None,
+                donation_signature: None,
             };
 
 
@@ -261,12 +472,12 @@ pub fn run_single_mining_cycle(
             if let Some(base_dir) = data_dir_base {
                 let temp_data_dir = DataDir::Ephemeral(&mining_address);
                 if let Err(e) = temp_data_dir.save_found_solution(base_dir, &challenge_params.challenge_id, &pending_solution) {
-                     eprintln!("FATAL: Solution found but could not save recovery file {}: {}", FILE_NAME_FOUND_SOLUTION, e);
+                     ceprintln!("FATAL: Solution found but could not save recovery file {}: {}", FILE_NAME_FOUND_SOLUTION, e);
                      return (MiningResult::MiningFailed, total_hashes, elapsed_secs);
                 }
             } else {
                 // If no data_dir is set, the solution is lost.
-                eprintln!("FATAL: Solution found but no data_dir specified. Solution lost.");
+                ceprintln!("FATAL: Solution found but no data_dir specified. Solution lost.");
                 return (MiningResult::MiningFailed, total_hashes, elapsed_secs);
             }
 
@@ -274,17 +485,17 @@ pub fn run_single_mining_cycle(
             if let Some(base_dir) = data_dir_base {
                 let temp_data_dir = DataDir::Ephemeral(&mining_address);
                 if let Err(e) = temp_data_dir.save_pending_solution(base_dir, &pending_solution) {
-                     eprintln!("FATAL: Solution found but could not save to queue: {}", e);
+                     ceprintln!("FATAL: Solution found but could not save to queue: {}", e);
                      // If queue save fails, the recovery file is still there, so we return MiningFailed.
                      return (MiningResult::MiningFailed, total_hashes, elapsed_secs);
                 }
 
                 // CRITICAL STEP 3: If save to queue is successful, delete the temporary file
                 if let Err(e) = temp_data_dir.delete_found_solution(base_dir, &challenge_params.challenge_id) {
-                    eprintln!("WARNING: Failed to delete recovery file {}: {}", FILE_NAME_FOUND_SOLUTION, e);
+                    ceprintln!("WARNING: Failed to delete recovery file {}: {}", FILE_NAME_FOUND_SOLUTION, e);
                 }
 
-                println!("🚀 Solution queued successfully. Mining continues.");
+                cprintln!("🚀 Solution queued successfully. Mining continues.");
             }
             // else case is handled above and returns MiningFailed
 
@@ -300,22 +511,45 @@ pub fn print_mining_setup(
     threads: u32,
     challenge_params: &ChallengeData,
 ) {
-    let address_display = address.unwrap_or("[Not Set / Continuous Generation]");
-    println!("\n==============================================");
-    println!("⛏️  Shadow Harvester: Mining Cycle Setup");
-    println!("==============================================");
-    println!("API URL: {}", api_url);
-    println!("Mining Address: {}", address_display);
-    println!("Worker Threads: {}", threads);
-    println!("----------------------------------------------");
-    println!("CHALLENGE DETAILS:");
-    println!("  ID:               {}", challenge_params.challenge_id);
-    println!("  Day:              {}", challenge_params.day);
-    println!("  Difficulty Mask:  {}", challenge_params.difficulty);
-    println!("  Submission Deadline: {}", challenge_params.latest_submission);
-    println!("  ROM Key (no_pre_mine): {}", challenge_params.no_pre_mine_key);
-    println!("  Hash Input Hour:  {}", challenge_params.no_pre_mine_hour_str);
-    println!("----------------------------------------------");
+    // Built as one block so concurrent output cannot interleave with it.
+    let mut out = String::new();
+    let address_display = address.unwrap_or(tr(Msg::AddressNotSet));
+    let _ = writeln!(out, "\n==============================================");
+    let _ = writeln!(out, "⛏️  {}", tr(Msg::SetupTitle));
+    let _ = writeln!(out, "==============================================");
+    let _ = writeln!(out, "{}: {}", tr(Msg::ApiUrl), api_url);
+    let _ = writeln!(out, "{}: {}", tr(Msg::MiningAddress), address_display);
+    let _ = writeln!(out, "{}: {}", tr(Msg::WorkerThreads), threads);
+    let cpu_limit = shadow_harvester_lib::cpu_limit();
+    if cpu_limit < 100 {
+        let _ = writeln!(out, "{}", trf(Msg::CpuLimit, &[&cpu_limit]));
+    }
+    if shadow_harvester_lib::background_priority() {
+        let _ = writeln!(out, "Worker Priority: background (idle)");
+    }
+    if shadow_harvester_lib::low_memory() {
+        let _ = writeln!(out, "ROM Mode: low-memory (chunks regenerated on demand, reduced hashrate)");
+    }
+    if shadow_harvester_lib::rom_prefetch() {
+        let _ = writeln!(out, "ROM Prefetch: io_uring read-ahead for memory-mapped cache files");
+    }
+    if shadow_harvester_lib::huge_pages() {
+        match shadow_harvester_lib::last_rom_pages() {
+            Some(pages @ (RomPages::Explicit | RomPages::Transparent)) => { let _ = writeln!(out, "ROM Pages: {} ✅", pages); }
+            Some(pages) => { let _ = writeln!(out, "ROM Pages: huge pages unavailable, using {} ⚠️", pages); }
+            None => { let _ = writeln!(out, "ROM Pages: huge pages requested (ROM not allocated yet)"); }
+        }
+    }
+    let _ = writeln!(out, "----------------------------------------------");
+    let _ = writeln!(out, "{}:", tr(Msg::ChallengeDetails));
+    let _ = writeln!(out, "  {}: {}", tr(Msg::ChallengeId), challenge_params.challenge_id);
+    let _ = writeln!(out, "  {}: {}", tr(Msg::Day), challenge_params.day);
+    let _ = writeln!(out, "  {}: {}", tr(Msg::DifficultyMask), challenge_params.difficulty);
+    let _ = writeln!(out, "  {}: {}", tr(Msg::SubmissionDeadline), challenge_params.latest_submission);
+    let _ = writeln!(out, "  {}: {}", tr(Msg::RomKey), challenge_params.no_pre_mine_key);
+    let _ = writeln!(out, "  {}: {}", tr(Msg::HashInputHour), challenge_params.no_pre_mine_hour_str);
+    let _ = writeln!(out, "----------------------------------------------");
+    crate::console::out(out.trim_end().to_string());
 }
 
 // New function to check if a specific index already has a receipt
@@ -354,7 +588,7 @@ pub fn next_wallet_deriv_index_for_challenge(
                 receipt_path.push(FILE_NAME_RECEIPT);
                 match std::fs::exists(&receipt_path) {
                     Err(e) => {
-                        eprintln!("Could not check for receipt at {:?}: {}", path, e);
+                        ceprintln!("Could not check for receipt at {:?}: {}", path, e);
                         true
                     },
                     Ok(exists) => exists,
@@ -365,7 +599,7 @@ pub fn next_wallet_deriv_index_for_challenge(
         parsed_indices.sort();
 
         if parsed_indices.is_empty() {
-            eprintln!("no highest index: using {}", START_INDEX);
+            ceprintln!("no highest index: using {}", START_INDEX);
             START_INDEX
         } else {
             let mut expected_index = START_INDEX;
@@ -379,7 +613,7 @@ pub fn next_wallet_deriv_index_for_challenge(
                         "N/A".to_string()
                     };
 
-                    eprintln!("Gap found in receipts. Highest continuous index is {}. Retrying missing index {}.", highest_continuous_index_display, expected_index);
+                    ceprintln!("Gap found in receipts. Highest continuous index is {}. Retrying missing index {}.", highest_continuous_index_display, expected_index);
                     return Ok(expected_index);
                 }
                 expected_index = index.wrapping_add(1);
@@ -395,41 +629,271 @@ pub fn next_wallet_deriv_index_for_challenge(
 // CORE DISPATCHER AND SETUP FUNCTION
 // ===============================================
 
+/// Default of `--ws-port`, used to tell whether the flag was changed.
+const DEFAULT_WS_PORT: u16 = 8080;
+
+/// Lists options that are ambiguous or silently ignored in the given flag combination.
+/// Hard conflicts (e.g. two key modes) are rejected separately in `setup_app`.
+fn ambiguous_options(cli: &crate::cli::Cli) -> Vec<String> {
+    let mut issues = Vec::new();
+    let has_mnemonic = cli.mnemonic.is_some() || cli.mnemonic_file.is_some();
+
+    if cli.address.is_some() {
+        issues.push("'--address' is not used by any mining mode; the mining address is derived from the key.".to_string());
+    }
+    if let Some(port) = cli.mock_api_port
+        && cli.api_url.is_some()
+    {
+        issues.push(format!("'--api-url' is set but the mock API server is running. Ensure it is http://127.0.0.1:{}/api or unset it.", port));
+    }
+    if cli.websocket {
+        if cli.challenge.is_some() {
+            issues.push("'--challenge' and '--websocket' both supply challenges; WebSocket challenges may replace the fixed one.".to_string());
+        }
+        if cli.api_url.is_some() {
+            issues.push("'--api-url' is not used for polling or submission in '--websocket' mode.".to_string());
+        }
+    } else if cli.ws_port != DEFAULT_WS_PORT {
+        issues.push("'--ws-port' is ignored without '--websocket'.".to_string());
+    }
+    if cli.offline && (cli.api_url.is_some() || cli.mock_api_port.is_some()) {
+        issues.push("'--api-url' / '--mock-api-port' are not contacted in '--offline' mode.".to_string());
+    }
+    if cli.offline_export.is_some() && !cli.offline {
+        issues.push("'--offline-export' is ignored without '--offline'.".to_string());
+    }
+    if cli.max_threads.is_some_and(|max| max <= cli.threads) {
+        issues.push("'--max-threads' is not above '--threads'; worker threads are never scaled up.".to_string());
+    }
+    if !has_mnemonic && (cli.mnemonic_account != 0 || cli.mnemonic_starting_index != 0) {
+        issues.push("'--mnemonic-account' / '--mnemonic-starting-index' are ignored without '--mnemonic' or '--mnemonic-file'.".to_string());
+    }
+    if !has_mnemonic && cli.mnemonic_passphrase.is_some() {
+        issues.push("'--mnemonic-passphrase' is ignored without '--mnemonic' or '--mnemonic-file'.".to_string());
+    }
+    if cli.base_address && !has_mnemonic && !cli.ledger {
+        issues.push("'--base-address' only applies to mnemonic and '--ledger' mining; payment-key and ephemeral addresses have no stake key.".to_string());
+    }
+    if !cli.ledger && (cli.ledger_device.is_some() || cli.ledger_account != 0 || cli.ledger_index != 0) {
+        issues.push("'--ledger-device' / '--ledger-account' / '--ledger-index' are ignored without '--ledger'.".to_string());
+    }
+
+    issues
+}
+
+/// Environment variable holding the database passphrase when `--db-passphrase-file` is not set.
+const DB_PASSPHRASE_ENV: &str = "SHADOW_HARVESTER_DB_PASSPHRASE";
+
+/// The database encryption passphrase from `--db-passphrase-file`, the environment or (with
+/// `--keyring`) the OS keyring, in that order, if any.
+pub fn db_passphrase(cli: &crate::cli::Cli) -> Result<Option<String>, String> {
+    if let Some(path) = cli.db_passphrase_file.as_deref() {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read database passphrase file {}: {}", path, e))?;
+        let passphrase = content.lines().next().unwrap_or("").trim_end_matches('\r').to_string();
+        if passphrase.is_empty() {
+            return Err(format!("Database passphrase file {} is empty.", path));
+        }
+        return Ok(Some(passphrase));
+    }
+    if let Some(passphrase) = std::env::var(DB_PASSPHRASE_ENV).ok().filter(|passphrase| !passphrase.is_empty()) {
+        return Ok(Some(passphrase));
+    }
+    if cli.keyring {
+        return crate::secrets::get(crate::cli::KeyringSecret::DbPassphrase);
+    }
+    Ok(None)
+}
+
+/// Minimum size of a `--db-encrypt` key file.
+const DB_KEY_FILE_MIN_LEN: usize = 32;
+
+/// What the database key is derived from.
+#[derive(Clone)]
+enum DbSecret {
+    Passphrase(String),
+    KeyFile(Vec<u8>),
+}
+
+/// At-rest encryption settings of the state database.
+#[derive(Clone)]
+pub struct DbEncryption {
+    secret: DbSecret,
+    /// Encrypt only the entries under `schema::SENSITIVE_PREFIXES` (--db-encrypt).
+    selective: bool,
+}
+
+/// Resolves --db-encrypt and the database passphrase into encryption settings, or None when
+/// the database is not encrypted.
+pub fn db_encryption(cli: &crate::cli::Cli) -> Result<Option<DbEncryption>, String> {
+    let passphrase = db_passphrase(cli)?;
+    let secret = match cli.db_encrypt.as_deref() {
+        None => return Ok(passphrase.map(|passphrase| DbEncryption { secret: DbSecret::Passphrase(passphrase), selective: false })),
+        Some("password") => DbSecret::Passphrase(passphrase
+            .ok_or_else(|| format!("--db-encrypt password needs a passphrase: --db-passphrase-file, {} or --keyring.", DB_PASSPHRASE_ENV))?),
+        Some(path) => {
+            if passphrase.is_some() {
+                return Err("--db-encrypt with a key file cannot be combined with a database passphrase.".to_string());
+            }
+            let secret = std::fs::read(path).map_err(|e| format!("Failed to read database key file {}: {}", path, e))?;
+            if secret.len() < DB_KEY_FILE_MIN_LEN {
+                return Err(format!("Database key file {} must hold at least {} bytes.", path, DB_KEY_FILE_MIN_LEN));
+            }
+            DbSecret::KeyFile(secret)
+        }
+    };
+    Ok(Some(DbEncryption { secret, selective: true }))
+}
+
+/// Enables at-rest encryption on `persistence` when configured.
+pub fn apply_db_encryption(persistence: Persistence, encryption: Option<&DbEncryption>) -> Result<Persistence, String> {
+    let Some(encryption) = encryption else { return Ok(persistence) };
+    let persistence = match &encryption.secret {
+        DbSecret::Passphrase(passphrase) => persistence.with_encryption(passphrase)?,
+        DbSecret::KeyFile(secret) => persistence.with_encryption_secret(secret)?,
+    };
+    if encryption.selective {
+        return persistence.with_encrypted_prefixes(crate::schema::SENSITIVE_PREFIXES);
+    }
+    Ok(persistence)
+}
+
+/// Collects the `--rom-*` overrides and checks that they produce a valid ROM configuration.
+pub fn rom_overrides(cli: &crate::cli::Cli) -> Result<RomParamsOverride, String> {
+    let overrides = RomParamsOverride {
+        size: cli.rom_size,
+        pre_size: cli.rom_pre_size,
+        mixing_numbers: cli.rom_mixing_numbers,
+    };
+    let params = overrides.resolve(None);
+    params.validate()?;
+    if params != RomParams::default() {
+        ceprintln!("⚠️ WARNING: ROM parameters differ from the protocol defaults ({:?}). Hashes will not match the network's unless its parameters changed accordingly.", params);
+    }
+    Ok(overrides)
+}
+
+/// File under the data dir holding the last T&C fetched from the API.
+const TANDC_CACHE_FILENAME: &str = "tandc.json";
+
+fn tandc_cache_path(cli: &crate::cli::Cli) -> Option<std::path::PathBuf> {
+    if cli.db_backend == DbBackend::Memory {
+        return None;
+    }
+    cli.data_dir.as_deref().map(|dir| std::path::Path::new(dir).join(TANDC_CACHE_FILENAME))
+}
+
+/// Fetches the T&C, caching it for offline starts. When the API is unreachable the cached copy
+/// is returned with `true`.
+fn fetch_or_load_tandc(cli: &crate::cli::Cli, client: &Client, api_url: &str) -> Result<(TandCResponse, bool), String> {
+    let cache_path = tandc_cache_path(cli);
+    match api::block_on(api::fetch_tandc(client, api_url)) {
+        Ok(tandc) => {
+            if let Some(path) = &cache_path {
+                let written = serde_json::to_string(&tandc)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| {
+                        if let Some(dir) = path.parent() {
+                            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                        }
+                        std::fs::write(path, json).map_err(|e| e.to_string())
+                    });
+                if let Err(e) = written {
+                    ceprintln!("⚠️ WARNING: Failed to cache T&C at {}: {}", path.display(), e);
+                }
+            }
+            Ok((tandc, false))
+        }
+        Err(e) => {
+            let cached = cache_path
+                .as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|json| serde_json::from_str::<TandCResponse>(&json).ok());
+            match cached {
+                Some(tandc) => {
+                    ceprintln!("⚠️ API unreachable at {} ({}).", api_url, e);
+                    ceprintln!("🔌 Starting OFFLINE with the cached T&C (version {}). Address registration is deferred until the API is reachable.", tandc.version);
+                    Ok((tandc, true))
+                }
+                None => Err(format!(
+                    "Could not fetch T&C from API URL: {}. Details: {}. No cached T&C to start offline with; run once while the API is reachable.",
+                    api_url, e
+                )),
+            }
+        }
+    }
+}
+
 /// Handles the initial setup, argument validation, T&C, and pre-mining command dispatch.
 /// Returns the necessary context for the main mining loop functions.
-pub fn setup_app(cli: &crate::cli::Cli) -> Result<MiningContext, String> {
+pub fn setup_app(cli: &crate::cli::Cli) -> Result<MiningContext, ExitError> {
+    // 0. Ambiguous or ignored options: fatal in --strict mode, warnings otherwise.
+    let issues = ambiguous_options(cli);
+    if cli.strict && !issues.is_empty() {
+        return Err(ExitError::new(exit_codes::USAGE_ERROR, format!(
+            "Strict mode: refusing to start with ambiguous or ignored options:\n  - {}",
+            issues.join("\n  - ")
+        )));
+    }
+    for issue in &issues {
+        ceprintln!("⚠️ WARNING: {}", issue);
+    }
+    if cli.db_backend == DbBackend::Memory {
+        ceprintln!("⚠️ WARNING: '--db-backend memory' keeps all state in RAM. Queued solutions that are not yet submitted, receipts and the mnemonic index are lost when the process exits.");
+    }
+
     // 1. Check for --api-url
     let api_url: String = match cli.api_url.clone() {
+        _ if cli.offline => "OFFLINE".to_string(),
         Some(url) => url,
         None => {
             // FIX: Allow missing API URL only if in WebSocket mode
-            if cli.websocket {
+            // The mock API comes first: with --challenge-priority it feeds the poller next to the WebSocket.
+            if let Some(port) = cli.mock_api_port {
+                format!("http://localhost:{}/api", port)
+            } else if cli.websocket {
                 "MOCK_WS_API_URL".to_string()
-            } else if cli.mock_api_port.is_some() {
-                format!("http://localhost:{}/api", cli.mock_api_port.unwrap())
             } else {
-                return Err("The '--api-url' flag must be specified to connect to the Scavenger Mine API.".to_string());
+                return Err(ExitError::new(exit_codes::USAGE_ERROR, "The '--api-url' flag must be specified to connect to the Scavenger Mine API."));
             }
         }
     };
 
+    let rom_overrides = rom_overrides(cli).exit_code(exit_codes::USAGE_ERROR)?;
+
     // 2. Check for argument conflicts
     if cli.mnemonic.is_some() && cli.mnemonic_file.is_some() {
-        return Err("Cannot use both '--mnemonic' and '--mnemonic-file' flags simultaneously.".to_string());
+        return Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use both '--mnemonic' and '--mnemonic-file' flags simultaneously."));
+    }
+
+    // A common mistake is passing the path of the phrase file to --mnemonic.
+    if let Some(phrase) = cli.mnemonic.as_deref().map(str::trim)
+        && !phrase.contains(' ')
+        && std::path::Path::new(phrase).is_file()
+    {
+        return Err(ExitError::new(exit_codes::USAGE_ERROR, format!(
+            "'--mnemonic' expects the phrase itself, but '{}' is a file. Use '--mnemonic-file {}' instead.",
+            phrase, phrase
+        )));
+    }
+
+    // A Ledger holds the key: no other key source may be given.
+    if cli.ledger && (cli.ephemeral_key || cli.payment_key.is_some() || cli.mnemonic.is_some() || cli.mnemonic_file.is_some()) {
+        return Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use '--ledger' with '--ephemeral-key', '--payment-key', '--mnemonic' or '--mnemonic-file'."));
     }
 
     // Ephemeral key conflicts with payment key and mnemonic
     if cli.ephemeral_key {
         if cli.payment_key.is_some() {
-             return Err("Cannot use '--ephemeral-key' with '--payment-key' simultaneously.".to_string());
+             return Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use '--ephemeral-key' with '--payment-key' simultaneously."));
         }
         if cli.mnemonic.is_some() || cli.mnemonic_file.is_some() {
-             return Err("Cannot use '--ephemeral-key' with '--mnemonic' or '--mnemonic-file' simultaneously.".to_string());
+             return Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use '--ephemeral-key' with '--mnemonic' or '--mnemonic-file' simultaneously."));
         }
     } else {
         // Existing check for payment_key vs mnemonic, now only run if not ephemeral mode
         if cli.payment_key.is_some() && (cli.mnemonic.is_some() || cli.mnemonic_file.is_some()) {
-            return Err("Cannot use both '--payment-key' and '--mnemonic' or '--mnemonic-file' flags simultaneously.".to_string());
+            return Err(ExitError::new(exit_codes::USAGE_ERROR, "Cannot use both '--payment-key' and '--mnemonic' or '--mnemonic-file' flags simultaneously."));
         }
     }
 
@@ -438,29 +902,47 @@ pub fn setup_app(cli: &crate::cli::Cli) -> Result<MiningContext, String> {
 
     // --- COMMAND HANDLERS ---
     if let Some(crate::cli::Commands::Challenges) = cli.command {
-        let challenge_response = api::fetch_challenge_status(&client, &api_url)
-            .map_err(|e| format!("Could not fetch challenge status: {}", e))?;
+        let challenge_response = api::block_on(api::fetch_challenge_status(&client, &api_url))
+            .map_err(|e| format!("Could not fetch challenge status: {}", e))
+            .exit_code(exit_codes::NETWORK_ERROR)?;
         // FIX: Print full detailed status info from the ChallengeResponse object
         print_non_active_status(&challenge_response);
-        println!("Challenge status fetched: {:?}", challenge_response);
+        cprintln!("Challenge status fetched: {:?}", challenge_response);
         // We use a specific error string to signal successful execution and exit in run_app
-        return Err("COMMAND EXECUTED".to_string());
+        return Err("COMMAND EXECUTED".to_string().into());
     }
 
     // 3. Fetch T&C message (always required for registration payload)
-    let tc_response: TandCResponse = if cli.websocket {
+    let (tc_response, offline_start): (TandCResponse, bool) = if cli.websocket {
         // FIX: In WebSocket mode, skip API contact and use custom placeholder
         let tos_message = "Please read the Terms of Service at https://sm.midnight.gd. Re-run with --accept-tos if you agree to the terms.".to_string();
-        TandCResponse {
+        (TandCResponse {
             version: "WS-MOCK".to_string(),
             content: tos_message.clone(), // Use custom content
             message: "MOCK_WS_REGISTRATION_MESSAGE".to_string(), // Keep mock message for signing
+        }, false)
+    } else if cli.offline {
+        // The registration proof attached to exported solutions is signed over the cached T&C
+        // message; without one, the importing machine must register the address itself.
+        let cached = tandc_cache_path(cli)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<TandCResponse>(&json).ok());
+        match cached {
+            Some(tandc) => {
+                cprintln!("🔌 Offline mode: using the cached T&C (version {}) for registration proofs.", tandc.version);
+                (tandc, false)
+            }
+            None => {
+                ceprintln!("⚠️ Offline mode: no cached T&C in the data dir. Exported solutions carry no registration proof.");
+                (TandCResponse {
+                    version: "OFFLINE".to_string(),
+                    content: "Offline mode: the Terms and Conditions could not be fetched. Re-run with --accept-tos if you accepted them before.".to_string(),
+                    message: String::new(),
+                }, false)
+            }
         }
     } else {
-        match api::fetch_tandc(&client, &api_url) {
-            Ok(t) => t,
-            Err(e) => return Err(format!("Could not fetch T&C from API URL: {}. Details: {}", api_url, e)),
-        }
+        fetch_or_load_tandc(cli, &client, &api_url).exit_code(exit_codes::NETWORK_ERROR)?
     };
 
     // 4. Conditional T&C display and acceptance check
@@ -468,13 +950,22 @@ pub fn setup_app(cli: &crate::cli::Cli) -> Result<MiningContext, String> {
         // FIX: Modify display based on WS mode
         if cli.websocket {
              // Directly print the content for WS mode
-             println!("{}", tc_response.content);
+             cprintln!("{}", tc_response.content);
         } else {
              // Standard display for HTTP mode
-             println!("Terms and Conditions (Version {}):", tc_response.version);
-             println!("{}", tc_response.content);
+             cprintln!("{}", trf(Msg::TermsTitle, &[&tc_response.version]));
+             cprintln!("{}", tc_response.content);
+             cprintln!("{}", tr(Msg::AcceptTermsHint));
+        }
+        return Err(ExitError::new(exit_codes::USAGE_ERROR, "You must pass the '--accept-tos' flag to proceed with mining."));
+    }
+
+    // 5. Warn before mining when the challenge response no longer matches this build.
+    if !cli.websocket && !cli.offline && !offline_start {
+        match crate::api_schema::probe(&client, &api_url, &tc_response.version) {
+            Ok(report) => crate::api_schema::print_report(&report),
+            Err(e) => ceprintln!("⚠️ WARNING: API schema probe failed: {}", e),
         }
-        return Err("You must pass the '--accept-tos' flag to proceed with mining.".to_string());
     }
 
     Ok(MiningContext {
@@ -483,7 +974,15 @@ pub fn setup_app(cli: &crate::cli::Cli) -> Result<MiningContext, String> {
         tc_response,
         donate_to_option: cli.donate_to.clone(),
         threads: cli.threads,
+        solutions_per_cycle: cli.solutions_per_cycle,
+        rom_check_interval: cli.rom_check_interval,
+        // A second ROM in memory defeats --low-memory.
+        rom_prebuild: !cli.no_rom_prebuild && !cli.low_memory,
+        rom_store_size: cli.rom_store_size,
+        rom_overrides,
+        rom_cache_dir: if cli.no_rom_cache || cli.db_backend == DbBackend::Memory { None } else { Some(cli.data_dir.clone().unwrap_or_else(|| "state".to_string())) },
         cli_challenge: cli.challenge.clone(),
         data_dir: cli.data_dir.clone(),
+        offline_start,
     })
 }
@@ -0,0 +1,146 @@
+// src/verify.rs
+
+use crate::data_types::{ChallengeData, FailedSolution, RomParamsOverride};
+use indicatif::{ProgressBar, ProgressStyle};
+use shadow_harvester_lib::{ChallengeHasher, CpuHasher, Rom};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// ===============================================
+// PARALLEL HASH VERIFICATION (challenge verify)
+// ===============================================
+//
+// Re-hashes the preimages of stored receipts and error records. Entries are grouped by
+// challenge so each ROM is built once, then hashed by a pool of worker threads: one entry costs
+// a full VM run, so a sequential audit of thousands of receipts takes hours.
+
+/// One preimage to re-hash.
+pub struct VerifyEntry {
+    pub address: String,
+    pub challenge_id: String,
+    pub preimage: String,
+    /// Hash recorded with a failed submission; receipts carry none.
+    pub stored_hash: Option<String>,
+    /// Why the entry cannot be re-hashed (e.g. a receipt without a preimage). Reported as
+    /// `Verdict::Skipped` instead of stopping the whole run.
+    pub skip_reason: Option<String>,
+}
+
+impl VerifyEntry {
+    /// Builds an entry from a receipt (the API's crypto_receipt, which contains the preimage).
+    pub fn from_receipt(address: &str, challenge_id: &str, receipt_json: &str) -> Self {
+        let preimage = match serde_json::from_str::<serde_json::Value>(receipt_json) {
+            Ok(receipt) => receipt["preimage"].as_str().map(str::to_string).ok_or_else(|| "the receipt has no 'preimage' field".to_string()),
+            Err(e) => Err(format!("the receipt JSON is unreadable: {}", e)),
+        };
+        let (preimage, skip_reason) = match preimage {
+            Ok(preimage) => (preimage, None),
+            Err(reason) => (String::new(), Some(reason)),
+        };
+        VerifyEntry { address: address.to_string(), challenge_id: challenge_id.to_string(), preimage, stored_hash: None, skip_reason }
+    }
+
+    pub fn from_failed_solution(failed: FailedSolution) -> Self {
+        VerifyEntry { address: failed.address, challenge_id: failed.challenge_id, preimage: failed.preimage, stored_hash: Some(failed.hash_output), skip_reason: None }
+    }
+}
+
+/// Outcome of re-hashing one entry.
+pub enum Verdict {
+    /// Receipt hash meets the difficulty, or error-record hash matches the stored one.
+    Ok,
+    /// Receipt hash does not meet the challenge difficulty.
+    BelowDifficulty(String),
+    /// Error-record hash differs from the hash recorded at submission time.
+    HashMismatch { computed: String, stored: String },
+    /// The entry could not be checked (e.g. its challenge is not stored locally).
+    Skipped(String),
+}
+
+/// Re-hashes every entry with `threads` workers and returns the verdicts in input order.
+pub fn verify_entries(
+    entries: &[VerifyEntry],
+    challenges: &BTreeMap<String, ChallengeData>,
+    rom_overrides: &RomParamsOverride,
+    threads: usize,
+) -> Result<Vec<Verdict>, String> {
+    let pb = if crate::console::plain_progress() { ProgressBar::hidden() } else { ProgressBar::new(entries.len() as u64) };
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} ETA {eta} {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    let mut verdicts: Vec<Option<Verdict>> = (0..entries.len()).map(|_| None).collect();
+
+    // Entry indices per challenge, so each ROM is generated once.
+    let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if let Some(reason) = &entry.skip_reason {
+            verdicts[i] = Some(Verdict::Skipped(reason.clone()));
+            pb.inc(1);
+            continue;
+        }
+        groups.entry(entry.challenge_id.as_str()).or_default().push(i);
+    }
+
+    for (challenge_id, indices) in groups {
+        let Some(challenge) = challenges.get(challenge_id) else {
+            for &i in &indices {
+                verdicts[i] = Some(Verdict::Skipped(format!("challenge {} is not in the local database", challenge_id)));
+                pb.inc(1);
+            }
+            continue;
+        };
+        let difficulty_mask = u32::from_str_radix(&challenge.difficulty, 16)
+            .map_err(|_| format!("Challenge {} has a non-hex difficulty '{}'.", challenge_id, challenge.difficulty))?;
+
+        pb.set_message(format!("building ROM for {}", challenge_id));
+        let rom_params = rom_overrides.resolve(Some(challenge));
+        rom_params.validate()?;
+        let rom_key = challenge.no_pre_mine_key.clone();
+        let rom = crate::shutdown::run_cancellable(move || Rom::from_params(rom_key.as_bytes(), &rom_params));
+        // Cancelled: leave the rest unchecked and report what was verified so far.
+        let Ok(rom) = rom else {
+            break;
+        };
+        let hasher = CpuHasher::new(Arc::new(rom));
+        pb.set_message(format!("hashing {}", challenge_id));
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(indices.len()));
+        thread::scope(|s| {
+            for _ in 0..threads.clamp(1, indices.len()) {
+                s.spawn(|| {
+                    while let Some(&i) = indices.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if crate::shutdown::cancelled() {
+                            break;
+                        }
+                        let entry = &entries[i];
+                        let computed = hasher.hash(entry.preimage.as_bytes());
+                        let computed_hex = hex::encode(computed);
+                        let verdict = match &entry.stored_hash {
+                            Some(stored) if *stored != computed_hex => Verdict::HashMismatch { computed: computed_hex, stored: stored.clone() },
+                            Some(_) => Verdict::Ok,
+                            None if shadow_harvester_lib::hash_structure_good(&computed, difficulty_mask) => Verdict::Ok,
+                            None => Verdict::BelowDifficulty(computed_hex),
+                        };
+                        results.lock().unwrap().push((i, verdict));
+                        pb.inc(1);
+                    }
+                });
+            }
+        });
+        for (i, verdict) in results.into_inner().unwrap() {
+            verdicts[i] = Some(verdict);
+        }
+    }
+
+    pb.finish_and_clear();
+    Ok(verdicts
+        .into_iter()
+        .map(|verdict| verdict.unwrap_or_else(|| Verdict::Skipped(if crate::shutdown::cancelled() { "cancelled" } else { "not executed" }.to_string())))
+        .collect())
+}
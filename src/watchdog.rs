@@ -0,0 +1,188 @@
+// src/watchdog.rs
+
+use crate::data_types::{ManagerCommand, SubmitterCommand};
+use crate::polling_client::polling_interval;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+// ===============================================
+// SYSTEMD WATCHDOG (WatchdogSec=)
+// ===============================================
+//
+// When systemd starts the miner with WatchdogSec= set, it exports WATCHDOG_USEC and
+// NOTIFY_SOCKET and restarts the service if no WATCHDOG=1 arrives in time. Pinging from a
+// timer alone would keep a wedged miner alive, so the ping is only sent while the manager and
+// state worker answer heartbeats and the poller keeps polling.
+//
+// With Type=notify, systemd also waits for READY=1 before considering the service started,
+// and STOPPING=1 tells it a graceful shutdown (Sled flush) is in progress.
+
+/// Threads whose liveness gates the watchdog ping.
+#[derive(Clone, Copy, Debug)]
+pub enum Component {
+    Manager = 0,
+    StateWorker = 1,
+    Poller = 2,
+    /// Beats on every hash progress report of the running miner (health check only).
+    Miner = 3,
+}
+
+impl Component {
+    fn name(self) -> &'static str {
+        match self {
+            Component::Manager => "Manager",
+            Component::StateWorker => "State worker",
+            Component::Poller => "Poller",
+            Component::Miner => "Miner",
+        }
+    }
+}
+
+// Unix millis of each component's last heartbeat (0 = none yet).
+static LAST_BEATS: [AtomicI64; 4] = [AtomicI64::new(0), AtomicI64::new(0), AtomicI64::new(0), AtomicI64::new(0)];
+// Whether each component has beaten from its own thread at least once.
+static STARTED: [AtomicBool; 4] = [AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false)];
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Records that `component` is alive.
+pub fn beat(component: Component) {
+    LAST_BEATS[component as usize].store(now_millis(), Ordering::Relaxed);
+    STARTED[component as usize].store(true, Ordering::Relaxed);
+}
+
+/// Time since `component` last beat, `None` if it never did.
+pub fn beat_age(component: Component) -> Option<Duration> {
+    if !STARTED[component as usize].load(Ordering::Relaxed) {
+        return None;
+    }
+    let age = now_millis() - LAST_BEATS[component as usize].load(Ordering::Relaxed);
+    Some(Duration::from_millis(age.max(0) as u64))
+}
+
+/// Sends `state` (e.g. "WATCHDOG=1") to systemd's notification socket. Does nothing when the
+/// process was not started by systemd with a notification socket.
+#[cfg(unix)]
+pub fn sd_notify(state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let socket = UnixDatagram::unbound()?;
+    let path = path.to_string_lossy();
+    match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), path.as_ref())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn sd_notify(_state: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Sends `state` to systemd, logging (not failing on) errors.
+pub fn sd_notify_or_warn(state: &str) {
+    if let Err(e) = sd_notify(state) {
+        ceprintln!("⚠️ Failed to notify systemd ({}): {}", state.lines().next().unwrap_or(state), e);
+    }
+}
+
+/// Starts a thread that sends READY=1 once the manager and state worker have both answered a
+/// heartbeat, i.e. the database is open and the manager is processing commands. Does nothing
+/// outside systemd. A thread that never comes up leaves the unit in "activating" until
+/// systemd's TimeoutStartSec= kills it.
+pub fn spawn_ready_notifier(manager_tx: &Sender<ManagerCommand>, submitter_tx: &Sender<SubmitterCommand>) {
+    if std::env::var_os("NOTIFY_SOCKET").is_none() {
+        return;
+    }
+    if manager_tx.send(ManagerCommand::Heartbeat).is_err() || submitter_tx.send(SubmitterCommand::Heartbeat).is_err() {
+        return;
+    }
+    thread::spawn(|| {
+        let started = |component: Component| STARTED[component as usize].load(Ordering::Relaxed);
+        while !(started(Component::Manager) && started(Component::StateWorker)) {
+            thread::sleep(Duration::from_millis(100));
+        }
+        sd_notify_or_warn(&format!("READY=1\nMAINPID={}\nSTATUS=Mining", std::process::id()));
+    });
+}
+
+/// The watchdog timeout systemd expects pings within, if it applies to this process.
+fn watchdog_timeout() -> Option<Duration> {
+    std::env::var_os("NOTIFY_SOCKET")?;
+    if let Ok(pid) = std::env::var("WATCHDOG_PID")
+        && pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+        return None;
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.trim().parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+/// Starts the systemd watchdog thread if systemd requested one. Every half timeout it asks the
+/// manager and state worker for a heartbeat, then pings systemd only if every tracked
+/// component beat recently. The poller beats once per poll, so it is allowed a full polling
+/// interval on top of the timeout. Returns the timeout when the watchdog was started.
+pub fn spawn_systemd_watchdog(
+    manager_tx: Sender<ManagerCommand>,
+    submitter_tx: Sender<SubmitterCommand>,
+    poller_enabled: bool,
+) -> Option<Duration> {
+    let timeout = watchdog_timeout()?;
+    let interval = timeout / 2;
+    let poller_max_age = timeout + polling_interval();
+
+    // Give every component a fresh start; they must beat again before the first check.
+    let now = now_millis();
+    for last_beat in &LAST_BEATS {
+        last_beat.store(now, Ordering::Relaxed);
+    }
+
+    thread::spawn(move || {
+        let mut withheld = false;
+        loop {
+            if manager_tx.send(ManagerCommand::Heartbeat).is_err() || submitter_tx.send(SubmitterCommand::Heartbeat).is_err() {
+                return; // Shutting down.
+            }
+            thread::sleep(interval);
+
+            let now = now_millis();
+            let mut tracked = vec![(Component::Manager, timeout), (Component::StateWorker, timeout)];
+            if poller_enabled {
+                tracked.push((Component::Poller, poller_max_age));
+            }
+            let stale: Vec<String> = tracked
+                .into_iter()
+                .filter_map(|(component, max_age)| {
+                    let age = Duration::from_millis((now - LAST_BEATS[component as usize].load(Ordering::Relaxed)).max(0) as u64);
+                    (age > max_age).then(|| format!("{} ({}s)", component.name(), age.as_secs()))
+                })
+                .collect();
+
+            if stale.is_empty() {
+                if withheld {
+                    cprintln!("🐶 All heartbeats fresh again. Resuming systemd watchdog pings.");
+                    withheld = false;
+                }
+                sd_notify_or_warn("WATCHDOG=1");
+            } else if !withheld {
+                ceprintln!("🐶 Withholding systemd watchdog ping, stale heartbeat(s): {}. systemd will restart the miner if this persists.", stale.join(", "));
+                withheld = true;
+            }
+        }
+    });
+    Some(timeout)
+}
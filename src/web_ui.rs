@@ -0,0 +1,82 @@
+// src/web_ui.rs
+
+use crate::console;
+use crate::control;
+use crate::data_types::{ControlRequest, ManagerCommand};
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use warp::sse::Event;
+use warp::{Filter, Rejection, Reply};
+
+// ===============================================
+// WEB UI (feature "web-ui", on --control-port)
+// ===============================================
+//
+//   GET  /          a single static page: status, hash-rate chart, event log, control buttons
+//   GET  /events    Server-Sent Events: every JSON event plus a `status` snapshot every few seconds
+//
+// The page needs no credentials to load. It calls the REST API (and /events) with the token
+// entered on the page; browsers cannot set headers on an EventSource, so the token is also
+// accepted as a `token` query parameter.
+
+const INDEX_HTML: &str = include_str!("web_ui/index.html");
+/// How often /events sends the manager's status, which feeds the hash-rate chart.
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The page and its event stream. `auth` guards the stream; `manager` yields the manager channel.
+pub fn routes(
+    auth: impl Filter<Extract = (), Error = Rejection> + Clone + Send + Sync + 'static,
+    manager: impl Filter<Extract = (Sender<ManagerCommand>,), Error = Infallible> + Clone + Send + Sync + 'static,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let index_route = warp::path::end()
+        .and(warp::get())
+        .map(|| warp::reply::html(INDEX_HTML));
+    let events_route = warp::path!("events")
+        .and(warp::get())
+        .and(auth)
+        .and(manager)
+        .map(|manager_tx| warp::sse::reply(warp::sse::keep_alive().stream(event_stream(manager_tx))));
+    index_route.or(events_route)
+}
+
+/// Forwards console events and periodic status snapshots to one /events client until it
+/// disconnects.
+fn event_stream(manager_tx: Sender<ManagerCommand>) -> UnboundedReceiverStream<Result<Event, Infallible>> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut events = console::subscribe_events();
+
+    tokio::spawn(async move {
+        let mut status_tick = tokio::time::interval(STATUS_INTERVAL);
+        loop {
+            let data = tokio::select! {
+                _ = status_tick.tick() => {
+                    let manager_tx = manager_tx.clone();
+                    let status = tokio::task::spawn_blocking(move || control::ask_manager(&manager_tx, ControlRequest::Status))
+                        .await
+                        .unwrap_or_else(|e| Err(format!("Control request failed: {}", e)));
+                    let ts = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                    match status {
+                        Ok(status) => json!({ "ts": ts, "event": "status", "status": status }),
+                        Err(e) => json!({ "ts": ts, "event": "status", "error": e }),
+                    }.to_string()
+                }
+                event = events.recv() => match event {
+                    Ok(line) => line,
+                    // A slow client misses the oldest events rather than stalling the miner.
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = tx.closed() => break,
+            };
+            if tx.send(Ok(Event::default().data(data))).is_err() {
+                break;
+            }
+        }
+    });
+
+    UnboundedReceiverStream::new(rx)
+}
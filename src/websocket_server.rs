@@ -1,5 +1,6 @@
 // src/websocket_server.rs
 
+use crate::challenge_sources::ChallengeSource;
 use crate::data_types::{ChallengeResponse, ManagerCommand, WebSocketCommand, PendingSolution}; // <-- NEW: Added WebSocketCommand, PendingSolution
 use std::sync::mpsc::{Sender, Receiver, TryRecvError}; // <-- NEW: Added Receiver, TryRecvError
 use std::net::{TcpListener, SocketAddr, TcpStream};
@@ -18,10 +19,10 @@ pub fn start_server(
     port: u16
 ) -> Result<(), String> {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    let listener = TcpListener::bind(&addr)
+    let listener = TcpListener::bind(addr)
         .map_err(|e| format!("Failed to bind WebSocket server to {}: {}", addr, e))?;
 
-    println!("🌐 WebSocket Server listening on ws://{}.", addr);
+    cprintln!("🌐 WebSocket Server listening on ws://{}.", addr);
 
     // Main loop waits for a TCP connection
     loop {
@@ -33,9 +34,7 @@ pub fn start_server(
                 Ok((s, _)) => Ok(s),
                 Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
                     // Check for pending solutions while waiting for a connection
-                    if let Err(e) = check_for_pending_solutions_on_disconnect(&solution_rx) {
-                        return Err(e); // Fatal if the core channel disconnects
-                    }
+                    check_for_pending_solutions_on_disconnect(&solution_rx)?; // Fatal if the core channel disconnects
                     continue;
                 }
                 Err(e) => Err(format!("Incoming TCP connection failed: {}", e)),
@@ -50,7 +49,7 @@ pub fn start_server(
 
         match accept(stream) {
             Ok(mut websocket) => {
-                println!("🌐 WebSocket client connected. Awaiting challenge posts...");
+                cprintln!("🌐 WebSocket client connected. Awaiting challenge posts...");
 
                 // Inner loop handles open connection
                 loop {
@@ -62,9 +61,12 @@ pub fn start_server(
                         Ok(WebSocketCommand::SubmitSolution(solution)) => {
                             send_solution_to_client(&mut websocket, solution);
                         }
+                        Ok(WebSocketCommand::ReportRomDigest { challenge_id, rom_key, rom_id, digest }) => {
+                            send_rom_digest_to_client(&mut websocket, &challenge_id, &rom_key, &rom_id, &digest);
+                        }
                         Err(TryRecvError::Empty) => { /* Continue */ }
                         Err(TryRecvError::Disconnected) => {
-                            eprintln!("❌ Core solution channel closed. Shutting down WS server.");
+                            ceprintln!("❌ Core solution channel closed. Shutting down WS server.");
                             return Err("Core solution channel closed.".to_string());
                         }
                     }
@@ -80,7 +82,7 @@ pub fn start_server(
                                         let _ = websocket.send(Message::Text("Challenge accepted.".to_string().into()));
                                     }
                                     Err(e) => {
-                                        eprintln!("⚠️ WS Challenge Handling Error: {}", e);
+                                        ceprintln!("⚠️ WS Challenge Handling Error: {}", e);
                                         let _ = websocket.send(Message::Text(format!("Error: {}", e).into()));
                                     }
                                 }
@@ -95,7 +97,7 @@ pub fn start_server(
                 }
             }
             Err(e) => {
-                eprintln!("⚠️ Failed to establish WebSocket connection: {}", e);
+                ceprintln!("⚠️ Failed to establish WebSocket connection: {}", e);
             }
         }
     }
@@ -109,10 +111,14 @@ fn check_for_pending_solutions_on_disconnect(solution_rx: &Receiver<WebSocketCom
             // The logic would require persisting it to SLED in the WS server if the client is not connected,
             // but the Submitter thread already does this (by keeping it in the pending queue).
             let pending_key = format!("{}:{}", solution.address, solution.challenge_id);
-            println!("⚠️ Found solution for {} in queue, but no WebSocket client is connected. The solution will be resent immediately upon client reconnection.", pending_key);
+            cprintln!("⚠️ Found solution for {} in queue, but no WebSocket client is connected. The solution will be resent immediately upon client reconnection.", pending_key);
             // Since this is just a loss of the current MPSC send, we let the Submitter handle retries or rely on the client reconnecting.
             Ok(())
         }
+        Ok(WebSocketCommand::ReportRomDigest { challenge_id, .. }) => {
+            cprintln!("⚠️ ROM digest for challenge {} not reported: no WebSocket client is connected.", challenge_id);
+            Ok(())
+        }
         Err(TryRecvError::Disconnected) => {
             Err("Core solution channel closed.".to_string())
         }
@@ -124,7 +130,7 @@ fn send_solution_to_client(websocket: &mut tungstenite::WebSocket<TcpStream>, so
     let payload = serde_json::to_string(&solution)
         .map_err(|e| format!("Failed to serialize solution: {}", e))
         .unwrap_or_else(|e| {
-            eprintln!("Fatal: Solution serialization failed: {}", e);
+            ceprintln!("Fatal: Solution serialization failed: {}", e);
             "{}".to_string()
         });
 
@@ -138,8 +144,26 @@ fn send_solution_to_client(websocket: &mut tungstenite::WebSocket<TcpStream>, so
     }).to_string();
 
     match websocket.send(Message::Text(final_payload.into())) {
-        Ok(_) => println!("🚀 Sent solution for {} to client via WebSocket.", solution.challenge_id),
-        Err(e) => eprintln!("⚠️ Failed to send solution over WebSocket: {}", e),
+        Ok(_) => cprintln!("🚀 Sent solution for {} to client via WebSocket.", solution.challenge_id),
+        Err(e) => ceprintln!("⚠️ Failed to send solution over WebSocket: {}", e),
+    }
+}
+
+/// Reports this host's ROM digest so the controller can detect hosts with a diverging ROM.
+fn send_rom_digest_to_client(websocket: &mut tungstenite::WebSocket<TcpStream>, challenge_id: &str, rom_key: &str, rom_id: &str, digest: &str) {
+    let payload = serde_json::json!({
+        "type": "rom_digest",
+        "data": {
+            "challenge_id": challenge_id,
+            "rom_key": rom_key,
+            "rom_id": rom_id,
+            "digest": digest,
+        },
+    }).to_string();
+
+    match websocket.send(Message::Text(payload.into())) {
+        Ok(_) => cprintln!("🧬 Reported ROM digest for {} to client via WebSocket.", challenge_id),
+        Err(e) => ceprintln!("⚠️ Failed to send ROM digest over WebSocket: {}", e),
     }
 }
 
@@ -147,20 +171,20 @@ fn handle_websocket_disconnect(e: TungsteniteError) {
     // ... (logic remains the same)
     match e {
         TungsteniteError::ConnectionClosed | TungsteniteError::Protocol(_) | TungsteniteError::Url(_) => {
-            println!("🌐 WebSocket client disconnected or protocol error: {}", e);
+            cprintln!("🌐 WebSocket client disconnected or protocol error: {}", e);
         }
         TungsteniteError::Io(ref io_err) => {
             match io_err.kind() {
                 ErrorKind::ConnectionReset | ErrorKind::BrokenPipe => {
-                    println!("🌐 WebSocket client disconnected gracefully (IO error: {}).", io_err);
+                    cprintln!("🌐 WebSocket client disconnected gracefully (IO error: {}).", io_err);
                 }
                 _ => {
-                    eprintln!("⚠️ WebSocket read IO error: {}", io_err);
+                    ceprintln!("⚠️ WebSocket read IO error: {}", io_err);
                 }
             }
         }
         _ => {
-            eprintln!("⚠️ WebSocket read error: {}", e);
+            ceprintln!("⚠️ WebSocket read error: {}", e);
         }
     }
 }
@@ -173,8 +197,8 @@ fn handle_incoming_challenge(json_payload: &str, manager_tx: &Sender<ManagerComm
     match challenge_response.code.as_str() {
         "active" => {
             if let Some(challenge_data) = challenge_response.challenge {
-                println!("🌐 Received new ACTIVE challenge {} via WebSocket. Forwarding to Manager.", challenge_data.challenge_id);
-                manager_tx.send(ManagerCommand::NewChallenge(challenge_data))
+                cprintln!("🌐 Received new ACTIVE challenge {} via WebSocket. Forwarding to Manager.", challenge_data.challenge_id);
+                manager_tx.send(ManagerCommand::ChallengeOffer(challenge_data, ChallengeSource::WebSocket))
                     .map_err(|_| "Manager channel closed (Manager thread crashed or shut down).".to_string())?;
                 Ok(())
             } else {
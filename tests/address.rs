@@ -0,0 +1,42 @@
+use shadow_harvester_lib::cardano::*;
+use shadow_harvester_lib::ledger::LedgerKey;
+
+// Wallet test vectors of this mnemonic: payment key 1852'/1815'/0'/0/0 (the CIP-19 enterprise
+// address) and stake key 1852'/1815'/0'/2/0, whose reward address is
+// stake1uyevw2xnsc0pvn9t9r9c7qryfqfeerchgrlm3ea2nefr9hqxdekzz.
+const MNEMONIC: &str = "test walk nut penalty hip pave soap entry language right filter choice";
+const BASE_ADDRESS: &str = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3jcu5d8ps7zex2k2xt3uqxgjqnnj83ws8lhrn648jjxtwqfjkjv7";
+const ENTERPRISE_ADDRESS: &str = "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8";
+
+#[test]
+fn base_address_matches_the_wallet() {
+    let key_pair = derive_key_pair_from_mnemonic_base(MNEMONIC, None, 0, 0, 0);
+    assert_eq!(key_pair.2.to_bech32().unwrap(), BASE_ADDRESS);
+}
+
+#[test]
+fn base_addresses_share_the_stake_key() {
+    // Only the payment part changes with the index; the stake part stays that of .../2/0.
+    let first = derive_key_pair_from_mnemonic_base(MNEMONIC, None, 0, 0, 0).2.to_vec();
+    let second = derive_key_pair_from_mnemonic_base(MNEMONIC, None, 0, 1, 0).2.to_vec();
+    assert_ne!(first[1..29], second[1..29]);
+    assert_eq!(first[29..], second[29..]);
+
+    let other_stake = derive_key_pair_from_mnemonic_base(MNEMONIC, None, 0, 0, 1).2.to_vec();
+    assert_eq!(first[..29], other_stake[..29]);
+    assert_ne!(first[29..], other_stake[29..]);
+}
+
+#[test]
+fn enterprise_address_matches_the_wallet() {
+    let key_pair = derive_key_pair_from_mnemonic(MNEMONIC, None, 0, 0);
+    assert_eq!(key_pair.2.to_bech32().unwrap(), ENTERPRISE_ADDRESS);
+}
+
+#[test]
+fn ledger_stake_path_defaults_to_index_zero() {
+    let key = LedgerKey::payment(None, 0, 5, 1, Some(0));
+    assert_eq!(key.path_string(), "1852'/1815'/0'/0/5");
+    assert_eq!(key.stake_path, Some(vec![1852 | 0x8000_0000, 1815 | 0x8000_0000, 0x8000_0000, 2, 0]));
+    assert!(LedgerKey::payment(None, 0, 5, 1, None).stake_path.is_none());
+}
@@ -71,4 +71,19 @@ mod cip8_tests {
             signature.0
         );
     }
+
+    #[test]
+    /// Tests that a BIP-39 passphrase changes the derived wallet, and that an empty one does not.
+    fn test_mnemonic_passphrase_derivation() {
+        const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art";
+
+        let plain = derive_key_pair_from_mnemonic(MNEMONIC, None, 0, 0).2.to_bech32().unwrap();
+        let empty = derive_key_pair_from_mnemonic(MNEMONIC, Some(""), 0, 0).2.to_bech32().unwrap();
+        let with_passphrase = derive_key_pair_from_mnemonic(MNEMONIC, Some("TREZOR"), 0, 0).2.to_bech32().unwrap();
+
+        assert_eq!(plain, empty);
+        assert_ne!(plain, with_passphrase);
+        assert_eq!(mnemonic_hash(MNEMONIC, None), mnemonic_hash(MNEMONIC, Some("")));
+        assert_ne!(mnemonic_hash(MNEMONIC, None), mnemonic_hash(MNEMONIC, Some("TREZOR")));
+    }
 }
@@ -0,0 +1,46 @@
+use shadow_harvester_lib::DifficultyRule;
+
+fn hash_with_prefix(prefix: [u8; 4]) -> [u8; 64] {
+    let mut hash = [0xff; 64];
+    hash[..4].copy_from_slice(&prefix);
+    hash
+}
+
+#[test]
+fn difficulty_rules_agree_on_contiguous_masks() {
+    let mask = 0x000f_ffff;
+    for prefix in [[0x00, 0x0f, 0xff, 0xff], [0x00, 0x00, 0x12, 0x34], [0x00, 0x10, 0x00, 0x00], [0x80, 0x00, 0x00, 0x00]] {
+        assert_eq!(DifficultyRule::disagreement(&hash_with_prefix(prefix), mask), None, "prefix {:02x?}", prefix);
+    }
+    assert!(DifficultyRule::Mask.meets(&hash_with_prefix([0x00, 0x0f, 0xff, 0xff]), mask));
+    assert!(!DifficultyRule::Mask.meets(&hash_with_prefix([0x00, 0x10, 0x00, 0x00]), mask));
+}
+
+#[test]
+fn difficulty_rules_differ_on_other_masks() {
+    // 0x00F0_8000 has a bit outside the mask, but is numerically smaller and has as many
+    // leading zeros; byte-wise its third byte exceeds the mask's.
+    let mask = 0x00ff_0f00;
+    let hash = hash_with_prefix([0x00, 0xf0, 0x80, 0x00]);
+    assert!(!DifficultyRule::Mask.meets(&hash, mask));
+    assert!(DifficultyRule::LeadingZeroBits.meets(&hash, mask));
+    assert!(DifficultyRule::NumericPrefix.meets(&hash, mask));
+    assert!(!DifficultyRule::BytePrefix.meets(&hash, mask));
+
+    // Every byte at most the mask's byte, yet a bit outside the mask.
+    let mask = 0x00ff_f000;
+    let hash = hash_with_prefix([0x00, 0xf0, 0x81, 0x00]);
+    assert!(!DifficultyRule::Mask.meets(&hash, mask));
+    assert!(DifficultyRule::BytePrefix.meets(&hash, mask));
+
+    let verdicts = DifficultyRule::disagreement(&hash, mask).expect("rules disagree");
+    assert_eq!(verdicts.len(), DifficultyRule::ALL.len());
+}
+
+#[test]
+fn difficulty_rules_parse_by_name() {
+    for rule in DifficultyRule::ALL {
+        assert_eq!(rule.to_string().parse::<DifficultyRule>(), Ok(rule));
+    }
+    assert!("zeros".parse::<DifficultyRule>().is_err());
+}
@@ -0,0 +1,17 @@
+use shadow_harvester_lib::cardano::{derive_key_pair_from_mnemonic, set_network_id};
+
+// The network is process-wide, so this file holds a single test.
+#[test]
+fn test_network_selects_address_prefix() {
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art";
+
+    let mainnet = derive_key_pair_from_mnemonic(MNEMONIC, None, 0, 0);
+    set_network_id(0);
+    let testnet = derive_key_pair_from_mnemonic(MNEMONIC, None, 0, 0);
+    set_network_id(1);
+
+    assert!(mainnet.2.to_bech32().unwrap().starts_with("addr1"));
+    assert!(testnet.2.to_bech32().unwrap().starts_with("addr_test1"));
+    // Same key, different address header.
+    assert_eq!(mainnet.1, testnet.1);
+}
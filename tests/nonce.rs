@@ -0,0 +1,159 @@
+use shadow_harvester_lib::{scavenge_challenge, set_active_workers, set_nonce_space, spin, ChallengeHasher, ChallengeParams, NonceAllocator, NonceBlock, Result};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn nonce_allocator_hands_out_disjoint_blocks() {
+    let allocator = NonceAllocator::with_range(100, 110, 4);
+    assert_eq!(allocator.reserve(), Some(NonceBlock { start: 100, end: 104 }));
+    assert_eq!(allocator.reserve(), Some(NonceBlock { start: 104, end: 108 }));
+    // The last block is cut short at the end of the range.
+    assert_eq!(allocator.reserve(), Some(NonceBlock { start: 108, end: 110 }));
+    assert_eq!(allocator.reserve(), None);
+    assert_eq!(allocator.remaining(), 0);
+
+    let near_end = NonceAllocator::new(u64::MAX - 3);
+    assert_eq!(near_end.reserve().map(|block| block.len()), Some(3));
+    assert_eq!(near_end.reserve(), None);
+}
+
+#[test]
+fn nonce_allocator_covers_range_across_threads() {
+    let allocator = Arc::new(NonceAllocator::with_range(0, 10_000, 7));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let allocator = allocator.clone();
+            thread::spawn(move || std::iter::from_fn(|| allocator.reserve()).collect::<Vec<_>>())
+        })
+        .collect();
+
+    let mut blocks: Vec<NonceBlock> = handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+    blocks.sort_by_key(|block| block.start);
+    assert_eq!(blocks.first().map(|block| block.start), Some(0));
+    assert!(blocks.windows(2).all(|pair| pair[0].end == pair[1].start));
+    assert_eq!(blocks.last().map(|block| block.end), Some(10_000));
+}
+
+/// Never meets any difficulty, so workers only stop when they run out of nonces.
+struct NoSolutionHasher;
+
+impl ChallengeHasher for NoSolutionHasher {
+    fn name(&self) -> &str {
+        "test"
+    }
+
+    fn hash(&self, _preimage: &[u8]) -> [u8; 64] {
+        [0xff; 64]
+    }
+}
+
+#[test]
+fn spin_reports_exhausted_search_space() {
+    let params = ChallengeParams {
+        rom_key: "00".to_string(),
+        difficulty_mask: 0,
+        address: "addr_test".to_string(),
+        challenge_id: "**D01C01".to_string(),
+        latest_submission: "2999-01-01T00:00:00Z".to_string(),
+        no_pre_mine_hour: "0".to_string(),
+        hasher: Arc::new(NoSolutionHasher),
+    };
+    let nonces = Arc::new(NonceAllocator::with_range(u64::MAX - 10, u64::MAX, 4));
+    let (sender, receiver) = channel();
+    let workers: Vec<_> = (0..2)
+        .map(|worker| {
+            let (params, sender, nonces) = (params.clone(), sender.clone(), nonces.clone());
+            thread::spawn(move || spin(params, sender, Arc::new(AtomicBool::new(false)), nonces, worker))
+        })
+        .collect();
+    drop(sender);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let results: Vec<Result> = receiver.iter().collect();
+    let mut exhausted: Vec<usize> = results
+        .iter()
+        .filter_map(|r| match r {
+            Result::SpaceExhausted { worker } => Some(*worker),
+            _ => None,
+        })
+        .collect();
+    exhausted.sort();
+    assert_eq!(exhausted, vec![0, 1]);
+
+    // Every nonce was hashed exactly once: no wraparound past u64::MAX.
+    let hashed: u64 = results
+        .iter()
+        .filter_map(|r| match r {
+            Result::Checkpoint { hashes, .. } => Some(*hashes),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(hashed, 10);
+}
+
+#[test]
+fn scavenge_stops_at_nonce_space_limit() {
+    set_nonce_space(3000);
+    let params = ChallengeParams::builder()
+        .address("addr_test")
+        .challenge_id("**D01C01")
+        .difficulty("00000000".parse().unwrap())
+        .rom_key("00".parse().unwrap())
+        .deadline("2999-01-01T00:00:00Z".parse().unwrap())
+        .no_pre_mine_hour("0")
+        .hasher(Arc::new(NoSolutionHasher))
+        .build()
+        .unwrap();
+    let report = scavenge_challenge(params, 3, 1, None);
+    set_nonce_space(u64::MAX);
+
+    assert!(report.space_exhausted);
+    assert!(report.nonces.is_empty());
+    assert_eq!(report.total_hashes, 3000);
+}
+
+#[test]
+fn parked_workers_only_report_exhaustion() {
+    set_active_workers(1);
+    let params = ChallengeParams {
+        rom_key: "00".to_string(),
+        difficulty_mask: 0,
+        address: "addr_test".to_string(),
+        challenge_id: "**D01C01".to_string(),
+        latest_submission: "2999-01-01T00:00:00Z".to_string(),
+        no_pre_mine_hour: "0".to_string(),
+        hasher: Arc::new(NoSolutionHasher),
+    };
+    let nonces = Arc::new(NonceAllocator::with_range(0, 100, 4));
+    let (sender, receiver) = channel();
+    let workers: Vec<_> = (0..2)
+        .map(|worker| {
+            let (params, sender, nonces) = (params.clone(), sender.clone(), nonces.clone());
+            thread::spawn(move || spin(params, sender, Arc::new(AtomicBool::new(false)), nonces, worker))
+        })
+        .collect();
+    drop(sender);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    set_active_workers(usize::MAX);
+
+    let results: Vec<Result> = receiver.iter().collect();
+    // Only worker 0 hashed, yet both end the cycle as exhausted. The parked worker only
+    // checkpoints its position so it does not hold the nonce cursor back.
+    assert!(results.iter().all(|r| !matches!(r, Result::Checkpoint { worker: 1, hashes, .. } if *hashes > 0)));
+    let hashed: u64 = results
+        .iter()
+        .filter_map(|r| match r {
+            Result::Checkpoint { hashes, .. } => Some(*hashes),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(hashed, 100);
+    let exhausted = results.iter().filter(|r| matches!(r, Result::SpaceExhausted { .. })).count();
+    assert_eq!(exhausted, 2);
+}
@@ -0,0 +1,56 @@
+use shadow_harvester_lib::{ChallengeHasher, ChallengeParams, Deadline, DifficultyMask, RomKey};
+use std::sync::Arc;
+
+struct ZeroHasher;
+
+impl ChallengeHasher for ZeroHasher {
+    fn name(&self) -> &str {
+        "test"
+    }
+
+    fn hash(&self, _preimage: &[u8]) -> [u8; 64] {
+        [0; 64]
+    }
+}
+
+#[test]
+fn typed_challenge_fields_reject_malformed_values() {
+    assert_eq!("000FFFFF".parse::<DifficultyMask>(), Ok(DifficultyMask(0x000f_ffff)));
+    assert_eq!(DifficultyMask(0xfff).to_string(), "00000FFF");
+    assert!("".parse::<DifficultyMask>().is_err());
+    assert!("0x0FFF".parse::<DifficultyMask>().is_err());
+    assert!("000FFFFFF".parse::<DifficultyMask>().is_err());
+
+    assert!("abcd".parse::<RomKey>().is_ok());
+    assert!("".parse::<RomKey>().is_err());
+    assert!("not hex".parse::<RomKey>().is_err());
+
+    let deadline: Deadline = "2025-11-20T23:59:59.000Z".parse().unwrap();
+    // The preimage uses the text exactly as the API sent it.
+    assert_eq!(deadline.as_str(), "2025-11-20T23:59:59.000Z");
+    assert!(deadline.has_passed());
+    assert!("tomorrow".parse::<Deadline>().is_err());
+}
+
+#[test]
+fn builder_requires_every_field() {
+    let complete = || {
+        ChallengeParams::builder()
+            .address("addr_test")
+            .challenge_id("**D01C01")
+            .difficulty("000FFFFF".parse().unwrap())
+            .rom_key("abcd".parse().unwrap())
+            .deadline("2999-01-01T00:00:00Z".parse().unwrap())
+            .no_pre_mine_hour("42")
+            .hasher(Arc::new(ZeroHasher))
+    };
+
+    let params = complete().build().unwrap();
+    assert_eq!(params.difficulty_mask, 0x000f_ffff);
+    assert_eq!(params.rom_key, "abcd");
+    assert_eq!(params.latest_submission, "2999-01-01T00:00:00Z");
+
+    assert!(complete().address("").build().is_err());
+    assert!(complete().no_pre_mine_hour("4h").build().is_err());
+    assert!(ChallengeParams::builder().address("addr_test").build().is_err());
+}